@@ -0,0 +1,31 @@
+// Offline migration from Redis: converts a dump.rdb into a KiloDB snapshot
+// that the server loads at startup.
+//
+//   kilodb-import-rdb <dump.rdb> [output, default ./dump.kdb]
+use KiloDB::persistence::{rdb, snapshot};
+use std::path::Path;
+use std::process::exit;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.len() > 2 {
+        eprintln!("Usage: kilodb-import-rdb <dump.rdb> [output.kdb]");
+        exit(2);
+    }
+    let output = args.get(1).map_or("dump.kdb", String::as_str);
+
+    let data = std::fs::read(&args[0]).unwrap_or_else(|e| {
+        eprintln!("Can't read {}: {}", args[0], e);
+        exit(1);
+    });
+    let entries = rdb::deserialize(&data).unwrap_or_else(|e| {
+        eprintln!("Can't import {}: {}", args[0], e);
+        exit(1);
+    });
+    let snapshot = snapshot::serialize(entries.iter().map(|(key, value, expires_at)| (key.as_slice(), value, *expires_at)));
+    if let Err(e) = snapshot::write_file(Path::new(output), &snapshot) {
+        eprintln!("Can't write {}: {}", output, e);
+        exit(1);
+    }
+    println!("Imported {} keys into {}", entries.len(), output);
+}
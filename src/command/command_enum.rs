@@ -1,219 +1,821 @@
+use crate::store::sorted_set_store::{LexBound, ScoreBound};
+use std::str::FromStr;
+
+const NOT_AN_INTEGER: &str = "ERR value is not an integer or out of range";
+const NOT_A_FLOAT: &str = "ERR value is not a valid float";
+const SYNTAX_ERROR: &str = "ERR syntax error";
+const NOT_POSITIVE: &str = "ERR value is out of range, must be positive";
+const NOT_A_SCORE_BOUND: &str = "ERR min or max is not a float";
+const NOT_A_LEX_BOUND: &str = "ERR min or max not valid string range item";
+
+// An expiry option as given on the command line. Relative forms are turned into
+// an absolute deadline only when the command runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expiry {
+    Ex(u64),   // seconds from now
+    Px(u64),   // milliseconds from now
+    ExAt(u64), // unix time in seconds
+    PxAt(u64), // unix time in milliseconds
+}
+
+impl Expiry {
+    // Absolute deadline in milliseconds, None if it does not fit.
+    pub fn deadline_ms(&self, now: u64) -> Option<u64> {
+        let deadline = match *self {
+            Expiry::Ex(seconds) => seconds.checked_mul(1000)?.checked_add(now)?,
+            Expiry::Px(ms) => ms.checked_add(now)?,
+            Expiry::ExAt(seconds) => seconds.checked_mul(1000)?,
+            Expiry::PxAt(ms) => ms,
+        };
+        (deadline <= i64::MAX as u64).then_some(deadline)
+    }
+}
+
+// SET only writes if the key does not exist (NX) or already exists (XX).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SetCondition {
+    NX,
+    XX,
+}
+
+// ZADD flags. NX/XX apply to members rather than keys; GT/LT only let a score
+// move up/down; CH counts updated members too; INCR behaves like ZINCRBY.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ZAddOptions {
+    pub condition: Option<SetCondition>,
+    pub gt: bool,
+    pub lt: bool,
+    pub ch: bool,
+    pub incr: bool,
+}
+
+// How ZUNION and ZINTER combine the scores of a member found in several sets.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+}
+
+// The three ways ZRANGE and the ZREMRANGEBY* commands select members.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZRange {
+    Rank(isize, isize),
+    Score(ScoreBound, ScoreBound),
+    Lex(LexBound, LexBound),
+}
+
+// MATCH, COUNT and TYPE options shared by the SCAN family. TYPE is only
+// accepted by SCAN and NOVALUES only by HSCAN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanOptions {
+    pub pattern: Option<Vec<u8>>,
+    pub count: usize,
+    pub type_name: Option<Vec<u8>>,
+    pub no_values: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            pattern: None,
+            count: 10, // Redis' default COUNT
+            type_name: None,
+            no_values: false,
+        }
+    }
+}
+
+// Which end of a list a command pushes to or pops from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+impl ListEnd {
+    fn parse(arg: &[u8]) -> Option<ListEnd> {
+        match arg.to_ascii_uppercase().as_slice() {
+            b"LEFT" => Some(ListEnd::Left),
+            b"RIGHT" => Some(ListEnd::Right),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum Command {
     // String commands
     SET {
-        key: String,
-        value: String,
-        ttl: Option<u64>, // TTL in seconds (from EX or PX)
+        key: Vec<u8>,
+        value: Vec<u8>,
+        expiry: Option<Expiry>,
+        condition: Option<SetCondition>,
+        get: bool,      // reply with the old value
+        keep_ttl: bool, // keep the key's current TTL
     },
     GET {
-        key: String,
+        key: Vec<u8>,
     },
     DEL {
-        keys: Vec<String>,
+        keys: Vec<Vec<u8>>,
     },
     EXISTS {
-        keys: Vec<String>,
+        keys: Vec<Vec<u8>>,
     },
     EXPIRE {
-        key: String,
-        seconds: u64,
+        key: Vec<u8>,
+        seconds: i64, // negative or zero deletes the key
+    },
+    PEXPIREAT {
+        key: Vec<u8>,
+        deadline_ms: i64, // in the past deletes the key
     },
     INCR {
-        key: String,
+        key: Vec<u8>,
     },
     DECR {
-        key: String,
+        key: Vec<u8>,
     },
     INCRBY {
-        key: String,
+        key: Vec<u8>,
         increment: i64,
     },
     DECRBY {
-        key: String,
+        key: Vec<u8>,
         decrement: i64,
     },
     APPEND {
-        key: String,
-        value: String,
+        key: Vec<u8>,
+        value: Vec<u8>,
     },
     STRLEN {
-        key: String,
+        key: Vec<u8>,
     },
     MGET {
-        keys: Vec<String>,
+        keys: Vec<Vec<u8>>,
     },
     MSET {
-        pairs: Vec<(String, String)>,
+        pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+    MSETNX {
+        pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+    INCRBYFLOAT {
+        key: Vec<u8>,
+        increment: f64,
+    },
+    GETRANGE {
+        key: Vec<u8>,
+        start: i64,
+        end: i64,
+    },
+    SETRANGE {
+        key: Vec<u8>,
+        offset: usize,
+        value: Vec<u8>,
+    },
+    GETSET {
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    GETDEL {
+        key: Vec<u8>,
+    },
+    GETEX {
+        key: Vec<u8>,
+        expiry: Option<Expiry>,
+        persist: bool,
+    },
+    SETNX {
+        key: Vec<u8>,
+        value: Vec<u8>,
+    },
+    SETEX {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        expiry: Expiry, // Ex for SETEX, Px for PSETEX
     },
 
     // Key management commands
     KEYS {
-        pattern: String,
+        pattern: Vec<u8>,
     },
     TYPE {
-        key: String,
+        key: Vec<u8>,
     },
     TTL {
-        key: String,
+        key: Vec<u8>,
     },
     PERSIST {
-        key: String,
+        key: Vec<u8>,
     },
     RENAME {
-        key: String,
-        newkey: String,
+        key: Vec<u8>,
+        newkey: Vec<u8>,
+    },
+    RENAMENX {
+        key: Vec<u8>,
+        newkey: Vec<u8>,
+    },
+    PTTL {
+        key: Vec<u8>,
+    },
+    COPY {
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        replace: bool,
+    },
+    DUMP {
+        key: Vec<u8>,
+    },
+    RESTORE {
+        key: Vec<u8>,
+        ttl_ms: u64, // 0 for no expiry
+        payload: Vec<u8>,
+        replace: bool,
+        absttl: bool,          // ttl_ms is a unix time in ms
+        idletime: Option<u64>, // seconds
+        freq: Option<u8>,
+    },
+    TOUCH {
+        keys: Vec<Vec<u8>>,
+    },
+    UNLINK {
+        keys: Vec<Vec<u8>>,
+    },
+    RANDOMKEY,
+    SCAN {
+        cursor: u64,
+        options: ScanOptions,
+    },
+    HSCAN {
+        key: Vec<u8>,
+        cursor: u64,
+        options: ScanOptions,
+    },
+    SSCAN {
+        key: Vec<u8>,
+        cursor: u64,
+        options: ScanOptions,
+    },
+    ZSCAN {
+        key: Vec<u8>,
+        cursor: u64,
+        options: ScanOptions,
     },
 
     // Hash commands
     HSET {
-        key: String,
-        fields: Vec<(String, String)>, // field-value pairs
+        key: Vec<u8>,
+        fields: Vec<(Vec<u8>, Vec<u8>)>, // field-value pairs
     },
     HGET {
-        key: String,
-        field: String,
+        key: Vec<u8>,
+        field: Vec<u8>,
     },
     HGETALL {
-        key: String,
+        key: Vec<u8>,
     },
     HDEL {
-        key: String,
-        fields: Vec<String>,
+        key: Vec<u8>,
+        fields: Vec<Vec<u8>>,
     },
     HEXISTS {
-        key: String,
-        field: String,
+        key: Vec<u8>,
+        field: Vec<u8>,
     },
     HLEN {
-        key: String,
+        key: Vec<u8>,
     },
     HKEYS {
-        key: String,
+        key: Vec<u8>,
     },
     HVALS {
-        key: String,
+        key: Vec<u8>,
+    },
+    HMGET {
+        key: Vec<u8>,
+        fields: Vec<Vec<u8>>,
+    },
+    HSETNX {
+        key: Vec<u8>,
+        field: Vec<u8>,
+        value: Vec<u8>,
+    },
+    HINCRBY {
+        key: Vec<u8>,
+        field: Vec<u8>,
+        increment: i64,
+    },
+    HINCRBYFLOAT {
+        key: Vec<u8>,
+        field: Vec<u8>,
+        increment: f64,
+    },
+    HSTRLEN {
+        key: Vec<u8>,
+        field: Vec<u8>,
+    },
+    HRANDFIELD {
+        key: Vec<u8>,
+        count: Option<i64>, // negative allows repeats
+        with_values: bool,
     },
 
     // List commands
     LPUSH {
-        key: String,
-        values: Vec<String>,
+        key: Vec<u8>,
+        values: Vec<Vec<u8>>,
     },
     RPUSH {
-        key: String,
-        values: Vec<String>,
+        key: Vec<u8>,
+        values: Vec<Vec<u8>>,
     },
     LPOP {
-        key: String,
+        key: Vec<u8>,
+        count: Option<usize>, // with a count the reply is an array
     },
     RPOP {
-        key: String,
+        key: Vec<u8>,
+        count: Option<usize>,
     },
     LRANGE {
-        key: String,
+        key: Vec<u8>,
         start: isize,
         stop: isize,
     },
     LLEN {
-        key: String,
+        key: Vec<u8>,
     },
     LINDEX {
-        key: String,
+        key: Vec<u8>,
+        index: isize,
+    },
+    LSET {
+        key: Vec<u8>,
         index: isize,
+        value: Vec<u8>,
+    },
+    LINSERT {
+        key: Vec<u8>,
+        before: bool,
+        pivot: Vec<u8>,
+        value: Vec<u8>,
+    },
+    LREM {
+        key: Vec<u8>,
+        count: i64,
+        value: Vec<u8>,
+    },
+    LTRIM {
+        key: Vec<u8>,
+        start: isize,
+        stop: isize,
+    },
+    LPOS {
+        key: Vec<u8>,
+        value: Vec<u8>,
+        rank: i64,
+        count: Option<usize>, // with a count the reply is an array
+        maxlen: usize,        // 0 means no limit
+    },
+    LPUSHX {
+        key: Vec<u8>,
+        values: Vec<Vec<u8>>,
+    },
+    RPUSHX {
+        key: Vec<u8>,
+        values: Vec<Vec<u8>>,
+    },
+    LMOVE {
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        from: ListEnd,
+        to: ListEnd,
+    },
+    LMPOP {
+        keys: Vec<Vec<u8>>,
+        end: ListEnd,
+        count: usize,
+    },
+    // Blocking forms; a timeout of 0 blocks forever
+    BLPOP {
+        keys: Vec<Vec<u8>>,
+        timeout_ms: u64,
+    },
+    BRPOP {
+        keys: Vec<Vec<u8>>,
+        timeout_ms: u64,
+    },
+    BLMOVE {
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        from: ListEnd,
+        to: ListEnd,
+        timeout_ms: u64,
+    },
+    BLMPOP {
+        keys: Vec<Vec<u8>>,
+        end: ListEnd,
+        count: usize,
+        timeout_ms: u64,
     },
 
     // Set commands
     SADD {
-        key: String,
-        members: Vec<String>,
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
     },
     SREM {
-        key: String,
-        members: Vec<String>,
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
     },
     SMEMBERS {
-        key: String,
+        key: Vec<u8>,
     },
     SISMEMBER {
-        key: String,
-        member: String,
+        key: Vec<u8>,
+        member: Vec<u8>,
     },
     SCARD {
-        key: String,
+        key: Vec<u8>,
+    },
+    SMISMEMBER {
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
+    },
+    SMOVE {
+        source: Vec<u8>,
+        destination: Vec<u8>,
+        member: Vec<u8>,
+    },
+    SPOP {
+        key: Vec<u8>,
+        count: Option<usize>,
+    },
+    SRANDMEMBER {
+        key: Vec<u8>,
+        count: Option<i64>,
+    },
+    // SUNIONSTORE and friends carry their destination, the others reply
+    SUNION {
+        destination: Option<Vec<u8>>,
+        keys: Vec<Vec<u8>>,
+    },
+    SINTER {
+        destination: Option<Vec<u8>>,
+        keys: Vec<Vec<u8>>,
+    },
+    SDIFF {
+        destination: Option<Vec<u8>>,
+        keys: Vec<Vec<u8>>,
+    },
+    SINTERCARD {
+        keys: Vec<Vec<u8>>,
+        limit: usize,
     },
 
     // Sorted Set
     ZADD {
-        key: String,
-        entries: Vec<(f64, String)>, // (score, member)
+        key: Vec<u8>,
+        entries: Vec<(f64, Vec<u8>)>, // (score, member)
+        options: ZAddOptions,
     },
     ZREM {
-        key: String,
-        members: Vec<String>,
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
     },
     ZRANGE {
-        key: String,
-        start: isize,
-        stop: isize,
+        key: Vec<u8>,
+        range: ZRange, // score and lex bounds are stored min first, even with REV
+        rev: bool,
+        limit: Option<(i64, i64)>, // (offset, count), a negative count means all
+        with_scores: bool,
     },
     ZCARD {
-        key: String,
+        key: Vec<u8>,
     },
     ZRANK {
-        key: String,
-        member: String,
+        key: Vec<u8>,
+        member: Vec<u8>,
+        with_score: bool,
+    },
+    ZREVRANK {
+        key: Vec<u8>,
+        member: Vec<u8>,
+        with_score: bool,
     },
     ZSCORE {
-        key: String,
-        member: String,
+        key: Vec<u8>,
+        member: Vec<u8>,
+    },
+    ZMSCORE {
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
+    },
+    ZCOUNT {
+        key: Vec<u8>,
+        min: ScoreBound,
+        max: ScoreBound,
+    },
+    ZLEXCOUNT {
+        key: Vec<u8>,
+        min: LexBound,
+        max: LexBound,
+    },
+    ZINCRBY {
+        key: Vec<u8>,
+        increment: f64,
+        member: Vec<u8>,
+    },
+    ZPOPMIN {
+        key: Vec<u8>,
+        count: Option<usize>,
+    },
+    ZPOPMAX {
+        key: Vec<u8>,
+        count: Option<usize>,
+    },
+    // ZREMRANGEBYRANK, ZREMRANGEBYSCORE and ZREMRANGEBYLEX
+    ZREMRANGE {
+        key: Vec<u8>,
+        range: ZRange,
+    },
+    // ZUNION, ZINTER and ZDIFF; the STORE forms have a destination
+    ZUNION {
+        destination: Option<Vec<u8>>,
+        keys: Vec<Vec<u8>>,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+        with_scores: bool,
+    },
+    ZINTER {
+        destination: Option<Vec<u8>>,
+        keys: Vec<Vec<u8>>,
+        weights: Option<Vec<f64>>,
+        aggregate: Aggregate,
+        with_scores: bool,
+    },
+    ZDIFF {
+        destination: Option<Vec<u8>>,
+        keys: Vec<Vec<u8>>,
+        with_scores: bool,
+    },
+    ZINTERCARD {
+        keys: Vec<Vec<u8>>,
+        limit: usize, // 0 means no limit
+    },
+    ZRANDMEMBER {
+        key: Vec<u8>,
+        count: Option<i64>, // negative allows repeats
+        with_scores: bool,
     },
 
     // Misc
     PING,
     ECHO {
-        message: String,
+        message: Vec<u8>,
     },
     FLUSHDB,
     DBSIZE,
+    OBJECT_ENCODING {
+        key: Vec<u8>,
+    },
+    OBJECT_FREQ {
+        key: Vec<u8>,
+    },
+    OBJECT_IDLETIME {
+        key: Vec<u8>,
+    },
+    INFO {
+        sections: Vec<Vec<u8>>, // empty for the default sections
+    },
+    MEMORY_USAGE {
+        key: Vec<u8>,
+        samples: usize, // 0 measures every element
+    },
+    MEMORY_STATS,
+    SAVE,
+    BGSAVE,
+    LASTSAVE,
+    BGREWRITEAOF,
+    CONFIG_GET {
+        patterns: Vec<Vec<u8>>,
+    },
+    CONFIG_SET {
+        pairs: Vec<(Vec<u8>, Vec<u8>)>,
+    },
+
+    // Connection state, handled by the connection whatever mode it is in
+    // (see `server::connection`)
+    QUIT,
+    RESET,
+
+    // Transactions, handled per connection (see `server::transaction`)
+    MULTI,
+    EXEC,
+    DISCARD,
+    WATCH {
+        keys: Vec<Vec<u8>>,
+    },
+    UNWATCH,
+
+    // Pub/sub. The subscribe family is handled per connection (see
+    // `server::pubsub`); an empty list unsubscribes from everything.
+    SUBSCRIBE {
+        channels: Vec<Vec<u8>>,
+    },
+    UNSUBSCRIBE {
+        channels: Vec<Vec<u8>>,
+    },
+    PSUBSCRIBE {
+        patterns: Vec<Vec<u8>>,
+    },
+    PUNSUBSCRIBE {
+        patterns: Vec<Vec<u8>>,
+    },
+    SSUBSCRIBE {
+        channels: Vec<Vec<u8>>,
+    },
+    SUNSUBSCRIBE {
+        channels: Vec<Vec<u8>>,
+    },
+    PUBLISH {
+        channel: Vec<u8>,
+        message: Vec<u8>,
+    },
+    SPUBLISH {
+        channel: Vec<u8>,
+        message: Vec<u8>,
+    },
+    PUBSUB_CHANNELS {
+        pattern: Option<Vec<u8>>,
+    },
+    PUBSUB_NUMSUB {
+        channels: Vec<Vec<u8>>,
+    },
+    PUBSUB_NUMPAT,
+    PUBSUB_SHARDCHANNELS {
+        pattern: Option<Vec<u8>>,
+    },
+    PUBSUB_SHARDNUMSUB {
+        channels: Vec<Vec<u8>>,
+    },
+
+    // Known command whose arguments were rejected; replied to as-is
+    Invalid {
+        error: String,
+    },
 
-    // Unknown or unhandled
+    // A command name the server does not know, or a known one called with
+    // the wrong number of arguments
     Unknown {
-        raw: Vec<String>,
+        raw: Vec<Vec<u8>>,
+        wrong_arity: bool,
     },
 }
 impl Command {
-    pub fn new(command: &[String]) -> Command {
+    // For the commands that may block: the keys they wait on and their timeout.
+    pub fn blocking_keys(&self) -> Option<(Vec<Vec<u8>>, u64)> {
+        match self {
+            Command::BLPOP { keys, timeout_ms }
+            | Command::BRPOP { keys, timeout_ms }
+            | Command::BLMPOP { keys, timeout_ms, .. } => Some((keys.clone(), *timeout_ms)),
+            Command::BLMOVE { source, timeout_ms, .. } => Some((vec![source.clone()], *timeout_ms)),
+            _ => None,
+        }
+    }
+
+    // Commands that may grow the dataset, refused while used memory is over
+    // maxmemory and nothing can be evicted (Redis' denyoom flag).
+    pub fn grows_memory(&self) -> bool {
+        match self {
+            Command::SET { .. }
+            | Command::SETNX { .. }
+            | Command::SETEX { .. }
+            | Command::APPEND { .. }
+            | Command::INCR { .. }
+            | Command::DECR { .. }
+            | Command::INCRBY { .. }
+            | Command::DECRBY { .. }
+            | Command::INCRBYFLOAT { .. }
+            | Command::SETRANGE { .. }
+            | Command::GETSET { .. }
+            | Command::MSET { .. }
+            | Command::MSETNX { .. }
+            | Command::COPY { .. }
+            | Command::RESTORE { .. }
+            | Command::HSET { .. }
+            | Command::HSETNX { .. }
+            | Command::HINCRBY { .. }
+            | Command::HINCRBYFLOAT { .. }
+            | Command::LPUSH { .. }
+            | Command::RPUSH { .. }
+            | Command::LPUSHX { .. }
+            | Command::RPUSHX { .. }
+            | Command::LSET { .. }
+            | Command::LINSERT { .. }
+            | Command::LMOVE { .. }
+            | Command::BLMOVE { .. }
+            | Command::SADD { .. }
+            | Command::SMOVE { .. }
+            | Command::ZADD { .. }
+            | Command::ZINCRBY { .. } => true,
+            Command::SUNION { destination, .. }
+            | Command::SINTER { destination, .. }
+            | Command::SDIFF { destination, .. }
+            | Command::ZUNION { destination, .. }
+            | Command::ZINTER { destination, .. }
+            | Command::ZDIFF { destination, .. } => destination.is_some(),
+            _ => false,
+        }
+    }
+
+    // The key a command gives a TTL relative to now. The AOF logs its absolute
+    // deadline after such a command, so replaying it later does not extend it.
+    pub fn relative_ttl_key(&self) -> Option<&[u8]> {
+        match self {
+            Command::EXPIRE { key, .. } | Command::SETEX { key, .. } => Some(key),
+            Command::RESTORE { key, absttl: false, .. } => Some(key),
+            Command::SET {
+                key,
+                expiry: Some(Expiry::Ex(_) | Expiry::Px(_)),
+                ..
+            }
+            | Command::GETEX {
+                key,
+                expiry: Some(Expiry::Ex(_) | Expiry::Px(_)),
+                ..
+            } => Some(key),
+            _ => None,
+        }
+    }
+
+    // The commands that change a connection's subscriptions.
+    pub fn is_subscription(&self) -> bool {
+        matches!(
+            self,
+            Command::SUBSCRIBE { .. }
+                | Command::UNSUBSCRIBE { .. }
+                | Command::PSUBSCRIBE { .. }
+                | Command::PUNSUBSCRIBE { .. }
+                | Command::SSUBSCRIBE { .. }
+                | Command::SUNSUBSCRIBE { .. }
+        )
+    }
+
+    // The error a command that could not be parsed is answered with, in the
+    // words of Redis, which clients match on. None for a parsed command.
+    pub fn rejection(&self) -> Option<String> {
+        match self {
+            Command::Invalid { error } => Some(error.clone()),
+            Command::Unknown { raw, wrong_arity: true } => Some(format!(
+                "ERR wrong number of arguments for '{}' command",
+                String::from_utf8_lossy(&raw[0]).to_lowercase()
+            )),
+            Command::Unknown { raw, .. } => {
+                // Redis quotes the arguments until 128 bytes of them are shown
+                let mut args = String::new();
+                for arg in raw.iter().skip(1) {
+                    if args.len() >= 128 {
+                        break;
+                    }
+                    let arg = String::from_utf8_lossy(arg);
+                    let shown: String = arg.chars().take(128 - args.len()).collect();
+                    args.push_str(&format!("'{}' ", shown));
+                }
+                let name = raw.first().map_or(String::new(), |name| String::from_utf8_lossy(name).into_owned());
+                let error = format!(
+                    "ERR unknown command '{}', with args beginning with: {}",
+                    name.chars().take(128).collect::<String>(),
+                    args
+                );
+                // The reply is a single line
+                Some(error.replace(['\r', '\n'], " "))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn new(command: &[Vec<u8>]) -> Command {
         if command.is_empty() {
-            return Command::Unknown { raw: vec![] };
+            return Command::Unknown {
+                raw: vec![],
+                wrong_arity: false,
+            };
         }
 
-        let cmd = command[0].to_uppercase();
+        let cmd = String::from_utf8_lossy(&command[0]).to_uppercase();
 
         match cmd.as_str() {
             // --- String commands ---
             "SET" => {
-                if command.len() == 3 {
-                    Command::SET {
-                        key: command[1].clone(),
-                        value: command[2].clone(),
-                        ttl: None,
-                    }
-                } else if command.len() == 5 && command[3].to_uppercase() == "EX" {
-                    match command[4].parse::<u64>() {
-                        Ok(ttl) => Command::SET {
-                            key: command[1].clone(),
-                            value: command[2].clone(),
-                            ttl: Some(ttl),
-                        },
-                        Err(_) => Command::Unknown {
-                            raw: command.to_vec(),
-                        },
-                    }
+                if command.len() >= 3 {
+                    parse_set(command)
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "GET" => {
@@ -222,9 +824,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "DEL" => {
@@ -233,9 +833,7 @@ impl Command {
                         keys: command[1..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "EXISTS" => {
@@ -244,26 +842,33 @@ impl Command {
                         keys: command[1..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "EXPIRE" => {
                 if command.len() == 3 {
-                    match command[2].parse::<u64>() {
+                    match parse_arg::<i64>(&command[2]) {
                         Ok(secs) => Command::EXPIRE {
                             key: command[1].clone(),
                             seconds: secs,
                         },
-                        Err(_) => Command::Unknown {
-                            raw: command.to_vec(),
-                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "PEXPIREAT" => {
+                if command.len() == 3 {
+                    match parse_arg::<i64>(&command[2]) {
+                        Ok(deadline_ms) => Command::PEXPIREAT {
+                            key: command[1].clone(),
+                            deadline_ms,
+                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
                     }
+                } else {
+                    wrong_arity(command)
                 }
             }
             "INCR" => {
@@ -272,9 +877,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "DECR" => {
@@ -283,43 +886,33 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "INCRBY" => {
                 if command.len() == 3 {
-                    match command[2].parse::<i64>() {
+                    match parse_arg::<i64>(&command[2]) {
                         Ok(inc) => Command::INCRBY {
                             key: command[1].clone(),
                             increment: inc,
                         },
-                        Err(_) => Command::Unknown {
-                            raw: command.to_vec(),
-                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "DECRBY" => {
                 if command.len() == 3 {
-                    match command[2].parse::<i64>() {
+                    match parse_arg::<i64>(&command[2]) {
                         Ok(dec) => Command::DECRBY {
                             key: command[1].clone(),
                             decrement: dec,
                         },
-                        Err(_) => Command::Unknown {
-                            raw: command.to_vec(),
-                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "APPEND" => {
@@ -329,9 +922,7 @@ impl Command {
                         value: command[2].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "STRLEN" => {
@@ -340,9 +931,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "MGET" => {
@@ -351,13 +940,11 @@ impl Command {
                         keys: command[1..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "MSET" => {
-                if command.len() >= 3 && (command.len() - 1) % 2 == 0 {
+                if command.len() >= 3 && (command.len() - 1).is_multiple_of(2) {
                     let mut pairs = vec![];
                     let args = &command[1..];
                     for chunk in args.chunks(2) {
@@ -365,9 +952,112 @@ impl Command {
                     }
                     Command::MSET { pairs }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+
+            "MSETNX" => {
+                if command.len() >= 3 && (command.len() - 1).is_multiple_of(2) {
+                    let pairs = command[1..]
+                        .chunks(2)
+                        .map(|chunk| (chunk[0].clone(), chunk[1].clone()))
+                        .collect();
+                    Command::MSETNX { pairs }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "INCRBYFLOAT" => {
+                if command.len() == 3 {
+                    match parse_arg::<f64>(&command[2]) {
+                        Ok(inc) if inc.is_finite() => Command::INCRBYFLOAT {
+                            key: command[1].clone(),
+                            increment: inc,
+                        },
+                        _ => invalid(NOT_A_FLOAT),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "GETRANGE" => {
+                if command.len() == 4 {
+                    match (parse_arg::<i64>(&command[2]), parse_arg::<i64>(&command[3])) {
+                        (Ok(start), Ok(end)) => Command::GETRANGE {
+                            key: command[1].clone(),
+                            start,
+                            end,
+                        },
+                        _ => invalid(NOT_AN_INTEGER),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "SETRANGE" => {
+                if command.len() == 4 {
+                    match parse_arg::<i64>(&command[2]) {
+                        Ok(offset) if offset < 0 => invalid("ERR offset is out of range"),
+                        Ok(offset) => Command::SETRANGE {
+                            key: command[1].clone(),
+                            offset: offset as usize,
+                            value: command[3].clone(),
+                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "GETSET" => {
+                if command.len() == 3 {
+                    Command::GETSET {
+                        key: command[1].clone(),
+                        value: command[2].clone(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "GETDEL" => {
+                if command.len() == 2 {
+                    Command::GETDEL {
+                        key: command[1].clone(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "GETEX" => {
+                if command.len() >= 2 {
+                    parse_getex(command)
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "SETNX" => {
+                if command.len() == 3 {
+                    Command::SETNX {
+                        key: command[1].clone(),
+                        value: command[2].clone(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "SETEX" | "PSETEX" => {
+                if command.len() == 4 {
+                    let unit = if cmd == "SETEX" { b"EX".as_slice() } else { b"PX".as_slice() };
+                    match parse_expiry(unit, &command[2], &cmd) {
+                        Ok(expiry) => Command::SETEX {
+                            key: command[1].clone(),
+                            value: command[3].clone(),
+                            expiry,
+                        },
+                        Err(error) => error,
                     }
+                } else {
+                    wrong_arity(command)
                 }
             }
 
@@ -378,9 +1068,7 @@ impl Command {
                         pattern: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "TYPE" => {
@@ -389,9 +1077,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "TTL" => {
@@ -400,9 +1086,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "PERSIST" => {
@@ -411,9 +1095,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "RENAME" => {
@@ -423,73 +1105,155 @@ impl Command {
                         newkey: command[2].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
 
-            // --- Hash commands ---
-            "HSET" => {
-                if command.len() >= 4 && (command.len() - 2) % 2 == 0 {
-                    let key = command[1].clone();
-                    let mut fields = vec![];
-                    let pairs = &command[2..];
-                    for chunk in pairs.chunks(2) {
-                        fields.push((chunk[0].clone(), chunk[1].clone()));
-                    }
-                    Command::HSET { key, fields }
-                } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
-                }
-            }
-            "HGET" => {
+            "RENAMENX" => {
                 if command.len() == 3 {
-                    Command::HGET {
+                    Command::RENAMENX {
                         key: command[1].clone(),
-                        field: command[2].clone(),
+                        newkey: command[2].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
-            "HGETALL" => {
+            "PTTL" => {
                 if command.len() == 2 {
-                    Command::HGETALL {
+                    Command::PTTL {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
-            "HDEL" => {
+            "COPY" => {
                 if command.len() >= 3 {
-                    Command::HDEL {
+                    parse_copy(command)
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "DUMP" => {
+                if command.len() == 2 {
+                    Command::DUMP {
                         key: command[1].clone(),
-                        fields: command[2..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
-            "HEXISTS" => {
+            "RESTORE" => {
+                if command.len() >= 4 {
+                    parse_restore(command)
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "TOUCH" => {
+                if command.len() >= 2 {
+                    Command::TOUCH {
+                        keys: command[1..].to_vec(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "UNLINK" => {
+                if command.len() >= 2 {
+                    Command::UNLINK {
+                        keys: command[1..].to_vec(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "RANDOMKEY" => {
+                if command.len() == 1 {
+                    Command::RANDOMKEY
+                } else {
+                    wrong_arity(command)
+                }
+            }
+
+            "SCAN" => {
+                if command.len() >= 2 {
+                    match parse_scan(command, 1, &cmd) {
+                        Ok((cursor, options)) => Command::SCAN { cursor, options },
+                        Err(error) => error,
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HSCAN" | "SSCAN" | "ZSCAN" => {
+                if command.len() >= 3 {
+                    let key = command[1].clone();
+                    match parse_scan(command, 2, &cmd) {
+                        Ok((cursor, options)) => match cmd.as_str() {
+                            "HSCAN" => Command::HSCAN { key, cursor, options },
+                            "SSCAN" => Command::SSCAN { key, cursor, options },
+                            _ => Command::ZSCAN { key, cursor, options },
+                        },
+                        Err(error) => error,
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+
+            // --- Hash commands ---
+            "HSET" => {
+                if command.len() >= 4 && (command.len() - 2).is_multiple_of(2) {
+                    let key = command[1].clone();
+                    let mut fields = vec![];
+                    let pairs = &command[2..];
+                    for chunk in pairs.chunks(2) {
+                        fields.push((chunk[0].clone(), chunk[1].clone()));
+                    }
+                    Command::HSET { key, fields }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HGET" => {
                 if command.len() == 3 {
-                    Command::HEXISTS {
+                    Command::HGET {
                         key: command[1].clone(),
                         field: command[2].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "HGETALL" => {
+                if command.len() == 2 {
+                    Command::HGETALL {
+                        key: command[1].clone(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HDEL" => {
+                if command.len() >= 3 {
+                    Command::HDEL {
+                        key: command[1].clone(),
+                        fields: command[2..].to_vec(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HEXISTS" => {
+                if command.len() == 3 {
+                    Command::HEXISTS {
+                        key: command[1].clone(),
+                        field: command[2].clone(),
                     }
+                } else {
+                    wrong_arity(command)
                 }
             }
             "HLEN" => {
@@ -498,9 +1262,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "HKEYS" => {
@@ -509,9 +1271,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "HVALS" => {
@@ -520,9 +1280,73 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "HMGET" => {
+                if command.len() >= 3 {
+                    Command::HMGET {
+                        key: command[1].clone(),
+                        fields: command[2..].to_vec(),
                     }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HSETNX" => {
+                if command.len() == 4 {
+                    Command::HSETNX {
+                        key: command[1].clone(),
+                        field: command[2].clone(),
+                        value: command[3].clone(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HINCRBY" => {
+                if command.len() == 4 {
+                    match parse_arg::<i64>(&command[3]) {
+                        Ok(increment) => Command::HINCRBY {
+                            key: command[1].clone(),
+                            field: command[2].clone(),
+                            increment,
+                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HINCRBYFLOAT" => {
+                if command.len() == 4 {
+                    match parse_arg::<f64>(&command[3]) {
+                        Ok(increment) if increment.is_finite() => Command::HINCRBYFLOAT {
+                            key: command[1].clone(),
+                            field: command[2].clone(),
+                            increment,
+                        },
+                        _ => invalid(NOT_A_FLOAT),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HSTRLEN" => {
+                if command.len() == 3 {
+                    Command::HSTRLEN {
+                        key: command[1].clone(),
+                        field: command[2].clone(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "HRANDFIELD" => {
+                if (2..=4).contains(&command.len()) {
+                    parse_hrandfield(command)
+                } else {
+                    wrong_arity(command)
                 }
             }
 
@@ -534,9 +1358,7 @@ impl Command {
                         values: command[2..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "RPUSH" => {
@@ -546,74 +1368,212 @@ impl Command {
                         values: command[2..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
-            "LPOP" => {
-                if command.len() == 2 {
-                    Command::LPOP {
-                        key: command[1].clone(),
+            "LPOP" | "RPOP" => {
+                if command.len() == 2 || command.len() == 3 {
+                    let count = match command.get(2).map(|arg| parse_arg::<i64>(arg)) {
+                        Some(Ok(count)) if count < 0 => return invalid(NOT_POSITIVE),
+                        Some(Ok(count)) => Some(count as usize),
+                        Some(Err(_)) => return invalid(NOT_AN_INTEGER),
+                        None => None,
+                    };
+                    let key = command[1].clone();
+                    if cmd == "LPOP" {
+                        Command::LPOP { key, count }
+                    } else {
+                        Command::RPOP { key, count }
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "LRANGE" | "LTRIM" => {
+                if command.len() == 4 {
+                    match (parse_arg::<isize>(&command[2]), parse_arg::<isize>(&command[3])) {
+                        (Ok(start), Ok(stop)) if cmd == "LRANGE" => Command::LRANGE {
+                            key: command[1].clone(),
+                            start,
+                            stop,
+                        },
+                        (Ok(start), Ok(stop)) => Command::LTRIM {
+                            key: command[1].clone(),
+                            start,
+                            stop,
+                        },
+                        _ => invalid(NOT_AN_INTEGER),
                     }
+                } else {
+                    wrong_arity(command)
                 }
             }
-            "RPOP" => {
+            "LLEN" => {
                 if command.len() == 2 {
-                    Command::RPOP {
+                    Command::LLEN {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "LINDEX" => {
+                if command.len() == 3 {
+                    match parse_arg::<isize>(&command[2]) {
+                        Ok(index) => Command::LINDEX {
+                            key: command[1].clone(),
+                            index,
+                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
                     }
+                } else {
+                    wrong_arity(command)
                 }
             }
-            "LRANGE" => {
+            "LSET" => {
                 if command.len() == 4 {
-                    let start = command[2].parse().unwrap_or(0);
-                    let stop = command[3].parse().unwrap_or(0);
-                    Command::LRANGE {
+                    match parse_arg::<isize>(&command[2]) {
+                        Ok(index) => Command::LSET {
+                            key: command[1].clone(),
+                            index,
+                            value: command[3].clone(),
+                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "LINSERT" => {
+                if command.len() == 5 {
+                    let before = match command[2].to_ascii_uppercase().as_slice() {
+                        b"BEFORE" => true,
+                        b"AFTER" => false,
+                        _ => return invalid(SYNTAX_ERROR),
+                    };
+                    Command::LINSERT {
                         key: command[1].clone(),
-                        start,
-                        stop,
+                        before,
+                        pivot: command[3].clone(),
+                        value: command[4].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "LREM" => {
+                if command.len() == 4 {
+                    match parse_arg::<i64>(&command[2]) {
+                        Ok(count) => Command::LREM {
+                            key: command[1].clone(),
+                            count,
+                            value: command[3].clone(),
+                        },
+                        Err(_) => invalid(NOT_AN_INTEGER),
                     }
+                } else {
+                    wrong_arity(command)
                 }
             }
-            "LLEN" => {
-                if command.len() == 2 {
-                    Command::LLEN {
-                        key: command[1].clone(),
+            "LPOS" => {
+                if command.len() >= 3 {
+                    parse_lpos(command)
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "LPUSHX" | "RPUSHX" => {
+                if command.len() >= 3 {
+                    let key = command[1].clone();
+                    let values = command[2..].to_vec();
+                    if cmd == "LPUSHX" {
+                        Command::LPUSHX { key, values }
+                    } else {
+                        Command::RPUSHX { key, values }
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "LMOVE" => {
+                if command.len() == 5 {
+                    match (ListEnd::parse(&command[3]), ListEnd::parse(&command[4])) {
+                        (Some(from), Some(to)) => Command::LMOVE {
+                            source: command[1].clone(),
+                            destination: command[2].clone(),
+                            from,
+                            to,
+                        },
+                        _ => invalid(SYNTAX_ERROR),
                     }
+                } else {
+                    wrong_arity(command)
                 }
             }
-            "LINDEX" => {
-                if command.len() == 3 {
-                    match command[2].parse::<isize>() {
-                        Ok(index) => Command::LINDEX {
-                            key: command[1].clone(),
-                            index,
+            "BLPOP" | "BRPOP" => {
+                if command.len() >= 3 {
+                    match parse_timeout(&command[command.len() - 1]) {
+                        Ok(timeout_ms) => {
+                            let keys = command[1..command.len() - 1].to_vec();
+                            if cmd == "BLPOP" {
+                                Command::BLPOP { keys, timeout_ms }
+                            } else {
+                                Command::BRPOP { keys, timeout_ms }
+                            }
+                        }
+                        Err(error) => error,
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "BLMOVE" => {
+                if command.len() == 6 {
+                    let timeout_ms = match parse_timeout(&command[5]) {
+                        Ok(timeout_ms) => timeout_ms,
+                        Err(error) => return error,
+                    };
+                    match (ListEnd::parse(&command[3]), ListEnd::parse(&command[4])) {
+                        (Some(from), Some(to)) => Command::BLMOVE {
+                            source: command[1].clone(),
+                            destination: command[2].clone(),
+                            from,
+                            to,
+                            timeout_ms,
                         },
-                        Err(_) => Command::Unknown {
-                            raw: command.to_vec(),
+                        _ => invalid(SYNTAX_ERROR),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "BLMPOP" => {
+                if command.len() >= 5 {
+                    let timeout_ms = match parse_timeout(&command[1]) {
+                        Ok(timeout_ms) => timeout_ms,
+                        Err(error) => return error,
+                    };
+                    match parse_mpop(command, 2, ListEnd::parse) {
+                        Ok((keys, end, count)) => Command::BLMPOP {
+                            keys,
+                            end,
+                            count,
+                            timeout_ms,
                         },
+                        Err(error) => error,
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "LMPOP" => {
+                if command.len() >= 4 {
+                    match parse_mpop(command, 1, ListEnd::parse) {
+                        Ok((keys, end, count)) => Command::LMPOP { keys, end, count },
+                        Err(error) => error,
                     }
+                } else {
+                    wrong_arity(command)
                 }
             }
 
@@ -625,9 +1585,7 @@ impl Command {
                         members: command[2..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "SREM" => {
@@ -637,9 +1595,7 @@ impl Command {
                         members: command[2..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "SMEMBERS" => {
@@ -648,9 +1604,7 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "SISMEMBER" => {
@@ -660,9 +1614,7 @@ impl Command {
                         member: command[2].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "SCARD" => {
@@ -671,32 +1623,96 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+            "SMISMEMBER" => {
+                if command.len() >= 3 {
+                    Command::SMISMEMBER {
+                        key: command[1].clone(),
+                        members: command[2..].to_vec(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "SMOVE" => {
+                if command.len() == 4 {
+                    Command::SMOVE {
+                        source: command[1].clone(),
+                        destination: command[2].clone(),
+                        member: command[3].clone(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "SPOP" => {
+                if command.len() == 2 || command.len() == 3 {
+                    let count = match command.get(2).map(|arg| parse_arg::<i64>(arg)) {
+                        Some(Ok(count)) if count < 0 => return invalid(NOT_POSITIVE),
+                        Some(Ok(count)) => Some(count as usize),
+                        Some(Err(_)) => return invalid(NOT_AN_INTEGER),
+                        None => None,
+                    };
+                    Command::SPOP {
+                        key: command[1].clone(),
+                        count,
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "SRANDMEMBER" => {
+                if command.len() == 2 || command.len() == 3 {
+                    // No WITH flag: the arity check leaves no room for one
+                    match parse_random_count(command, b"") {
+                        Ok((count, _)) => Command::SRANDMEMBER {
+                            key: command[1].clone(),
+                            count,
+                        },
+                        Err(error) => error,
                     }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "SUNION" | "SINTER" | "SDIFF" | "SUNIONSTORE" | "SINTERSTORE" | "SDIFFSTORE" => {
+                let store = cmd.ends_with("STORE");
+                if command.len() >= if store { 3 } else { 2 } {
+                    let (destination, keys) = if store {
+                        (Some(command[1].clone()), command[2..].to_vec())
+                    } else {
+                        (None, command[1..].to_vec())
+                    };
+                    if cmd.starts_with("SUNION") {
+                        Command::SUNION { destination, keys }
+                    } else if cmd.starts_with("SINTER") {
+                        Command::SINTER { destination, keys }
+                    } else {
+                        Command::SDIFF { destination, keys }
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "SINTERCARD" => {
+                if command.len() >= 3 {
+                    match parse_intercard(command) {
+                        Ok((keys, limit)) => Command::SINTERCARD { keys, limit },
+                        Err(error) => error,
+                    }
+                } else {
+                    wrong_arity(command)
                 }
             }
 
             // --- Sorted set commands ---
             "ZADD" => {
-                if command.len() >= 4 && (command.len() - 2) % 2 == 0 {
-                    let key = command[1].clone();
-                    let mut entries = vec![];
-                    let pairs = &command[2..];
-                    for chunk in pairs.chunks(2) {
-                        if let Ok(score) = chunk[0].parse::<f64>() {
-                            entries.push((score, chunk[1].clone()));
-                        } else {
-                            return Command::Unknown {
-                                raw: command.to_vec(),
-                            };
-                        }
-                    }
-                    Command::ZADD { key, entries }
+                if command.len() >= 4 {
+                    parse_zadd(command)
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "ZREM" => {
@@ -706,24 +1722,14 @@ impl Command {
                         members: command[2..].to_vec(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "ZRANGE" => {
-                if command.len() == 4 {
-                    let start = command[2].parse().unwrap_or(0);
-                    let stop = command[3].parse().unwrap_or(0);
-                    Command::ZRANGE {
-                        key: command[1].clone(),
-                        start,
-                        stop,
-                    }
+                if command.len() >= 4 {
+                    parse_zrange(command)
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "ZCARD" => {
@@ -732,21 +1738,25 @@ impl Command {
                         key: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
-            "ZRANK" => {
-                if command.len() == 3 {
-                    Command::ZRANK {
-                        key: command[1].clone(),
-                        member: command[2].clone(),
+            "ZRANK" | "ZREVRANK" => {
+                if command.len() == 3 || command.len() == 4 {
+                    let with_score = match command.get(3) {
+                        Some(option) if option.eq_ignore_ascii_case(b"WITHSCORE") => true,
+                        Some(_) => return invalid(SYNTAX_ERROR),
+                        None => false,
+                    };
+                    let key = command[1].clone();
+                    let member = command[2].clone();
+                    if cmd == "ZRANK" {
+                        Command::ZRANK { key, member, with_score }
+                    } else {
+                        Command::ZREVRANK { key, member, with_score }
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "ZSCORE" => {
@@ -756,9 +1766,128 @@ impl Command {
                         member: command[2].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
+                    wrong_arity(command)
+                }
+            }
+
+            "ZMSCORE" => {
+                if command.len() >= 3 {
+                    Command::ZMSCORE {
+                        key: command[1].clone(),
+                        members: command[2..].to_vec(),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "ZCOUNT" => {
+                if command.len() == 4 {
+                    match (parse_score_bound(&command[2]), parse_score_bound(&command[3])) {
+                        (Some(min), Some(max)) => Command::ZCOUNT {
+                            key: command[1].clone(),
+                            min,
+                            max,
+                        },
+                        _ => invalid(NOT_A_SCORE_BOUND),
                     }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "ZLEXCOUNT" => {
+                if command.len() == 4 {
+                    match (parse_lex_bound(&command[2]), parse_lex_bound(&command[3])) {
+                        (Some(min), Some(max)) => Command::ZLEXCOUNT {
+                            key: command[1].clone(),
+                            min,
+                            max,
+                        },
+                        _ => invalid(NOT_A_LEX_BOUND),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "ZINCRBY" => {
+                if command.len() == 4 {
+                    match parse_score(&command[2]) {
+                        Some(increment) => Command::ZINCRBY {
+                            key: command[1].clone(),
+                            increment,
+                            member: command[3].clone(),
+                        },
+                        None => invalid(NOT_A_FLOAT),
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "ZPOPMIN" | "ZPOPMAX" => {
+                if command.len() == 2 || command.len() == 3 {
+                    let count = match command.get(2).map(|arg| parse_arg::<i64>(arg)) {
+                        Some(Ok(count)) if count < 0 => return invalid(NOT_POSITIVE),
+                        Some(Ok(count)) => Some(count as usize),
+                        Some(Err(_)) => return invalid(NOT_AN_INTEGER),
+                        None => None,
+                    };
+                    let key = command[1].clone();
+                    if cmd == "ZPOPMIN" {
+                        Command::ZPOPMIN { key, count }
+                    } else {
+                        Command::ZPOPMAX { key, count }
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "ZREMRANGEBYRANK" | "ZREMRANGEBYSCORE" | "ZREMRANGEBYLEX" => {
+                if command.len() == 4 {
+                    let by = match cmd.as_str() {
+                        "ZREMRANGEBYRANK" => RangeBy::Rank,
+                        "ZREMRANGEBYSCORE" => RangeBy::Score,
+                        _ => RangeBy::Lex,
+                    };
+                    match parse_zrange_bounds(by, &command[2], &command[3]) {
+                        Ok(range) => Command::ZREMRANGE {
+                            key: command[1].clone(),
+                            range,
+                        },
+                        Err(error) => error,
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "ZUNION" | "ZINTER" | "ZDIFF" | "ZUNIONSTORE" | "ZINTERSTORE" | "ZDIFFSTORE" => {
+                let store = cmd.ends_with("STORE");
+                if command.len() >= if store { 4 } else { 3 } {
+                    parse_zsetop(command, &cmd, store)
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "ZINTERCARD" => {
+                if command.len() >= 3 {
+                    match parse_intercard(command) {
+                        Ok((keys, limit)) => Command::ZINTERCARD { keys, limit },
+                        Err(error) => error,
+                    }
+                } else {
+                    wrong_arity(command)
+                }
+            }
+            "ZRANDMEMBER" => {
+                if (2..=4).contains(&command.len()) {
+                    match parse_random_count(command, b"WITHSCORES") {
+                        Ok((count, with_scores)) => Command::ZRANDMEMBER {
+                            key: command[1].clone(),
+                            count,
+                            with_scores,
+                        },
+                        Err(error) => error,
+                    }
+                } else {
+                    wrong_arity(command)
                 }
             }
 
@@ -770,18 +1899,765 @@ impl Command {
                         message: command[1].clone(),
                     }
                 } else {
-                    Command::Unknown {
-                        raw: command.to_vec(),
-                    }
+                    wrong_arity(command)
                 }
             }
             "FLUSHDB" => Command::FLUSHDB,
             "DBSIZE" => Command::DBSIZE,
+            "OBJECT" if command.len() >= 2 => {
+                let subcommand = command[1].to_ascii_uppercase();
+                let name = match subcommand.as_slice() {
+                    b"ENCODING" | b"FREQ" | b"IDLETIME" => String::from_utf8_lossy(&subcommand).to_lowercase(),
+                    _ => return invalid(&unknown_subcommand(&command[1], "OBJECT")),
+                };
+                if command.len() != 3 {
+                    return invalid(&format!("ERR wrong number of arguments for 'object|{}' command", name));
+                }
+                let key = command[2].clone();
+                match subcommand.as_slice() {
+                    b"ENCODING" => Command::OBJECT_ENCODING { key },
+                    b"FREQ" => Command::OBJECT_FREQ { key },
+                    _ => Command::OBJECT_IDLETIME { key },
+                }
+            }
+            "MEMORY" if command.len() >= 2 => {
+                match command[1].to_ascii_uppercase().as_slice() {
+                    b"USAGE" if command.len() == 3 || command.len() == 5 => {
+                        let mut samples = 5;
+                        if command.len() == 5 {
+                            if !command[3].eq_ignore_ascii_case(b"SAMPLES") {
+                                return invalid(SYNTAX_ERROR);
+                            }
+                            samples = match parse_arg::<i64>(&command[4]) {
+                                Ok(samples) if samples < 0 => return invalid(SYNTAX_ERROR),
+                                Ok(samples) => samples as usize,
+                                Err(_) => return invalid(NOT_AN_INTEGER),
+                            };
+                        }
+                        Command::MEMORY_USAGE {
+                            key: command[2].clone(),
+                            samples,
+                        }
+                    }
+                    b"USAGE" if command.len() >= 3 => invalid(SYNTAX_ERROR),
+                    b"USAGE" => invalid("ERR wrong number of arguments for 'memory|usage' command"),
+                    b"STATS" if command.len() == 2 => Command::MEMORY_STATS,
+                    b"STATS" => invalid("ERR wrong number of arguments for 'memory|stats' command"),
+                    _ => invalid(&unknown_subcommand(&command[1], "MEMORY")),
+                }
+            }
+            "SAVE" if command.len() == 1 => Command::SAVE,
+            "BGSAVE" if command.len() == 1 => Command::BGSAVE,
+            "LASTSAVE" if command.len() == 1 => Command::LASTSAVE,
+            "BGREWRITEAOF" if command.len() == 1 => Command::BGREWRITEAOF,
+            "SAVE" | "BGSAVE" | "LASTSAVE" | "BGREWRITEAOF" => invalid(&format!(
+                "ERR wrong number of arguments for '{}' command",
+                String::from_utf8_lossy(&command[0]).to_lowercase()
+            )),
+            // Redis ignores any arguments to QUIT
+            "QUIT" => Command::QUIT,
+            "RESET" if command.len() == 1 => Command::RESET,
+            "RESET" => invalid("ERR wrong number of arguments for 'reset' command"),
+            "MULTI" if command.len() == 1 => Command::MULTI,
+            "EXEC" if command.len() == 1 => Command::EXEC,
+            "DISCARD" if command.len() == 1 => Command::DISCARD,
+            "UNWATCH" if command.len() == 1 => Command::UNWATCH,
+            "WATCH" if command.len() >= 2 => Command::WATCH {
+                keys: command[1..].to_vec(),
+            },
+            "MULTI" | "EXEC" | "DISCARD" | "UNWATCH" | "WATCH" => invalid(&format!(
+                "ERR wrong number of arguments for '{}' command",
+                String::from_utf8_lossy(&command[0]).to_lowercase()
+            )),
+            "SUBSCRIBE" if command.len() >= 2 => Command::SUBSCRIBE {
+                channels: command[1..].to_vec(),
+            },
+            "PSUBSCRIBE" if command.len() >= 2 => Command::PSUBSCRIBE {
+                patterns: command[1..].to_vec(),
+            },
+            "SSUBSCRIBE" if command.len() >= 2 => Command::SSUBSCRIBE {
+                channels: command[1..].to_vec(),
+            },
+            "UNSUBSCRIBE" => Command::UNSUBSCRIBE {
+                channels: command[1..].to_vec(),
+            },
+            "PUNSUBSCRIBE" => Command::PUNSUBSCRIBE {
+                patterns: command[1..].to_vec(),
+            },
+            "SUNSUBSCRIBE" => Command::SUNSUBSCRIBE {
+                channels: command[1..].to_vec(),
+            },
+            "PUBLISH" if command.len() == 3 => Command::PUBLISH {
+                channel: command[1].clone(),
+                message: command[2].clone(),
+            },
+            "SPUBLISH" if command.len() == 3 => Command::SPUBLISH {
+                channel: command[1].clone(),
+                message: command[2].clone(),
+            },
+            "SUBSCRIBE" | "PSUBSCRIBE" | "SSUBSCRIBE" | "PUBLISH" | "SPUBLISH" => invalid(&format!(
+                "ERR wrong number of arguments for '{}' command",
+                String::from_utf8_lossy(&command[0]).to_lowercase()
+            )),
+            "PUBSUB" if command.len() >= 2 => {
+                let subcommand = command[1].to_ascii_uppercase();
+                match subcommand.as_slice() {
+                    b"CHANNELS" if command.len() <= 3 => Command::PUBSUB_CHANNELS {
+                        pattern: command.get(2).cloned(),
+                    },
+                    b"SHARDCHANNELS" if command.len() <= 3 => Command::PUBSUB_SHARDCHANNELS {
+                        pattern: command.get(2).cloned(),
+                    },
+                    b"NUMSUB" => Command::PUBSUB_NUMSUB {
+                        channels: command[2..].to_vec(),
+                    },
+                    b"SHARDNUMSUB" => Command::PUBSUB_SHARDNUMSUB {
+                        channels: command[2..].to_vec(),
+                    },
+                    b"NUMPAT" if command.len() == 2 => Command::PUBSUB_NUMPAT,
+                    b"CHANNELS" | b"SHARDCHANNELS" | b"NUMPAT" => invalid(&format!(
+                        "ERR wrong number of arguments for 'pubsub|{}' command",
+                        String::from_utf8_lossy(&subcommand).to_lowercase()
+                    )),
+                    _ => invalid(&unknown_subcommand(&command[1], "PUBSUB")),
+                }
+            }
+            "PUBSUB" => invalid("ERR wrong number of arguments for 'pubsub' command"),
+            "INFO" => Command::INFO {
+                sections: command[1..].to_vec(),
+            },
+            "CONFIG" if command.len() >= 2 => {
+                match command[1].to_ascii_uppercase().as_slice() {
+                    b"GET" if command.len() >= 3 => Command::CONFIG_GET {
+                        patterns: command[2..].to_vec(),
+                    },
+                    b"SET" if command.len() >= 4 && command.len().is_multiple_of(2) => Command::CONFIG_SET {
+                        pairs: command[2..]
+                            .chunks(2)
+                            .map(|pair| (pair[0].clone(), pair[1].clone()))
+                            .collect(),
+                    },
+                    b"GET" => invalid("ERR wrong number of arguments for 'config|get' command"),
+                    b"SET" => invalid("ERR wrong number of arguments for 'config|set' command"),
+                    _ => invalid(&unknown_subcommand(&command[1], "CONFIG")),
+                }
+            }
+
+            "OBJECT" | "MEMORY" | "CONFIG" => wrong_arity(command),
 
             // --- Fallback ---
             _ => Command::Unknown {
                 raw: command.to_vec(),
+                wrong_arity: false,
             },
         }
     }
 }
+
+fn unknown_subcommand(subcommand: &[u8], cmd: &str) -> String {
+    format!(
+        "ERR unknown subcommand '{}'. Try {} HELP.",
+        String::from_utf8_lossy(subcommand),
+        cmd
+    )
+}
+
+fn wrong_arity(command: &[Vec<u8>]) -> Command {
+    Command::Unknown {
+        raw: command.to_vec(),
+        wrong_arity: true,
+    }
+}
+
+fn invalid(error: &str) -> Command {
+    Command::Invalid {
+        error: error.to_owned(),
+    }
+}
+
+// Parses the value following an EX/PX/EXAT/PXAT option. Like Redis, the time
+// must be a positive integer; the error names the command it came from.
+fn parse_expiry(unit: &[u8], arg: &[u8], command_name: &str) -> Result<Expiry, Command> {
+    let value = match parse_arg::<i64>(arg) {
+        Ok(value) => value,
+        Err(_) => return Err(invalid(NOT_AN_INTEGER)),
+    };
+    if value <= 0 {
+        return Err(invalid(&format!(
+            "ERR invalid expire time in '{}' command",
+            command_name.to_lowercase()
+        )));
+    }
+    let value = value as u64;
+    match unit.to_ascii_uppercase().as_slice() {
+        b"EX" => Ok(Expiry::Ex(value)),
+        b"PX" => Ok(Expiry::Px(value)),
+        b"EXAT" => Ok(Expiry::ExAt(value)),
+        b"PXAT" => Ok(Expiry::PxAt(value)),
+        _ => Err(invalid(SYNTAX_ERROR)),
+    }
+}
+
+// SET key value [NX | XX] [GET] [EX seconds | PX milliseconds |
+//   EXAT unix-time | PXAT unix-time-ms | KEEPTTL]
+// Options may come in any order; conflicting ones are a syntax error.
+fn parse_set(command: &[Vec<u8>]) -> Command {
+    let mut expiry = None;
+    let mut condition = None;
+    let mut get = false;
+    let mut keep_ttl = false;
+    let mut i = 3;
+    while i < command.len() {
+        let option = command[i].to_ascii_uppercase();
+        let ttl_taken = expiry.is_some() || keep_ttl;
+        match option.as_slice() {
+            b"NX" if condition != Some(SetCondition::XX) => condition = Some(SetCondition::NX),
+            b"XX" if condition != Some(SetCondition::NX) => condition = Some(SetCondition::XX),
+            b"GET" => get = true,
+            b"KEEPTTL" if !ttl_taken => keep_ttl = true,
+            b"EX" | b"PX" | b"EXAT" | b"PXAT" if !ttl_taken && i + 1 < command.len() => {
+                match parse_expiry(&option, &command[i + 1], "set") {
+                    Ok(parsed) => expiry = Some(parsed),
+                    Err(error) => return error,
+                }
+                i += 1;
+            }
+            _ => return invalid(SYNTAX_ERROR),
+        }
+        i += 1;
+    }
+    Command::SET {
+        key: command[1].clone(),
+        value: command[2].clone(),
+        expiry,
+        condition,
+        get,
+        keep_ttl,
+    }
+}
+
+// COPY source destination [DB destination-db] [REPLACE]
+// There is a single database, so DB is only accepted when it names it.
+fn parse_copy(command: &[Vec<u8>]) -> Command {
+    let mut replace = false;
+    let mut i = 3;
+    while i < command.len() {
+        let option = command[i].to_ascii_uppercase();
+        match option.as_slice() {
+            b"REPLACE" => replace = true,
+            b"DB" if i + 1 < command.len() => {
+                match parse_arg::<i64>(&command[i + 1]) {
+                    Ok(0) => {}
+                    Ok(_) => return invalid("ERR DB index is out of range"),
+                    Err(_) => return invalid(NOT_AN_INTEGER),
+                }
+                i += 1;
+            }
+            _ => return invalid(SYNTAX_ERROR),
+        }
+        i += 1;
+    }
+    Command::COPY {
+        source: command[1].clone(),
+        destination: command[2].clone(),
+        replace,
+    }
+}
+
+// <command> [key] cursor [MATCH pattern] [COUNT count] [TYPE type] [NOVALUES]
+// `cursor_at` is the index of the cursor argument.
+fn parse_scan(command: &[Vec<u8>], cursor_at: usize, command_name: &str) -> Result<(u64, ScanOptions), Command> {
+    let cursor = parse_arg::<u64>(&command[cursor_at]).map_err(|_| invalid("ERR invalid cursor"))?;
+    let mut options = ScanOptions::default();
+    let mut i = cursor_at + 1;
+    while i < command.len() {
+        let option = command[i].to_ascii_uppercase();
+        let has_arg = i + 1 < command.len();
+        match option.as_slice() {
+            b"MATCH" if has_arg => {
+                options.pattern = Some(command[i + 1].clone());
+                i += 1;
+            }
+            b"COUNT" if has_arg => {
+                match parse_arg::<i64>(&command[i + 1]) {
+                    Ok(count) if count >= 1 => options.count = count as usize,
+                    Ok(_) => return Err(invalid(SYNTAX_ERROR)),
+                    Err(_) => return Err(invalid(NOT_AN_INTEGER)),
+                }
+                i += 1;
+            }
+            b"TYPE" if has_arg && command_name == "SCAN" => {
+                options.type_name = Some(command[i + 1].to_ascii_lowercase());
+                i += 1;
+            }
+            b"NOVALUES" if command_name == "HSCAN" => options.no_values = true,
+            _ => return Err(invalid(SYNTAX_ERROR)),
+        }
+        i += 1;
+    }
+    Ok((cursor, options))
+}
+
+// HRANDFIELD key [count [WITHVALUES]]
+fn parse_hrandfield(command: &[Vec<u8>]) -> Command {
+    match parse_random_count(command, b"WITHVALUES") {
+        Ok((count, with_values)) => Command::HRANDFIELD {
+            key: command[1].clone(),
+            count,
+            with_values,
+        },
+        Err(error) => error,
+    }
+}
+
+// The `[count [WITHVALUES|WITHSCORES]]` tail of HRANDFIELD and ZRANDMEMBER.
+fn parse_random_count(command: &[Vec<u8>], with_flag: &[u8]) -> Result<(Option<i64>, bool), Command> {
+    let count = match command.get(2) {
+        Some(arg) => match parse_arg::<i64>(arg) {
+            Ok(count) => Some(count),
+            Err(_) => return Err(invalid(NOT_AN_INTEGER)),
+        },
+        None => None,
+    };
+    let with_pairs = match command.get(3) {
+        Some(option) if option.eq_ignore_ascii_case(with_flag) => true,
+        Some(_) => return Err(invalid(SYNTAX_ERROR)),
+        None => false,
+    };
+    // Pairs are twice as many reply elements, as checked by Redis
+    if with_pairs && count.is_some_and(|count| count < -(i64::MAX / 2)) {
+        return Err(invalid("ERR value is out of range"));
+    }
+    Ok((count, with_pairs))
+}
+
+// ZADD key [NX|XX] [GT|LT] [CH] [INCR] score member [score member ...]
+fn parse_zadd(command: &[Vec<u8>]) -> Command {
+    let mut options = ZAddOptions::default();
+    let mut nx = false;
+    let mut xx = false;
+    let mut i = 2;
+    while i < command.len() {
+        match command[i].to_ascii_uppercase().as_slice() {
+            b"NX" => nx = true,
+            b"XX" => xx = true,
+            b"GT" => options.gt = true,
+            b"LT" => options.lt = true,
+            b"CH" => options.ch = true,
+            b"INCR" => options.incr = true,
+            _ => break,
+        }
+        i += 1;
+    }
+
+    let pairs = &command[i..];
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return invalid(SYNTAX_ERROR);
+    }
+    if nx && xx {
+        return invalid("ERR XX and NX options at the same time are not compatible");
+    }
+    if (nx && (options.gt || options.lt)) || (options.gt && options.lt) {
+        return invalid("ERR GT, LT, and/or NX options at the same time are not compatible");
+    }
+    if options.incr && pairs.len() > 2 {
+        return invalid("ERR INCR option supports a single increment-element pair");
+    }
+    options.condition = match (nx, xx) {
+        (true, _) => Some(SetCondition::NX),
+        (_, true) => Some(SetCondition::XX),
+        _ => None,
+    };
+
+    let mut entries = Vec::with_capacity(pairs.len() / 2);
+    for chunk in pairs.chunks(2) {
+        match parse_score(&chunk[0]) {
+            Some(score) => entries.push((score, chunk[1].clone())),
+            None => return invalid(NOT_A_FLOAT),
+        }
+    }
+    Command::ZADD {
+        key: command[1].clone(),
+        entries,
+        options,
+    }
+}
+
+// ZUNION|ZINTER|ZDIFF numkeys key [key ...] [WEIGHTS weight ...]
+//     [AGGREGATE SUM|MIN|MAX] [WITHSCORES]
+// The STORE forms take a destination first and have no WITHSCORES; ZDIFF has
+// neither WEIGHTS nor AGGREGATE.
+fn parse_zsetop(command: &[Vec<u8>], cmd: &str, store: bool) -> Command {
+    let numkeys_at = if store { 2 } else { 1 };
+    let is_diff = cmd.starts_with("ZDIFF");
+    let numkeys = match parse_arg::<i64>(&command[numkeys_at]) {
+        Ok(numkeys) if numkeys > 0 => numkeys as usize,
+        Ok(_) => {
+            return invalid(&format!(
+                "ERR at least 1 input key is needed for '{}' command",
+                cmd.to_ascii_lowercase()
+            ))
+        }
+        Err(_) => return invalid(NOT_AN_INTEGER),
+    };
+    let keys_end = match (numkeys_at + 1).checked_add(numkeys) {
+        Some(keys_end) if keys_end <= command.len() => keys_end,
+        _ => return invalid(SYNTAX_ERROR),
+    };
+    let keys = command[numkeys_at + 1..keys_end].to_vec();
+
+    let mut weights = None;
+    let mut aggregate = Aggregate::Sum;
+    let mut with_scores = false;
+    let mut i = keys_end;
+    while i < command.len() {
+        let remaining = command.len() - i - 1;
+        match command[i].to_ascii_uppercase().as_slice() {
+            b"WEIGHTS" if !is_diff && remaining >= numkeys => {
+                let mut parsed = Vec::with_capacity(numkeys);
+                for arg in &command[i + 1..i + 1 + numkeys] {
+                    match parse_score(arg) {
+                        Some(weight) => parsed.push(weight),
+                        None => return invalid("ERR weight value is not a float"),
+                    }
+                }
+                weights = Some(parsed);
+                i += numkeys;
+            }
+            b"AGGREGATE" if !is_diff && remaining >= 1 => {
+                aggregate = match command[i + 1].to_ascii_uppercase().as_slice() {
+                    b"SUM" => Aggregate::Sum,
+                    b"MIN" => Aggregate::Min,
+                    b"MAX" => Aggregate::Max,
+                    _ => return invalid(SYNTAX_ERROR),
+                };
+                i += 1;
+            }
+            b"WITHSCORES" if !store => with_scores = true,
+            _ => return invalid(SYNTAX_ERROR),
+        }
+        i += 1;
+    }
+
+    let destination = store.then(|| command[1].clone());
+    if is_diff {
+        Command::ZDIFF {
+            destination,
+            keys,
+            with_scores,
+        }
+    } else if cmd.starts_with("ZUNION") {
+        Command::ZUNION {
+            destination,
+            keys,
+            weights,
+            aggregate,
+            with_scores,
+        }
+    } else {
+        Command::ZINTER {
+            destination,
+            keys,
+            weights,
+            aggregate,
+            with_scores,
+        }
+    }
+}
+
+// ZINTERCARD and SINTERCARD: numkeys key [key ...] [LIMIT limit]. A limit of
+// 0 means no limit.
+fn parse_intercard(command: &[Vec<u8>]) -> Result<(Vec<Vec<u8>>, usize), Command> {
+    let numkeys = match parse_arg::<i64>(&command[1]) {
+        Ok(numkeys) if numkeys > 0 => numkeys as usize,
+        Ok(_) => return Err(invalid("ERR numkeys should be greater than 0")),
+        Err(_) => return Err(invalid(NOT_AN_INTEGER)),
+    };
+    let keys_end = match numkeys.checked_add(2) {
+        Some(keys_end) if keys_end <= command.len() => keys_end,
+        _ => return Err(invalid(SYNTAX_ERROR)),
+    };
+    let limit = match &command[keys_end..] {
+        [] => 0,
+        [option, limit] if option.eq_ignore_ascii_case(b"LIMIT") => match parse_arg::<i64>(limit) {
+            Ok(limit) if limit < 0 => return Err(invalid("ERR LIMIT can't be negative")),
+            Ok(limit) => limit as usize,
+            Err(_) => return Err(invalid(NOT_AN_INTEGER)),
+        },
+        _ => return Err(invalid(SYNTAX_ERROR)),
+    };
+    Ok((command[2..keys_end].to_vec(), limit))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RangeBy {
+    Rank,
+    Score,
+    Lex,
+}
+
+// ZRANGE key start stop [BYSCORE|BYLEX] [REV] [LIMIT offset count] [WITHSCORES]
+fn parse_zrange(command: &[Vec<u8>]) -> Command {
+    let mut by = RangeBy::Rank;
+    let mut rev = false;
+    let mut limit = None;
+    let mut with_scores = false;
+    let mut i = 4;
+    while i < command.len() {
+        match command[i].to_ascii_uppercase().as_slice() {
+            b"BYSCORE" => by = RangeBy::Score,
+            b"BYLEX" => by = RangeBy::Lex,
+            b"REV" => rev = true,
+            b"WITHSCORES" => with_scores = true,
+            b"LIMIT" if i + 2 < command.len() => {
+                match (parse_arg::<i64>(&command[i + 1]), parse_arg::<i64>(&command[i + 2])) {
+                    (Ok(offset), Ok(count)) => limit = Some((offset, count)),
+                    _ => return invalid(NOT_AN_INTEGER),
+                }
+                i += 2;
+            }
+            _ => return invalid(SYNTAX_ERROR),
+        }
+        i += 1;
+    }
+    if limit.is_some() && by == RangeBy::Rank {
+        return invalid("ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX");
+    }
+    if with_scores && by == RangeBy::Lex {
+        return invalid("ERR syntax error, WITHSCORES not supported in combination with BYLEX");
+    }
+
+    // With REV, score and lex ranges are given max first
+    let (start, stop) = if rev && by != RangeBy::Rank {
+        (&command[3], &command[2])
+    } else {
+        (&command[2], &command[3])
+    };
+    match parse_zrange_bounds(by, start, stop) {
+        Ok(range) => Command::ZRANGE {
+            key: command[1].clone(),
+            range,
+            rev,
+            limit,
+            with_scores,
+        },
+        Err(error) => error,
+    }
+}
+
+fn parse_zrange_bounds(by: RangeBy, start: &[u8], stop: &[u8]) -> Result<ZRange, Command> {
+    match by {
+        RangeBy::Rank => match (parse_arg::<isize>(start), parse_arg::<isize>(stop)) {
+            (Ok(start), Ok(stop)) => Ok(ZRange::Rank(start, stop)),
+            _ => Err(invalid(NOT_AN_INTEGER)),
+        },
+        RangeBy::Score => match (parse_score_bound(start), parse_score_bound(stop)) {
+            (Some(min), Some(max)) => Ok(ZRange::Score(min, max)),
+            _ => Err(invalid(NOT_A_SCORE_BOUND)),
+        },
+        RangeBy::Lex => match (parse_lex_bound(start), parse_lex_bound(stop)) {
+            (Some(min), Some(max)) => Ok(ZRange::Lex(min, max)),
+            _ => Err(invalid(NOT_A_LEX_BOUND)),
+        },
+    }
+}
+
+// `1.5`, `(1.5`, `-inf` or `+inf`.
+fn parse_score_bound(arg: &[u8]) -> Option<ScoreBound> {
+    match arg.strip_prefix(b"(") {
+        Some(score) => parse_score(score).map(|score| ScoreBound { score, exclusive: true }),
+        None => parse_score(arg).map(|score| ScoreBound { score, exclusive: false }),
+    }
+}
+
+// `[member`, `(member`, `-` or `+`.
+fn parse_lex_bound(arg: &[u8]) -> Option<LexBound> {
+    match arg {
+        b"-" => Some(LexBound::Min),
+        b"+" => Some(LexBound::Max),
+        [b'[', member @ ..] => Some(LexBound::Inclusive(member.to_vec())),
+        [b'(', member @ ..] => Some(LexBound::Exclusive(member.to_vec())),
+        _ => None,
+    }
+}
+
+// LPOS key element [RANK rank] [COUNT num-matches] [MAXLEN len]
+fn parse_lpos(command: &[Vec<u8>]) -> Command {
+    let mut rank = 1;
+    let mut count = None;
+    let mut maxlen = 0;
+    let mut i = 3;
+    while i < command.len() {
+        if i + 1 >= command.len() {
+            return invalid(SYNTAX_ERROR);
+        }
+        let value = match parse_arg::<i64>(&command[i + 1]) {
+            Ok(value) => value,
+            Err(_) => return invalid(NOT_AN_INTEGER),
+        };
+        match command[i].to_ascii_uppercase().as_slice() {
+            b"RANK" if value == 0 => {
+                return invalid(
+                    "ERR RANK can't be zero: use 1 to start from the first match, 2 from the second ... or use negative to start from the last match",
+                )
+            }
+            // -i64::MIN does not fit, and no list is that long anyway
+            b"RANK" if value == i64::MIN => return invalid("ERR value is out of range"),
+            b"RANK" => rank = value,
+            b"COUNT" if value < 0 => return invalid("ERR COUNT can't be negative"),
+            b"COUNT" => count = Some(value as usize),
+            b"MAXLEN" if value < 0 => return invalid("ERR MAXLEN can't be negative"),
+            b"MAXLEN" => maxlen = value as usize,
+            _ => return invalid(SYNTAX_ERROR),
+        }
+        i += 2;
+    }
+    Command::LPOS {
+        key: command[1].clone(),
+        value: command[2].clone(),
+        rank,
+        count,
+        maxlen,
+    }
+}
+
+// A sorted set score: any float including inf and -inf, but never NaN.
+fn parse_score(arg: &[u8]) -> Option<f64> {
+    parse_arg::<f64>(arg).ok().filter(|score| !score.is_nan())
+}
+
+// Timeout of the blocking commands: seconds, fractions allowed, 0 for forever.
+fn parse_timeout(arg: &[u8]) -> Result<u64, Command> {
+    let seconds = match parse_arg::<f64>(arg) {
+        Ok(seconds) if seconds.is_finite() => seconds,
+        _ => return Err(invalid("ERR timeout is not a float or out of range")),
+    };
+    if seconds < 0.0 {
+        return Err(invalid("ERR timeout is negative"));
+    }
+    let millis = (seconds * 1000.0).ceil();
+    if millis > i64::MAX as f64 {
+        return Err(invalid("ERR timeout is out of range"));
+    }
+    Ok(millis as u64)
+}
+
+// The tail shared by LMPOP and ZMPOP (and their blocking forms):
+//   numkeys key [key ...] <where> [COUNT count]
+// `numkeys_at` is the index of numkeys; `parse_where` reads LEFT/RIGHT or MIN/MAX.
+fn parse_mpop<T>(
+    command: &[Vec<u8>],
+    numkeys_at: usize,
+    parse_where: impl Fn(&[u8]) -> Option<T>,
+) -> Result<(Vec<Vec<u8>>, T, usize), Command> {
+    let numkeys = match parse_arg::<i64>(&command[numkeys_at]) {
+        Ok(numkeys) if numkeys > 0 => numkeys as usize,
+        Ok(_) => return Err(invalid("ERR numkeys should be greater than 0")),
+        Err(_) => return Err(invalid(NOT_AN_INTEGER)),
+    };
+    let keys_end = match (numkeys_at + 1).checked_add(numkeys) {
+        Some(keys_end) if keys_end < command.len() => keys_end,
+        _ => return Err(invalid(SYNTAX_ERROR)),
+    };
+    let keys = command[numkeys_at + 1..keys_end].to_vec();
+    let end = parse_where(&command[keys_end]).ok_or_else(|| invalid(SYNTAX_ERROR))?;
+
+    let mut count = 1;
+    match &command[keys_end + 1..] {
+        [] => {}
+        [option, value] if option.eq_ignore_ascii_case(b"COUNT") => match parse_arg::<i64>(value) {
+            Ok(value) if value > 0 => count = value as usize,
+            Ok(_) => return Err(invalid("ERR count should be greater than 0")),
+            Err(_) => return Err(invalid(NOT_AN_INTEGER)),
+        },
+        _ => return Err(invalid(SYNTAX_ERROR)),
+    }
+    Ok((keys, end, count))
+}
+
+// RESTORE key ttl payload [REPLACE] [ABSTTL] [IDLETIME seconds] [FREQ frequency]
+fn parse_restore(command: &[Vec<u8>]) -> Command {
+    let ttl_ms = match parse_arg::<i64>(&command[2]) {
+        Ok(ttl_ms) if ttl_ms < 0 => return invalid("ERR Invalid TTL value, must be >= 0"),
+        Ok(ttl_ms) => ttl_ms as u64,
+        Err(_) => return invalid(NOT_AN_INTEGER),
+    };
+    let mut replace = false;
+    let mut absttl = false;
+    let mut idletime = None;
+    let mut freq = None;
+    let mut i = 4;
+    while i < command.len() {
+        let option = command[i].to_ascii_uppercase();
+        match option.as_slice() {
+            b"REPLACE" => replace = true,
+            b"ABSTTL" => absttl = true,
+            // Only one of them applies, depending on the eviction policy
+            b"IDLETIME" if i + 1 < command.len() && freq.is_none() => {
+                idletime = match parse_arg::<i64>(&command[i + 1]) {
+                    Ok(seconds) if seconds >= 0 => Some(seconds as u64),
+                    Ok(_) => return invalid("ERR Invalid IDLETIME value, must be >= 0"),
+                    Err(_) => return invalid(NOT_AN_INTEGER),
+                };
+                i += 1;
+            }
+            b"FREQ" if i + 1 < command.len() && idletime.is_none() => {
+                freq = match parse_arg::<i64>(&command[i + 1]) {
+                    Ok(frequency) if (0..=255).contains(&frequency) => Some(frequency as u8),
+                    Ok(_) => return invalid("ERR Invalid FREQ value, must be >= 0 and <= 255"),
+                    Err(_) => return invalid(NOT_AN_INTEGER),
+                };
+                i += 1;
+            }
+            _ => return invalid(SYNTAX_ERROR),
+        }
+        i += 1;
+    }
+    Command::RESTORE {
+        key: command[1].clone(),
+        ttl_ms,
+        payload: command[3].clone(),
+        replace,
+        absttl,
+        idletime,
+        freq,
+    }
+}
+
+// GETEX key [EX seconds | PX milliseconds | EXAT unix-time | PXAT unix-time-ms | PERSIST]
+fn parse_getex(command: &[Vec<u8>]) -> Command {
+    let mut expiry = None;
+    let mut persist = false;
+    let mut i = 2;
+    while i < command.len() {
+        let option = command[i].to_ascii_uppercase();
+        let exclusive_taken = expiry.is_some() || persist;
+        match option.as_slice() {
+            b"PERSIST" if !exclusive_taken => {
+                persist = true;
+                i += 1;
+            }
+            b"EX" | b"PX" | b"EXAT" | b"PXAT" if !exclusive_taken && i + 1 < command.len() => {
+                match parse_expiry(&option, &command[i + 1], "getex") {
+                    Ok(parsed) => expiry = Some(parsed),
+                    Err(error) => return error,
+                }
+                i += 2;
+            }
+            _ => return invalid(SYNTAX_ERROR),
+        }
+    }
+    Command::GETEX {
+        key: command[1].clone(),
+        expiry,
+        persist,
+    }
+}
+
+// Arguments are raw bytes; numeric ones must also be valid UTF-8 digits.
+fn parse_arg<T: FromStr>(arg: &[u8]) -> Result<T, ()> {
+    std::str::from_utf8(arg)
+        .map_err(|_| ())?
+        .parse::<T>()
+        .map_err(|_| ())
+}
@@ -1,20 +1,50 @@
-use crate::command::executor::{del, dbsize, echo, exists, expire, flushdb, get, ping, set, incr, decr, hset, hget, lpush, sadd, zadd};
+use crate::command::executor::{
+    append, copy, dbsize, dump, decr, decrby, del, echo, exists, expire, flushdb, get, getdel, getex,
+    getrange, getset, hget, hset, incr, incrby, incrbyfloat, key_type, keys, lpush, mget, mset,
+    msetnx, persist, ping, pttl, randomkey, rename, renamenx, sadd, scan, set, setex, setnx,
+    setrange, strlen, touch, ttl, unlink, zadd, hscan, sscan, zscan, hgetall, hdel, hexists, hlen,
+    hkeys, hvals, hmget, hsetnx, hincrby, hincrbyfloat, hstrlen, hrandfield, rpush, lpushx, rpushx,
+    lpop, rpop, lrange, llen, lindex, lset, linsert, lrem, ltrim, lpos, lmove, lmpop,
+    blpop, brpop, blmove, blmpop, zrem, zrange, zcard, zrank, zrevrank, zscore, zmscore, zcount,
+    zlexcount, zincrby, zpopmin, zpopmax, zremrange, zrandmember,
+    zunion, zinter, zdiff, zintercard, srem, smembers, sismember, scard, smismember, smove, spop,
+    srandmember, sunion, sinter, sdiff, sintercard, object, config, info, memory, save,
+    publish, pubsub,
+};
+use crate::persistence::aof;
 use crate::traits::command::commandExecutor;
 use crate::{command::command_enum::Command, store_containers::core_context::context};
 use std::error::Error;
 pub struct command_executor {}
 
 impl command_executor {
+    // Runs a command a client sent as `args` and logs it to the AOF if it
+    // changed the dataset. Everything but AOF replay goes through here.
+    pub fn call(command: &Command, args: &[Vec<u8>], context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        let dirty_before = context.DataBase.dirty;
+        let result = Self::execute_command(command, context);
+        aof::propagate(context, command, args, dirty_before);
+        result
+    }
+
     pub fn execute_command(
         command: &Command,
         context: &mut context,
     ) -> Result<Vec<u8>, Box<dyn Error>> {
-        match command {
-            Command::SET {
-                key: _key,
-                value: _value,
-                ttl: _ttl,
-            } => set::set::execute(command, context),
+        // Make room before running anything; only writes that could grow the
+        // dataset are refused when that fails
+        if !context.perform_evictions() && command.grows_memory() {
+            return Ok(b"-OOM command not allowed when used memory > 'maxmemory'.\r\n".to_vec());
+        }
+        Self::dispatch(command, context)
+    }
+
+    // Runs the command without making room first. For replaying the AOF,
+    // which must rebuild the keyspace that was logged whatever maxmemory is
+    // now: like Redis, nothing is evicted or refused while loading.
+    pub fn dispatch(command: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        let result = match command {
+            Command::SET { .. } => set::set::execute(command, context),
             Command::GET { key: _key } => get::get::execute(command, context),
             Command::DEL { keys: _keys } => del::del::execute(command, context),
             Command::EXISTS { keys: _keys } => exists::exists::execute(command, context),
@@ -22,8 +52,40 @@ impl command_executor {
                 key: _key,
                 seconds: _seconds,
             } => expire::expire::execute(command, context),
+            Command::PEXPIREAT { .. } => expire::expire::execute(command, context),
+            Command::TTL { key: _key } => ttl::ttl::execute(command, context),
+            Command::PERSIST { key: _key } => persist::persist::execute(command, context),
+            Command::PTTL { .. } => pttl::pttl::execute(command, context),
+            Command::KEYS { .. } => keys::keys::execute(command, context),
+            Command::TYPE { .. } => key_type::key_type::execute(command, context),
+            Command::RENAME { .. } => rename::rename::execute(command, context),
+            Command::RENAMENX { .. } => renamenx::renamenx::execute(command, context),
+            Command::COPY { .. } => copy::copy::execute(command, context),
+            Command::DUMP { .. } | Command::RESTORE { .. } => dump::dump::execute(command, context),
+            Command::TOUCH { .. } => touch::touch::execute(command, context),
+            Command::UNLINK { .. } => unlink::unlink::execute(command, context),
+            Command::RANDOMKEY => randomkey::randomkey::execute(command, context),
+            Command::SCAN { .. } => scan::scan::execute(command, context),
+            Command::HSCAN { .. } => hscan::hscan::execute(command, context),
+            Command::SSCAN { .. } => sscan::sscan::execute(command, context),
+            Command::ZSCAN { .. } => zscan::zscan::execute(command, context),
             Command::INCR { key: _key } => incr::incr::execute(command, context),
             Command::DECR { key: _key } => decr::decr::execute(command, context),
+            Command::INCRBY { .. } => incrby::incrby::execute(command, context),
+            Command::DECRBY { .. } => decrby::decrby::execute(command, context),
+            Command::INCRBYFLOAT { .. } => incrbyfloat::incrbyfloat::execute(command, context),
+            Command::APPEND { .. } => append::append::execute(command, context),
+            Command::STRLEN { .. } => strlen::strlen::execute(command, context),
+            Command::MGET { .. } => mget::mget::execute(command, context),
+            Command::MSET { .. } => mset::mset::execute(command, context),
+            Command::MSETNX { .. } => msetnx::msetnx::execute(command, context),
+            Command::GETRANGE { .. } => getrange::getrange::execute(command, context),
+            Command::SETRANGE { .. } => setrange::setrange::execute(command, context),
+            Command::GETSET { .. } => getset::getset::execute(command, context),
+            Command::GETDEL { .. } => getdel::getdel::execute(command, context),
+            Command::GETEX { .. } => getex::getex::execute(command, context),
+            Command::SETNX { .. } => setnx::setnx::execute(command, context),
+            Command::SETEX { .. } => setex::setex::execute(command, context),
             Command::FLUSHDB => flushdb::flushdb::execute(command, context),
             Command::DBSIZE => dbsize::dbsize::execute(command, context),
             Command::ECHO { message: _message } => echo::echo::execute(command, context),
@@ -32,17 +94,103 @@ impl command_executor {
             // Hash commands
             Command::HSET { key: _key, fields: _fields } => hset::hset::execute(command, context),
             Command::HGET { key: _key, field: _field } => hget::hget::execute(command, context),
+            Command::HGETALL { .. } => hgetall::hgetall::execute(command, context),
+            Command::HDEL { .. } => hdel::hdel::execute(command, context),
+            Command::HEXISTS { .. } => hexists::hexists::execute(command, context),
+            Command::HLEN { .. } => hlen::hlen::execute(command, context),
+            Command::HKEYS { .. } => hkeys::hkeys::execute(command, context),
+            Command::HVALS { .. } => hvals::hvals::execute(command, context),
+            Command::HMGET { .. } => hmget::hmget::execute(command, context),
+            Command::HSETNX { .. } => hsetnx::hsetnx::execute(command, context),
+            Command::HINCRBY { .. } => hincrby::hincrby::execute(command, context),
+            Command::HINCRBYFLOAT { .. } => hincrbyfloat::hincrbyfloat::execute(command, context),
+            Command::HSTRLEN { .. } => hstrlen::hstrlen::execute(command, context),
+            Command::HRANDFIELD { .. } => hrandfield::hrandfield::execute(command, context),
             
             // List commands
             Command::LPUSH { key: _key, values: _values } => lpush::lpush::execute(command, context),
+            Command::RPUSH { .. } => rpush::rpush::execute(command, context),
+            Command::LPUSHX { .. } => lpushx::lpushx::execute(command, context),
+            Command::RPUSHX { .. } => rpushx::rpushx::execute(command, context),
+            Command::LPOP { .. } => lpop::lpop::execute(command, context),
+            Command::RPOP { .. } => rpop::rpop::execute(command, context),
+            Command::LRANGE { .. } => lrange::lrange::execute(command, context),
+            Command::LLEN { .. } => llen::llen::execute(command, context),
+            Command::LINDEX { .. } => lindex::lindex::execute(command, context),
+            Command::LSET { .. } => lset::lset::execute(command, context),
+            Command::LINSERT { .. } => linsert::linsert::execute(command, context),
+            Command::LREM { .. } => lrem::lrem::execute(command, context),
+            Command::LTRIM { .. } => ltrim::ltrim::execute(command, context),
+            Command::LPOS { .. } => lpos::lpos::execute(command, context),
+            Command::LMOVE { .. } => lmove::lmove::execute(command, context),
+            Command::LMPOP { .. } => lmpop::lmpop::execute(command, context),
+            Command::BLPOP { .. } => blpop::blpop::execute(command, context),
+            Command::BRPOP { .. } => brpop::brpop::execute(command, context),
+            Command::BLMOVE { .. } => blmove::blmove::execute(command, context),
+            Command::BLMPOP { .. } => blmpop::blmpop::execute(command, context),
             
             // Set commands
             Command::SADD { key: _key, members: _members } => sadd::sadd::execute(command, context),
+            Command::SREM { .. } => srem::srem::execute(command, context),
+            Command::SMEMBERS { .. } => smembers::smembers::execute(command, context),
+            Command::SISMEMBER { .. } => sismember::sismember::execute(command, context),
+            Command::SCARD { .. } => scard::scard::execute(command, context),
+            Command::SMISMEMBER { .. } => smismember::smismember::execute(command, context),
+            Command::SMOVE { .. } => smove::smove::execute(command, context),
+            Command::SPOP { .. } => spop::spop::execute(command, context),
+            Command::SRANDMEMBER { .. } => srandmember::srandmember::execute(command, context),
+            Command::SUNION { .. } => sunion::sunion::execute(command, context),
+            Command::SINTER { .. } => sinter::sinter::execute(command, context),
+            Command::SDIFF { .. } => sdiff::sdiff::execute(command, context),
+            Command::SINTERCARD { .. } => sintercard::sintercard::execute(command, context),
             
             // Sorted Set commands
-            Command::ZADD { key: _key, entries: _entries } => zadd::zadd::execute(command, context),
+            Command::ZADD { .. } => zadd::zadd::execute(command, context),
+            Command::ZREM { .. } => zrem::zrem::execute(command, context),
+            Command::ZRANGE { .. } => zrange::zrange::execute(command, context),
+            Command::ZCARD { .. } => zcard::zcard::execute(command, context),
+            Command::ZRANK { .. } => zrank::zrank::execute(command, context),
+            Command::ZREVRANK { .. } => zrevrank::zrevrank::execute(command, context),
+            Command::ZSCORE { .. } => zscore::zscore::execute(command, context),
+            Command::ZMSCORE { .. } => zmscore::zmscore::execute(command, context),
+            Command::ZCOUNT { .. } => zcount::zcount::execute(command, context),
+            Command::ZLEXCOUNT { .. } => zlexcount::zlexcount::execute(command, context),
+            Command::ZINCRBY { .. } => zincrby::zincrby::execute(command, context),
+            Command::ZPOPMIN { .. } => zpopmin::zpopmin::execute(command, context),
+            Command::ZPOPMAX { .. } => zpopmax::zpopmax::execute(command, context),
+            Command::ZREMRANGE { .. } => zremrange::zremrange::execute(command, context),
+            Command::ZRANDMEMBER { .. } => zrandmember::zrandmember::execute(command, context),
+            Command::ZUNION { .. } => zunion::zunion::execute(command, context),
+            Command::ZINTER { .. } => zinter::zinter::execute(command, context),
+            Command::ZDIFF { .. } => zdiff::zdiff::execute(command, context),
+            Command::ZINTERCARD { .. } => zintercard::zintercard::execute(command, context),
+            Command::OBJECT_ENCODING { .. } | Command::OBJECT_FREQ { .. } | Command::OBJECT_IDLETIME { .. } => {
+                object::object::execute(command, context)
+            }
+            Command::CONFIG_GET { .. } | Command::CONFIG_SET { .. } => config::config::execute(command, context),
+            Command::INFO { .. } => info::info::execute(command, context),
+            Command::MEMORY_USAGE { .. } | Command::MEMORY_STATS => memory::memory::execute(command, context),
+            Command::SAVE | Command::BGSAVE | Command::LASTSAVE | Command::BGREWRITEAOF => save::save::execute(command, context),
+            Command::PUBLISH { .. } | Command::SPUBLISH { .. } => publish::publish::execute(command, context),
+            Command::PUBSUB_CHANNELS { .. }
+            | Command::PUBSUB_SHARDCHANNELS { .. }
+            | Command::PUBSUB_NUMSUB { .. }
+            | Command::PUBSUB_SHARDNUMSUB { .. }
+            | Command::PUBSUB_NUMPAT => pubsub::pubsub::execute(command, context),
             
+            // Only meaningful on a connection, which handles them itself
+            Command::QUIT | Command::RESET => Err("ERR connection commands are handled by the connection".into()),
+            Command::MULTI | Command::EXEC | Command::DISCARD | Command::WATCH { .. } | Command::UNWATCH => {
+                Err("ERR transactions are handled by the connection".into())
+            }
+            _ if command.is_subscription() => Err("ERR subscriptions are handled by the connection".into()),
+
+            Command::Invalid { error } => Ok(format!("-{}\r\n", error).into_bytes()),
+
             _ => Ok(b"$-1\r\n".to_vec()),
-        }
+        };
+        context.commit_changes();
+        // Errors raised by an executor (e.g. WRONGTYPE) are replies, not failures
+        result.or_else(|e| Ok(format!("-{}\r\n", e).into_bytes()))
     }
 }
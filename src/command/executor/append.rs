@@ -0,0 +1,26 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::string_store::{StringStore, MAX_STRING_SIZE};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct append;
+
+impl commandExecutor for append {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::APPEND { key, value } => {
+                let string_store = context.get_or_create::<StringStore>(key)?;
+                if string_store.len() + value.len() > MAX_STRING_SIZE {
+                    return Ok(b"-ERR string exceeds maximum allowed size (proto-max-bulk-len)\r\n".to_vec());
+                }
+                string_store.get_value_mut().extend_from_slice(value);
+                let length = string_store.len();
+                context.DataBase.mark_changed(key);
+                Ok(integer(length as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::lmove::move_element;
+use crate::resp::reply::{bulk_string, NIL};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct blmove;
+
+impl commandExecutor for blmove {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::BLMOVE {
+                source,
+                destination,
+                from,
+                to,
+                ..
+            } => match move_element(context, source, destination, *from, *to)? {
+                Some(element) => Ok(bulk_string(&element)),
+                None => Ok(NIL.to_vec()),
+            },
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
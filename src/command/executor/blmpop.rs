@@ -0,0 +1,16 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::lmpop::mpop;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct blmpop;
+
+impl commandExecutor for blmpop {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::BLMPOP { keys, end, count, .. } => mpop(context, keys, *end, *count),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::command::command_enum::{Command, ListEnd};
+use crate::command::executor::lpop::pop_elements;
+use crate::resp::reply::{array, bulk_string, NIL_ARRAY};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct blpop;
+
+// Like the other blocking executors this only makes the non-blocking attempt.
+// A nil reply tells the connection to park the client (see `server::blocking`).
+impl commandExecutor for blpop {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::BLPOP { keys, .. } => blocking_pop(context, keys, ListEnd::Left),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// Pops one element from the first non-empty list: [key, element]. Shared with BRPOP.
+pub fn blocking_pop(context: &mut context, keys: &[Vec<u8>], end: ListEnd) -> Result<Vec<u8>, Box<dyn Error>> {
+    for key in keys {
+        if let Some(elements) = pop_elements(context, key, end, 1)? {
+            if let Some(element) = elements.first() {
+                return Ok(array(vec![bulk_string(key), bulk_string(element)]));
+            }
+        }
+    }
+    Ok(NIL_ARRAY.to_vec())
+}
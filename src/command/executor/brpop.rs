@@ -0,0 +1,16 @@
+use crate::command::command_enum::{Command, ListEnd};
+use crate::command::executor::blpop::blocking_pop;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct brpop;
+
+impl commandExecutor for brpop {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::BRPOP { keys, .. } => blocking_pop(context, keys, ListEnd::Right),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
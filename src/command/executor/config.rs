@@ -0,0 +1,59 @@
+use crate::command::command_enum::Command;
+use crate::config::{Config, PARAMETERS};
+use crate::persistence::aof;
+use crate::resp::reply::{array, bulk_string};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use crate::utils::glob::glob_match;
+use std::error::Error;
+
+pub struct config;
+
+impl commandExecutor for config {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // Name and value of every parameter matching one of the patterns
+            Command::CONFIG_GET { patterns } => {
+                let mut items = Vec::new();
+                crate::config::with(|current| {
+                    for name in PARAMETERS {
+                        if patterns.iter().any(|pattern| glob_match(&pattern.to_ascii_lowercase(), name.as_bytes())) {
+                            let value = current.get(name).expect("every parameter has a value");
+                            items.push(bulk_string(name.as_bytes()));
+                            items.push(bulk_string(value.as_bytes()));
+                        }
+                    }
+                });
+                Ok(array(items))
+            }
+            // All or nothing: one bad pair leaves every parameter unchanged
+            Command::CONFIG_SET { pairs } => {
+                let mut updated: Config = crate::config::with(Config::clone);
+                for (name, value) in pairs {
+                    let name = String::from_utf8_lossy(name).to_ascii_lowercase();
+                    match updated.set(&name, value) {
+                        Ok(()) => {}
+                        Err(None) => {
+                            return Ok(format!(
+                                "-ERR Unknown option or number of arguments for CONFIG SET - '{}'\r\n",
+                                name
+                            )
+                            .into_bytes())
+                        }
+                        Err(Some(reason)) => {
+                            return Ok(format!(
+                                "-ERR CONFIG SET failed (possibly related to argument '{}') - {}\r\n",
+                                name, reason
+                            )
+                            .into_bytes())
+                        }
+                    }
+                }
+                crate::config::replace(updated);
+                aof::apply_config(context);
+                Ok(b"+OK\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
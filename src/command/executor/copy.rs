@@ -0,0 +1,31 @@
+use crate::command::command_enum::Command;
+use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct copy;
+
+impl commandExecutor for copy {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::COPY { source, destination, replace } => {
+                if source == destination {
+                    return Ok(b"-ERR source and destination objects are the same\r\n".to_vec());
+                }
+                let mut entry = match context.lookup_key(source) {
+                    Some(entry) => entry.clone(),
+                    None => return Ok(b":0\r\n".to_vec()),
+                };
+                if !replace && context.lookup_key(destination).is_some() {
+                    return Ok(b":0\r\n".to_vec());
+                }
+                // The copy is a new key: it inherits the TTL but not the access time
+                entry.lru = now_ms();
+                context.insert_entry(destination.to_owned(), entry);
+                Ok(b":1\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
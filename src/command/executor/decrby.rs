@@ -0,0 +1,20 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::incr::incr_by;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct decrby;
+
+impl commandExecutor for decrby {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::DECRBY { key, decrement } => match decrement.checked_neg() {
+                Some(delta) => incr_by(context, key, delta),
+                // -i64::MIN does not fit, as in Redis
+                None => Ok(b"-ERR decrement would overflow\r\n".to_vec()),
+            },
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -1,20 +1,28 @@
 use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
 use crate::store_containers::core_context::context;
 use crate::traits::command::commandExecutor;
 use std::error::Error;
 
 pub struct del;
 
+// Deletes the keys and returns how many of them existed. Shared with UNLINK,
+// which is the same thing here since values are always freed inline.
+pub fn delete_keys(context: &mut context, keys: &[Vec<u8>]) -> Vec<u8> {
+    let mut deleted = 0;
+    for key in keys {
+        // An expired key is dropped by the lookup and not counted
+        if context.lookup_key(key).is_some() && context.remove_key(key) {
+            deleted += 1;
+        }
+    }
+    integer(deleted)
+}
+
 impl commandExecutor for del {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
-            Command::DEL { keys } => {
-                for key in keys {
-                    context.DataBase.store.remove(key.as_str());
-                    // Note: TTL removal would need to be implemented based on your TTL store structure
-                }
-                Ok(b"+OK\r\n".to_vec())
-            }
+            Command::DEL { keys } => Ok(delete_keys(context, keys)),
             _ => Ok(b"-ERR wrong command\r\n".to_vec()),
         }
     }
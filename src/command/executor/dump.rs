@@ -0,0 +1,69 @@
+use crate::command::command_enum::Command;
+use crate::config;
+use crate::persistence::snapshot::{self, SnapshotError};
+use crate::resp::reply::{bulk_string, NIL};
+use crate::store_containers::core_context::context;
+use crate::store_containers::DictStore::Entry;
+use crate::store_containers::TTLStore::now_ms;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct dump;
+
+impl commandExecutor for dump {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // The value alone: the TTL is passed to RESTORE separately
+            Command::DUMP { key } => match context.lookup_key(key) {
+                Some(entry) => Ok(bulk_string(&snapshot::dump_value(&entry.value))),
+                None => Ok(NIL.to_vec()),
+            },
+            Command::RESTORE {
+                key,
+                ttl_ms,
+                payload,
+                replace,
+                absttl,
+                idletime,
+                freq,
+            } => {
+                if !replace && context.lookup_key(key).is_some() {
+                    return Ok(b"-BUSYKEY Target key name already exists.\r\n".to_vec());
+                }
+                let value = match snapshot::restore_value(payload) {
+                    Ok(value) => value,
+                    Err(SnapshotError::UnsupportedVersion(_) | SnapshotError::BadChecksum) => {
+                        return Ok(b"-ERR DUMP payload version or checksum are wrong\r\n".to_vec())
+                    }
+                    Err(_) => return Ok(b"-ERR Bad data format\r\n".to_vec()),
+                };
+
+                let now = now_ms();
+                let deadline = match (*ttl_ms, *absttl) {
+                    (0, _) => None,
+                    (deadline, true) => Some(deadline),
+                    (ttl, false) => Some(ttl.saturating_add(now)),
+                };
+                // Already expired: it replaces the old key with nothing
+                if deadline.is_some_and(|deadline| deadline <= now) {
+                    context.remove_key(key);
+                    return Ok(b"+OK\r\n".to_vec());
+                }
+
+                let mut entry = Entry::new(value, now);
+                entry.expires_at = deadline;
+                // Access metadata only counts under the policy that uses it
+                if config::with(|config| config.maxmemory_policy.is_lfu()) {
+                    if let Some(freq) = freq {
+                        entry.lfu = *freq;
+                    }
+                } else if let Some(seconds) = idletime {
+                    entry.lru = now.saturating_sub(seconds.saturating_mul(1000));
+                }
+                context.insert_entry(key.to_owned(), entry);
+                Ok(b"+OK\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
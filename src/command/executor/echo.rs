@@ -1,4 +1,5 @@
 use crate::command::command_enum::Command;
+use crate::resp::reply::bulk_string;
 use crate::store_containers::core_context::context;
 use crate::traits::command::commandExecutor;
 use std::error::Error;
@@ -6,12 +7,11 @@ use std::error::Error;
 pub struct echo;
 
 impl commandExecutor for echo {
-    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+    fn execute(commandObject: &Command, _context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
             Command::ECHO { message } => {
                 // Return the message in RESP bulk string format
-                let response = format!("${}\r\n{}\r\n", message.len(), message);
-                Ok(response.into_bytes())
+                Ok(bulk_string(message))
             }
             _ => {
                 // This should never happen since we only match ECHO
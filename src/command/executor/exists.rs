@@ -11,16 +11,9 @@ impl commandExecutor for exists {
             Command::EXISTS { keys } => {
                 let mut count = 0;
                 for key in keys {
-                    match context.DataBase.store.get(key.as_str()) {
-                        Some(Some(weak_ref)) => {
-                            // Check if the weak reference is still valid
-                            if weak_ref.upgrade().is_some() {
-                                count += 1;
-                            }
-                        }
-                        Some(None) | None => {
-                            // Key doesn't exist
-                        }
+                    // Expired keys are dropped by the lookup and not counted
+                    if context.lookup_key(key).is_some() {
+                        count += 1;
                     }
                 }
                 Ok(format!(":{}\r\n", count).into_bytes())
@@ -1,5 +1,6 @@
 use crate::command::command_enum::Command;
 use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
 use crate::traits::command::commandExecutor;
 use std::error::Error;
 
@@ -9,23 +10,29 @@ impl commandExecutor for expire {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
             Command::EXPIRE { key, seconds } => {
-                // Check if the key exists in the database
-                match context.DataBase.store.get(key.as_str()) {
-                    Some(Some(weak_ref)) => {
-                        // Try to upgrade the weak reference
-                        if let Some(store_ref) = weak_ref.upgrade() {
-                            // Add to TTL store with the specified expiration time
-                            context.TTLStore.store.insert(*seconds as usize, store_ref);
-                            Ok(b":1\r\n".to_vec()) // Return 1 to indicate success
-                        } else {
-                            // Weak reference is invalid, key has expired
-                            Ok(b":0\r\n".to_vec()) // Return 0 to indicate key doesn't exist
-                        }
-                    }
-                    Some(None) | None => {
-                        // Key doesn't exist
-                        Ok(b":0\r\n".to_vec()) // Return 0 to indicate key doesn't exist
+                // Deadlines are absolute, so convert the relative TTL right away
+                let deadline = match seconds
+                    .checked_mul(1000)
+                    .and_then(|ms| ms.checked_add(now_ms() as i64))
+                {
+                    Some(deadline) => deadline.max(0) as u64,
+                    None => {
+                        return Ok(b"-ERR invalid expire time in 'expire' command\r\n".to_vec())
                     }
+                };
+
+                // A deadline in the past deletes the key, as in Redis
+                if context.set_expiry(key, deadline) {
+                    Ok(b":1\r\n".to_vec()) // Return 1 to indicate success
+                } else {
+                    Ok(b":0\r\n".to_vec()) // Return 0 to indicate key doesn't exist
+                }
+            }
+            Command::PEXPIREAT { key, deadline_ms } => {
+                if context.set_expiry(key, (*deadline_ms).max(0) as u64) {
+                    Ok(b":1\r\n".to_vec())
+                } else {
+                    Ok(b":0\r\n".to_vec())
                 }
             }
             _ => Ok(b"-ERR wrong command\r\n".to_vec()),
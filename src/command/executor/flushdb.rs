@@ -9,11 +9,8 @@ impl commandExecutor for flushdb {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
             Command::FLUSHDB => {
-                // Clear the main database store
-                context.DataBase.store.clear();
-                
-                // Clear the TTL store
-                context.TTLStore.store.clear();
+                // Clear the main database store together with every TTL
+                context.flush();
                 
                 // Return OK response in RESP format
                 Ok(b"+OK\r\n".to_vec())
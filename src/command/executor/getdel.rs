@@ -0,0 +1,24 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{bulk_string, NIL};
+use crate::store::string_store::StringStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct getdel;
+
+impl commandExecutor for getdel {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::GETDEL { key } => {
+                let reply = match context.get::<StringStore>(key)? {
+                    Some(string_store) => bulk_string(string_store.get_value()),
+                    None => return Ok(NIL.to_vec()),
+                };
+                context.remove_key(key);
+                Ok(reply)
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{bulk_string, NIL};
+use crate::store::string_store::StringStore;
+use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct getex;
+
+impl commandExecutor for getex {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::GETEX { key, expiry, persist } => {
+                let reply = match context.get::<StringStore>(key)? {
+                    Some(string_store) => bulk_string(string_store.get_value()),
+                    None => return Ok(NIL.to_vec()),
+                };
+                if let Some(expiry) = expiry {
+                    match expiry.deadline_ms(now_ms()) {
+                        // A deadline already in the past deletes the key after the read
+                        Some(deadline) => {
+                            context.set_expiry(key, deadline);
+                        }
+                        None => return Ok(b"-ERR invalid expire time in 'getex' command\r\n".to_vec()),
+                    }
+                } else if *persist {
+                    context.persist_key(key);
+                }
+                Ok(reply)
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
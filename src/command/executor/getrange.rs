@@ -0,0 +1,60 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::bulk_string;
+use crate::store::string_store::StringStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct getrange;
+
+// Resolves an inclusive [start, end] range with negative indexes counting from
+// the end, clamped to the string. None if the range is empty.
+fn resolve_range(start: i64, end: i64, len: usize) -> Option<(usize, usize)> {
+    let len = len as i64;
+    if len == 0 || (start < 0 && end < 0 && start > end) {
+        return None;
+    }
+    let start = if start < 0 { (len + start).max(0) } else { start };
+    let end = if end < 0 { (len + end).max(0) } else { end.min(len - 1) };
+    if start > end {
+        return None;
+    }
+    Some((start as usize, end as usize))
+}
+
+impl commandExecutor for getrange {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::GETRANGE { key, start, end } => {
+                let value = match context.get::<StringStore>(key)? {
+                    Some(string_store) => string_store.get_value(),
+                    None => return Ok(bulk_string(b"")),
+                };
+                match resolve_range(*start, *end, value.len()) {
+                    Some((start, end)) => Ok(bulk_string(&value[start..=end])),
+                    None => Ok(bulk_string(b"")),
+                }
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_range() {
+        // "This is a string"
+        assert_eq!(resolve_range(0, 3, 16), Some((0, 3)));
+        assert_eq!(resolve_range(-3, -1, 16), Some((13, 15)));
+        assert_eq!(resolve_range(0, -1, 16), Some((0, 15)));
+        assert_eq!(resolve_range(10, 100, 16), Some((10, 15)));
+        assert_eq!(resolve_range(-100, 2, 16), Some((0, 2)));
+        assert_eq!(resolve_range(5, 2, 16), None);
+        assert_eq!(resolve_range(-1, -5, 16), None);
+        assert_eq!(resolve_range(20, 30, 16), None);
+        assert_eq!(resolve_range(0, -1, 0), None);
+    }
+}
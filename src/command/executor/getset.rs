@@ -0,0 +1,26 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{bulk_string, NIL};
+use crate::store::string_store::StringStore;
+use crate::store::value::Value;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct getset;
+
+impl commandExecutor for getset {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::GETSET { key, value } => {
+                let reply = match context.get::<StringStore>(key)? {
+                    Some(string_store) => bulk_string(string_store.get_value()),
+                    None => NIL.to_vec(),
+                };
+                // Like SET, this discards any TTL
+                context.insert_key(key.to_owned(), Value::String(StringStore::new(value.to_owned())));
+                Ok(reply)
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
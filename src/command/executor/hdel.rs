@@ -0,0 +1,28 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hdel;
+
+impl commandExecutor for hdel {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HDEL { key, fields } => {
+                let hash_store = match context.get_mut::<HashStore>(key)? {
+                    Some(hash_store) => hash_store,
+                    None => return Ok(b":0\r\n".to_vec()),
+                };
+                let deleted = fields.iter().filter(|field| hash_store.delete_field(field)).count();
+                if deleted > 0 {
+                    context.DataBase.mark_changed(key);
+                }
+                context.delete_if_empty(key);
+                Ok(integer(deleted as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
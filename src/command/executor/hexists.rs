@@ -0,0 +1,22 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hexists;
+
+impl commandExecutor for hexists {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HEXISTS { key, field } => {
+                let exists = context
+                    .get::<HashStore>(key)?
+                    .is_some_and(|hash_store| hash_store.exists_field(field));
+                Ok(integer(exists as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
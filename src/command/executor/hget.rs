@@ -1,4 +1,5 @@
 use crate::command::command_enum::Command;
+use crate::resp::reply::bulk_string;
 use crate::store::hash_store::HashStore;
 use crate::store_containers::core_context::context;
 use crate::traits::command::commandExecutor;
@@ -9,27 +10,13 @@ pub struct hget;
 impl commandExecutor for hget {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
-            Command::HGET { key, field } => {
-                match context.DataBase.store.get(key.as_str()) {
-                    Some(Some(weak_ref)) => {
-                        match weak_ref.upgrade() {
-                            Some(store_ref) => {
-                                let store = store_ref.borrow();
-                                if let Some(hash_store) = (&*store as &dyn std::any::Any).downcast_ref::<HashStore>() {
-                                    match hash_store.get_field(field) {
-                                        Some(value) => Ok(format!("${}\r\n{}\r\n", value.len(), value).into_bytes()),
-                                        None => Ok(b"$-1\r\n".to_vec()),
-                                    }
-                                } else {
-                                    Ok(b"-ERR WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_vec())
-                                }
-                            }
-                            None => Ok(b"$-1\r\n".to_vec()),
-                        }
-                    }
-                    Some(None) | None => Ok(b"$-1\r\n".to_vec()),
-                }
-            }
+            Command::HGET { key, field } => match context.get::<HashStore>(key)? {
+                Some(hash_store) => match hash_store.get_field(field) {
+                    Some(value) => Ok(bulk_string(value)),
+                    None => Ok(b"$-1\r\n".to_vec()),
+                },
+                None => Ok(b"$-1\r\n".to_vec()),
+            },
             _ => Ok(b"-ERR wrong command\r\n".to_vec()),
         }
     }
@@ -38,16 +25,19 @@ impl commandExecutor for hget {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::store_containers::{DictStore::DictStore, TTLStore::TTLStore};
-    use crate::traits::Store::Store;
-    use std::cell::RefCell;
-    use std::rc::Rc;
+    use crate::store::value::Value;
+    use crate::store_containers::{AofStore::AofStore, BlockingStore::BlockingStore, DictStore::DictStore, EvictionStore::EvictionStore, PersistenceStore::PersistenceStore, PubSubStore::PubSubStore, TTLStore::TTLStore, WatchStore::WatchStore};
 
     fn create_test_context() -> context {
         context {
             DataBase: DictStore::new(),
             TTLStore: TTLStore::new(),
-            EvictionStore: "TODO".to_owned(),
+            EvictionStore: EvictionStore::new(),
+            BlockingStore: BlockingStore::new(),
+            PersistenceStore: PersistenceStore::new(),
+            AofStore: AofStore::new(),
+            WatchStore: WatchStore::new(),
+            PubSubStore: PubSubStore::new(),
         }
     }
 
@@ -57,15 +47,13 @@ mod tests {
         
         // Create hash with data
         let mut hash = HashStore::new();
-        hash.set_field("name", "John");
-        hash.set_field("age", "30");
-        let shared_store: Rc<RefCell<dyn Store>> = Rc::new(RefCell::new(hash));
-        ctx.DataBase.store.insert("user:1".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        hash.set_field(b"name", b"John");
+        hash.set_field(b"age", b"30");
+        ctx.insert_key(b"user:1".to_vec(), Value::Hash(hash));
         
         let command = Command::HGET {
-            key: "user:1".to_string(),
-            field: "name".to_string(),
+            key: b"user:1".to_vec(),
+            field: b"name".to_vec(),
         };
         
         let result = hget::execute(&command, &mut ctx).unwrap();
@@ -78,14 +66,12 @@ mod tests {
         
         // Create hash with data
         let mut hash = HashStore::new();
-        hash.set_field("name", "John");
-        let shared_store: Rc<RefCell<dyn Store>> = Rc::new(RefCell::new(hash));
-        ctx.DataBase.store.insert("user:1".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        hash.set_field(b"name", b"John");
+        ctx.insert_key(b"user:1".to_vec(), Value::Hash(hash));
         
         let command = Command::HGET {
-            key: "user:1".to_string(),
-            field: "age".to_string(),
+            key: b"user:1".to_vec(),
+            field: b"age".to_vec(),
         };
         
         let result = hget::execute(&command, &mut ctx).unwrap();
@@ -97,8 +83,8 @@ mod tests {
         let mut ctx = create_test_context();
         
         let command = Command::HGET {
-            key: "user:1".to_string(),
-            field: "name".to_string(),
+            key: b"user:1".to_vec(),
+            field: b"name".to_vec(),
         };
         
         let result = hget::execute(&command, &mut ctx).unwrap();
@@ -111,14 +97,12 @@ mod tests {
         
         // Create hash with empty value
         let mut hash = HashStore::new();
-        hash.set_field("empty", "");
-        let shared_store: Rc<RefCell<dyn Store>> = Rc::new(RefCell::new(hash));
-        ctx.DataBase.store.insert("user:1".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        hash.set_field(b"empty", b"");
+        ctx.insert_key(b"user:1".to_vec(), Value::Hash(hash));
         
         let command = Command::HGET {
-            key: "user:1".to_string(),
-            field: "empty".to_string(),
+            key: b"user:1".to_vec(),
+            field: b"empty".to_vec(),
         };
         
         let result = hget::execute(&command, &mut ctx).unwrap();
@@ -131,14 +115,12 @@ mod tests {
         
         // Create hash with unicode value
         let mut hash = HashStore::new();
-        hash.set_field("greeting", "Hello 世界");
-        let shared_store: Rc<RefCell<dyn Store>> = Rc::new(RefCell::new(hash));
-        ctx.DataBase.store.insert("user:1".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        hash.set_field(b"greeting", "Hello 世界".as_bytes());
+        ctx.insert_key(b"user:1".to_vec(), Value::Hash(hash));
         
         let command = Command::HGET {
-            key: "user:1".to_string(),
-            field: "greeting".to_string(),
+            key: b"user:1".to_vec(),
+            field: b"greeting".to_vec(),
         };
         
         let result = hget::execute(&command, &mut ctx).unwrap();
@@ -148,7 +130,7 @@ mod tests {
     #[test]
     fn test_hget_wrong_command() {
         let mut ctx = create_test_context();
-        let command = Command::GET { key: "test".to_string() };
+        let command = Command::GET { key: b"test".to_vec() };
         
         let result = hget::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b"-ERR wrong command\r\n");
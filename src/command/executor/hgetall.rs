@@ -0,0 +1,26 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string};
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hgetall;
+
+impl commandExecutor for hgetall {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HGETALL { key } => {
+                let mut items = Vec::new();
+                if let Some(hash_store) = context.get::<HashStore>(key)? {
+                    for (field, value) in hash_store.iter() {
+                        items.push(bulk_string(field));
+                        items.push(bulk_string(value));
+                    }
+                }
+                Ok(array(items))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
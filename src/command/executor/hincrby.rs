@@ -0,0 +1,35 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use crate::utils::integer::parse_canonical;
+use std::error::Error;
+
+pub struct hincrby;
+
+impl commandExecutor for hincrby {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HINCRBY { key, field, increment } => {
+                let current_val = match context.get::<HashStore>(key)?.and_then(|hash_store| hash_store.get_field(field)) {
+                    Some(value) => match parse_canonical(value) {
+                        Some(current_val) => current_val,
+                        None => return Ok(b"-ERR hash value is not an integer\r\n".to_vec()),
+                    },
+                    None => 0,
+                };
+                let new_val = match current_val.checked_add(*increment) {
+                    Some(new_val) => new_val,
+                    None => return Ok(b"-ERR increment or decrement would overflow\r\n".to_vec()),
+                };
+                context
+                    .get_or_create::<HashStore>(key)?
+                    .set_field(field, new_val.to_string().as_bytes());
+                context.DataBase.mark_changed(key);
+                Ok(integer(new_val))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::bulk_string;
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hincrbyfloat;
+
+impl commandExecutor for hincrbyfloat {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HINCRBYFLOAT { key, field, increment } => {
+                let current_val = match context.get::<HashStore>(key)?.and_then(|hash_store| hash_store.get_field(field)) {
+                    Some(value) => match std::str::from_utf8(value)
+                        .ok()
+                        .and_then(|value| value.parse::<f64>().ok())
+                        .filter(|value| value.is_finite())
+                    {
+                        Some(current_val) => current_val,
+                        None => return Ok(b"-ERR hash value is not a float\r\n".to_vec()),
+                    },
+                    None => 0.0,
+                };
+                let new_val = current_val + increment;
+                if !new_val.is_finite() {
+                    return Ok(b"-ERR increment would produce NaN or Infinity\r\n".to_vec());
+                }
+                let encoded = new_val.to_string().into_bytes();
+                context.get_or_create::<HashStore>(key)?.set_field(field, &encoded);
+                context.DataBase.mark_changed(key);
+                Ok(bulk_string(&encoded))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
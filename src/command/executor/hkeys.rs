@@ -0,0 +1,23 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string};
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hkeys;
+
+impl commandExecutor for hkeys {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HKEYS { key } => {
+                let fields = match context.get::<HashStore>(key)? {
+                    Some(hash_store) => hash_store.iter().map(|(field, _)| bulk_string(field)).collect(),
+                    None => Vec::new(),
+                };
+                Ok(array(fields))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
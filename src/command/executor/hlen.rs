@@ -0,0 +1,20 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hlen;
+
+impl commandExecutor for hlen {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HLEN { key } => {
+                let length = context.get::<HashStore>(key)?.map_or(0, |hash_store| hash_store.len());
+                Ok(integer(length as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, NIL};
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hmget;
+
+impl commandExecutor for hmget {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HMGET { key, fields } => {
+                let hash_store = context.get::<HashStore>(key)?;
+                let values = fields
+                    .iter()
+                    .map(|field| match hash_store.and_then(|hash_store| hash_store.get_field(field)) {
+                        Some(value) => bulk_string(value),
+                        None => NIL.to_vec(),
+                    })
+                    .collect();
+                Ok(array(values))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
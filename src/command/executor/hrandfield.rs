@@ -0,0 +1,57 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, NIL};
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use rand::seq::SliceRandom;
+use std::error::Error;
+
+pub struct hrandfield;
+
+impl commandExecutor for hrandfield {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HRANDFIELD { key, count, with_values } => {
+                let hash_store = context.get::<HashStore>(key)?;
+                let count = match count {
+                    Some(count) => *count,
+                    // Without a count the reply is a single field, not an array
+                    None => {
+                        return Ok(match hash_store.and_then(|hash_store| hash_store.random_field()) {
+                            Some((field, _)) => bulk_string(field),
+                            None => NIL.to_vec(),
+                        })
+                    }
+                };
+                let hash_store = match hash_store {
+                    Some(hash_store) => hash_store,
+                    None => return Ok(array(vec![])),
+                };
+
+                // A positive count returns distinct fields, a negative one may
+                // return the same field several times
+                let picked: Vec<(&[u8], &[u8])> = if count >= 0 {
+                    let mut fields: Vec<_> = hash_store.iter().collect();
+                    let count = (count as usize).min(fields.len());
+                    fields.partial_shuffle(&mut rand::thread_rng(), count);
+                    fields.truncate(count);
+                    fields
+                } else {
+                    (0..count.unsigned_abs())
+                        .filter_map(|_| hash_store.random_field())
+                        .collect()
+                };
+
+                let mut items = Vec::new();
+                for (field, value) in picked {
+                    items.push(bulk_string(field));
+                    if *with_values {
+                        items.push(bulk_string(value));
+                    }
+                }
+                Ok(array(items))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::scan::{matches_pattern, scan_reply};
+use crate::resp::reply::bulk_string;
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hscan;
+
+impl commandExecutor for hscan {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HSCAN { key, cursor, options } => {
+                let hash_store = match context.get::<HashStore>(key)? {
+                    Some(hash_store) => hash_store,
+                    None => return Ok(scan_reply(0, vec![])),
+                };
+                let mut items = Vec::new();
+                let next_cursor = hash_store.scan(*cursor, options.count, |field, value| {
+                    if matches_pattern(&options.pattern, field) {
+                        items.push(bulk_string(field));
+                        if !options.no_values {
+                            items.push(bulk_string(value));
+                        }
+                    }
+                });
+                Ok(scan_reply(next_cursor, items))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
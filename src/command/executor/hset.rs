@@ -2,10 +2,7 @@ use crate::command::command_enum::Command;
 use crate::store::hash_store::HashStore;
 use crate::store_containers::core_context::context;
 use crate::traits::command::commandExecutor;
-use crate::traits::Store::Store;
-use std::cell::RefCell;
 use std::error::Error;
-use std::rc::Rc;
 
 pub struct hset;
 
@@ -13,61 +10,16 @@ impl commandExecutor for hset {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
             Command::HSET { key, fields } => {
+                let hash_store = context.get_or_create::<HashStore>(key)?;
                 let mut fields_added = 0;
-                
-                match context.DataBase.store.get(key.as_str()) {
-                    Some(Some(weak_ref)) => {
-                        match weak_ref.upgrade() {
-                            Some(store_ref) => {
-                                let mut store = store_ref.borrow_mut();
-                                if let Some(hash_store) = (&mut *store as &mut dyn std::any::Any).downcast_mut::<HashStore>() {
-                                    for (field, value) in fields {
-                                        if hash_store.set_field(field, value) {
-                                            fields_added += 1;
-                                        }
-                                    }
-                                    Ok(format!(":{}\r\n", fields_added).into_bytes())
-                                } else {
-                                    Ok(b"-ERR WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_vec())
-                                }
-                            }
-                            None => {
-                                // Create new hash
-                                let mut new_hash = HashStore::new();
-                                for (field, value) in fields {
-                                    if new_hash.set_field(field, value) {
-                                        fields_added += 1;
-                                    }
-                                }
-                                let shared_store: Rc<RefCell<dyn Store>> =
-                                    Rc::new(RefCell::new(new_hash));
-                                context
-                                    .DataBase
-                                    .store
-                                    .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                                context.TTLStore.store.insert(86400, shared_store);
-                                Ok(format!(":{}\r\n", fields_added).into_bytes())
-                            }
-                        }
-                    }
-                    Some(None) | None => {
-                        // Create new hash
-                        let mut new_hash = HashStore::new();
-                        for (field, value) in fields {
-                            if new_hash.set_field(field, value) {
-                                fields_added += 1;
-                            }
-                        }
-                        let shared_store: Rc<RefCell<dyn Store>> =
-                            Rc::new(RefCell::new(new_hash));
-                        context
-                            .DataBase
-                            .store
-                            .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                        context.TTLStore.store.insert(86400, shared_store);
-                        Ok(format!(":{}\r\n", fields_added).into_bytes())
+                for (field, value) in fields {
+                    if hash_store.set_field(field, value) {
+                        fields_added += 1;
                     }
                 }
+                // Like Redis, a field set to the value it had still counts
+                context.DataBase.mark_changed(key);
+                Ok(format!(":{}\r\n", fields_added).into_bytes())
             }
             _ => Ok(b"-ERR wrong command\r\n".to_vec()),
         }
@@ -77,13 +29,19 @@ impl commandExecutor for hset {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::store_containers::{DictStore::DictStore, TTLStore::TTLStore};
+    use crate::store::value::Value;
+    use crate::store_containers::{AofStore::AofStore, BlockingStore::BlockingStore, DictStore::DictStore, EvictionStore::EvictionStore, PersistenceStore::PersistenceStore, PubSubStore::PubSubStore, TTLStore::TTLStore, WatchStore::WatchStore};
 
     fn create_test_context() -> context {
         context {
             DataBase: DictStore::new(),
             TTLStore: TTLStore::new(),
-            EvictionStore: "TODO".to_owned(),
+            EvictionStore: EvictionStore::new(),
+            BlockingStore: BlockingStore::new(),
+            PersistenceStore: PersistenceStore::new(),
+            AofStore: AofStore::new(),
+            WatchStore: WatchStore::new(),
+            PubSubStore: PubSubStore::new(),
         }
     }
 
@@ -91,10 +49,10 @@ mod tests {
     fn test_hset_new_hash() {
         let mut ctx = create_test_context();
         let command = Command::HSET {
-            key: "user:1".to_string(),
+            key: b"user:1".to_vec(),
             fields: vec![
-                ("name".to_string(), "John".to_string()),
-                ("age".to_string(), "30".to_string()),
+                (b"name".to_vec(), b"John".to_vec()),
+                (b"age".to_vec(), b"30".to_vec()),
             ],
         };
         
@@ -108,16 +66,14 @@ mod tests {
         
         // Create existing hash
         let mut existing_hash = HashStore::new();
-        existing_hash.set_field("name", "John");
-        let shared_store: Rc<RefCell<dyn Store>> = Rc::new(RefCell::new(existing_hash));
-        ctx.DataBase.store.insert("user:1".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        existing_hash.set_field(b"name", b"John");
+        ctx.insert_key(b"user:1".to_vec(), Value::Hash(existing_hash));
         
         let command = Command::HSET {
-            key: "user:1".to_string(),
+            key: b"user:1".to_vec(),
             fields: vec![
-                ("name".to_string(), "Jane".to_string()), // Update existing
-                ("age".to_string(), "25".to_string()),    // Add new
+                (b"name".to_vec(), b"Jane".to_vec()), // Update existing
+                (b"age".to_vec(), b"25".to_vec()),    // Add new
             ],
         };
         
@@ -129,8 +85,8 @@ mod tests {
     fn test_hset_single_field() {
         let mut ctx = create_test_context();
         let command = Command::HSET {
-            key: "user:1".to_string(),
-            fields: vec![("name".to_string(), "John".to_string())],
+            key: b"user:1".to_vec(),
+            fields: vec![(b"name".to_vec(), b"John".to_vec())],
         };
         
         let result = hset::execute(&command, &mut ctx).unwrap();
@@ -141,7 +97,7 @@ mod tests {
     fn test_hset_empty_fields() {
         let mut ctx = create_test_context();
         let command = Command::HSET {
-            key: "user:1".to_string(),
+            key: b"user:1".to_vec(),
             fields: vec![],
         };
         
@@ -152,7 +108,7 @@ mod tests {
     #[test]
     fn test_hset_wrong_command() {
         let mut ctx = create_test_context();
-        let command = Command::GET { key: "test".to_string() };
+        let command = Command::GET { key: b"test".to_vec() };
         
         let result = hset::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b"-ERR wrong command\r\n");
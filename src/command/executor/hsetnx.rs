@@ -0,0 +1,24 @@
+use crate::command::command_enum::Command;
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hsetnx;
+
+impl commandExecutor for hsetnx {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HSETNX { key, field, value } => {
+                let hash_store = context.get_or_create::<HashStore>(key)?;
+                if hash_store.exists_field(field) {
+                    return Ok(b":0\r\n".to_vec());
+                }
+                hash_store.set_field(field, value);
+                context.DataBase.mark_changed(key);
+                Ok(b":1\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
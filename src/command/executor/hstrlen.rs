@@ -0,0 +1,23 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hstrlen;
+
+impl commandExecutor for hstrlen {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HSTRLEN { key, field } => {
+                let length = context
+                    .get::<HashStore>(key)?
+                    .and_then(|hash_store| hash_store.get_field(field))
+                    .map_or(0, |value| value.len());
+                Ok(integer(length as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
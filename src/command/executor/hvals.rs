@@ -0,0 +1,23 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string};
+use crate::store::hash_store::HashStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct hvals;
+
+impl commandExecutor for hvals {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::HVALS { key } => {
+                let values = match context.get::<HashStore>(key)? {
+                    Some(hash_store) => hash_store.iter().map(|(_, value)| bulk_string(value)).collect(),
+                    None => Vec::new(),
+                };
+                Ok(array(values))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -1,62 +1,43 @@
 use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
 use crate::store::string_store::StringStore;
+use crate::store::value::Value;
 use crate::store_containers::core_context::context;
 use crate::traits::command::commandExecutor;
-use crate::traits::Store::Store;
-use std::cell::RefCell;
 use std::error::Error;
-use std::rc::Rc;
 
 pub struct incr;
 
+// Adds `delta` to the integer stored at `key`, shared by INCR, DECR, INCRBY and
+// DECRBY. A missing key counts as 0, and an existing key keeps its TTL.
+pub fn incr_by(context: &mut context, key: &[u8], delta: i64) -> Result<Vec<u8>, Box<dyn Error>> {
+    let current_val = match context.get::<StringStore>(key)? {
+        Some(string_store) => match string_store.as_integer() {
+            Some(current_val) => current_val,
+            None => return Ok(b"-ERR value is not an integer or out of range\r\n".to_vec()),
+        },
+        None => 0,
+    };
+    let new_val = match current_val.checked_add(delta) {
+        Some(new_val) => new_val,
+        None => return Ok(b"-ERR increment or decrement would overflow\r\n".to_vec()),
+    };
+
+    let encoded = new_val.to_string().into_bytes();
+    match context.get_mut::<StringStore>(key)? {
+        Some(string_store) => {
+            string_store.set_value(encoded);
+            context.DataBase.mark_changed(key);
+        }
+        None => context.insert_key(key.to_owned(), Value::String(StringStore::new(encoded))),
+    }
+    Ok(integer(new_val))
+}
+
 impl commandExecutor for incr {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
-            Command::INCR { key } => {
-                match context.DataBase.store.get(key.as_str()) {
-                    Some(Some(weak_ref)) => {
-                        match weak_ref.upgrade() {
-                            Some(store_ref) => {
-                                let mut store = store_ref.borrow_mut();
-                                if let Some(string_store) = (&mut *store as &mut dyn std::any::Any).downcast_mut::<StringStore>() {
-                                    match string_store.get_value().parse::<i64>() {
-                                        Ok(current_val) => {
-                                            let new_val = current_val + 1;
-                                            *string_store = StringStore::new(new_val.to_string());
-                                            Ok(format!(":{}\r\n", new_val).into_bytes())
-                                        }
-                                        Err(_) => Ok(b"-ERR value is not an integer or out of range\r\n".to_vec()),
-                                    }
-                                } else {
-                                    Ok(b"-ERR WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_vec())
-                                }
-                            }
-                            None => {
-                                // Key expired or deleted, treat as 0
-                                let shared_store: Rc<RefCell<dyn Store>> =
-                                    Rc::new(RefCell::new(StringStore::new("1".to_string())));
-                                context
-                                    .DataBase
-                                    .store
-                                    .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                                context.TTLStore.store.insert(86400, shared_store);
-                                Ok(b":1\r\n".to_vec())
-                            }
-                        }
-                    }
-                    Some(None) | None => {
-                        // Key doesn't exist, start with 1
-                        let shared_store: Rc<RefCell<dyn Store>> =
-                            Rc::new(RefCell::new(StringStore::new("1".to_string())));
-                        context
-                            .DataBase
-                            .store
-                            .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                        context.TTLStore.store.insert(86400, shared_store);
-                        Ok(b":1\r\n".to_vec())
-                    }
-                }
-            }
+            Command::INCR { key } => incr_by(context, key, 1),
             _ => Ok(b"-ERR wrong command\r\n".to_vec()),
         }
     }
@@ -65,20 +46,25 @@ impl commandExecutor for incr {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::store_containers::{DictStore::DictStore, TTLStore::TTLStore};
+    use crate::store_containers::{AofStore::AofStore, BlockingStore::BlockingStore, DictStore::DictStore, EvictionStore::EvictionStore, PersistenceStore::PersistenceStore, PubSubStore::PubSubStore, TTLStore::TTLStore, WatchStore::WatchStore};
 
     fn create_test_context() -> context {
         context {
             DataBase: DictStore::new(),
             TTLStore: TTLStore::new(),
-            EvictionStore: "TODO".to_owned(),
+            EvictionStore: EvictionStore::new(),
+            BlockingStore: BlockingStore::new(),
+            PersistenceStore: PersistenceStore::new(),
+            AofStore: AofStore::new(),
+            WatchStore: WatchStore::new(),
+            PubSubStore: PubSubStore::new(),
         }
     }
 
     #[test]
     fn test_incr_new_key() {
         let mut ctx = create_test_context();
-        let command = Command::INCR { key: "counter".to_string() };
+        let command = Command::INCR { key: b"counter".to_vec() };
         
         let result = incr::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b":1\r\n");
@@ -89,12 +75,9 @@ mod tests {
         let mut ctx = create_test_context();
         
         // First, set a value
-        let shared_store: Rc<RefCell<dyn Store>> =
-            Rc::new(RefCell::new(StringStore::new("5".to_string())));
-        ctx.DataBase.store.insert("counter".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        ctx.insert_key(b"counter".to_vec(), Value::String(StringStore::new(b"5".to_vec())));
         
-        let command = Command::INCR { key: "counter".to_string() };
+        let command = Command::INCR { key: b"counter".to_vec() };
         let result = incr::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b":6\r\n");
     }
@@ -104,12 +87,9 @@ mod tests {
         let mut ctx = create_test_context();
         
         // Set a negative value
-        let shared_store: Rc<RefCell<dyn Store>> =
-            Rc::new(RefCell::new(StringStore::new("-1".to_string())));
-        ctx.DataBase.store.insert("counter".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        ctx.insert_key(b"counter".to_vec(), Value::String(StringStore::new(b"-1".to_vec())));
         
-        let command = Command::INCR { key: "counter".to_string() };
+        let command = Command::INCR { key: b"counter".to_vec() };
         let result = incr::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b":0\r\n");
     }
@@ -119,20 +99,28 @@ mod tests {
         let mut ctx = create_test_context();
         
         // Set a non-numeric value
-        let shared_store: Rc<RefCell<dyn Store>> =
-            Rc::new(RefCell::new(StringStore::new("not_a_number".to_string())));
-        ctx.DataBase.store.insert("counter".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        ctx.insert_key(b"counter".to_vec(), Value::String(StringStore::new(b"not_a_number".to_vec())));
         
-        let command = Command::INCR { key: "counter".to_string() };
+        let command = Command::INCR { key: b"counter".to_vec() };
         let result = incr::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b"-ERR value is not an integer or out of range\r\n");
     }
 
+    #[test]
+    fn test_incr_overflow() {
+        let mut ctx = create_test_context();
+        ctx.insert_key(b"counter".to_vec(), Value::String(StringStore::new(i64::MAX.to_string().into_bytes())));
+
+        let command = Command::INCR { key: b"counter".to_vec() };
+        let result = incr::execute(&command, &mut ctx).unwrap();
+        assert_eq!(result, b"-ERR increment or decrement would overflow\r\n");
+        assert_eq!(ctx.get::<StringStore>(b"counter").unwrap().unwrap().as_integer(), Some(i64::MAX));
+    }
+
     #[test]
     fn test_incr_wrong_command() {
         let mut ctx = create_test_context();
-        let command = Command::GET { key: "test".to_string() };
+        let command = Command::GET { key: b"test".to_vec() };
         
         let result = incr::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b"-ERR wrong command\r\n");
@@ -0,0 +1,16 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::incr::incr_by;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct incrby;
+
+impl commandExecutor for incrby {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::INCRBY { key, increment } => incr_by(context, key, *increment),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::bulk_string;
+use crate::store::string_store::StringStore;
+use crate::store::value::Value;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct incrbyfloat;
+
+impl commandExecutor for incrbyfloat {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::INCRBYFLOAT { key, increment } => {
+                let current_val = match context.get::<StringStore>(key)? {
+                    Some(string_store) => match string_store.as_float() {
+                        Some(current_val) => current_val,
+                        None => return Ok(b"-ERR value is not a valid float\r\n".to_vec()),
+                    },
+                    None => 0.0,
+                };
+                let new_val = current_val + increment;
+                if !new_val.is_finite() {
+                    return Ok(b"-ERR increment would produce NaN or Infinity\r\n".to_vec());
+                }
+
+                // Shortest representation that reads back as the same number
+                let encoded = new_val.to_string().into_bytes();
+                let reply = bulk_string(&encoded);
+                match context.get_mut::<StringStore>(key)? {
+                    Some(string_store) => {
+                        string_store.set_value(encoded);
+                        context.DataBase.mark_changed(key);
+                    }
+                    None => context.insert_key(key.to_owned(), Value::String(StringStore::new(encoded))),
+                }
+                Ok(reply)
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_incrbyfloat() {
+        let mut ctx = context::new();
+        ctx.insert_key(b"f".to_vec(), Value::String(StringStore::new(b"10.50".to_vec())));
+
+        let command = Command::INCRBYFLOAT { key: b"f".to_vec(), increment: 0.1 };
+        assert_eq!(incrbyfloat::execute(&command, &mut ctx).unwrap(), b"$4\r\n10.6\r\n");
+
+        let command = Command::INCRBYFLOAT { key: b"f".to_vec(), increment: -5.6 };
+        assert_eq!(incrbyfloat::execute(&command, &mut ctx).unwrap(), b"$1\r\n5\r\n");
+
+        let command = Command::INCRBYFLOAT { key: b"new".to_vec(), increment: 3e3 };
+        assert_eq!(incrbyfloat::execute(&command, &mut ctx).unwrap(), b"$4\r\n3000\r\n");
+    }
+
+    #[test]
+    fn test_incrbyfloat_errors() {
+        let mut ctx = context::new();
+        ctx.insert_key(b"text".to_vec(), Value::String(StringStore::new(b"abc".to_vec())));
+        ctx.insert_key(b"big".to_vec(), Value::String(StringStore::new(b"1.7e308".to_vec())));
+
+        let command = Command::INCRBYFLOAT { key: b"text".to_vec(), increment: 1.0 };
+        assert_eq!(incrbyfloat::execute(&command, &mut ctx).unwrap(), b"-ERR value is not a valid float\r\n");
+
+        let command = Command::INCRBYFLOAT { key: b"big".to_vec(), increment: 1.7e308 };
+        assert_eq!(
+            incrbyfloat::execute(&command, &mut ctx).unwrap(),
+            b"-ERR increment would produce NaN or Infinity\r\n"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use crate::command::command_enum::Command;
+use crate::config;
+use crate::resp::reply::bulk_string;
+use crate::store_containers::core_context::context;
+use crate::store_containers::PubSubStore::Subscription;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct info;
+
+// Sections in the order INFO prints them.
+const SECTIONS: &[&str] = &["memory", "persistence", "stats", "keyspace"];
+
+impl commandExecutor for info {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // Every section by default; unknown section names are ignored
+            Command::INFO { sections } => {
+                let requested: Vec<String> = sections
+                    .iter()
+                    .map(|section| String::from_utf8_lossy(section).to_ascii_lowercase())
+                    .collect();
+                let everything = requested.is_empty()
+                    || requested.iter().any(|section| matches!(section.as_str(), "all" | "default" | "everything"));
+                let mut text = String::new();
+                for section in SECTIONS {
+                    if !everything && !requested.iter().any(|requested| requested == section) {
+                        continue;
+                    }
+                    if !text.is_empty() {
+                        text.push_str("\r\n");
+                    }
+                    text.push_str(&render(section, context));
+                }
+                Ok(bulk_string(text.as_bytes()))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// One section: its header and a `field:value` line per field.
+fn render(section: &str, context: &context) -> String {
+    let fields: Vec<(&str, String)> = match section {
+        "memory" => {
+            let (maxmemory, policy) = config::with(|config| (config.maxmemory, config.maxmemory_policy));
+            vec![
+                ("used_memory", context.DataBase.used_memory.to_string()),
+                ("maxmemory", maxmemory.to_string()),
+                ("maxmemory_policy", policy.name().to_owned()),
+            ]
+        }
+        "persistence" => {
+            let persistence = &context.PersistenceStore;
+            let aof = &context.AofStore;
+            let status = |ok: bool| if ok { "ok" } else { "err" }.to_owned();
+            vec![
+                ("rdb_changes_since_last_save", context.DataBase.dirty.to_string()),
+                ("rdb_bgsave_in_progress", (persistence.is_saving() as u8).to_string()),
+                ("rdb_last_save_time", persistence.last_save.to_string()),
+                ("rdb_last_bgsave_status", status(persistence.last_bgsave_ok)),
+                ("aof_enabled", (aof.enabled as u8).to_string()),
+                ("aof_rewrite_in_progress", (aof.rewrite.is_some() as u8).to_string()),
+                ("aof_last_bgrewrite_status", status(aof.last_rewrite_ok)),
+                ("aof_last_write_status", status(aof.last_write_ok)),
+            ]
+        }
+        "stats" => {
+            let pubsub = &context.PubSubStore;
+            let count = |kind| pubsub.names(kind).len().to_string();
+            vec![
+                ("evicted_keys", context.EvictionStore.evicted_keys.to_string()),
+                ("pubsub_channels", count(Subscription::Channel)),
+                ("pubsub_patterns", count(Subscription::Pattern)),
+                ("pubsubshard_channels", count(Subscription::ShardChannel)),
+            ]
+        }
+        // Like Redis, an empty database is left out
+        _ if context.DataBase.store.is_empty() => Vec::new(),
+        _ => vec![(
+            "db0",
+            format!("keys={},expires={}", context.DataBase.store.len(), context.TTLStore.len()),
+        )],
+    };
+    let mut text = format!("# {}{}\r\n", section[..1].to_ascii_uppercase(), &section[1..]);
+    for (field, value) in fields {
+        text.push_str(&format!("{}:{}\r\n", field, value));
+    }
+    text
+}
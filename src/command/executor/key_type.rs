@@ -0,0 +1,19 @@
+use crate::command::command_enum::Command;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+// TYPE; named so it does not clash with the keyword.
+pub struct key_type;
+
+impl commandExecutor for key_type {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::TYPE { key } => {
+                let type_name = context.lookup_key(key).map_or("none", |entry| entry.type_name());
+                Ok(format!("+{}\r\n", type_name).into_bytes())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use crate::utils::glob::glob_match;
+use std::error::Error;
+
+pub struct keys;
+
+impl commandExecutor for keys {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::KEYS { pattern } => {
+                let matches = context
+                    .live_keys()
+                    .into_iter()
+                    .filter(|key| glob_match(pattern, key))
+                    .map(|key| bulk_string(key))
+                    .collect();
+                Ok(array(matches))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
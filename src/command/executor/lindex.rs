@@ -0,0 +1,22 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{bulk_string, NIL};
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct lindex;
+
+impl commandExecutor for lindex {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LINDEX { key, index } => {
+                let element = context
+                    .get::<VectorStore>(key)?
+                    .and_then(|vector_store| vector_store.get_index(*index));
+                Ok(element.map_or(NIL.to_vec(), |element| bulk_string(element)))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct linsert;
+
+impl commandExecutor for linsert {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LINSERT {
+                key,
+                before,
+                pivot,
+                value,
+            } => {
+                let vector_store = match context.get_mut::<VectorStore>(key)? {
+                    Some(vector_store) => vector_store,
+                    None => return Ok(integer(0)),
+                };
+                match vector_store.insert_at_pivot(pivot, value, *before) {
+                    Some(length) => {
+                        context.DataBase.mark_changed(key);
+                        Ok(integer(length as i64))
+                    }
+                    None => Ok(integer(-1)),
+                }
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
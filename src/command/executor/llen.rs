@@ -0,0 +1,20 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct llen;
+
+impl commandExecutor for llen {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LLEN { key } => {
+                let length = context.get::<VectorStore>(key)?.map_or(0, |vector_store| vector_store.len());
+                Ok(integer(length as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
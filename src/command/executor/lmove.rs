@@ -0,0 +1,64 @@
+use crate::command::command_enum::{Command, ListEnd};
+use crate::resp::reply::{bulk_string, NIL};
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct lmove;
+
+impl commandExecutor for lmove {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LMOVE {
+                source,
+                destination,
+                from,
+                to,
+            } => match move_element(context, source, destination, *from, *to)? {
+                Some(element) => Ok(bulk_string(&element)),
+                None => Ok(NIL.to_vec()),
+            },
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// Moves one element between two lists, which may be the same key. The
+// destination is type checked before anything is popped, so a WRONGTYPE error
+// leaves both keys untouched. None if the source does not exist.
+pub fn move_element(
+    context: &mut context,
+    source: &[u8],
+    destination: &[u8],
+    from: ListEnd,
+    to: ListEnd,
+) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    if context.get::<VectorStore>(source)?.is_none() {
+        return Ok(None);
+    }
+    context.get::<VectorStore>(destination)?;
+
+    let source_store = context.get_mut::<VectorStore>(source)?.expect("source was just checked");
+    let element = match from {
+        ListEnd::Left => source_store.pop_left(),
+        ListEnd::Right => source_store.pop_right(),
+    };
+    let element = match element {
+        Some(element) => element,
+        None => return Ok(None),
+    };
+    context.DataBase.mark_changed(source);
+
+    // Push before cleaning up the source, so rotating a one-element list in
+    // place does not delete the key and its TTL
+    let destination_store = context.get_or_create::<VectorStore>(destination)?;
+    match to {
+        ListEnd::Left => destination_store.push_left(&element),
+        ListEnd::Right => destination_store.push_right(&element),
+    };
+    context.DataBase.mark_changed(destination);
+    context.BlockingStore.signal_key_ready(destination);
+    context.delete_if_empty(source);
+    Ok(Some(element))
+}
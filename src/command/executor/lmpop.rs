@@ -0,0 +1,28 @@
+use crate::command::command_enum::{Command, ListEnd};
+use crate::command::executor::lpop::pop_elements;
+use crate::resp::reply::{array, bulk_string, NIL_ARRAY};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct lmpop;
+
+impl commandExecutor for lmpop {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LMPOP { keys, end, count } => mpop(context, keys, *end, *count),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// Pops from the first key that holds a list: [key, [elements]]. Shared with BLMPOP.
+pub fn mpop(context: &mut context, keys: &[Vec<u8>], end: ListEnd, count: usize) -> Result<Vec<u8>, Box<dyn Error>> {
+    for key in keys {
+        if let Some(elements) = pop_elements(context, key, end, count)? {
+            let elements = elements.iter().map(|element| bulk_string(element)).collect();
+            return Ok(array(vec![bulk_string(key), array(elements)]));
+        }
+    }
+    Ok(NIL_ARRAY.to_vec())
+}
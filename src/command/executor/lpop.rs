@@ -0,0 +1,64 @@
+use crate::command::command_enum::{Command, ListEnd};
+use crate::resp::reply::{array, bulk_string, NIL, NIL_ARRAY};
+use crate::store::value::WrongTypeError;
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct lpop;
+
+impl commandExecutor for lpop {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LPOP { key, count } => pop_reply(context, key, ListEnd::Left, *count),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// Pops up to `count` elements from one end of the list at `key`, deleting the
+// key if that empties it. None if there is no such key.
+pub fn pop_elements(
+    context: &mut context,
+    key: &[u8],
+    end: ListEnd,
+    count: usize,
+) -> Result<Option<Vec<Vec<u8>>>, WrongTypeError> {
+    let vector_store = match context.get_mut::<VectorStore>(key)? {
+        Some(vector_store) => vector_store,
+        None => return Ok(None),
+    };
+    let mut elements = Vec::with_capacity(count.min(vector_store.len()));
+    while elements.len() < count {
+        let element = match end {
+            ListEnd::Left => vector_store.pop_left(),
+            ListEnd::Right => vector_store.pop_right(),
+        };
+        match element {
+            Some(element) => elements.push(element),
+            None => break,
+        }
+    }
+    if !elements.is_empty() {
+        context.DataBase.mark_changed(key);
+    }
+    context.delete_if_empty(key);
+    Ok(Some(elements))
+}
+
+// Shared by LPOP and RPOP: a single bulk string without a count, an array with one.
+pub fn pop_reply(
+    context: &mut context,
+    key: &[u8],
+    end: ListEnd,
+    count: Option<usize>,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let elements = pop_elements(context, key, end, count.unwrap_or(1))?;
+    match (elements, count) {
+        (None, None) => Ok(NIL.to_vec()),
+        (None, Some(_)) => Ok(NIL_ARRAY.to_vec()),
+        (Some(elements), None) => Ok(elements.first().map_or(NIL.to_vec(), |element| bulk_string(element))),
+        (Some(elements), Some(_)) => Ok(array(elements.iter().map(|element| bulk_string(element)).collect())),
+    }
+}
@@ -0,0 +1,33 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, integer, NIL};
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct lpos;
+
+impl commandExecutor for lpos {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LPOS {
+                key,
+                value,
+                rank,
+                count,
+                maxlen,
+            } => {
+                // Without COUNT only the first match is wanted
+                let positions = match context.get::<VectorStore>(key)? {
+                    Some(vector_store) => vector_store.positions(value, *rank, count.unwrap_or(1), *maxlen),
+                    None => Vec::new(),
+                };
+                match count {
+                    Some(_) => Ok(array(positions.iter().map(|&position| integer(position as i64)).collect())),
+                    None => Ok(positions.first().map_or(NIL.to_vec(), |&position| integer(position as i64))),
+                }
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
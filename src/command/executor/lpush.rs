@@ -1,84 +1,66 @@
-use crate::command::command_enum::Command;
+use crate::command::command_enum::{Command, ListEnd};
+use crate::resp::reply::integer;
 use crate::store::vector_store::VectorStore;
 use crate::store_containers::core_context::context;
 use crate::traits::command::commandExecutor;
-use crate::traits::Store::Store;
-use std::cell::RefCell;
 use std::error::Error;
-use std::rc::Rc;
 
 pub struct lpush;
 
 impl commandExecutor for lpush {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
-            Command::LPUSH { key, values } => {
-                match context.DataBase.store.get(key.as_str()) {
-                    Some(Some(weak_ref)) => {
-                        match weak_ref.upgrade() {
-                            Some(store_ref) => {
-                                let mut store = store_ref.borrow_mut();
-                                if let Some(vector_store) = (&mut *store as &mut dyn std::any::Any).downcast_mut::<VectorStore>() {
-                                    for value in values.iter().rev() { // Reverse to maintain order
-                                        vector_store.push_left(value);
-                                    }
-                                    let length = vector_store.len();
-                                    Ok(format!(":{}\r\n", length).into_bytes())
-                                } else {
-                                    Ok(b"-ERR WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_vec())
-                                }
-                            }
-                            None => {
-                                // Create new list
-                                let mut new_list = VectorStore::new();
-                                for value in values.iter().rev() {
-                                    new_list.push_left(value);
-                                }
-                                let length = new_list.len();
-                                let shared_store: Rc<RefCell<dyn Store>> =
-                                    Rc::new(RefCell::new(new_list));
-                                context
-                                    .DataBase
-                                    .store
-                                    .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                                context.TTLStore.store.insert(86400, shared_store);
-                                Ok(format!(":{}\r\n", length).into_bytes())
-                            }
-                        }
-                    }
-                    Some(None) | None => {
-                        // Create new list
-                        let mut new_list = VectorStore::new();
-                        for value in values.iter().rev() {
-                            new_list.push_left(value);
-                        }
-                        let length = new_list.len();
-                        let shared_store: Rc<RefCell<dyn Store>> =
-                            Rc::new(RefCell::new(new_list));
-                        context
-                            .DataBase
-                            .store
-                            .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                        context.TTLStore.store.insert(86400, shared_store);
-                        Ok(format!(":{}\r\n", length).into_bytes())
-                    }
-                }
-            }
+            Command::LPUSH { key, values } => push(context, key, values, ListEnd::Left, true),
             _ => Ok(b"-ERR wrong command\r\n".to_vec()),
         }
     }
 }
 
+// Shared by LPUSH, RPUSH, LPUSHX and RPUSHX. Replies with the new length. With
+// `create` unset a missing key is left alone and the reply is 0.
+pub fn push(
+    context: &mut context,
+    key: &[u8],
+    values: &[Vec<u8>],
+    end: ListEnd,
+    create: bool,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    if values.is_empty() || !create {
+        match context.get_mut::<VectorStore>(key)? {
+            Some(_) if !values.is_empty() => {}
+            Some(vector_store) => return Ok(integer(vector_store.len() as i64)),
+            None => return Ok(integer(0)),
+        }
+    }
+    let vector_store = context.get_or_create::<VectorStore>(key)?;
+    for value in values {
+        match end {
+            ListEnd::Left => vector_store.push_left(value),
+            ListEnd::Right => vector_store.push_right(value),
+        };
+    }
+    let length = vector_store.len();
+    context.DataBase.mark_changed(key);
+    context.BlockingStore.signal_key_ready(key);
+    Ok(integer(length as i64))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::store_containers::{DictStore::DictStore, TTLStore::TTLStore};
+    use crate::store::value::Value;
+    use crate::store_containers::{AofStore::AofStore, BlockingStore::BlockingStore, DictStore::DictStore, EvictionStore::EvictionStore, PersistenceStore::PersistenceStore, PubSubStore::PubSubStore, TTLStore::TTLStore, WatchStore::WatchStore};
 
     fn create_test_context() -> context {
         context {
             DataBase: DictStore::new(),
             TTLStore: TTLStore::new(),
-            EvictionStore: "TODO".to_owned(),
+            EvictionStore: EvictionStore::new(),
+            BlockingStore: BlockingStore::new(),
+            PersistenceStore: PersistenceStore::new(),
+            AofStore: AofStore::new(),
+            WatchStore: WatchStore::new(),
+            PubSubStore: PubSubStore::new(),
         }
     }
 
@@ -86,8 +68,8 @@ mod tests {
     fn test_lpush_new_list() {
         let mut ctx = create_test_context();
         let command = Command::LPUSH {
-            key: "mylist".to_string(),
-            values: vec!["item1".to_string(), "item2".to_string(), "item3".to_string()],
+            key: b"mylist".to_vec(),
+            values: vec![b"item1".to_vec(), b"item2".to_vec(), b"item3".to_vec()],
         };
         
         let result = lpush::execute(&command, &mut ctx).unwrap();
@@ -100,14 +82,12 @@ mod tests {
         
         // Create existing list
         let mut existing_list = VectorStore::new();
-        existing_list.push_left("existing");
-        let shared_store: Rc<RefCell<dyn Store>> = Rc::new(RefCell::new(existing_list));
-        ctx.DataBase.store.insert("mylist".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        existing_list.push_left(b"existing");
+        ctx.insert_key(b"mylist".to_vec(), Value::List(existing_list));
         
         let command = Command::LPUSH {
-            key: "mylist".to_string(),
-            values: vec!["item1".to_string(), "item2".to_string()],
+            key: b"mylist".to_vec(),
+            values: vec![b"item1".to_vec(), b"item2".to_vec()],
         };
         
         let result = lpush::execute(&command, &mut ctx).unwrap();
@@ -118,8 +98,8 @@ mod tests {
     fn test_lpush_single_value() {
         let mut ctx = create_test_context();
         let command = Command::LPUSH {
-            key: "mylist".to_string(),
-            values: vec!["single".to_string()],
+            key: b"mylist".to_vec(),
+            values: vec![b"single".to_vec()],
         };
         
         let result = lpush::execute(&command, &mut ctx).unwrap();
@@ -130,7 +110,7 @@ mod tests {
     fn test_lpush_empty_values() {
         let mut ctx = create_test_context();
         let command = Command::LPUSH {
-            key: "mylist".to_string(),
+            key: b"mylist".to_vec(),
             values: vec![],
         };
         
@@ -141,7 +121,7 @@ mod tests {
     #[test]
     fn test_lpush_wrong_command() {
         let mut ctx = create_test_context();
-        let command = Command::GET { key: "test".to_string() };
+        let command = Command::GET { key: b"test".to_vec() };
         
         let result = lpush::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b"-ERR wrong command\r\n");
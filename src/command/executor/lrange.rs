@@ -0,0 +1,23 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string};
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct lrange;
+
+impl commandExecutor for lrange {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LRANGE { key, start, stop } => {
+                let elements = match context.get::<VectorStore>(key)? {
+                    Some(vector_store) => vector_store.get_range(*start, *stop),
+                    None => Vec::new(),
+                };
+                Ok(array(elements.iter().map(|element| bulk_string(element)).collect()))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct lrem;
+
+impl commandExecutor for lrem {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LREM { key, count, value } => {
+                let removed = match context.get_mut::<VectorStore>(key)? {
+                    Some(vector_store) => vector_store.remove_value(value, *count),
+                    None => 0,
+                };
+                if removed > 0 {
+                    context.DataBase.mark_changed(key);
+                }
+                context.delete_if_empty(key);
+                Ok(integer(removed as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use crate::command::command_enum::Command;
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct lset;
+
+impl commandExecutor for lset {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LSET { key, index, value } => match context.get_mut::<VectorStore>(key)? {
+                Some(vector_store) => match vector_store.set_index(*index, value) {
+                    true => {
+                        context.DataBase.mark_changed(key);
+                        Ok(b"+OK\r\n".to_vec())
+                    }
+                    false => Ok(b"-ERR index out of range\r\n".to_vec()),
+                },
+                None => Ok(b"-ERR no such key\r\n".to_vec()),
+            },
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use crate::command::command_enum::Command;
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct ltrim;
+
+impl commandExecutor for ltrim {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::LTRIM { key, start, stop } => {
+                if let Some(vector_store) = context.get_mut::<VectorStore>(key)? {
+                    let length = vector_store.len();
+                    vector_store.trim(*start, *stop);
+                    if vector_store.len() != length {
+                        context.DataBase.mark_changed(key);
+                        context.delete_if_empty(key);
+                    }
+                }
+                Ok(b"+OK\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
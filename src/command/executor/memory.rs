@@ -0,0 +1,50 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, double, integer, NIL};
+use crate::store_containers::core_context::context;
+use crate::store_containers::DictStore::entry_size;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct memory;
+
+impl commandExecutor for memory {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // Bytes of the key, its value and its slot in the keyspace
+            Command::MEMORY_USAGE { key, samples } => match context.peek_key(key) {
+                Some(entry) => Ok(integer(entry_size(key, &entry.value, *samples) as i64)),
+                None => Ok(NIL.to_vec()),
+            },
+            // The dataset is what maxmemory counts; the keyspace's bucket
+            // array and the TTL index are overhead on top of it
+            Command::MEMORY_STATS => {
+                let dataset = context.DataBase.used_memory;
+                let main = context.DataBase.store.bucket_array_size();
+                let expires = context.TTLStore.memory_usage();
+                let overhead = main + expires;
+                let total = dataset + overhead;
+                let keys = context.DataBase.store.len();
+                let field = |name: &str, value: Vec<u8>| vec![bulk_string(name.as_bytes()), value];
+                let items = [
+                    field("total.allocated", integer(total as i64)),
+                    field("overhead.total", integer(overhead as i64)),
+                    field(
+                        "db.0",
+                        array(vec![
+                            bulk_string(b"overhead.hashtable.main"),
+                            integer(main as i64),
+                            bulk_string(b"overhead.hashtable.expires"),
+                            integer(expires as i64),
+                        ]),
+                    ),
+                    field("keys.count", integer(keys as i64)),
+                    field("keys.bytes-per-key", integer(total.checked_div(keys).unwrap_or(0) as i64)),
+                    field("dataset.bytes", integer(dataset as i64)),
+                    field("dataset.percentage", double(dataset as f64 * 100.0 / total.max(1) as f64)),
+                ];
+                Ok(array(items.into_iter().flatten().collect()))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, NIL};
+use crate::store::string_store::StringStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct mget;
+
+impl commandExecutor for mget {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::MGET { keys } => {
+                // Keys holding another type read as nil instead of failing the command
+                let values = keys
+                    .iter()
+                    .map(|key| match context.get::<StringStore>(key) {
+                        Ok(Some(string_store)) => bulk_string(string_store.get_value()),
+                        _ => NIL.to_vec(),
+                    })
+                    .collect();
+                Ok(array(values))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -12,5 +12,104 @@ pub mod decr;
 pub mod hset;
 pub mod hget;
 pub mod lpush;
+pub mod rpush;
+pub mod lpushx;
+pub mod rpushx;
+pub mod lpop;
+pub mod rpop;
+pub mod lrange;
+pub mod llen;
+pub mod lindex;
+pub mod lset;
+pub mod linsert;
+pub mod lrem;
+pub mod ltrim;
+pub mod lpos;
+pub mod lmove;
+pub mod lmpop;
+pub mod blpop;
+pub mod brpop;
+pub mod blmove;
+pub mod blmpop;
 pub mod sadd;
+pub mod srem;
+pub mod smembers;
+pub mod sismember;
+pub mod scard;
+pub mod smismember;
+pub mod smove;
+pub mod spop;
+pub mod srandmember;
+pub mod sunion;
+pub mod sinter;
+pub mod sdiff;
+pub mod sintercard;
 pub mod zadd;
+pub mod zrem;
+pub mod zrange;
+pub mod zcard;
+pub mod zrank;
+pub mod zrevrank;
+pub mod zscore;
+pub mod zmscore;
+pub mod zcount;
+pub mod zlexcount;
+pub mod zincrby;
+pub mod zpopmin;
+pub mod zpopmax;
+pub mod zremrange;
+pub mod zrandmember;
+pub mod zunion;
+pub mod zinter;
+pub mod zdiff;
+pub mod zintercard;
+pub mod ttl;
+pub mod persist;
+pub mod incrby;
+pub mod decrby;
+pub mod incrbyfloat;
+pub mod append;
+pub mod strlen;
+pub mod mget;
+pub mod mset;
+pub mod msetnx;
+pub mod getrange;
+pub mod setrange;
+pub mod getset;
+pub mod getdel;
+pub mod getex;
+pub mod setnx;
+pub mod setex;
+pub mod keys;
+pub mod key_type;
+pub mod pttl;
+pub mod rename;
+pub mod renamenx;
+pub mod copy;
+pub mod dump;
+pub mod touch;
+pub mod unlink;
+pub mod randomkey;
+pub mod scan;
+pub mod hscan;
+pub mod sscan;
+pub mod zscan;
+pub mod hgetall;
+pub mod hdel;
+pub mod hexists;
+pub mod hlen;
+pub mod hkeys;
+pub mod hvals;
+pub mod hmget;
+pub mod hsetnx;
+pub mod hincrby;
+pub mod hincrbyfloat;
+pub mod hstrlen;
+pub mod hrandfield;
+pub mod object;
+pub mod config;
+pub mod info;
+pub mod memory;
+pub mod save;
+pub mod publish;
+pub mod pubsub;
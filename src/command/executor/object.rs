@@ -0,0 +1,40 @@
+use crate::command::command_enum::Command;
+use crate::config;
+use crate::resp::reply::{bulk_string, integer, NIL};
+use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct object;
+
+impl commandExecutor for object {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        // OBJECT inspects a key without counting as an access to it
+        match commandObject {
+            Command::OBJECT_ENCODING { key } => match context.peek_key(key) {
+                Some(entry) => Ok(bulk_string(entry.encoding().as_bytes())),
+                None => Ok(NIL.to_vec()),
+            },
+            Command::OBJECT_FREQ { key } => {
+                if !config::with(|config| config.maxmemory_policy.is_lfu()) {
+                    return Ok(b"-ERR An LFU maxmemory policy is not selected, access frequency not tracked.\r\n".to_vec());
+                }
+                match context.peek_key(key) {
+                    Some(entry) => Ok(integer(entry.lfu_count(now_ms()) as i64)),
+                    None => Ok(NIL.to_vec()),
+                }
+            }
+            Command::OBJECT_IDLETIME { key } => {
+                if config::with(|config| config.maxmemory_policy.is_lfu()) {
+                    return Ok(b"-ERR An LRU maxmemory policy is not selected, access time not tracked.\r\n".to_vec());
+                }
+                match context.peek_key(key) {
+                    Some(entry) => Ok(integer((now_ms().saturating_sub(entry.lru) / 1000) as i64)),
+                    None => Ok(NIL.to_vec()),
+                }
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
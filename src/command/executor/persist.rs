@@ -0,0 +1,22 @@
+use crate::command::command_enum::Command;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct persist;
+
+impl commandExecutor for persist {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::PERSIST { key } => {
+                // The lookup drops the key if it already expired
+                if context.lookup_key(key).is_some() && context.persist_key(key) {
+                    Ok(b":1\r\n".to_vec()) // Timeout removed
+                } else {
+                    Ok(b":0\r\n".to_vec()) // Key missing or without a timeout
+                }
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
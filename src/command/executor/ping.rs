@@ -6,7 +6,7 @@ use std::error::Error;
 pub struct ping;
 
 impl commandExecutor for ping {
-    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+    fn execute(commandObject: &Command, _context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
             Command::PING => {
                 // Return PONG in RESP simple string format
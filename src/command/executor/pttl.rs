@@ -0,0 +1,26 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct pttl;
+
+impl commandExecutor for pttl {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::PTTL { key } => {
+                // Same -2/-1 conventions as TTL, in milliseconds
+                if context.lookup_key(key).is_none() {
+                    return Ok(b":-2\r\n".to_vec());
+                }
+                match context.get_expiry(key) {
+                    Some(deadline) => Ok(integer(deadline.saturating_sub(now_ms()) as i64)),
+                    None => Ok(b":-1\r\n".to_vec()),
+                }
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct publish;
+
+impl commandExecutor for publish {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // Replies how many clients it was sent to
+            Command::PUBLISH { channel, message } => Ok(integer(context.PubSubStore.publish(channel, message) as i64)),
+            Command::SPUBLISH { channel, message } => {
+                Ok(integer(context.PubSubStore.publish_shard(channel, message) as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
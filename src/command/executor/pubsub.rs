@@ -0,0 +1,41 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, integer};
+use crate::store_containers::core_context::context;
+use crate::store_containers::PubSubStore::Subscription;
+use crate::traits::command::commandExecutor;
+use crate::utils::glob::glob_match;
+use std::error::Error;
+
+pub struct pubsub;
+
+impl commandExecutor for pubsub {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        let store = &context.PubSubStore;
+        // Active channels, those with at least one subscriber
+        let channels = |kind: Subscription, pattern: &Option<Vec<u8>>| {
+            let names = store
+                .names(kind)
+                .into_iter()
+                .filter(|name| pattern.as_ref().is_none_or(|pattern| glob_match(pattern, name)))
+                .map(|name| bulk_string(name))
+                .collect();
+            array(names)
+        };
+        let numsub = |kind: Subscription, names: &[Vec<u8>]| {
+            let counts = names
+                .iter()
+                .flat_map(|name| [bulk_string(name), integer(store.subscriber_count(kind, name) as i64)])
+                .collect();
+            array(counts)
+        };
+        match commandObject {
+            Command::PUBSUB_CHANNELS { pattern } => Ok(channels(Subscription::Channel, pattern)),
+            Command::PUBSUB_SHARDCHANNELS { pattern } => Ok(channels(Subscription::ShardChannel, pattern)),
+            Command::PUBSUB_NUMSUB { channels } => Ok(numsub(Subscription::Channel, channels)),
+            Command::PUBSUB_SHARDNUMSUB { channels } => Ok(numsub(Subscription::ShardChannel, channels)),
+            // Distinct patterns across all clients
+            Command::PUBSUB_NUMPAT => Ok(integer(store.names(Subscription::Pattern).len() as i64)),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
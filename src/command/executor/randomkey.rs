@@ -0,0 +1,19 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{bulk_string, NIL};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct randomkey;
+
+impl commandExecutor for randomkey {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::RANDOMKEY => match context.random_key() {
+                Some(key) => Ok(bulk_string(&key)),
+                None => Ok(NIL.to_vec()),
+            },
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
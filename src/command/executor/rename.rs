@@ -0,0 +1,26 @@
+use crate::command::command_enum::Command;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct rename;
+
+impl commandExecutor for rename {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::RENAME { key, newkey } => {
+                if context.lookup_key(key).is_none() {
+                    return Ok(b"-ERR no such key\r\n".to_vec());
+                }
+                if key != newkey {
+                    // The value moves with its TTL and overwrites newkey
+                    if let Some(entry) = context.take_entry(key) {
+                        context.insert_entry(newkey.to_owned(), entry);
+                    }
+                }
+                Ok(b"+OK\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
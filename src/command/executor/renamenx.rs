@@ -0,0 +1,26 @@
+use crate::command::command_enum::Command;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct renamenx;
+
+impl commandExecutor for renamenx {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::RENAMENX { key, newkey } => {
+                if context.lookup_key(key).is_none() {
+                    return Ok(b"-ERR no such key\r\n".to_vec());
+                }
+                if key == newkey || context.lookup_key(newkey).is_some() {
+                    return Ok(b":0\r\n".to_vec());
+                }
+                if let Some(entry) = context.take_entry(key) {
+                    context.insert_entry(newkey.to_owned(), entry);
+                }
+                Ok(b":1\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::command::command_enum::{Command, ListEnd};
+use crate::command::executor::lpop::pop_reply;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct rpop;
+
+impl commandExecutor for rpop {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::RPOP { key, count } => pop_reply(context, key, ListEnd::Right, *count),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
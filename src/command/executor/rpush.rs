@@ -0,0 +1,16 @@
+use crate::command::command_enum::{Command, ListEnd};
+use crate::command::executor::lpush::push;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct rpush;
+
+impl commandExecutor for rpush {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::RPUSH { key, values } => push(context, key, values, ListEnd::Right, true),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::command::command_enum::{Command, ListEnd};
+use crate::command::executor::lpush::push;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct rpushx;
+
+impl commandExecutor for rpushx {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::RPUSHX { key, values } => push(context, key, values, ListEnd::Right, false),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
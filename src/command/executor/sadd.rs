@@ -2,10 +2,7 @@ use crate::command::command_enum::Command;
 use crate::store::set_store::SetStore;
 use crate::store_containers::core_context::context;
 use crate::traits::command::commandExecutor;
-use crate::traits::Store::Store;
-use std::cell::RefCell;
 use std::error::Error;
-use std::rc::Rc;
 
 pub struct sadd;
 
@@ -13,61 +10,17 @@ impl commandExecutor for sadd {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
             Command::SADD { key, members } => {
+                let set_store = context.get_or_create::<SetStore>(key)?;
                 let mut added_count = 0;
-                
-                match context.DataBase.store.get(key.as_str()) {
-                    Some(Some(weak_ref)) => {
-                        match weak_ref.upgrade() {
-                            Some(store_ref) => {
-                                let mut store = store_ref.borrow_mut();
-                                if let Some(set_store) = (&mut *store as &mut dyn std::any::Any).downcast_mut::<SetStore>() {
-                                    for member in members {
-                                        if set_store.add_member(member) {
-                                            added_count += 1;
-                                        }
-                                    }
-                                    Ok(format!(":{}\r\n", added_count).into_bytes())
-                                } else {
-                                    Ok(b"-ERR WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_vec())
-                                }
-                            }
-                            None => {
-                                // Create new set
-                                let mut new_set = SetStore::new();
-                                for member in members {
-                                    if new_set.add_member(member) {
-                                        added_count += 1;
-                                    }
-                                }
-                                let shared_store: Rc<RefCell<dyn Store>> =
-                                    Rc::new(RefCell::new(new_set));
-                                context
-                                    .DataBase
-                                    .store
-                                    .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                                context.TTLStore.store.insert(86400, shared_store);
-                                Ok(format!(":{}\r\n", added_count).into_bytes())
-                            }
-                        }
-                    }
-                    Some(None) | None => {
-                        // Create new set
-                        let mut new_set = SetStore::new();
-                        for member in members {
-                            if new_set.add_member(member) {
-                                added_count += 1;
-                            }
-                        }
-                        let shared_store: Rc<RefCell<dyn Store>> =
-                            Rc::new(RefCell::new(new_set));
-                        context
-                            .DataBase
-                            .store
-                            .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                        context.TTLStore.store.insert(86400, shared_store);
-                        Ok(format!(":{}\r\n", added_count).into_bytes())
+                for member in members {
+                    if set_store.add_member(member) {
+                        added_count += 1;
                     }
                 }
+                if added_count > 0 {
+                    context.DataBase.mark_changed(key);
+                }
+                Ok(format!(":{}\r\n", added_count).into_bytes())
             }
             _ => Ok(b"-ERR wrong command\r\n".to_vec()),
         }
@@ -77,13 +30,19 @@ impl commandExecutor for sadd {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::store_containers::{DictStore::DictStore, TTLStore::TTLStore};
+    use crate::store::value::Value;
+    use crate::store_containers::{AofStore::AofStore, BlockingStore::BlockingStore, DictStore::DictStore, EvictionStore::EvictionStore, PersistenceStore::PersistenceStore, PubSubStore::PubSubStore, TTLStore::TTLStore, WatchStore::WatchStore};
 
     fn create_test_context() -> context {
         context {
             DataBase: DictStore::new(),
             TTLStore: TTLStore::new(),
-            EvictionStore: "TODO".to_owned(),
+            EvictionStore: EvictionStore::new(),
+            BlockingStore: BlockingStore::new(),
+            PersistenceStore: PersistenceStore::new(),
+            AofStore: AofStore::new(),
+            WatchStore: WatchStore::new(),
+            PubSubStore: PubSubStore::new(),
         }
     }
 
@@ -91,8 +50,8 @@ mod tests {
     fn test_sadd_new_set() {
         let mut ctx = create_test_context();
         let command = Command::SADD {
-            key: "myset".to_string(),
-            members: vec!["member1".to_string(), "member2".to_string(), "member3".to_string()],
+            key: b"myset".to_vec(),
+            members: vec![b"member1".to_vec(), b"member2".to_vec(), b"member3".to_vec()],
         };
         
         let result = sadd::execute(&command, &mut ctx).unwrap();
@@ -105,15 +64,13 @@ mod tests {
         
         // Create existing set
         let mut existing_set = SetStore::new();
-        existing_set.add_member("existing1");
-        existing_set.add_member("existing2");
-        let shared_store: Rc<RefCell<dyn Store>> = Rc::new(RefCell::new(existing_set));
-        ctx.DataBase.store.insert("myset".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        existing_set.add_member(b"existing1");
+        existing_set.add_member(b"existing2");
+        ctx.insert_key(b"myset".to_vec(), Value::Set(existing_set));
         
         let command = Command::SADD {
-            key: "myset".to_string(),
-            members: vec!["existing1".to_string(), "new1".to_string(), "new2".to_string()],
+            key: b"myset".to_vec(),
+            members: vec![b"existing1".to_vec(), b"new1".to_vec(), b"new2".to_vec()],
         };
         
         let result = sadd::execute(&command, &mut ctx).unwrap();
@@ -124,8 +81,8 @@ mod tests {
     fn test_sadd_duplicate_members() {
         let mut ctx = create_test_context();
         let command = Command::SADD {
-            key: "myset".to_string(),
-            members: vec!["member1".to_string(), "member1".to_string(), "member2".to_string()],
+            key: b"myset".to_vec(),
+            members: vec![b"member1".to_vec(), b"member1".to_vec(), b"member2".to_vec()],
         };
         
         let result = sadd::execute(&command, &mut ctx).unwrap();
@@ -136,7 +93,7 @@ mod tests {
     fn test_sadd_empty_members() {
         let mut ctx = create_test_context();
         let command = Command::SADD {
-            key: "myset".to_string(),
+            key: b"myset".to_vec(),
             members: vec![],
         };
         
@@ -148,8 +105,8 @@ mod tests {
     fn test_sadd_single_member() {
         let mut ctx = create_test_context();
         let command = Command::SADD {
-            key: "myset".to_string(),
-            members: vec!["single".to_string()],
+            key: b"myset".to_vec(),
+            members: vec![b"single".to_vec()],
         };
         
         let result = sadd::execute(&command, &mut ctx).unwrap();
@@ -159,7 +116,7 @@ mod tests {
     #[test]
     fn test_sadd_wrong_command() {
         let mut ctx = create_test_context();
-        let command = Command::GET { key: "test".to_string() };
+        let command = Command::GET { key: b"test".to_vec() };
         
         let result = sadd::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b"-ERR wrong command\r\n");
@@ -0,0 +1,36 @@
+use crate::command::command_enum::Command;
+use crate::persistence::{self, aof};
+use crate::resp::reply::integer;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct save;
+
+impl commandExecutor for save {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SAVE => {
+                if context.PersistenceStore.is_saving() {
+                    return Ok(b"-ERR Background save already in progress\r\n".to_vec());
+                }
+                match persistence::save(context) {
+                    Ok(()) => Ok(b"+OK\r\n".to_vec()),
+                    Err(e) => Ok(format!("-ERR {}\r\n", e).into_bytes()),
+                }
+            }
+            Command::BGSAVE => match persistence::background_save(context) {
+                Ok(()) => Ok(b"+Background saving started\r\n".to_vec()),
+                Err(error) => Ok(format!("-ERR {}\r\n", error).into_bytes()),
+            },
+            // Unix time of the last successful save
+            Command::LASTSAVE => Ok(integer(context.PersistenceStore.last_save as i64)),
+            // Works whether or not appendonly is on, like in Redis
+            Command::BGREWRITEAOF => match aof::background_rewrite(context) {
+                Ok(()) => Ok(b"+Background append only file rewriting started\r\n".to_vec()),
+                Err(error) => Ok(format!("-ERR {}\r\n", error).into_bytes()),
+            },
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
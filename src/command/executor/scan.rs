@@ -0,0 +1,50 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use crate::utils::glob::glob_match;
+use std::error::Error;
+
+pub struct scan;
+
+// [cursor, [items...]], the reply shape of every command in the SCAN family.
+// The cursor is sent as a bulk string, as Redis does.
+pub fn scan_reply(cursor: u64, items: Vec<Vec<u8>>) -> Vec<u8> {
+    array(vec![bulk_string(cursor.to_string().as_bytes()), array(items)])
+}
+
+pub fn matches_pattern(pattern: &Option<Vec<u8>>, candidate: &[u8]) -> bool {
+    pattern.as_ref().is_none_or(|pattern| glob_match(pattern, candidate))
+}
+
+impl commandExecutor for scan {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SCAN { cursor, options } => {
+                let mut keys = Vec::new();
+                let next_cursor = context
+                    .DataBase
+                    .store
+                    .scan_batch(*cursor, options.count, |key, _| keys.push(key.clone()));
+
+                // Filters apply after the walk, so a page may hold fewer than COUNT
+                // keys, or none, while the cursor is still non-zero
+                let mut items = Vec::new();
+                for key in keys {
+                    let type_name = match context.lookup_key(&key) {
+                        Some(entry) => entry.type_name(),
+                        None => continue, // expired, and now deleted
+                    };
+                    if options.type_name.as_ref().is_some_and(|wanted| wanted != type_name.as_bytes()) {
+                        continue;
+                    }
+                    if matches_pattern(&options.pattern, &key) {
+                        items.push(bulk_string(&key));
+                    }
+                }
+                Ok(scan_reply(next_cursor, items))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct scard;
+
+impl commandExecutor for scard {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SCARD { key } => {
+                let length = context.get::<SetStore>(key)?.map_or(0, |set_store| set_store.len());
+                Ok(integer(length as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::sunion::{read_sets, reply_or_store};
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct sdiff;
+
+impl commandExecutor for sdiff {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // The members of the first set found in none of the others
+            Command::SDIFF { destination, keys } => {
+                let mut result = SetStore::new();
+                let sets = read_sets(context, keys)?;
+                let (first, rest) = sets.split_first().expect("the parser requires a key");
+                if let Some(first) = first {
+                    for member in first.iter() {
+                        if !rest.iter().flatten().any(|set_store| set_store.is_member(&member)) {
+                            result.add_member(&member);
+                        }
+                    }
+                }
+                Ok(reply_or_store(context, destination, result))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -1,47 +1,65 @@
-use crate::command::command_enum::Command;
+use crate::command::command_enum::{Command, SetCondition};
+use crate::resp::reply::{bulk_string, NIL};
 use crate::store::string_store::StringStore;
+use crate::store::value::Value;
 use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
 use crate::traits::command::commandExecutor;
-use crate::traits::Store::Store;
-use std::cell::RefCell;
 use std::error::Error;
-use std::rc::{Rc, Weak};
 
 pub struct set;
 
 impl commandExecutor for set {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
-            Command::SET { key, value, ttl } => match ttl {
-                Some(_val) => {
-                    let shared_store: Rc<RefCell<dyn Store>> =
-                        Rc::new(RefCell::new(StringStore::new(value.to_owned())));
+            Command::SET {
+                key,
+                value,
+                expiry,
+                condition,
+                get,
+                keep_ttl,
+            } => {
+                let deadline = match expiry {
+                    Some(expiry) => match expiry.deadline_ms(now_ms()) {
+                        Some(deadline) => Some(deadline),
+                        None => return Ok(b"-ERR invalid expire time in 'set' command\r\n".to_vec()),
+                    },
+                    None => None,
+                };
 
+                // With GET the old value must be a string, or nothing is written
+                let old_value = if *get {
                     context
-                        .DataBase
-                        .store
-                        .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
+                        .get::<StringStore>(key)?
+                        .map(|string_store| string_store.get_value().clone())
+                } else {
+                    None
+                };
 
-                    context
-                        .TTLStore
-                        .store
-                        .insert(_val.to_owned() as usize, shared_store);
+                let exists = context.lookup_key(key).is_some();
+                let should_set = match condition {
+                    Some(SetCondition::NX) => !exists,
+                    Some(SetCondition::XX) => exists,
+                    None => true,
+                };
+                if should_set {
+                    let deadline = if *keep_ttl { context.get_expiry(key) } else { deadline };
+                    context.insert_key(key.to_owned(), Value::String(StringStore::new(value.to_owned())));
+                    if let Some(deadline) = deadline {
+                        context.set_expiry(key, deadline);
+                    }
                 }
-                None => {
-                    let shared_store: Rc<RefCell<dyn Store>> =
-                        Rc::new(RefCell::new(StringStore::new(value.to_owned())));
-
-                    context
-                        .DataBase
-                        .store
-                        .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
 
-                    context.TTLStore.store.insert(86400, shared_store);
+                if *get {
+                    Ok(old_value.map_or(NIL.to_vec(), |old_value| bulk_string(&old_value)))
+                } else if should_set {
+                    Ok(b"+OK\r\n".to_vec())
+                } else {
+                    Ok(NIL.to_vec())
                 }
-            },
-            _ => {}
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
         }
-
-        Ok(b"+OK\r\n".to_vec())
     }
 }
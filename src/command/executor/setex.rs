@@ -0,0 +1,30 @@
+use crate::command::command_enum::{Command, Expiry};
+use crate::store::string_store::StringStore;
+use crate::store::value::Value;
+use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct setex;
+
+// Handles both SETEX (seconds) and PSETEX (milliseconds).
+impl commandExecutor for setex {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SETEX { key, value, expiry } => {
+                let deadline = match expiry.deadline_ms(now_ms()) {
+                    Some(deadline) => deadline,
+                    None => {
+                        let name = if matches!(expiry, Expiry::Px(_)) { "psetex" } else { "setex" };
+                        return Ok(format!("-ERR invalid expire time in '{}' command\r\n", name).into_bytes());
+                    }
+                };
+                context.insert_key(key.to_owned(), Value::String(StringStore::new(value.to_owned())));
+                context.set_expiry(key, deadline);
+                Ok(b"+OK\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
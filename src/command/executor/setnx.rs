@@ -0,0 +1,23 @@
+use crate::command::command_enum::Command;
+use crate::store::string_store::StringStore;
+use crate::store::value::Value;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct setnx;
+
+impl commandExecutor for setnx {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SETNX { key, value } => {
+                if context.lookup_key(key).is_some() {
+                    return Ok(b":0\r\n".to_vec());
+                }
+                context.insert_key(key.to_owned(), Value::String(StringStore::new(value.to_owned())));
+                Ok(b":1\r\n".to_vec())
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
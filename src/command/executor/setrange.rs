@@ -0,0 +1,38 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::string_store::{StringStore, MAX_STRING_SIZE};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct setrange;
+
+impl commandExecutor for setrange {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SETRANGE { key, offset, value } => {
+                // An empty write changes nothing, not even a missing key
+                if value.is_empty() {
+                    let length = context.get::<StringStore>(key)?.map_or(0, |string_store| string_store.len());
+                    return Ok(integer(length as i64));
+                }
+                if offset.saturating_add(value.len()) > MAX_STRING_SIZE {
+                    return Ok(b"-ERR string exceeds maximum allowed size (proto-max-bulk-len)\r\n".to_vec());
+                }
+
+                // The gap before the offset, if any, is padded with zero bytes
+                let string_store = context.get_or_create::<StringStore>(key)?;
+                let bytes = string_store.get_value_mut();
+                let end = offset + value.len();
+                if bytes.len() < end {
+                    bytes.resize(end, 0);
+                }
+                bytes[*offset..end].copy_from_slice(value);
+                let length = bytes.len();
+                context.DataBase.mark_changed(key);
+                Ok(integer(length as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::sunion::{read_sets, reply_or_store};
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct sinter;
+
+impl commandExecutor for sinter {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SINTER { destination, keys } => {
+                let mut result = SetStore::new();
+                if let Some(sets) = smallest_first(read_sets(context, keys)?) {
+                    let (smallest, rest) = sets.split_first().expect("the parser requires a key");
+                    for member in smallest.iter() {
+                        if rest.iter().all(|set_store| set_store.is_member(&member)) {
+                            result.add_member(&member);
+                        }
+                    }
+                }
+                Ok(reply_or_store(context, destination, result))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// The sets sorted by size, so an intersection walks the smallest one and only
+// probes the others. None if a key is missing, which makes any intersection empty.
+pub fn smallest_first(sets: Vec<Option<&SetStore>>) -> Option<Vec<&SetStore>> {
+    let mut sets = sets.into_iter().collect::<Option<Vec<_>>>()?;
+    sets.sort_by_key(|set_store| set_store.len());
+    Some(sets)
+}
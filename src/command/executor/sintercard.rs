@@ -0,0 +1,33 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::sinter::smallest_first;
+use crate::command::executor::sunion::read_sets;
+use crate::resp::reply::integer;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct sintercard;
+
+impl commandExecutor for sintercard {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // Size of the intersection, counting stops early once LIMIT is reached
+            Command::SINTERCARD { keys, limit } => {
+                let count = match smallest_first(read_sets(context, keys)?) {
+                    Some(sets) => {
+                        let (smallest, rest) = sets.split_first().expect("the parser requires a key");
+                        let limit = if *limit == 0 { usize::MAX } else { *limit };
+                        smallest
+                            .iter()
+                            .filter(|member| rest.iter().all(|set_store| set_store.is_member(member)))
+                            .take(limit)
+                            .count()
+                    }
+                    None => 0,
+                };
+                Ok(integer(count as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
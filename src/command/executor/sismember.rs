@@ -0,0 +1,21 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct sismember;
+
+impl commandExecutor for sismember {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SISMEMBER { key, member } => {
+                let set_store = context.get::<SetStore>(key)?;
+                let is_member = set_store.is_some_and(|set_store| set_store.is_member(member));
+                Ok(integer(is_member as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string};
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct smembers;
+
+impl commandExecutor for smembers {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SMEMBERS { key } => {
+                let members = match context.get::<SetStore>(key)? {
+                    Some(set_store) => set_store.iter().map(|member| bulk_string(&member)).collect(),
+                    None => vec![],
+                };
+                Ok(array(members))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
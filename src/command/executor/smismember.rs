@@ -0,0 +1,27 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, integer};
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct smismember;
+
+impl commandExecutor for smismember {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SMISMEMBER { key, members } => {
+                let set_store = context.get::<SetStore>(key)?;
+                let replies = members
+                    .iter()
+                    .map(|member| {
+                        let is_member = set_store.is_some_and(|set_store| set_store.is_member(member));
+                        integer(is_member as i64)
+                    })
+                    .collect();
+                Ok(array(replies))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
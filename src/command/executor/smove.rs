@@ -0,0 +1,47 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct smove;
+
+impl commandExecutor for smove {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SMOVE {
+                source,
+                destination,
+                member,
+            } => {
+                // Like Redis, a missing source wins over a destination of the
+                // wrong type
+                if context.get::<SetStore>(source)?.is_none() {
+                    return Ok(integer(0));
+                }
+                context.get::<SetStore>(destination)?;
+                if source == destination {
+                    let is_member = context
+                        .get::<SetStore>(source)?
+                        .is_some_and(|set_store| set_store.is_member(member));
+                    return Ok(integer(is_member as i64));
+                }
+
+                let removed = context
+                    .get_mut::<SetStore>(source)?
+                    .is_some_and(|set_store| set_store.remove_member(member));
+                if !removed {
+                    return Ok(integer(0));
+                }
+                context.DataBase.mark_changed(source);
+                context.delete_if_empty(source);
+                if context.get_or_create::<SetStore>(destination)?.add_member(member) {
+                    context.DataBase.mark_changed(destination);
+                }
+                Ok(integer(1))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
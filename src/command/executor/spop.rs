@@ -0,0 +1,48 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, NIL};
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct spop;
+
+impl commandExecutor for spop {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SPOP { key, count } => {
+                let set_store = match context.get_mut::<SetStore>(key)? {
+                    Some(set_store) => set_store,
+                    None if count.is_some() => return Ok(array(vec![])),
+                    None => return Ok(NIL.to_vec()),
+                };
+
+                let mut popped = Vec::new();
+                for _ in 0..count.unwrap_or(1).min(set_store.len()) {
+                    let member = set_store.random_member().expect("the set is not empty").into_owned();
+                    set_store.remove_member(&member);
+                    popped.push(member);
+                }
+                if !popped.is_empty() {
+                    context.DataBase.mark_changed(key);
+                }
+                context.delete_if_empty(key);
+
+                // Log what was actually popped, as replaying SPOP would pick others
+                if !popped.is_empty() {
+                    let mut srem = vec![b"SREM".to_vec(), key.clone()];
+                    srem.extend(popped.iter().cloned());
+                    context.AofStore.also_propagate(srem);
+                }
+                context.AofStore.prevent_propagation();
+
+                // Without a count the reply is a single member, not an array
+                match count {
+                    Some(_) => Ok(array(popped.iter().map(|member| bulk_string(member)).collect())),
+                    None => Ok(bulk_string(&popped[0])),
+                }
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, NIL};
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use rand::seq::SliceRandom;
+use std::borrow::Cow;
+use std::error::Error;
+
+pub struct srandmember;
+
+impl commandExecutor for srandmember {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SRANDMEMBER { key, count } => {
+                let set_store = context.get::<SetStore>(key)?;
+                let count = match count {
+                    Some(count) => *count,
+                    // Without a count the reply is a single member, not an array
+                    None => {
+                        return Ok(match set_store.and_then(|set_store| set_store.random_member()) {
+                            Some(member) => bulk_string(&member),
+                            None => NIL.to_vec(),
+                        })
+                    }
+                };
+                let set_store = match set_store {
+                    Some(set_store) => set_store,
+                    None => return Ok(array(vec![])),
+                };
+
+                // A positive count returns distinct members, a negative one may
+                // return the same member several times
+                let picked: Vec<Cow<[u8]>> = if count >= 0 {
+                    let mut members: Vec<_> = set_store.iter().collect();
+                    let count = (count as usize).min(members.len());
+                    members.partial_shuffle(&mut rand::thread_rng(), count);
+                    members.truncate(count);
+                    members
+                } else {
+                    (0..count.unsigned_abs())
+                        .filter_map(|_| set_store.random_member())
+                        .collect()
+                };
+                Ok(array(picked.iter().map(|member| bulk_string(member)).collect()))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
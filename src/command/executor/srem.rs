@@ -0,0 +1,31 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct srem;
+
+impl commandExecutor for srem {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SREM { key, members } => {
+                let set_store = match context.get_mut::<SetStore>(key)? {
+                    Some(set_store) => set_store,
+                    None => return Ok(integer(0)),
+                };
+                let removed = members
+                    .iter()
+                    .filter(|member| set_store.remove_member(member))
+                    .count();
+                if removed > 0 {
+                    context.DataBase.mark_changed(key);
+                    context.delete_if_empty(key);
+                }
+                Ok(integer(removed as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
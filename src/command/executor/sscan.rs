@@ -0,0 +1,30 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::scan::{matches_pattern, scan_reply};
+use crate::resp::reply::bulk_string;
+use crate::store::set_store::SetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct sscan;
+
+impl commandExecutor for sscan {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SSCAN { key, cursor, options } => {
+                let set_store = match context.get::<SetStore>(key)? {
+                    Some(set_store) => set_store,
+                    None => return Ok(scan_reply(0, vec![])),
+                };
+                let mut items = Vec::new();
+                let next_cursor = set_store.scan(*cursor, options.count, |member| {
+                    if matches_pattern(&options.pattern, member) {
+                        items.push(bulk_string(member));
+                    }
+                });
+                Ok(scan_reply(next_cursor, items))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
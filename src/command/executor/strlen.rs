@@ -0,0 +1,20 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::string_store::StringStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct strlen;
+
+impl commandExecutor for strlen {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::STRLEN { key } => {
+                let length = context.get::<StringStore>(key)?.map_or(0, |string_store| string_store.len());
+                Ok(integer(length as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, integer};
+use crate::store::set_store::SetStore;
+use crate::store::value::{Value, WrongTypeError};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use crate::traits::Store::Store;
+use std::error::Error;
+
+pub struct sunion;
+
+impl commandExecutor for sunion {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::SUNION { destination, keys } => {
+                let mut result = SetStore::new();
+                for set_store in read_sets(context, keys)?.into_iter().flatten() {
+                    for member in set_store.iter() {
+                        result.add_member(&member);
+                    }
+                }
+                Ok(reply_or_store(context, destination, result))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// The sets at `keys`, in order, None for a missing key. Every key is checked
+// (and lazily expired) first, so the sets can then be borrowed all at once.
+pub fn read_sets<'a>(context: &'a mut context, keys: &[Vec<u8>]) -> Result<Vec<Option<&'a SetStore>>, WrongTypeError> {
+    for key in keys {
+        context.get::<SetStore>(key)?;
+    }
+    let store = &context.DataBase.store;
+    Ok(keys
+        .iter()
+        .map(|key| store.get(key).and_then(|entry| SetStore::from_value(&entry.value)))
+        .collect())
+}
+
+// Without a destination the result is the reply. With one it replaces the
+// destination (an empty result deletes it) and the reply is its size.
+pub fn reply_or_store(context: &mut context, destination: &Option<Vec<u8>>, result: SetStore) -> Vec<u8> {
+    match destination {
+        Some(destination) => {
+            let length = result.len();
+            if length == 0 {
+                context.remove_key(destination);
+            } else {
+                context.insert_key(destination.clone(), Value::Set(result));
+            }
+            integer(length as i64)
+        }
+        None => array(result.iter().map(|member| bulk_string(&member)).collect()),
+    }
+}
@@ -0,0 +1,20 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct touch;
+
+impl commandExecutor for touch {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::TOUCH { keys } => {
+                // The lookup itself refreshes the access time
+                let touched = keys.iter().filter(|key| context.lookup_key(key).is_some()).count();
+                Ok(integer(touched as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::command::command_enum::Command;
+use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct ttl;
+
+impl commandExecutor for ttl {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::TTL { key } => {
+                // -2 if the key does not exist (or just expired)
+                if context.lookup_key(key).is_none() {
+                    return Ok(b":-2\r\n".to_vec());
+                }
+                match context.get_expiry(key) {
+                    Some(deadline) => {
+                        // Round to the nearest second like Redis does
+                        let remaining_ms = deadline.saturating_sub(now_ms());
+                        Ok(format!(":{}\r\n", (remaining_ms + 500) / 1000).into_bytes())
+                    }
+                    // -1 if the key exists but has no associated expire
+                    None => Ok(b":-1\r\n".to_vec()),
+                }
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::string_store::StringStore;
+    use crate::store::value::Value;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    fn insert_string(ctx: &mut context, key: &[u8]) {
+        ctx.insert_key(key.to_vec(), Value::String(StringStore::new(b"value".to_vec())));
+    }
+
+    #[test]
+    fn test_ttl_missing_key() {
+        let mut ctx = context::new();
+        let command = Command::TTL { key: b"missing".to_vec() };
+
+        let result = ttl::execute(&command, &mut ctx).unwrap();
+        assert_eq!(result, b":-2\r\n");
+    }
+
+    #[test]
+    fn test_ttl_persistent_key() {
+        let mut ctx = context::new();
+        insert_string(&mut ctx, b"key");
+        let command = Command::TTL { key: b"key".to_vec() };
+
+        let result = ttl::execute(&command, &mut ctx).unwrap();
+        assert_eq!(result, b":-1\r\n");
+    }
+
+    #[test]
+    fn test_ttl_volatile_key() {
+        let mut ctx = context::new();
+        insert_string(&mut ctx, b"key");
+        ctx.set_expiry(b"key", now_ms() + 100_000);
+        let command = Command::TTL { key: b"key".to_vec() };
+
+        let result = ttl::execute(&command, &mut ctx).unwrap();
+        assert_eq!(result, b":100\r\n");
+    }
+
+    #[test]
+    fn test_ttl_expired_key() {
+        let mut ctx = context::new();
+        insert_string(&mut ctx, b"key");
+        ctx.set_expiry(b"key", now_ms() + 1);
+        sleep(Duration::from_millis(5));
+        let command = Command::TTL { key: b"key".to_vec() };
+
+        let result = ttl::execute(&command, &mut ctx).unwrap();
+        assert_eq!(result, b":-2\r\n");
+    }
+}
@@ -0,0 +1,16 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::del::delete_keys;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct unlink;
+
+impl commandExecutor for unlink {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::UNLINK { keys } => Ok(delete_keys(context, keys)),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -1,89 +1,107 @@
-use crate::command::command_enum::Command;
+use crate::command::command_enum::{Command, SetCondition, ZAddOptions};
+use crate::resp::reply::{double, integer, NIL};
 use crate::store::sorted_set_store::SortedSetStore;
 use crate::store_containers::core_context::context;
 use crate::traits::command::commandExecutor;
-use crate::traits::Store::Store;
-use std::cell::RefCell;
 use std::error::Error;
-use std::rc::Rc;
 
 pub struct zadd;
 
+// What ZADD did with one score-member pair.
+pub enum ZAddOutcome {
+    Added(f64),
+    Updated(f64),
+    Unchanged(f64),
+    // NX, XX, GT or LT vetoed the change
+    Skipped,
+    // INCR would have produced NaN (inf + -inf)
+    NotANumber,
+}
+
 impl commandExecutor for zadd {
     fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
         match commandObject {
-            Command::ZADD { key, entries } => {
-                let mut added_count = 0;
-                
-                match context.DataBase.store.get(key.as_str()) {
-                    Some(Some(weak_ref)) => {
-                        match weak_ref.upgrade() {
-                            Some(store_ref) => {
-                                let mut store = store_ref.borrow_mut();
-                                if let Some(zset_store) = (&mut *store as &mut dyn std::any::Any).downcast_mut::<SortedSetStore>() {
-                                    for (score, member) in entries {
-                                        if zset_store.add_member(member, *score) {
-                                            added_count += 1;
-                                        }
-                                    }
-                                    Ok(format!(":{}\r\n", added_count).into_bytes())
-                                } else {
-                                    Ok(b"-ERR WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_vec())
-                                }
-                            }
-                            None => {
-                                // Create new sorted set
-                                let mut new_zset = SortedSetStore::new();
-                                for (score, member) in entries {
-                                    if new_zset.add_member(member, *score) {
-                                        added_count += 1;
-                                    }
-                                }
-                                let shared_store: Rc<RefCell<dyn Store>> =
-                                    Rc::new(RefCell::new(new_zset));
-                                context
-                                    .DataBase
-                                    .store
-                                    .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                                context.TTLStore.store.insert(86400, shared_store);
-                                Ok(format!(":{}\r\n", added_count).into_bytes())
-                            }
-                        }
+            Command::ZADD { key, entries, options } => {
+                let zset_store = context.get_or_create::<SortedSetStore>(key)?;
+                let mut added = 0;
+                let mut updated = 0;
+                let mut last = ZAddOutcome::Skipped;
+                for (score, member) in entries {
+                    last = add_member(zset_store, member, *score, options);
+                    match last {
+                        ZAddOutcome::Added(_) => added += 1,
+                        ZAddOutcome::Updated(_) => updated += 1,
+                        _ => {}
                     }
-                    Some(None) | None => {
-                        // Create new sorted set
-                        let mut new_zset = SortedSetStore::new();
-                        for (score, member) in entries {
-                            if new_zset.add_member(member, *score) {
-                                added_count += 1;
-                            }
+                }
+                if added + updated > 0 {
+                    context.DataBase.mark_changed(key);
+                }
+                // XX on a missing key, or nothing to add, must not leave an empty key
+                context.delete_if_empty(key);
+
+                if options.incr {
+                    return Ok(match last {
+                        ZAddOutcome::Added(score) | ZAddOutcome::Updated(score) | ZAddOutcome::Unchanged(score) => {
+                            double(score)
                         }
-                        let shared_store: Rc<RefCell<dyn Store>> =
-                            Rc::new(RefCell::new(new_zset));
-                        context
-                            .DataBase
-                            .store
-                            .insert(key.to_owned(), Some(Rc::downgrade(&shared_store)));
-                        context.TTLStore.store.insert(86400, shared_store);
-                        Ok(format!(":{}\r\n", added_count).into_bytes())
-                    }
+                        ZAddOutcome::Skipped => NIL.to_vec(),
+                        ZAddOutcome::NotANumber => b"-ERR resulting score is not a number (NaN)\r\n".to_vec(),
+                    });
                 }
+                let changed = if options.ch { added + updated } else { added };
+                Ok(integer(changed))
             }
             _ => Ok(b"-ERR wrong command\r\n".to_vec()),
         }
     }
 }
 
+// Applies one score-member pair under the ZADD flags; ZINCRBY is ZADD INCR.
+pub fn add_member(zset_store: &mut SortedSetStore, member: &[u8], score: f64, options: &ZAddOptions) -> ZAddOutcome {
+    let current = zset_store.get_score(member);
+    match (current, options.condition) {
+        (Some(_), Some(SetCondition::NX)) | (None, Some(SetCondition::XX)) => return ZAddOutcome::Skipped,
+        _ => {}
+    }
+    let current = match current {
+        Some(current) => current,
+        None => {
+            zset_store.add_member(member, score);
+            return ZAddOutcome::Added(score);
+        }
+    };
+
+    let score = if options.incr { current + score } else { score };
+    if score.is_nan() {
+        return ZAddOutcome::NotANumber;
+    }
+    if (options.gt && score <= current) || (options.lt && score >= current) {
+        return ZAddOutcome::Skipped;
+    }
+    if score == current {
+        return ZAddOutcome::Unchanged(score);
+    }
+    zset_store.add_member(member, score);
+    ZAddOutcome::Updated(score)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::store_containers::{DictStore::DictStore, TTLStore::TTLStore};
+    use crate::store::value::Value;
+    use crate::store_containers::{AofStore::AofStore, BlockingStore::BlockingStore, DictStore::DictStore, EvictionStore::EvictionStore, PersistenceStore::PersistenceStore, PubSubStore::PubSubStore, TTLStore::TTLStore, WatchStore::WatchStore};
 
     fn create_test_context() -> context {
         context {
             DataBase: DictStore::new(),
             TTLStore: TTLStore::new(),
-            EvictionStore: "TODO".to_owned(),
+            EvictionStore: EvictionStore::new(),
+            BlockingStore: BlockingStore::new(),
+            PersistenceStore: PersistenceStore::new(),
+            AofStore: AofStore::new(),
+            WatchStore: WatchStore::new(),
+            PubSubStore: PubSubStore::new(),
         }
     }
 
@@ -91,12 +109,13 @@ mod tests {
     fn test_zadd_new_sorted_set() {
         let mut ctx = create_test_context();
         let command = Command::ZADD {
-            key: "myzset".to_string(),
+            key: b"myzset".to_vec(),
             entries: vec![
-                (1.0, "first".to_string()),
-                (2.0, "second".to_string()),
-                (3.0, "third".to_string()),
+                (1.0, b"first".to_vec()),
+                (2.0, b"second".to_vec()),
+                (3.0, b"third".to_vec()),
             ],
+            options: ZAddOptions::default(),
         };
         
         let result = zadd::execute(&command, &mut ctx).unwrap();
@@ -109,17 +128,16 @@ mod tests {
         
         // Create existing sorted set
         let mut existing_zset = SortedSetStore::new();
-        existing_zset.add_member("existing", 1.0);
-        let shared_store: Rc<RefCell<dyn Store>> = Rc::new(RefCell::new(existing_zset));
-        ctx.DataBase.store.insert("myzset".to_string(), Some(Rc::downgrade(&shared_store)));
-        ctx.TTLStore.store.insert(86400, shared_store);
+        existing_zset.add_member(b"existing", 1.0);
+        ctx.insert_key(b"myzset".to_vec(), Value::ZSet(existing_zset));
         
         let command = Command::ZADD {
-            key: "myzset".to_string(),
+            key: b"myzset".to_vec(),
             entries: vec![
-                (1.5, "existing".to_string()), // Update existing member
-                (2.0, "new".to_string()),      // Add new member
+                (1.5, b"existing".to_vec()), // Update existing member
+                (2.0, b"new".to_vec()),      // Add new member
             ],
+            options: ZAddOptions::default(),
         };
         
         let result = zadd::execute(&command, &mut ctx).unwrap();
@@ -130,12 +148,13 @@ mod tests {
     fn test_zadd_duplicate_scores() {
         let mut ctx = create_test_context();
         let command = Command::ZADD {
-            key: "myzset".to_string(),
+            key: b"myzset".to_vec(),
             entries: vec![
-                (1.0, "member1".to_string()),
-                (1.0, "member2".to_string()),
-                (1.0, "member3".to_string()),
+                (1.0, b"member1".to_vec()),
+                (1.0, b"member2".to_vec()),
+                (1.0, b"member3".to_vec()),
             ],
+            options: ZAddOptions::default(),
         };
         
         let result = zadd::execute(&command, &mut ctx).unwrap();
@@ -146,12 +165,13 @@ mod tests {
     fn test_zadd_negative_scores() {
         let mut ctx = create_test_context();
         let command = Command::ZADD {
-            key: "myzset".to_string(),
+            key: b"myzset".to_vec(),
             entries: vec![
-                (-1.0, "negative".to_string()),
-                (0.0, "zero".to_string()),
-                (1.0, "positive".to_string()),
+                (-1.0, b"negative".to_vec()),
+                (0.0, b"zero".to_vec()),
+                (1.0, b"positive".to_vec()),
             ],
+            options: ZAddOptions::default(),
         };
         
         let result = zadd::execute(&command, &mut ctx).unwrap();
@@ -162,8 +182,9 @@ mod tests {
     fn test_zadd_empty_entries() {
         let mut ctx = create_test_context();
         let command = Command::ZADD {
-            key: "myzset".to_string(),
+            key: b"myzset".to_vec(),
             entries: vec![],
+            options: ZAddOptions::default(),
         };
         
         let result = zadd::execute(&command, &mut ctx).unwrap();
@@ -174,8 +195,9 @@ mod tests {
     fn test_zadd_single_entry() {
         let mut ctx = create_test_context();
         let command = Command::ZADD {
-            key: "myzset".to_string(),
-            entries: vec![(1.0, "single".to_string())],
+            key: b"myzset".to_vec(),
+            entries: vec![(1.0, b"single".to_vec())],
+            options: ZAddOptions::default(),
         };
         
         let result = zadd::execute(&command, &mut ctx).unwrap();
@@ -185,7 +207,7 @@ mod tests {
     #[test]
     fn test_zadd_wrong_command() {
         let mut ctx = create_test_context();
-        let command = Command::GET { key: "test".to_string() };
+        let command = Command::GET { key: b"test".to_vec() };
         
         let result = zadd::execute(&command, &mut ctx).unwrap();
         assert_eq!(result, b"-ERR wrong command\r\n");
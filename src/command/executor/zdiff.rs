@@ -0,0 +1,29 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::zunion::{read_inputs, reply_or_store};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zdiff;
+
+impl commandExecutor for zdiff {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // The members of the first set found in none of the others, with
+            // their scores from the first set
+            Command::ZDIFF {
+                destination,
+                keys,
+                with_scores,
+            } => {
+                let mut inputs = read_inputs(context, keys)?.into_iter();
+                let mut result = inputs.next().expect("the parser requires a key");
+                for input in inputs {
+                    result.retain(|member, _| !input.contains_key(member));
+                }
+                Ok(reply_or_store(context, destination, result, *with_scores))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
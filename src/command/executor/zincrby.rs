@@ -0,0 +1,34 @@
+use crate::command::command_enum::{Command, ZAddOptions};
+use crate::command::executor::zadd::{add_member, ZAddOutcome};
+use crate::resp::reply::double;
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zincrby;
+
+impl commandExecutor for zincrby {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZINCRBY { key, increment, member } => {
+                let options = ZAddOptions {
+                    incr: true,
+                    ..ZAddOptions::default()
+                };
+                let zset_store = context.get_or_create::<SortedSetStore>(key)?;
+                let reply = match add_member(zset_store, member, *increment, &options) {
+                    ZAddOutcome::Added(score) | ZAddOutcome::Updated(score) => {
+                        context.DataBase.mark_changed(key);
+                        double(score)
+                    }
+                    ZAddOutcome::Unchanged(score) => double(score),
+                    _ => b"-ERR resulting score is not a number (NaN)\r\n".to_vec(),
+                };
+                context.delete_if_empty(key);
+                Ok(reply)
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::zunion::{combine, read_inputs, reply_or_store, weight_of, weighted};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::collections::HashMap;
+use std::error::Error;
+
+pub struct zinter;
+
+impl commandExecutor for zinter {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZINTER {
+                destination,
+                keys,
+                weights,
+                aggregate,
+                with_scores,
+            } => {
+                let inputs = read_inputs(context, keys)?;
+                let (first, rest) = inputs.split_first().expect("the parser requires a key");
+                let mut result: HashMap<Vec<u8>, f64> = HashMap::new();
+                'members: for (member, score) in first {
+                    let mut total = weighted(*score, weight_of(weights, 0));
+                    for (i, input) in rest.iter().enumerate() {
+                        match input.get(member) {
+                            Some(score) => total = combine(*aggregate, total, weighted(*score, weight_of(weights, i + 1))),
+                            None => continue 'members,
+                        }
+                    }
+                    result.insert(member.clone(), total);
+                }
+                Ok(reply_or_store(context, destination, result, *with_scores))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::zunion::read_inputs;
+use crate::resp::reply::integer;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zintercard;
+
+impl commandExecutor for zintercard {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            // Size of the intersection, counting stops early once LIMIT is reached
+            Command::ZINTERCARD { keys, limit } => {
+                let inputs = read_inputs(context, keys)?;
+                let (first, rest) = inputs.split_first().expect("the parser requires a key");
+                let limit = if *limit == 0 { usize::MAX } else { *limit };
+                let count = first
+                    .keys()
+                    .filter(|member| rest.iter().all(|input| input.contains_key(*member)))
+                    .take(limit)
+                    .count();
+                Ok(integer(count as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zlexcount;
+
+impl commandExecutor for zlexcount {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZLEXCOUNT { key, min, max } => {
+                let count = context
+                    .get::<SortedSetStore>(key)?
+                    .map_or(0, |zset_store| zset_store.lex_range(min, max).len());
+                Ok(integer(count as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, double, NIL};
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zmscore;
+
+impl commandExecutor for zmscore {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZMSCORE { key, members } => {
+                let zset_store = context.get::<SortedSetStore>(key)?;
+                let scores = members
+                    .iter()
+                    .map(|member| {
+                        let score = zset_store.and_then(|zset_store| zset_store.get_score(member));
+                        score.map_or(NIL.to_vec(), double)
+                    })
+                    .collect();
+                Ok(array(scores))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::zpopmin::pop_reply;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zpopmax;
+
+impl commandExecutor for zpopmax {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZPOPMAX { key, count } => pop_reply(context, key, count.unwrap_or(1), true),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
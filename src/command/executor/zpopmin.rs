@@ -0,0 +1,37 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, double};
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zpopmin;
+
+impl commandExecutor for zpopmin {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZPOPMIN { key, count } => pop_reply(context, key, count.unwrap_or(1), false),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// Shared by ZPOPMIN and ZPOPMAX: a flat [member, score, ...] array, with or
+// without a count.
+pub fn pop_reply(context: &mut context, key: &[u8], count: usize, max: bool) -> Result<Vec<u8>, Box<dyn Error>> {
+    let popped = match context.get_mut::<SortedSetStore>(key)? {
+        Some(zset_store) => zset_store.pop(count, max),
+        None => Vec::new(),
+    };
+    if !popped.is_empty() {
+        context.DataBase.mark_changed(key);
+    }
+    context.delete_if_empty(key);
+
+    let mut items = Vec::with_capacity(popped.len() * 2);
+    for (member, score) in popped {
+        items.push(bulk_string(&member));
+        items.push(double(score));
+    }
+    Ok(array(items))
+}
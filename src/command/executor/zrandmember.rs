@@ -0,0 +1,58 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, double, NIL};
+use crate::store::sorted_set_store::SortedSetStore;
+use rand::seq::SliceRandom;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zrandmember;
+
+impl commandExecutor for zrandmember {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZRANDMEMBER { key, count, with_scores } => {
+                let zset_store = context.get::<SortedSetStore>(key)?;
+                let count = match count {
+                    Some(count) => *count,
+                    // Without a count the reply is a single member, not an array
+                    None => {
+                        return Ok(match zset_store.and_then(|zset_store| zset_store.random_member()) {
+                            Some((member, _)) => bulk_string(member),
+                            None => NIL.to_vec(),
+                        })
+                    }
+                };
+                let zset_store = match zset_store {
+                    Some(zset_store) => zset_store,
+                    None => return Ok(array(vec![])),
+                };
+
+                // A positive count returns distinct members, a negative one may
+                // return the same member several times
+                let picked: Vec<(&[u8], f64)> = if count >= 0 {
+                    let mut members: Vec<_> = zset_store.iter().collect();
+                    let count = (count as usize).min(members.len());
+                    members.partial_shuffle(&mut rand::thread_rng(), count);
+                    members.truncate(count);
+                    members
+                } else {
+                    (0..count.unsigned_abs())
+                        .filter_map(|_| zset_store.random_member())
+                        .map(|(member, score)| (member.as_slice(), score))
+                        .collect()
+                };
+
+                let mut items = Vec::new();
+                for (member, score) in picked {
+                    items.push(bulk_string(member));
+                    if *with_scores {
+                        items.push(double(score));
+                    }
+                }
+                Ok(array(items))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
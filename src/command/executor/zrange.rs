@@ -0,0 +1,74 @@
+use crate::command::command_enum::{Command, ZRange};
+use crate::resp::reply::{array, bulk_string, double};
+use crate::store::sorted_set_store::SortedSetStore;
+use std::ops::Range;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zrange;
+
+impl commandExecutor for zrange {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZRANGE {
+                key,
+                range,
+                rev,
+                limit,
+                with_scores,
+            } => {
+                let zset_store = match context.get::<SortedSetStore>(key)? {
+                    Some(zset_store) => zset_store,
+                    None => return Ok(array(vec![])),
+                };
+                let mut ranks = match range {
+                    // Ranks count from the end with REV: rank 0 is the highest score
+                    ZRange::Rank(start, stop) if *rev => {
+                        let reversed = zset_store.rank_range(*start, *stop);
+                        zset_store.len() - reversed.end..zset_store.len() - reversed.start
+                    }
+                    _ => resolve_range(zset_store, range),
+                };
+                if let Some((offset, count)) = limit {
+                    ranks = apply_limit(ranks, *offset, *count, *rev);
+                }
+
+                let mut items = Vec::new();
+                for (member, score) in zset_store.range_entries(ranks, *rev) {
+                    items.push(bulk_string(&member));
+                    if *with_scores {
+                        items.push(double(score));
+                    }
+                }
+                Ok(array(items))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// The ranks a range selects, in ascending order. Shared with ZREMRANGEBY*.
+pub fn resolve_range(zset_store: &SortedSetStore, range: &ZRange) -> Range<usize> {
+    match range {
+        ZRange::Rank(start, stop) => zset_store.rank_range(*start, *stop),
+        ZRange::Score(min, max) => zset_store.score_range(*min, *max),
+        ZRange::Lex(min, max) => zset_store.lex_range(min, max),
+    }
+}
+
+// Narrows a range to LIMIT offset count, counted from its high end with REV.
+// A negative offset selects nothing and a negative count means no limit.
+fn apply_limit(ranks: Range<usize>, offset: i64, count: i64, rev: bool) -> Range<usize> {
+    if offset < 0 {
+        return 0..0;
+    }
+    let offset = (offset as usize).min(ranks.len());
+    let count = if count < 0 { usize::MAX } else { count as usize };
+    let kept = count.min(ranks.len() - offset);
+    if rev {
+        ranks.end - offset - kept..ranks.end - offset
+    } else {
+        ranks.start + offset..ranks.start + offset + kept
+    }
+}
@@ -0,0 +1,38 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, double, integer, NIL, NIL_ARRAY};
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zrank;
+
+impl commandExecutor for zrank {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZRANK { key, member, with_score } => rank_reply(context, key, member, *with_score, false),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// Shared by ZRANK and ZREVRANK: the rank, or [rank, score] with WITHSCORE.
+pub fn rank_reply(
+    context: &mut context,
+    key: &[u8],
+    member: &[u8],
+    with_score: bool,
+    reverse: bool,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let found = context.get::<SortedSetStore>(key)?.and_then(|zset_store| {
+        let rank = zset_store.get_rank(member)?;
+        let rank = if reverse { zset_store.len() - 1 - rank } else { rank };
+        Some((rank, zset_store.get_score(member)?))
+    });
+    Ok(match (found, with_score) {
+        (Some((rank, _)), false) => integer(rank as i64),
+        (Some((rank, score)), true) => array(vec![integer(rank as i64), double(score)]),
+        (None, false) => NIL.to_vec(),
+        (None, true) => NIL_ARRAY.to_vec(),
+    })
+}
@@ -0,0 +1,28 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::integer;
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zrem;
+
+impl commandExecutor for zrem {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZREM { key, members } => {
+                let zset_store = match context.get_mut::<SortedSetStore>(key)? {
+                    Some(zset_store) => zset_store,
+                    None => return Ok(integer(0)),
+                };
+                let removed = members.iter().filter(|member| zset_store.remove_member(member)).count();
+                if removed > 0 {
+                    context.DataBase.mark_changed(key);
+                }
+                context.delete_if_empty(key);
+                Ok(integer(removed as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::zrange::resolve_range;
+use crate::resp::reply::integer;
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zremrange;
+
+impl commandExecutor for zremrange {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZREMRANGE { key, range } => {
+                let removed = match context.get_mut::<SortedSetStore>(key)? {
+                    Some(zset_store) => {
+                        let ranks = resolve_range(zset_store, range);
+                        zset_store.remove_range(ranks)
+                    }
+                    None => 0,
+                };
+                if removed > 0 {
+                    context.DataBase.mark_changed(key);
+                }
+                context.delete_if_empty(key);
+                Ok(integer(removed as i64))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
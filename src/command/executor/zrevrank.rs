@@ -0,0 +1,16 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::zrank::rank_reply;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zrevrank;
+
+impl commandExecutor for zrevrank {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZREVRANK { key, member, with_score } => rank_reply(context, key, member, *with_score, true),
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::command::command_enum::Command;
+use crate::command::executor::scan::{matches_pattern, scan_reply};
+use crate::resp::reply::bulk_string;
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zscan;
+
+impl commandExecutor for zscan {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZSCAN { key, cursor, options } => {
+                let zset_store = match context.get::<SortedSetStore>(key)? {
+                    Some(zset_store) => zset_store,
+                    None => return Ok(scan_reply(0, vec![])),
+                };
+                let mut items = Vec::new();
+                let next_cursor = zset_store.scan(*cursor, options.count, |member, score| {
+                    if matches_pattern(&options.pattern, member) {
+                        items.push(bulk_string(member));
+                        items.push(bulk_string(score.to_string().as_bytes()));
+                    }
+                });
+                Ok(scan_reply(next_cursor, items))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{double, NIL};
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::error::Error;
+
+pub struct zscore;
+
+impl commandExecutor for zscore {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZSCORE { key, member } => {
+                let score = context
+                    .get::<SortedSetStore>(key)?
+                    .and_then(|zset_store| zset_store.get_score(member));
+                Ok(score.map_or(NIL.to_vec(), double))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
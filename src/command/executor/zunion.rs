@@ -0,0 +1,121 @@
+use crate::command::command_enum::{Aggregate, Command};
+use crate::resp::reply::{array, bulk_string, double, integer};
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store::value::{Value, WrongTypeError};
+use crate::store_containers::core_context::context;
+use crate::traits::command::commandExecutor;
+use std::collections::HashMap;
+use std::error::Error;
+
+pub struct zunion;
+
+impl commandExecutor for zunion {
+    fn execute(commandObject: &Command, context: &mut context) -> Result<Vec<u8>, Box<dyn Error>> {
+        match commandObject {
+            Command::ZUNION {
+                destination,
+                keys,
+                weights,
+                aggregate,
+                with_scores,
+            } => {
+                let inputs = read_inputs(context, keys)?;
+                let mut result: HashMap<Vec<u8>, f64> = HashMap::new();
+                for (i, input) in inputs.iter().enumerate() {
+                    let weight = weight_of(weights, i);
+                    for (member, score) in input {
+                        let score = weighted(*score, weight);
+                        result
+                            .entry(member.clone())
+                            .and_modify(|total| *total = combine(*aggregate, *total, score))
+                            .or_insert(score);
+                    }
+                }
+                Ok(reply_or_store(context, destination, result, *with_scores))
+            }
+            _ => Ok(b"-ERR wrong command\r\n".to_vec()),
+        }
+    }
+}
+
+// The members and scores of every input key, in order. Like Redis, plain sets
+// are accepted too, each member scoring 1. A missing key is an empty input.
+pub fn read_inputs(context: &mut context, keys: &[Vec<u8>]) -> Result<Vec<HashMap<Vec<u8>, f64>>, WrongTypeError> {
+    let mut inputs = Vec::with_capacity(keys.len());
+    for key in keys {
+        let input = match context.lookup_key(key).map(|entry| entry.value.as_ref()) {
+            Some(Value::ZSet(zset_store)) => zset_store.iter().map(|(member, score)| (member.to_vec(), score)).collect(),
+            Some(Value::Set(set_store)) => set_store.get_members().into_iter().map(|member| (member, 1.0)).collect(),
+            Some(_) => return Err(WrongTypeError),
+            None => HashMap::new(),
+        };
+        inputs.push(input);
+    }
+    Ok(inputs)
+}
+
+pub fn weight_of(weights: &Option<Vec<f64>>, index: usize) -> f64 {
+    weights.as_ref().map_or(1.0, |weights| weights[index])
+}
+
+// inf * 0 is NaN, which Redis turns into 0.
+pub fn weighted(score: f64, weight: f64) -> f64 {
+    let score = score * weight;
+    if score.is_nan() {
+        0.0
+    } else {
+        score
+    }
+}
+
+pub fn combine(aggregate: Aggregate, total: f64, score: f64) -> f64 {
+    match aggregate {
+        // inf + -inf is NaN, which Redis also turns into 0
+        Aggregate::Sum => {
+            let sum = total + score;
+            if sum.is_nan() {
+                0.0
+            } else {
+                sum
+            }
+        }
+        Aggregate::Min => total.min(score),
+        Aggregate::Max => total.max(score),
+    }
+}
+
+// Without a destination the result is the reply, sorted like ZRANGE. With one
+// it replaces the destination (an empty result deletes it) and the reply is
+// its size.
+pub fn reply_or_store(
+    context: &mut context,
+    destination: &Option<Vec<u8>>,
+    result: HashMap<Vec<u8>, f64>,
+    with_scores: bool,
+) -> Vec<u8> {
+    let mut zset_store = SortedSetStore::new();
+    for (member, score) in &result {
+        zset_store.add_member(member, *score);
+    }
+    match destination {
+        Some(destination) => {
+            let length = zset_store.len();
+            if length == 0 {
+                context.remove_key(destination);
+            } else {
+                context.insert_key(destination.clone(), Value::ZSet(zset_store));
+            }
+            integer(length as i64)
+        }
+        None => {
+            let mut items = Vec::new();
+            for (member, score) in zset_store.iter() {
+                items.push(bulk_string(member));
+                if with_scores {
+                    items.push(double(score));
+                }
+            }
+            array(items)
+        }
+    }
+}
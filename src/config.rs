@@ -0,0 +1,257 @@
+use crate::store_containers::{AofStore::AppendFsync, EvictionStore::EvictionPolicy};
+use std::cell::RefCell;
+
+// Server settings, readable with CONFIG GET and changed with CONFIG SET.
+#[derive(Debug, Clone)]
+pub struct Config {
+    // Past these sizes small hashes and sets leave their compact encodings for
+    // a hash table, like Redis' hash-max-listpack-* and set-max-* settings.
+    pub hash_max_listpack_entries: usize,
+    pub hash_max_listpack_value: usize,
+    pub set_max_intset_entries: usize,
+    pub set_max_listpack_entries: usize,
+    pub set_max_listpack_value: usize,
+
+    // Memory limit in bytes, 0 for none, and what to evict to stay under it.
+    pub maxmemory: u64,
+    pub maxmemory_policy: EvictionPolicy,
+    // Keys sampled per eviction, more is closer to true LRU/LFU but slower.
+    pub maxmemory_samples: usize,
+    // LFU counters grow logarithmically, slower with a higher factor, and
+    // drop by one for every lfu-decay-time minutes without access.
+    pub lfu_log_factor: u64,
+    pub lfu_decay_time: u64,
+
+    // Snapshot after `seconds` if at least `changes` writes happened, for
+    // each (seconds, changes) rule; no rules disables automatic snapshots.
+    pub save: Vec<(u64, u64)>,
+    // The snapshot file is `dir/dbfilename`.
+    pub dir: String,
+    pub dbfilename: String,
+
+    // Log every write to `dir/appendfilename`, replayed at startup instead of
+    // loading the snapshot.
+    pub appendonly: bool,
+    pub appendfilename: String,
+    pub appendfsync: AppendFsync,
+    // Load what precedes a truncated last command instead of refusing to start
+    pub aof_load_truncated: bool,
+}
+
+// Every parameter, in the order CONFIG GET * lists them.
+pub const PARAMETERS: &[&str] = &[
+    "hash-max-listpack-entries",
+    "hash-max-listpack-value",
+    "set-max-intset-entries",
+    "set-max-listpack-entries",
+    "set-max-listpack-value",
+    "maxmemory",
+    "maxmemory-policy",
+    "maxmemory-samples",
+    "lfu-log-factor",
+    "lfu-decay-time",
+    "save",
+    "dir",
+    "dbfilename",
+    "appendonly",
+    "appendfilename",
+    "appendfsync",
+    "aof-load-truncated",
+];
+
+impl Config {
+    pub fn new() -> Self {
+        Config {
+            hash_max_listpack_entries: 128,
+            hash_max_listpack_value: 64,
+            set_max_intset_entries: 512,
+            set_max_listpack_entries: 128,
+            set_max_listpack_value: 64,
+            maxmemory: 0,
+            maxmemory_policy: EvictionPolicy::NoEviction,
+            maxmemory_samples: 5,
+            lfu_log_factor: 10,
+            lfu_decay_time: 1,
+            save: vec![(3600, 1), (300, 100), (60, 10000)],
+            dir: ".".to_owned(),
+            dbfilename: "dump.kdb".to_owned(),
+            appendonly: false,
+            appendfilename: "appendonly.aof".to_owned(),
+            appendfsync: AppendFsync::EverySec,
+            aof_load_truncated: true,
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        let value = match name {
+            "hash-max-listpack-entries" => self.hash_max_listpack_entries.to_string(),
+            "hash-max-listpack-value" => self.hash_max_listpack_value.to_string(),
+            "set-max-intset-entries" => self.set_max_intset_entries.to_string(),
+            "set-max-listpack-entries" => self.set_max_listpack_entries.to_string(),
+            "set-max-listpack-value" => self.set_max_listpack_value.to_string(),
+            "maxmemory" => self.maxmemory.to_string(),
+            "maxmemory-policy" => self.maxmemory_policy.name().to_owned(),
+            "maxmemory-samples" => self.maxmemory_samples.to_string(),
+            "lfu-log-factor" => self.lfu_log_factor.to_string(),
+            "lfu-decay-time" => self.lfu_decay_time.to_string(),
+            "save" => {
+                let rules: Vec<String> = self
+                    .save
+                    .iter()
+                    .map(|(seconds, changes)| format!("{} {}", seconds, changes))
+                    .collect();
+                rules.join(" ")
+            }
+            "dir" => self.dir.clone(),
+            "dbfilename" => self.dbfilename.clone(),
+            "appendonly" => yes_no(self.appendonly),
+            "appendfilename" => self.appendfilename.clone(),
+            "appendfsync" => self.appendfsync.name().to_owned(),
+            "aof-load-truncated" => yes_no(self.aof_load_truncated),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    // Err(None) for an unknown parameter, Err(Some(reason)) for a bad value.
+    pub fn set(&mut self, name: &str, value: &[u8]) -> Result<(), Option<String>> {
+        let value = String::from_utf8_lossy(value).into_owned();
+        match name {
+            "hash-max-listpack-entries" => self.hash_max_listpack_entries = parse_integer(&value)?,
+            "hash-max-listpack-value" => self.hash_max_listpack_value = parse_integer(&value)?,
+            "set-max-intset-entries" => self.set_max_intset_entries = parse_integer(&value)?,
+            "set-max-listpack-entries" => self.set_max_listpack_entries = parse_integer(&value)?,
+            "set-max-listpack-value" => self.set_max_listpack_value = parse_integer(&value)?,
+            "maxmemory" => {
+                self.maxmemory = parse_memory(&value.to_ascii_lowercase())
+                    .ok_or_else(|| Some("argument must be a memory value".to_owned()))?
+            }
+            "maxmemory-policy" => {
+                self.maxmemory_policy = EvictionPolicy::parse(&value.to_ascii_lowercase()).ok_or_else(|| {
+                    let names: Vec<&str> = EvictionPolicy::ALL.iter().map(|policy| policy.name()).collect();
+                    Some(format!("argument(s) must be one of the following: {}", names.join(", ")))
+                })?
+            }
+            "maxmemory-samples" => match parse_integer(&value)? {
+                samples @ 1..=64 => self.maxmemory_samples = samples,
+                _ => return Err(Some("argument must be between 1 and 64 inclusive".to_owned())),
+            },
+            "lfu-log-factor" => self.lfu_log_factor = parse_integer(&value)?,
+            "lfu-decay-time" => self.lfu_decay_time = parse_integer(&value)?,
+            "save" => self.save = parse_save_rules(&value).ok_or_else(|| Some("Invalid save parameters".to_owned()))?,
+            "dir" => {
+                if !std::path::Path::new(&value).is_dir() {
+                    return Err(Some("No such file or directory".to_owned()));
+                }
+                self.dir = value
+            }
+            "dbfilename" => {
+                if value.is_empty() || value.contains('/') {
+                    return Err(Some("dbfilename can't be a path, just a filename".to_owned()));
+                }
+                self.dbfilename = value
+            }
+            "appendonly" => self.appendonly = parse_yes_no(&value)?,
+            "appendfilename" => {
+                if value.is_empty() || value.contains('/') {
+                    return Err(Some("appendfilename can't be a path, just a filename".to_owned()));
+                }
+                self.appendfilename = value
+            }
+            "appendfsync" => {
+                self.appendfsync = AppendFsync::parse(&value.to_ascii_lowercase()).ok_or_else(|| {
+                    let names: Vec<&str> = AppendFsync::ALL.iter().map(|policy| policy.name()).collect();
+                    Some(format!("argument(s) must be one of the following: {}", names.join(", ")))
+                })?
+            }
+            "aof-load-truncated" => self.aof_load_truncated = parse_yes_no(&value)?,
+            _ => return Err(None),
+        }
+        Ok(())
+    }
+}
+
+fn parse_integer<T: std::str::FromStr>(value: &str) -> Result<T, Option<String>> {
+    value
+        .parse()
+        .map_err(|_| Some("argument couldn't be parsed into an integer".to_owned()))
+}
+
+// A byte count with an optional unit, as in redis.conf: 1k is 1000 bytes and
+// 1kb is 1024, likewise for m/mb and g/gb.
+fn parse_memory(value: &str) -> Option<u64> {
+    let digits = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let multiplier = match &value[digits..] {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    value[..digits].parse::<u64>().ok()?.checked_mul(multiplier)
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_owned()
+}
+
+fn parse_yes_no(value: &str) -> Result<bool, Option<String>> {
+    match value.to_ascii_lowercase().as_str() {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(Some("argument must be 'yes' or 'no'".to_owned())),
+    }
+}
+
+// "3600 1 300 100": pairs of seconds and changes, or nothing at all.
+fn parse_save_rules(value: &str) -> Option<Vec<(u64, u64)>> {
+    let numbers: Vec<u64> = value
+        .split_whitespace()
+        .map(|number| number.parse().ok())
+        .collect::<Option<_>>()?;
+    if !numbers.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(numbers.chunks(2).map(|rule| (rule[0], rule[1])).collect())
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+thread_local! {
+    // The stores consult the encoding limits as they change, without access
+    // to the context, so the config lives here. Every command runs on the
+    // server's one thread, which makes this the process-wide setting (and
+    // gives each test thread its own).
+    static CONFIG: RefCell<Config> = RefCell::new(Config::new());
+}
+
+pub fn with<R>(read: impl FnOnce(&Config) -> R) -> R {
+    CONFIG.with(|config| read(&config.borrow()))
+}
+
+pub fn replace(config: Config) {
+    CONFIG.with(|current| *current.borrow_mut() = config);
+}
+
+// Applies `--name value` pairs from the command line, the way
+// `redis-server --maxmemory 100mb` overrides its config.
+pub fn apply_arguments(args: &[String]) -> Result<(), String> {
+    let mut config = with(Config::clone);
+    for pair in args.chunks(2) {
+        let name = pair[0].strip_prefix("--").ok_or_else(|| format!("expected --name, got '{}'", pair[0]))?;
+        let value = pair.get(1).ok_or_else(|| format!("missing value for '{}'", pair[0]))?;
+        config.set(&name.to_ascii_lowercase(), value.as_bytes()).map_err(|error| match error {
+            Some(reason) => format!("'{}': {}", name, reason),
+            None => format!("unknown option '{}'", name),
+        })?;
+    }
+    replace(config);
+    Ok(())
+}
@@ -1,4 +1,10 @@
+#![allow(non_snake_case, non_camel_case_types)]
 pub mod command;
+pub mod config;
+pub mod persistence;
+pub mod resp;
+pub mod server;
 pub mod store;
 pub mod store_containers;
-pub mod traits; 
\ No newline at end of file
+pub mod traits;
+pub mod utils;
@@ -1,12 +1,10 @@
-mod command;
-mod store;
-mod store_containers;
-mod traits;
-use crate::command::command_enum::Command;
-use crate::command::command_executor;
-use std::io::{Read, Write};
-use std::net::{TcpListener, TcpStream};
-use store_containers::core_context::context;
+use KiloDB::persistence::aof;
+use KiloDB::{config, persistence, server};
+use KiloDB::store_containers::core_context::context;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::net::TcpListener;
+use tokio::task::LocalSet;
 // fn main() -> Result<(), Box<dyn Error>> {
 //     let mut data = DictStore::new();
 //     let mut ttl_store = TTLStore::new();
@@ -35,113 +33,54 @@ use store_containers::core_context::context;
 //     Ok(())
 // }
 
-// RESP array parser
-fn stream_resp(input: &str) -> Result<Vec<String>, String> {
-    let mut lines = input.split_terminator("\r\n");
-
-    let header = lines.next().ok_or("Empty input")?;
-
-    if !header.starts_with('*') {
-        return Err("Expected RESP Array".to_string());
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> std::io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = config::apply_arguments(&args) {
+        eprintln!("Bad argument: {}", e);
+        std::process::exit(1);
     }
 
-    let num_elements: usize = header[1..]
-        .parse()
-        .map_err(|_| "Invalid array length".to_string())?;
-
-    let mut result = Vec::with_capacity(num_elements);
-
-    for _ in 0..num_elements {
-        let len_line = lines.next().ok_or("Missing $length")?;
-        if !len_line.starts_with('$') {
-            return Err("Expected Bulk String".to_string());
-        }
-
-        let len: usize = len_line[1..]
-            .parse()
-            .map_err(|_| "Invalid bulk string length".to_string())?;
-
-        let data = lines.next().ok_or("Missing data")?;
-
-        if data.len() != len {
-            return Err("Bulk string length mismatch".to_string());
-        }
-
-        result.push(data.to_string());
-    }
-
-    Ok(result)
-}
-
-// Client handler
-fn handle_client(mut stream: TcpStream, context: &mut context) -> std::io::Result<()> {
-    let peer = stream.peer_addr()?;
-    println!("Connected to: {}", peer);
-    // The context is now shared across all clients and lives for the entire program lifetime
-    let mut buffer = [0u8; 512];
-
-    loop {
-        let bytes_read = stream.read(&mut buffer)?;
-        if bytes_read == 0 {
-            println!("Client {} disconnected.", peer);
-            break;
-        }
-
-        let received = &buffer[..bytes_read];
-        let input = String::from_utf8_lossy(received);
-
-        println!("Received from {}:\n{}", peer, input);
-
-        // --- Parse RESP ---
-        let command = match stream_resp(&input) {
-            Ok(cmd) => cmd,
-            Err(e) => {
-                let err_msg = format!("-ERR {}\r\n", e);
-                stream.write_all(err_msg.as_bytes())?;
-                continue;
-            }
-        };
-
-        // --- Execute ---
-        println!("{:?}", command);
-        let mut response = b"+OK\r\n".to_vec();
-        let command_object = Command::new(command.as_slice());
-
-        match &command_object {
-            Command::Unknown { .. } => response = b"-ERR empty command\r\n".to_vec(),
-            _ => {
-                response =
-                    command_executor::command_executor::execute_command(&command_object, context)
-                        .unwrap_or(b"-ERR empty command\r\n".to_vec());
-            }
-        }
-        println!("{:#?}", command_object);
-        stream.write_all(&response)?;
-    }
-
-    Ok(())
-}
-
-fn main() -> std::io::Result<()> {
     // Create the singleton context that will live for the entire program lifetime
-    let mut shared_context = context::new();
+    let shared_context = Rc::new(RefCell::new(context::new()));
     println!("Created singleton context for the entire program lifetime");
 
-    let listener = TcpListener::bind("127.0.0.1:6379")?;
-    println!("TCP server (single-threaded) listening on Redis port 6379");
-
-    for stream in listener.incoming() {
-        match stream {
-            Ok(stream) => {
-                if let Err(e) = handle_client(stream, &mut shared_context) {
-                    eprintln!("Client error: {}", e);
+    // With appendonly on, the AOF is the most complete record and is loaded
+    // instead of the snapshot. Data that cannot be read stops the server rather
+    // than letting it start empty and overwrite the file later.
+    {
+        let mut ctx = shared_context.borrow_mut();
+        let appendonly = config::with(|config| config.appendonly);
+        if appendonly && aof::aof_path().exists() {
+            match aof::load(&mut ctx) {
+                Ok(commands) => println!("DB loaded from append only file: {} commands", commands),
+                Err(e) => {
+                    eprintln!("Failed loading {}: {}", aof::aof_path().display(), e);
+                    std::process::exit(1);
                 }
             }
-            Err(e) => {
-                eprintln!("Connection failed: {}", e);
+        } else {
+            match persistence::load(&mut ctx) {
+                Ok(keys) => println!("DB loaded from disk: {} keys", keys),
+                Err(e) => {
+                    eprintln!("Failed loading {}: {}", persistence::snapshot_path().display(), e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        if appendonly {
+            if let Err(e) = aof::start(&mut ctx) {
+                eprintln!("Can't open the append-only file: {}", e);
+                std::process::exit(1);
             }
         }
     }
 
-    Ok(())
+    let listener = TcpListener::bind("127.0.0.1:6379").await?;
+    println!("TCP server listening on Redis port 6379");
+
+    // Client tasks share the context through an Rc, so they all live on this thread.
+    LocalSet::new()
+        .run_until(server::run(listener, shared_context))
+        .await
 }
@@ -0,0 +1,521 @@
+use crate::command::command_enum::Command;
+use crate::command::command_executor::command_executor;
+use crate::config;
+use crate::resp::reply::format_double;
+use crate::store::value::Value;
+use crate::store_containers::core_context::context;
+use crate::store_containers::AofStore::{AppendFsync, Rewrite};
+use crate::store_containers::TTLStore::now_ms;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+// Elements per command when a rewrite turns a collection back into commands,
+// so huge keys do not become one huge command (Redis' AOF_REWRITE_ITEMS_PER_CMD).
+const ITEMS_PER_COMMAND: usize = 64;
+
+// How long after a failed rewrite the one creating the file is tried again,
+// in seconds.
+const REWRITE_RETRY_DELAY: u64 = 5;
+
+pub fn aof_path() -> PathBuf {
+    config::with(|config| PathBuf::from(&config.dir).join(&config.appendfilename))
+}
+
+// Appends `args` to `out` as a RESP multibulk command.
+pub fn encode_command<A: AsRef<[u8]>>(out: &mut Vec<u8>, args: &[A]) {
+    out.extend_from_slice(format!("*{}\r\n", args.len()).as_bytes());
+    for arg in args {
+        let arg = arg.as_ref();
+        out.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        out.extend_from_slice(arg);
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+// Called after every command a client ran. Logs the command as it was sent if
+// it changed the dataset (`dirty` moved), along with whatever it or the lazy
+// expiry under it queued in the AofStore. Relative TTLs are pinned with a
+// PEXPIREAT so a replay long after does not extend them.
+pub fn propagate(context: &mut context, command: &Command, args: &[Vec<u8>], dirty_before: u64) {
+    let (also, prevent_command) = context.AofStore.take_propagation();
+    if !context.AofStore.enabled {
+        return;
+    }
+    for extra in &also {
+        feed(context, extra);
+    }
+    if context.DataBase.dirty != dirty_before && !prevent_command {
+        feed(context, args);
+        if let Some(key) = command.relative_ttl_key() {
+            if let Some(deadline) = context.get_expiry(key) {
+                feed(context, &[b"PEXPIREAT".as_slice(), key, deadline.to_string().as_bytes()]);
+            }
+        }
+    }
+    // A transaction is written out whole, once its EXEC is logged
+    if context.AofStore.transaction.is_none() {
+        flush(context);
+    }
+}
+
+// Called before EXEC runs its queue. What the queued commands log is wrapped
+// in MULTI and EXEC, so replaying the AOF applies all of them or none.
+pub fn begin_transaction(context: &mut context) {
+    context.AofStore.transaction = Some(false);
+}
+
+pub fn end_transaction(context: &mut context) {
+    if context.AofStore.transaction.take() == Some(true) {
+        feed(context, &[b"EXEC"]);
+    }
+    flush(context);
+}
+
+fn feed<A: AsRef<[u8]>>(context: &mut context, args: &[A]) {
+    if context.AofStore.transaction == Some(false) {
+        context.AofStore.transaction = Some(true);
+        feed(context, &[b"MULTI"]);
+    }
+    let aof = &mut context.AofStore;
+    if let Some(rewrite) = aof.rewrite.as_mut() {
+        encode_command(&mut rewrite.buffer, args);
+    }
+    if aof.file.is_some() {
+        encode_command(&mut aof.buffer, args);
+    }
+}
+
+// Writes the buffered commands out, and syncs them right away under
+// `appendfsync always`. A failed write is kept buffered and retried.
+pub fn flush(context: &mut context) {
+    let aof = &mut context.AofStore;
+    let Some(file) = aof.file.as_mut() else {
+        return;
+    };
+    if aof.buffer.is_empty() {
+        return;
+    }
+    let always = config::with(|config| config.appendfsync == AppendFsync::Always);
+    let result = file
+        .write_all(&aof.buffer)
+        .and_then(|()| if always { file.sync_data() } else { Ok(()) });
+    match result {
+        Ok(()) => {
+            aof.buffer.clear();
+            aof.last_write_ok = true;
+        }
+        Err(e) => {
+            if aof.last_write_ok {
+                eprintln!("Error writing to the AOF: {}", e);
+            }
+            aof.last_write_ok = false;
+        }
+    }
+}
+
+// Run from the server cron: logs the deletions of active expiry, finishes a
+// rewrite that is done and starts the once-a-second fsync.
+pub fn cron(context: &mut context) {
+    let (also, _) = context.AofStore.take_propagation();
+    if context.AofStore.enabled {
+        for extra in &also {
+            feed(context, extra);
+        }
+        flush(context);
+    }
+
+    if context.AofStore.rewrite.as_ref().is_some_and(|rewrite| rewrite.handle.is_finished()) {
+        finish_rewrite(context);
+    }
+    if !context.AofStore.enabled {
+        return;
+    }
+    // The rewrite that creates the file failed, and nothing is logged until
+    // one succeeds: retry it like a failed BGSAVE
+    let aof = &context.AofStore;
+    if aof.file.is_none() && aof.rewrite.is_none() && (now_ms() / 1000).saturating_sub(aof.last_rewrite_try) > REWRITE_RETRY_DELAY {
+        if let Err(e) = background_rewrite(context) {
+            eprintln!("Could not start the AOF: {}", e);
+        }
+    }
+
+    let aof = &mut context.AofStore;
+    if aof.fsync.as_ref().is_some_and(|fsync| fsync.is_finished()) {
+        if let Some(Err(e)) = aof.fsync.take().map(|fsync| fsync.join().unwrap_or(Ok(()))) {
+            eprintln!("Error syncing the AOF: {}", e);
+        }
+    }
+    let everysec = config::with(|config| config.appendfsync == AppendFsync::EverySec);
+    let now = now_ms();
+    if everysec && aof.fsync.is_none() && now.saturating_sub(aof.last_fsync) >= 1000 {
+        if let Some(Ok(file)) = aof.file.as_ref().map(File::try_clone) {
+            aof.fsync = Some(std::thread::spawn(move || file.sync_data()));
+            aof.last_fsync = now;
+        }
+    }
+}
+
+// Opens the AOF for appending at startup, once it has been replayed. With no
+// file yet, a rewrite creates one from whatever the snapshot loaded; `cron`
+// tries again if it fails.
+pub fn start(context: &mut context) -> io::Result<()> {
+    context.AofStore.enabled = true;
+    let path = aof_path();
+    if path.exists() {
+        context.AofStore.file = Some(OpenOptions::new().append(true).open(path)?);
+        return Ok(());
+    }
+    background_rewrite(context).map_err(io::Error::other)
+}
+
+// Follows CONFIG SET appendonly. Turning it on rewrites the file from the
+// keyspace, since an old one would be stale; turning it off closes it.
+pub fn apply_config(context: &mut context) {
+    let appendonly = config::with(|config| config.appendonly);
+    if appendonly == context.AofStore.enabled {
+        return;
+    }
+    // A rewrite started while it was off has missed writes, start over
+    cancel_rewrite(context);
+    if appendonly {
+        context.AofStore.enabled = true;
+        if let Err(e) = background_rewrite(context) {
+            eprintln!("Could not start the AOF: {}", e);
+        }
+    } else {
+        flush(context);
+        let aof = &mut context.AofStore;
+        aof.enabled = false;
+        aof.file = None;
+        aof.buffer.clear();
+    }
+}
+
+fn cancel_rewrite(context: &mut context) {
+    if let Some(rewrite) = context.AofStore.rewrite.take() {
+        let _ = rewrite.handle.join();
+        let _ = fs::remove_file(rewrite.temp_path);
+    }
+}
+
+// BGREWRITEAOF: writes the shortest log that rebuilds the live keyspace to a
+// temporary file from another thread, from a copy-on-write snapshot taken
+// now. Clients keep being served; what they write meanwhile is added when the
+// thread is done.
+pub fn background_rewrite(context: &mut context) -> Result<(), &'static str> {
+    if context.AofStore.rewrite.is_some() {
+        return Err("Background append only file rewriting already in progress");
+    }
+    let entries = context.DataBase.snapshot(now_ms());
+    let temp_path = aof_path().with_file_name(format!("temp-rewriteaof-{}.aof", std::process::id()));
+    let path = temp_path.clone();
+    let handle = std::thread::spawn(move || {
+        let mut data = Vec::new();
+        for (key, value, expires_at) in &entries {
+            rewrite_key(&mut data, key, value, *expires_at);
+        }
+        let mut file = File::create(&path)?;
+        file.write_all(&data)?;
+        file.sync_all()
+    });
+    context.AofStore.last_rewrite_try = now_ms() / 1000;
+    context.AofStore.rewrite = Some(Rewrite {
+        handle,
+        temp_path,
+        buffer: Vec::new(),
+    });
+    Ok(())
+}
+
+// Appends what was written during the rewrite to its file and swaps it in
+// for the old log.
+fn finish_rewrite(context: &mut context) {
+    let Some(rewrite) = context.AofStore.rewrite.take() else {
+        return;
+    };
+    let Rewrite {
+        handle,
+        temp_path,
+        buffer,
+    } = rewrite;
+    let result = handle
+        .join()
+        .unwrap_or_else(|_| Err(io::Error::other("AOF rewrite panicked")))
+        .and_then(|()| {
+            let mut file = OpenOptions::new().append(true).open(&temp_path)?;
+            file.write_all(&buffer)?;
+            file.sync_all()?;
+            fs::rename(&temp_path, aof_path())?;
+            Ok(file)
+        });
+    let aof = &mut context.AofStore;
+    match result {
+        Ok(file) => {
+            // Everything in the old file's buffer is in the new file too. With
+            // appendonly off the new file is only left on disk.
+            aof.buffer.clear();
+            aof.file = Some(file).filter(|_| aof.enabled);
+            aof.last_rewrite_ok = true;
+        }
+        Err(e) => {
+            eprintln!("Background AOF rewrite failed: {}", e);
+            let _ = fs::remove_file(&temp_path);
+            aof.last_rewrite_ok = false;
+        }
+    }
+}
+
+// The commands that recreate one key.
+pub fn rewrite_key(out: &mut Vec<u8>, key: &[u8], value: &Value, expires_at: Option<u64>) {
+    match value {
+        Value::String(string) => encode_command(out, &[b"SET".as_slice(), key, string.get_value()]),
+        Value::List(list) => {
+            let elements: Vec<&[u8]> = list.iter().map(Vec::as_slice).collect();
+            rewrite_batches(out, b"RPUSH", key, elements, 1);
+        }
+        Value::Set(set) => {
+            let members: Vec<Vec<u8>> = set.iter().map(|member| member.into_owned()).collect();
+            rewrite_batches(out, b"SADD", key, members.iter().map(Vec::as_slice).collect(), 1);
+        }
+        Value::ZSet(zset) => {
+            let scores: Vec<String> = zset.iter().map(|(_, score)| format_double(score)).collect();
+            let mut items = Vec::new();
+            for ((member, _), score) in zset.iter().zip(&scores) {
+                items.push(score.as_bytes());
+                items.push(member);
+            }
+            rewrite_batches(out, b"ZADD", key, items, 2);
+        }
+        Value::Hash(hash) => {
+            let mut items = Vec::new();
+            for (field, value) in hash.iter() {
+                items.push(field);
+                items.push(value);
+            }
+            rewrite_batches(out, b"HSET", key, items, 2);
+        }
+    }
+    if let Some(deadline) = expires_at {
+        encode_command(out, &[b"PEXPIREAT".as_slice(), key, deadline.to_string().as_bytes()]);
+    }
+}
+
+// `name key item...`, ITEMS_PER_COMMAND elements at a time, an element being
+// `stride` items (a field and its value, a score and its member).
+fn rewrite_batches(out: &mut Vec<u8>, name: &[u8], key: &[u8], items: Vec<&[u8]>, stride: usize) {
+    for batch in items.chunks(ITEMS_PER_COMMAND * stride) {
+        let mut args = vec![name, key];
+        args.extend_from_slice(batch);
+        encode_command(out, &args);
+    }
+}
+
+#[derive(Debug)]
+pub enum AofError {
+    Io(io::Error),
+    // Not a sequence of RESP commands, at this byte offset
+    BadFormat(usize),
+    // Ends in the middle of a command and aof-load-truncated is off
+    Truncated(usize),
+    // A logged command that is not one the server knows
+    BadCommand(String),
+}
+
+impl fmt::Display for AofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AofError::Io(e) => write!(f, "{}", e),
+            AofError::BadFormat(offset) => write!(f, "bad file format at byte {}", offset),
+            AofError::Truncated(offset) => write!(
+                f,
+                "unexpected end of file at byte {}, set aof-load-truncated yes to load what precedes it",
+                offset
+            ),
+            AofError::BadCommand(name) => write!(f, "unknown command '{}' in the file", name),
+        }
+    }
+}
+
+impl std::error::Error for AofError {}
+
+// Replays the AOF into the keyspace at startup and returns how many commands
+// it held, MULTI and EXEC aside. If the last command is cut short, as after a
+// crash mid-write, the file is truncated to the commands before it when
+// aof-load-truncated is on; so is a transaction missing its EXEC.
+pub fn load(context: &mut context) -> Result<usize, AofError> {
+    let path = aof_path();
+    let data = match fs::read(&path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(AofError::Io(e)),
+    };
+    let mut offset = 0;
+    let mut replayed = 0;
+    let mut truncated_at = None;
+    // The offset of the MULTI being read, and the commands queued since
+    let mut transaction: Option<(usize, Vec<QueuedCommand>)> = None;
+    while offset < data.len() {
+        let (args, next) = match read_command(&data, offset) {
+            Ok(Some(command)) => command,
+            Ok(None) => {
+                truncated_at = Some(offset);
+                break;
+            }
+            Err(()) => return Err(AofError::BadFormat(offset)),
+        };
+        match (Command::new(&args), &mut transaction) {
+            (Command::MULTI, _) => transaction = Some((offset, Vec::new())),
+            (Command::EXEC, Some(_)) => {
+                let (_, queued) = transaction.take().expect("a transaction is open");
+                for (args, offset) in queued {
+                    replay(context, &args, offset)?;
+                    replayed += 1;
+                }
+            }
+            (_, Some((_, queued))) => queued.push((args, offset)),
+            (_, None) => {
+                replay(context, &args, offset)?;
+                replayed += 1;
+            }
+        }
+        offset = next;
+    }
+    if let Some((multi, _)) = transaction {
+        truncated_at = Some(multi);
+    }
+    if let Some(offset) = truncated_at {
+        if !config::with(|config| config.aof_load_truncated) {
+            return Err(AofError::Truncated(offset));
+        }
+        eprintln!("AOF ends with a truncated command; dropping its last {} bytes", data.len() - offset);
+        OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_len(offset as u64))
+            .map_err(AofError::Io)?;
+    }
+    context.AofStore.take_propagation();
+    context.DataBase.dirty = 0;
+    Ok(replayed)
+}
+
+// Runs one logged command, read at `offset`.
+fn replay(context: &mut context, args: &[Vec<u8>], offset: usize) -> Result<(), AofError> {
+    let command = Command::new(args);
+    if let Command::Unknown { .. } = command {
+        return Err(AofError::BadCommand(String::from_utf8_lossy(&args[0]).into_owned()));
+    }
+    // As in Redis, a command that fails against the data replayed so far
+    // changed nothing when it first ran either, so it is only reported
+    let reply =
+        command_executor::dispatch(&command, context).unwrap_or_else(|e| format!("-{}\r\n", e).into_bytes());
+    if reply.starts_with(b"-") {
+        eprintln!(
+            "AOF command at byte {} failed when replayed: {}",
+            offset,
+            String::from_utf8_lossy(&reply).trim_end()
+        );
+    }
+    Ok(())
+}
+
+// A command's arguments and the offset of the command after it.
+type ParsedCommand = (Vec<Vec<u8>>, usize);
+
+// A command read inside MULTI, and the offset it was read at.
+type QueuedCommand = (Vec<Vec<u8>>, usize);
+
+// The command starting at `offset`, Ok(None) if the data ends before it does,
+// Err if it is not a RESP multibulk command.
+fn read_command(data: &[u8], offset: usize) -> Result<Option<ParsedCommand>, ()> {
+    let Some((count, mut position)) = read_header(data, offset, b'*')? else {
+        return Ok(None);
+    };
+    let mut args = Vec::with_capacity(count.min(1024));
+    for _ in 0..count {
+        let Some((length, start)) = read_header(data, position, b'$')? else {
+            return Ok(None);
+        };
+        // The length comes from the file: it may be anything
+        let end = start.checked_add(length).ok_or(())?;
+        if data.len().saturating_sub(2) < end {
+            return Ok(None);
+        }
+        if &data[end..end + 2] != b"\r\n" {
+            return Err(());
+        }
+        args.push(data[start..end].to_vec());
+        position = end + 2;
+    }
+    Ok(Some((args, position)))
+}
+
+// A `*<n>\r\n` or `$<n>\r\n` line: the number and the offset after it.
+fn read_header(data: &[u8], offset: usize, prefix: u8) -> Result<Option<(usize, usize)>, ()> {
+    let Some(&first) = data.get(offset) else {
+        return Ok(None);
+    };
+    if first != prefix {
+        return Err(());
+    }
+    let Some(newline) = data[offset..].iter().position(|&byte| byte == b'\n') else {
+        return Ok(None);
+    };
+    let line = &data[offset + 1..offset + newline];
+    let digits = line.strip_suffix(b"\r").ok_or(())?;
+    let number = std::str::from_utf8(digits).ok().and_then(|digits| digits.parse().ok()).ok_or(())?;
+    Ok(Some((number, offset + newline + 1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_command_with_a_huge_length() {
+        assert!(read_command(b"*1\r\n$18446744073709551615\r\nx\r\n", 0).is_err());
+        assert!(matches!(read_command(b"*1\r\n$9223372036854775807\r\nx\r\n", 0), Ok(None)));
+    }
+
+    #[test]
+    fn test_read_command() {
+        let mut data = Vec::new();
+        encode_command(&mut data, &[&b"SET"[..], b"key", b"a\r\nb"]);
+        let first_end = data.len();
+        encode_command(&mut data, &[&b"DEL"[..], b"key"]);
+
+        let (args, next) = read_command(&data, 0).unwrap().unwrap();
+        assert_eq!(args, vec![b"SET".to_vec(), b"key".to_vec(), b"a\r\nb".to_vec()]);
+        assert_eq!(next, first_end);
+        assert_eq!(read_command(&data, next).unwrap().unwrap().1, data.len());
+
+        // Every cut inside the second command is a truncation, not an error
+        for cut in first_end..data.len() {
+            assert_eq!(read_command(&data[..cut], first_end), Ok(None), "cut at {}", cut);
+        }
+        assert_eq!(read_command(b"GET key\r\n", 0), Err(()));
+        assert_eq!(read_command(b"*1\r\n$3\r\nGETX\r\n", 0), Err(()));
+    }
+
+    #[test]
+    fn test_rewrite_batches_large_collections() {
+        let mut list = crate::store::vector_store::VectorStore::new();
+        for i in 0..150 {
+            list.push_right(i.to_string().as_bytes());
+        }
+        let mut data = Vec::new();
+        rewrite_key(&mut data, b"list", &Value::List(list), Some(123));
+
+        let mut commands = Vec::new();
+        let mut offset = 0;
+        while let Some((args, next)) = read_command(&data, offset).unwrap() {
+            commands.push(args);
+            offset = next;
+        }
+        let lengths: Vec<usize> = commands.iter().map(Vec::len).collect();
+        assert_eq!(lengths, vec![2 + 64, 2 + 64, 2 + 22, 3]);
+        assert_eq!(commands[3], vec![b"PEXPIREAT".to_vec(), b"list".to_vec(), b"123".to_vec()]);
+    }
+}
@@ -0,0 +1,124 @@
+pub mod aof;
+pub mod rdb;
+pub mod snapshot;
+
+use crate::config;
+use crate::persistence::snapshot::SnapshotError;
+use crate::store_containers::core_context::context;
+use crate::store_containers::TTLStore::now_ms;
+use std::io;
+use std::path::PathBuf;
+
+// How long after a failed BGSAVE the save rules may try again, in seconds.
+const BGSAVE_RETRY_DELAY: u64 = 5;
+
+// Where snapshots are written and loaded from: `dir/dbfilename`.
+pub fn snapshot_path() -> PathBuf {
+    config::with(|config| PathBuf::from(&config.dir).join(&config.dbfilename))
+}
+
+// SAVE: writes the snapshot before returning, blocking every client meanwhile.
+pub fn save(context: &mut context) -> io::Result<()> {
+    let now = now_ms();
+    let live = context.DataBase.store.iter().filter(|(_, entry)| !entry.is_expired(now));
+    let data = snapshot::serialize(live.map(|(key, entry)| (key.as_slice(), entry.value.as_ref(), entry.expires_at)));
+    snapshot::write_file(&snapshot_path(), &data)?;
+    context.DataBase.dirty = 0;
+    context.PersistenceStore.last_save = now / 1000;
+    context.PersistenceStore.last_bgsave_ok = true;
+    Ok(())
+}
+
+// BGSAVE: takes a copy-on-write snapshot of the live keys, which is the point
+// in time it captures, and leaves encoding and writing them to another
+// thread. The outcome is collected by `cron`.
+pub fn background_save(context: &mut context) -> Result<(), &'static str> {
+    if context.PersistenceStore.is_saving() {
+        return Err("Background save already in progress");
+    }
+    let entries = context.DataBase.snapshot(now_ms());
+    let path = snapshot_path();
+    let handle = std::thread::spawn(move || {
+        let data = snapshot::serialize(
+            entries
+                .iter()
+                .map(|(key, value, expires_at)| (key.as_slice(), value.as_ref(), *expires_at)),
+        );
+        snapshot::write_file(&path, &data)
+    });
+    context
+        .PersistenceStore
+        .start_background_save(handle, context.DataBase.dirty);
+    Ok(())
+}
+
+// Run from the server cron: collects a finished BGSAVE, then starts a new one
+// if a `save` rule is met.
+pub fn cron(context: &mut context) {
+    let now = now_ms() / 1000;
+    if let Some((result, dirty_at_start)) = context.PersistenceStore.finished_background_save() {
+        match result {
+            Ok(()) => {
+                // Changes made while saving still count towards the next one
+                context.DataBase.dirty = context.DataBase.dirty.saturating_sub(dirty_at_start);
+                context.PersistenceStore.last_save = now;
+                context.PersistenceStore.last_bgsave_ok = true;
+            }
+            Err(e) => {
+                eprintln!("Background saving error: {}", e);
+                context.PersistenceStore.last_bgsave_ok = false;
+            }
+        }
+    }
+
+    let persistence = &context.PersistenceStore;
+    if persistence.is_saving()
+        || (!persistence.last_bgsave_ok && now.saturating_sub(persistence.last_bgsave_try) <= BGSAVE_RETRY_DELAY)
+    {
+        return;
+    }
+    let dirty = context.DataBase.dirty;
+    let since_save = now.saturating_sub(persistence.last_save);
+    let due = config::with(|config| {
+        config
+            .save
+            .iter()
+            .any(|&(seconds, changes)| dirty >= changes && since_save > seconds)
+    });
+    if due {
+        let _ = background_save(context);
+    }
+}
+
+// Loads the snapshot into an empty keyspace at startup and returns how many
+// keys it held. A missing file is an empty dataset; keys whose deadline
+// passed while the server was down are dropped. A Redis RDB file is read as
+// well, so pointing dbfilename at a dump.rdb migrates it; the next save
+// writes it back in KiloDB's own format.
+pub fn load(context: &mut context) -> Result<usize, SnapshotError> {
+    let data = match std::fs::read(snapshot_path()) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+    let now = now_ms();
+    let mut loaded = 0;
+    let entries = if data.starts_with(rdb::MAGIC) {
+        rdb::deserialize(&data)?
+    } else {
+        snapshot::deserialize(&data)?
+    };
+    for (key, value, expires_at) in entries {
+        if expires_at.is_some_and(|deadline| deadline <= now) {
+            continue;
+        }
+        context.insert_key(key.clone(), value);
+        if let Some(deadline) = expires_at {
+            context.set_expiry(&key, deadline);
+        }
+        loaded += 1;
+    }
+    context.DataBase.account_memory();
+    context.DataBase.dirty = 0;
+    Ok(loaded)
+}
@@ -0,0 +1,732 @@
+use crate::persistence::snapshot::{Reader, SnapshotEntry, SnapshotError};
+use crate::store::hash_store::HashStore;
+use crate::store::set_store::SetStore;
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store::string_store::StringStore;
+use crate::store::value::Value;
+use crate::store::vector_store::VectorStore;
+use crate::utils::crc64::crc64;
+use crate::utils::lzf;
+
+// Reader for the RDB files of Redis 6 and 7 (RDB versions up to 12), so a
+// dataset can move from Redis to KiloDB. Layout:
+//
+//   "REDIS" version(4 digits)
+//   opcodes and keys:  [EXPIRETIME(_MS) deadline] [IDLE|FREQ ...] type key value
+//   EOF crc64
+//
+// Lengths use Redis' 6/14/32/64-bit encoding, whose last form instead marks a
+// string stored as an integer or LZF-compressed. Small collections come as
+// one string holding a ziplist, listpack or intset.
+pub const MAGIC: &[u8] = b"REDIS";
+const MAX_VERSION: u32 = 12;
+
+const OPCODE_SLOT_INFO: u8 = 0xf4;
+const OPCODE_FUNCTION2: u8 = 0xf5;
+const OPCODE_FUNCTION_PRE_GA: u8 = 0xf6;
+const OPCODE_MODULE_AUX: u8 = 0xf7;
+const OPCODE_IDLE: u8 = 0xf8;
+const OPCODE_FREQ: u8 = 0xf9;
+const OPCODE_AUX: u8 = 0xfa;
+const OPCODE_RESIZEDB: u8 = 0xfb;
+const OPCODE_EXPIRETIME_MS: u8 = 0xfc;
+const OPCODE_EXPIRETIME: u8 = 0xfd;
+const OPCODE_SELECTDB: u8 = 0xfe;
+const OPCODE_EOF: u8 = 0xff;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+const TYPE_ZSET_2: u8 = 5;
+const TYPE_MODULE_PRE_GA: u8 = 6;
+const TYPE_MODULE_2: u8 = 7;
+const TYPE_HASH_ZIPMAP: u8 = 9;
+const TYPE_LIST_ZIPLIST: u8 = 10;
+const TYPE_SET_INTSET: u8 = 11;
+const TYPE_ZSET_ZIPLIST: u8 = 12;
+const TYPE_HASH_ZIPLIST: u8 = 13;
+const TYPE_LIST_QUICKLIST: u8 = 14;
+const TYPE_STREAM_LISTPACKS: u8 = 15;
+const TYPE_HASH_LISTPACK: u8 = 16;
+const TYPE_ZSET_LISTPACK: u8 = 17;
+const TYPE_LIST_QUICKLIST_2: u8 = 18;
+const TYPE_STREAM_LISTPACKS_2: u8 = 19;
+const TYPE_SET_LISTPACK: u8 = 20;
+const TYPE_STREAM_LISTPACKS_3: u8 = 21;
+const TYPE_HASH_METADATA_PRE_GA: u8 = 22;
+const TYPE_HASH_LISTPACK_EX: u8 = 25;
+
+// The special string encodings, in the low bits of a length byte 0b11xxxxxx
+const ENCODING_INT8: u64 = 0;
+const ENCODING_INT16: u64 = 1;
+const ENCODING_INT32: u64 = 2;
+const ENCODING_LZF: u64 = 3;
+
+// Quicklist 2 nodes: a single element, or a listpack of them
+const QUICKLIST_NODE_PLAIN: usize = 1;
+const QUICKLIST_NODE_PACKED: usize = 2;
+
+// Every key of database 0, with its deadline in ms. Keys in other databases
+// are refused since KiloDB has only the one.
+pub fn deserialize(data: &[u8]) -> Result<Vec<SnapshotEntry>, SnapshotError> {
+    if !data.starts_with(MAGIC) {
+        return Err(SnapshotError::BadMagic);
+    }
+    let mut reader = Reader::new(&data[MAGIC.len()..]);
+    let version = std::str::from_utf8(reader.take(4)?)
+        .ok()
+        .and_then(|digits| digits.parse::<u32>().ok())
+        .ok_or(SnapshotError::BadMagic)?;
+    if !(1..=MAX_VERSION).contains(&version) {
+        return Err(SnapshotError::Unsupported(format!("RDB version {}", version)));
+    }
+
+    let mut entries = Vec::new();
+    let mut database = 0;
+    let mut expires_at = None;
+    loop {
+        match reader.byte()? {
+            OPCODE_EOF => break,
+            OPCODE_SELECTDB => database = read_length(&mut reader)?,
+            OPCODE_RESIZEDB => {
+                read_length(&mut reader)?;
+                read_length(&mut reader)?;
+            }
+            OPCODE_SLOT_INFO => {
+                for _ in 0..3 {
+                    read_length(&mut reader)?;
+                }
+            }
+            OPCODE_AUX => {
+                read_string(&mut reader)?;
+                read_string(&mut reader)?;
+            }
+            OPCODE_EXPIRETIME_MS => expires_at = Some(reader.u64()?),
+            OPCODE_EXPIRETIME => {
+                let seconds = u32::from_le_bytes(reader.take(4)?.try_into().expect("took 4 bytes"));
+                expires_at = Some(seconds as u64 * 1000);
+            }
+            // LRU and LFU data, which KiloDB starts afresh
+            OPCODE_IDLE => {
+                read_length(&mut reader)?;
+            }
+            OPCODE_FREQ => {
+                reader.byte()?;
+            }
+            OPCODE_MODULE_AUX => return Err(SnapshotError::Unsupported("module data".to_owned())),
+            OPCODE_FUNCTION2 | OPCODE_FUNCTION_PRE_GA => {
+                return Err(SnapshotError::Unsupported("functions".to_owned()))
+            }
+            value_type => {
+                let key = read_string(&mut reader)?;
+                if database != 0 {
+                    return Err(SnapshotError::Unsupported(format!("keys in database {}", database)));
+                }
+                let value = read_object(&mut reader, value_type, &key)?;
+                let deadline = expires_at.take();
+                // Redis never writes empty collections, but better safe
+                if !value.is_empty_collection() {
+                    entries.push((key, value, deadline));
+                }
+            }
+        }
+    }
+
+    // Version 5 added the checksum; 0 means it was turned off
+    if version >= 5 {
+        let body = data.len() - reader.remaining().len();
+        let checksum = reader.u64()?;
+        if checksum != 0 && checksum != crc64(0, &data[..body]) {
+            return Err(SnapshotError::BadChecksum);
+        }
+    }
+    Ok(entries)
+}
+
+fn read_object(reader: &mut Reader, value_type: u8, key: &[u8]) -> Result<Value, SnapshotError> {
+    let value = match value_type {
+        TYPE_STRING => Value::String(StringStore::new(read_string(reader)?)),
+        TYPE_LIST => {
+            let count = read_length(reader)?;
+            list((0..count).map(|_| read_string(reader)).collect::<Result<_, _>>()?)
+        }
+        TYPE_LIST_ZIPLIST => list(ziplist(&read_string(reader)?)?),
+        TYPE_LIST_QUICKLIST => {
+            let mut elements = Vec::new();
+            for _ in 0..read_length(reader)? {
+                elements.extend(ziplist(&read_string(reader)?)?);
+            }
+            list(elements)
+        }
+        TYPE_LIST_QUICKLIST_2 => {
+            let mut elements = Vec::new();
+            for _ in 0..read_length(reader)? {
+                let container = read_length(reader)?;
+                let node = read_string(reader)?;
+                match container {
+                    QUICKLIST_NODE_PLAIN => elements.push(node),
+                    QUICKLIST_NODE_PACKED => elements.extend(listpack(&node)?),
+                    _ => return Err(SnapshotError::Corrupt("unknown quicklist node")),
+                }
+            }
+            list(elements)
+        }
+        TYPE_SET => {
+            let count = read_length(reader)?;
+            set((0..count).map(|_| read_string(reader)).collect::<Result<_, _>>()?)
+        }
+        TYPE_SET_INTSET => set(intset(&read_string(reader)?)?),
+        TYPE_SET_LISTPACK => set(listpack(&read_string(reader)?)?),
+        TYPE_ZSET | TYPE_ZSET_2 => {
+            let mut zset = SortedSetStore::new();
+            for _ in 0..read_length(reader)? {
+                let member = read_string(reader)?;
+                let score = if value_type == TYPE_ZSET_2 {
+                    f64::from_bits(reader.u64()?)
+                } else {
+                    read_string_double(reader)?
+                };
+                if score.is_nan() {
+                    return Err(SnapshotError::Corrupt("NaN score"));
+                }
+                zset.add_member(&member, score);
+            }
+            Value::ZSet(zset)
+        }
+        TYPE_ZSET_ZIPLIST => zset(ziplist(&read_string(reader)?)?)?,
+        TYPE_ZSET_LISTPACK => zset(listpack(&read_string(reader)?)?)?,
+        TYPE_HASH => {
+            let mut hash = HashStore::new();
+            for _ in 0..read_length(reader)? {
+                let field = read_string(reader)?;
+                hash.set_field(&field, &read_string(reader)?);
+            }
+            Value::Hash(hash)
+        }
+        TYPE_HASH_ZIPMAP => hash(zipmap(&read_string(reader)?)?)?,
+        TYPE_HASH_ZIPLIST => hash(ziplist(&read_string(reader)?)?)?,
+        TYPE_HASH_LISTPACK => hash(listpack(&read_string(reader)?)?)?,
+        TYPE_MODULE_PRE_GA | TYPE_MODULE_2 => return Err(unsupported("module", key)),
+        TYPE_STREAM_LISTPACKS | TYPE_STREAM_LISTPACKS_2 | TYPE_STREAM_LISTPACKS_3 => {
+            return Err(unsupported("stream", key))
+        }
+        TYPE_HASH_METADATA_PRE_GA..=TYPE_HASH_LISTPACK_EX => {
+            return Err(unsupported("hash with field expiration", key))
+        }
+        other => return Err(SnapshotError::UnknownType(other)),
+    };
+    Ok(value)
+}
+
+fn unsupported(kind: &str, key: &[u8]) -> SnapshotError {
+    SnapshotError::Unsupported(format!("{} value at key '{}'", kind, String::from_utf8_lossy(key)))
+}
+
+fn list(elements: Vec<Vec<u8>>) -> Value {
+    let mut list = VectorStore::new();
+    for element in &elements {
+        list.push_right(element);
+    }
+    Value::List(list)
+}
+
+fn set(members: Vec<Vec<u8>>) -> Value {
+    let mut set = SetStore::new();
+    for member in &members {
+        set.add_member(member);
+    }
+    Value::Set(set)
+}
+
+// Encoded zsets alternate members and their scores.
+fn zset(items: Vec<Vec<u8>>) -> Result<Value, SnapshotError> {
+    if !items.len().is_multiple_of(2) {
+        return Err(SnapshotError::Corrupt("odd number of zset items"));
+    }
+    let mut zset = SortedSetStore::new();
+    for pair in items.chunks_exact(2) {
+        let score = parse_double(&pair[1]).ok_or(SnapshotError::Corrupt("bad zset score"))?;
+        zset.add_member(&pair[0], score);
+    }
+    Ok(Value::ZSet(zset))
+}
+
+// Encoded hashes alternate fields and their values.
+fn hash(items: Vec<Vec<u8>>) -> Result<Value, SnapshotError> {
+    if !items.len().is_multiple_of(2) {
+        return Err(SnapshotError::Corrupt("odd number of hash items"));
+    }
+    let mut hash = HashStore::new();
+    for pair in items.chunks_exact(2) {
+        hash.set_field(&pair[0], &pair[1]);
+    }
+    Ok(Value::Hash(hash))
+}
+
+fn parse_double(bytes: &[u8]) -> Option<f64> {
+    let score: f64 = std::str::from_utf8(bytes).ok()?.parse().ok()?;
+    (!score.is_nan()).then_some(score)
+}
+
+// A length, or for the 0b11 form the string encoding it stands for.
+fn read_length_or_encoding(reader: &mut Reader) -> Result<(u64, bool), SnapshotError> {
+    let first = reader.byte()?;
+    let length = match first >> 6 {
+        0 => (first & 0x3f) as u64,
+        1 => ((first as u64 & 0x3f) << 8) | reader.byte()? as u64,
+        2 => match first {
+            0x80 => u32::from_be_bytes(reader.take(4)?.try_into().expect("took 4 bytes")) as u64,
+            0x81 => u64::from_be_bytes(reader.take(8)?.try_into().expect("took 8 bytes")),
+            _ => return Err(SnapshotError::Corrupt("unknown length encoding")),
+        },
+        _ => return Ok(((first & 0x3f) as u64, true)),
+    };
+    Ok((length, false))
+}
+
+fn read_length(reader: &mut Reader) -> Result<usize, SnapshotError> {
+    match read_length_or_encoding(reader)? {
+        (length, false) => usize::try_from(length).map_err(|_| SnapshotError::Corrupt("length too long")),
+        (_, true) => Err(SnapshotError::Corrupt("encoded string where a length was expected")),
+    }
+}
+
+fn read_string(reader: &mut Reader) -> Result<Vec<u8>, SnapshotError> {
+    let (length, encoded) = read_length_or_encoding(reader)?;
+    if !encoded {
+        let length = usize::try_from(length).map_err(|_| SnapshotError::Corrupt("length too long"))?;
+        return Ok(reader.take(length)?.to_vec());
+    }
+    let string = match length {
+        ENCODING_INT8 => (reader.byte()? as i8).to_string().into_bytes(),
+        ENCODING_INT16 => i16::from_le_bytes(reader.take(2)?.try_into().expect("took 2 bytes"))
+            .to_string()
+            .into_bytes(),
+        ENCODING_INT32 => i32::from_le_bytes(reader.take(4)?.try_into().expect("took 4 bytes"))
+            .to_string()
+            .into_bytes(),
+        ENCODING_LZF => {
+            let compressed_length = read_length(reader)?;
+            let length = read_length(reader)?;
+            let compressed = reader.take(compressed_length)?;
+            lzf::decompress(compressed, length).ok_or(SnapshotError::Corrupt("bad LZF data"))?
+        }
+        _ => return Err(SnapshotError::Corrupt("unknown string encoding")),
+    };
+    Ok(string)
+}
+
+// The old zset score format: a length byte, with 253..255 for NaN and the
+// infinities, then the number as text.
+fn read_string_double(reader: &mut Reader) -> Result<f64, SnapshotError> {
+    match reader.byte()? {
+        253 => Ok(f64::NAN),
+        254 => Ok(f64::INFINITY),
+        255 => Ok(f64::NEG_INFINITY),
+        length => parse_double(reader.take(length as usize)?).ok_or(SnapshotError::Corrupt("bad zset score")),
+    }
+}
+
+// The elements of a ziplist (Redis < 7): a header, entries that each start
+// with the length of the previous one, and an 0xff end byte.
+fn ziplist(blob: &[u8]) -> Result<Vec<Vec<u8>>, SnapshotError> {
+    ziplist_entries(blob).ok_or(SnapshotError::Corrupt("bad ziplist"))
+}
+
+fn ziplist_entries(blob: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut reader = Reader::new(blob);
+    reader.take(10).ok()?;
+    let mut entries = Vec::new();
+    loop {
+        match reader.byte().ok()? {
+            0xff => return Some(entries),
+            0xfe => {
+                reader.take(4).ok()?;
+            }
+            _ => {}
+        }
+        let encoding = reader.byte().ok()?;
+        let entry = match encoding >> 6 {
+            0 => reader.take((encoding & 0x3f) as usize).ok()?.to_vec(),
+            1 => {
+                let length = ((encoding as usize & 0x3f) << 8) | reader.byte().ok()? as usize;
+                reader.take(length).ok()?.to_vec()
+            }
+            2 => {
+                let length = u32::from_be_bytes(reader.take(4).ok()?.try_into().ok()?);
+                reader.take(length as usize).ok()?.to_vec()
+            }
+            _ => {
+                let number = match encoding {
+                    0xc0 => read_int(&mut reader, 2)?,
+                    0xd0 => read_int(&mut reader, 4)?,
+                    0xe0 => read_int(&mut reader, 8)?,
+                    0xf0 => read_int(&mut reader, 3)?,
+                    0xfe => read_int(&mut reader, 1)?,
+                    0xf1..=0xfd => (encoding & 0x0f) as i64 - 1,
+                    _ => return None,
+                };
+                number.to_string().into_bytes()
+            }
+        };
+        entries.push(entry);
+    }
+}
+
+// The elements of a listpack (Redis 7): a header, then entries that each end
+// with their own length, for walking backwards, and an 0xff end byte.
+fn listpack(blob: &[u8]) -> Result<Vec<Vec<u8>>, SnapshotError> {
+    listpack_entries(blob).ok_or(SnapshotError::Corrupt("bad listpack"))
+}
+
+fn listpack_entries(blob: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut reader = Reader::new(blob);
+    reader.take(6).ok()?;
+    let mut entries = Vec::new();
+    loop {
+        let encoding = reader.byte().ok()?;
+        if encoding == 0xff {
+            return Some(entries);
+        }
+        let start = blob.len() - reader.remaining().len() - 1;
+        let entry = if encoding & 0x80 == 0 {
+            (encoding as i64).to_string().into_bytes()
+        } else if encoding & 0xc0 == 0x80 {
+            reader.take((encoding & 0x3f) as usize).ok()?.to_vec()
+        } else if encoding & 0xe0 == 0xc0 {
+            // 13-bit two's complement
+            let number = ((encoding as i64 & 0x1f) << 8) | reader.byte().ok()? as i64;
+            let number = if number >= 1 << 12 { number - (1 << 13) } else { number };
+            number.to_string().into_bytes()
+        } else if encoding & 0xf0 == 0xe0 {
+            let length = ((encoding as usize & 0x0f) << 8) | reader.byte().ok()? as usize;
+            reader.take(length).ok()?.to_vec()
+        } else {
+            match encoding {
+                0xf0 => {
+                    let length = u32::from_le_bytes(reader.take(4).ok()?.try_into().ok()?);
+                    reader.take(length as usize).ok()?.to_vec()
+                }
+                0xf1 => read_int(&mut reader, 2)?.to_string().into_bytes(),
+                0xf2 => read_int(&mut reader, 3)?.to_string().into_bytes(),
+                0xf3 => read_int(&mut reader, 4)?.to_string().into_bytes(),
+                0xf4 => read_int(&mut reader, 8)?.to_string().into_bytes(),
+                _ => return None,
+            }
+        };
+        let length = blob.len() - reader.remaining().len() - start;
+        reader.take(backlen_size(length)).ok()?;
+        entries.push(entry);
+    }
+}
+
+// Bytes used by a listpack entry's trailing length, 7 bits per byte.
+fn backlen_size(length: usize) -> usize {
+    match length {
+        0..=127 => 1,
+        128..=16382 => 2,
+        16383..=2097150 => 3,
+        2097151..=268435454 => 4,
+        _ => 5,
+    }
+}
+
+// The members of an intset: a width of 2, 4 or 8 bytes, a count, then the
+// sorted integers.
+fn intset(blob: &[u8]) -> Result<Vec<Vec<u8>>, SnapshotError> {
+    intset_members(blob).ok_or(SnapshotError::Corrupt("bad intset"))
+}
+
+fn intset_members(blob: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut reader = Reader::new(blob);
+    let width = read_int(&mut reader, 4)? as usize;
+    let count = read_int(&mut reader, 4)? as usize;
+    if !matches!(width, 2 | 4 | 8) || reader.remaining().len() != count * width {
+        return None;
+    }
+    (0..count)
+        .map(|_| read_int(&mut reader, width).map(|member| member.to_string().into_bytes()))
+        .collect()
+}
+
+// The field-value pairs of a zipmap, the hash encoding of Redis < 2.6.
+fn zipmap(blob: &[u8]) -> Result<Vec<Vec<u8>>, SnapshotError> {
+    zipmap_entries(blob).ok_or(SnapshotError::Corrupt("bad zipmap"))
+}
+
+fn zipmap_entries(blob: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut reader = Reader::new(blob);
+    reader.byte().ok()?;
+    let mut items = Vec::new();
+    loop {
+        let length = match reader.byte().ok()? {
+            0xff => break,
+            0xfe => u32::from_le_bytes(reader.take(4).ok()?.try_into().ok()?) as usize,
+            length => length as usize,
+        };
+        // Values are followed by unused bytes, counted right before them
+        let is_value = !items.len().is_multiple_of(2);
+        let free = if is_value { reader.byte().ok()? as usize } else { 0 };
+        items.push(reader.take(length).ok()?.to_vec());
+        reader.take(free).ok()?;
+    }
+    items.len().is_multiple_of(2).then_some(items)
+}
+
+// A little-endian signed integer of 1 to 8 bytes.
+fn read_int(reader: &mut Reader, width: usize) -> Option<i64> {
+    let bytes = reader.take(width).ok()?;
+    let mut padded = [0u8; 8];
+    padded[8 - width..].copy_from_slice(bytes);
+    // Shifting back down from the top bits sign-extends
+    Some(i64::from_le_bytes(padded) >> (8 * (8 - width)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(out: &mut Vec<u8>, bytes: &[u8]) {
+        assert!(bytes.len() < 64);
+        out.push(bytes.len() as u8);
+        out.extend_from_slice(bytes);
+    }
+
+    fn key(out: &mut Vec<u8>, value_type: u8, name: &[u8]) {
+        out.push(value_type);
+        string(out, name);
+    }
+
+    // A listpack holding `entries`, already encoded, each with its backlen.
+    fn listpack_blob(entries: &[Vec<u8>]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for entry in entries {
+            body.extend_from_slice(entry);
+            let length = entry.len();
+            if length <= 127 {
+                body.push(length as u8);
+            } else {
+                body.extend_from_slice(&[(length >> 7) as u8, (length & 127) as u8 | 128]);
+            }
+        }
+        body.push(0xff);
+        let mut blob = ((body.len() + 6) as u32).to_le_bytes().to_vec();
+        blob.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        blob.extend(body);
+        blob
+    }
+
+    fn lp_string(bytes: &[u8]) -> Vec<u8> {
+        let mut entry = if bytes.len() < 64 {
+            vec![0x80 | bytes.len() as u8]
+        } else {
+            vec![0xe0 | (bytes.len() >> 8) as u8, bytes.len() as u8]
+        };
+        entry.extend_from_slice(bytes);
+        entry
+    }
+
+    fn blob(out: &mut Vec<u8>, blob: &[u8]) {
+        if blob.len() < 64 {
+            string(out, blob);
+        } else {
+            out.extend_from_slice(&[0x40 | (blob.len() >> 8) as u8, blob.len() as u8]);
+            out.extend_from_slice(blob);
+        }
+    }
+
+    fn finish(mut data: Vec<u8>) -> Vec<u8> {
+        data.push(OPCODE_EOF);
+        let checksum = crc64(0, &data);
+        data.extend_from_slice(&checksum.to_le_bytes());
+        data
+    }
+
+    // What a value holds, in order for lists and sorted for the rest.
+    fn contents(value: &Value) -> Vec<String> {
+        let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        let mut items: Vec<String> = match value {
+            Value::String(string) => return vec![text(string.get_value())],
+            Value::List(list) => return list.iter().map(|element| text(element)).collect(),
+            Value::Set(set) => set.iter().map(|member| text(&member)).collect(),
+            Value::ZSet(zset) => zset.iter().map(|(member, score)| format!("{}={}", text(member), score)).collect(),
+            Value::Hash(hash) => hash.iter().map(|(field, value)| format!("{}={}", text(field), text(value))).collect(),
+        };
+        items.sort();
+        items
+    }
+
+    fn sample_file() -> Vec<u8> {
+        let mut data = b"REDIS0011".to_vec();
+        data.push(OPCODE_AUX);
+        string(&mut data, b"redis-ver");
+        string(&mut data, b"7.2.4");
+        data.push(OPCODE_AUX);
+        string(&mut data, b"redis-bits");
+        data.extend_from_slice(&[0xc0, 64]);
+        data.extend_from_slice(&[OPCODE_SELECTDB, 0, OPCODE_RESIZEDB, 20, 2]);
+
+        key(&mut data, TYPE_STRING, b"plain");
+        string(&mut data, b"hello");
+        key(&mut data, TYPE_STRING, b"int16");
+        data.extend_from_slice(&[0xc1, 0x39, 0x30]);
+        key(&mut data, TYPE_STRING, b"int32");
+        data.push(0xc2);
+        data.extend_from_slice(&(-100_000i32).to_le_bytes());
+        key(&mut data, TYPE_STRING, b"lzf");
+        data.extend_from_slice(&[0xc3, 9, 23, 0x00, b'a', 0xe0, 0x0a, 0x00, 0x02, b'b', b'c', b'd']);
+
+        data.push(OPCODE_EXPIRETIME_MS);
+        data.extend_from_slice(&1_700_000_000_123u64.to_le_bytes());
+        data.extend_from_slice(&[OPCODE_FREQ, 5, OPCODE_IDLE, 10]);
+        key(&mut data, TYPE_STRING, b"expiring");
+        string(&mut data, b"v");
+        data.push(OPCODE_EXPIRETIME);
+        data.extend_from_slice(&4_102_444_800u32.to_le_bytes());
+        key(&mut data, TYPE_STRING, b"seconds");
+        string(&mut data, b"v");
+
+        // Quicklist with a listpack node of every entry encoding and a plain node
+        key(&mut data, TYPE_LIST_QUICKLIST_2, b"list");
+        data.extend_from_slice(&[2, QUICKLIST_NODE_PACKED as u8]);
+        let long = vec![b'x'; 200];
+        blob(
+            &mut data,
+            &listpack_blob(&[
+                lp_string(b"a"),
+                vec![7],
+                vec![0xdf, 0xfb],
+                vec![0xc3, 0xe8],
+                vec![0xf2, 0xa0, 0x86, 0x01],
+                vec![0xf1, 0x18, 0xfc],
+                lp_string(&long),
+            ]),
+        );
+        data.push(QUICKLIST_NODE_PLAIN as u8);
+        string(&mut data, b"plain-node");
+
+        // Ziplist: a string, an immediate, an int16 and an int8
+        key(&mut data, TYPE_LIST_ZIPLIST, b"ziplist");
+        let entries = [0x00, 0x03, b'a', b'b', b'c', 0x05, 0xf3, 0x02, 0xc0, 0xd4, 0xfe, 0x03, 0xfe, 0xf9, 0xff];
+        let mut ziplist = ((entries.len() + 10) as u32).to_le_bytes().to_vec();
+        ziplist.extend_from_slice(&[0; 4]);
+        ziplist.extend_from_slice(&4u16.to_le_bytes());
+        ziplist.extend_from_slice(&entries);
+        blob(&mut data, &ziplist);
+
+        key(&mut data, TYPE_LIST, b"linked");
+        data.push(2);
+        string(&mut data, b"x");
+        string(&mut data, b"y");
+
+        key(&mut data, TYPE_SET_INTSET, b"intset");
+        let mut intset = 2u32.to_le_bytes().to_vec();
+        intset.extend_from_slice(&3u32.to_le_bytes());
+        for member in [-1i16, 2, 300] {
+            intset.extend_from_slice(&member.to_le_bytes());
+        }
+        blob(&mut data, &intset);
+        key(&mut data, TYPE_SET_LISTPACK, b"set");
+        blob(&mut data, &listpack_blob(&[lp_string(b"x"), lp_string(b"y")]));
+
+        key(&mut data, TYPE_HASH_LISTPACK, b"hash");
+        blob(&mut data, &listpack_blob(&[lp_string(b"f1"), lp_string(b"v1"), lp_string(b"f2"), vec![12]]));
+        key(&mut data, TYPE_HASH, b"dict-hash");
+        data.push(1);
+        string(&mut data, b"f");
+        string(&mut data, b"v");
+        key(&mut data, TYPE_HASH_ZIPMAP, b"zipmap");
+        blob(&mut data, &[1, 3, b'f', b'o', b'o', 3, 0, b'b', b'a', b'r', 0xff]);
+
+        key(&mut data, TYPE_ZSET_LISTPACK, b"zset");
+        blob(&mut data, &listpack_blob(&[lp_string(b"m1"), lp_string(b"1.5"), lp_string(b"m2"), vec![3]]));
+        key(&mut data, TYPE_ZSET_2, b"zset2");
+        data.push(2);
+        string(&mut data, b"a");
+        data.extend_from_slice(&f64::NEG_INFINITY.to_le_bytes());
+        string(&mut data, b"b");
+        data.extend_from_slice(&2.25f64.to_le_bytes());
+        key(&mut data, TYPE_ZSET, b"zset1");
+        data.push(2);
+        string(&mut data, b"c");
+        data.push(254);
+        string(&mut data, b"d");
+        string(&mut data, b"3.5");
+        finish(data)
+    }
+
+    #[test]
+    fn test_every_encoding() {
+        let entries = deserialize(&sample_file()).unwrap();
+        let loaded: Vec<(String, Vec<String>, Option<u64>)> = entries
+            .iter()
+            .map(|(key, value, expires_at)| (String::from_utf8_lossy(key).into_owned(), contents(value), *expires_at))
+            .collect();
+        let expect = |key: &str, items: &[&str], expires_at: Option<u64>| {
+            (key.to_owned(), items.iter().map(|item| item.to_string()).collect::<Vec<_>>(), expires_at)
+        };
+        let long = "x".repeat(200);
+        let lzf = format!("{}bcd", "a".repeat(20));
+        assert_eq!(
+            loaded,
+            vec![
+                expect("plain", &["hello"], None),
+                expect("int16", &["12345"], None),
+                expect("int32", &["-100000"], None),
+                expect("lzf", &[&lzf], None),
+                expect("expiring", &["v"], Some(1_700_000_000_123)),
+                expect("seconds", &["v"], Some(4_102_444_800_000)),
+                expect("list", &["a", "7", "-5", "1000", "100000", "-1000", &long, "plain-node"], None),
+                expect("ziplist", &["abc", "2", "-300", "-7"], None),
+                expect("linked", &["x", "y"], None),
+                expect("intset", &["-1", "2", "300"], None),
+                expect("set", &["x", "y"], None),
+                expect("hash", &["f1=v1", "f2=12"], None),
+                expect("dict-hash", &["f=v"], None),
+                expect("zipmap", &["foo=bar"], None),
+                expect("zset", &["m1=1.5", "m2=3"], None),
+                expect("zset2", &["a=-inf", "b=2.25"], None),
+                expect("zset1", &["c=inf", "d=3.5"], None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let data = sample_file();
+        assert!(matches!(deserialize(&data[..data.len() - 20]), Err(SnapshotError::Truncated)));
+        let mut bad_checksum = data.clone();
+        *bad_checksum.last_mut().unwrap() ^= 1;
+        assert!(matches!(deserialize(&bad_checksum), Err(SnapshotError::BadChecksum)));
+        // A zero checksum means it was not computed
+        let mut unchecked = data.clone();
+        let length = unchecked.len();
+        unchecked[length - 8..].fill(0);
+        assert!(deserialize(&unchecked).is_ok());
+
+        let error = |data: Vec<u8>| deserialize(&finish(data)).err().unwrap().to_string();
+        assert_eq!(error(b"REDIS0013".to_vec()), "RDB version 13 not supported");
+        let mut stream = b"REDIS0011".to_vec();
+        key(&mut stream, TYPE_STREAM_LISTPACKS_3, b"events");
+        assert_eq!(error(stream), "stream value at key 'events' not supported");
+        let mut other_db = b"REDIS0011".to_vec();
+        other_db.extend_from_slice(&[OPCODE_SELECTDB, 1]);
+        key(&mut other_db, TYPE_STRING, b"k");
+        string(&mut other_db, b"v");
+        assert_eq!(error(other_db), "keys in database 1 not supported");
+        let mut module = b"REDIS0011".to_vec();
+        module.push(OPCODE_MODULE_AUX);
+        assert_eq!(error(module), "module data not supported");
+        assert!(matches!(deserialize(b"KILODB\x01"), Err(SnapshotError::BadMagic)));
+    }
+
+    #[test]
+    fn test_read_int() {
+        let mut reader = Reader::new(&[0xff, 0xff, 0x7f, 0x00, 0x00, 0x80]);
+        assert_eq!(read_int(&mut reader, 3), Some(0x7fffff));
+        assert_eq!(read_int(&mut reader, 3), Some(-0x800000));
+        assert_eq!(read_int(&mut reader, 1), None);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::store::hash_store::HashStore;
+use crate::store::set_store::SetStore;
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store::string_store::StringStore;
+use crate::store::value::Value;
+use crate::store::vector_store::VectorStore;
+use crate::utils::crc64::crc64;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+
+// Layout of a snapshot file, modelled on Redis' RDB:
+//
+//   "KILODB" version
+//   per key:  [EXPIRY deadline_ms] type key value
+//   EOF crc64
+//
+// Lengths are LEB128 varints, strings are a length followed by the bytes, and
+// fixed-width numbers are little-endian. The checksum covers every byte before
+// it, so a truncated or damaged file is refused instead of half-loaded.
+const MAGIC: &[u8] = b"KILODB";
+const VERSION: u8 = 1;
+
+const OPCODE_EXPIRY: u8 = 0xfc;
+const OPCODE_EOF: u8 = 0xff;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+
+// A key as stored in a snapshot: name, value and absolute deadline in ms.
+pub type SnapshotEntry = (Vec<u8>, Value, Option<u64>);
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    BadChecksum,
+    UnknownType(u8),
+    // Well-formed bytes that do not make a valid value
+    Corrupt(&'static str),
+    // Valid data KiloDB has no equivalent for, like Redis streams
+    Unsupported(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "{}", e),
+            SnapshotError::BadMagic => write!(f, "not a KiloDB snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(f, "unsupported snapshot version {}", version),
+            SnapshotError::Truncated => write!(f, "unexpected end of file"),
+            SnapshotError::BadChecksum => write!(f, "checksum mismatch"),
+            SnapshotError::UnknownType(value_type) => write!(f, "unknown value type {}", value_type),
+            SnapshotError::Corrupt(reason) => write!(f, "corrupt value: {}", reason),
+            SnapshotError::Unsupported(what) => write!(f, "{} not supported", what),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(e: io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+// The whole file for `entries`.
+pub fn serialize<'a>(entries: impl Iterator<Item = (&'a [u8], &'a Value, Option<u64>)>) -> Vec<u8> {
+    let mut out = MAGIC.to_vec();
+    out.push(VERSION);
+    for (key, value, expires_at) in entries {
+        if let Some(deadline) = expires_at {
+            out.push(OPCODE_EXPIRY);
+            out.extend_from_slice(&deadline.to_le_bytes());
+        }
+        out.push(value_type(value));
+        write_bytes(&mut out, key);
+        write_value(&mut out, value);
+    }
+    out.push(OPCODE_EOF);
+    let checksum = crc64(0, &out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out
+}
+
+pub fn deserialize(data: &[u8]) -> Result<Vec<SnapshotEntry>, SnapshotError> {
+    if !data.starts_with(MAGIC) {
+        return Err(if MAGIC.starts_with(data) { SnapshotError::Truncated } else { SnapshotError::BadMagic });
+    }
+    let mut reader = Reader::new(&data[MAGIC.len()..]);
+    match reader.byte()? {
+        VERSION => {}
+        version => return Err(SnapshotError::UnsupportedVersion(version)),
+    }
+    let mut entries = Vec::new();
+    let mut expires_at = None;
+    loop {
+        match reader.byte()? {
+            OPCODE_EOF => break,
+            OPCODE_EXPIRY => expires_at = Some(reader.u64()?),
+            value_type => {
+                let key = reader.bytes()?.to_vec();
+                let value = read_value(&mut reader, value_type)?;
+                entries.push((key, value, expires_at.take()));
+            }
+        }
+    }
+    let body = data.len() - reader.remaining().len();
+    let checksum = reader.u64()?;
+    if checksum != crc64(0, &data[..body]) {
+        return Err(SnapshotError::BadChecksum);
+    }
+    Ok(entries)
+}
+
+// DUMP payload of a single value: its type and encoding as in a snapshot,
+// then the format version in 2 bytes and a crc64 of everything before it, the
+// same framing as Redis' payloads.
+pub fn dump_value(value: &Value) -> Vec<u8> {
+    let mut out = vec![value_type(value)];
+    write_value(&mut out, value);
+    out.extend_from_slice(&(VERSION as u16).to_le_bytes());
+    let checksum = crc64(0, &out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    out
+}
+
+// The value in a DUMP payload. A payload from another format version or with
+// a wrong checksum is `UnsupportedVersion` or `BadChecksum`, before anything
+// in it is decoded.
+pub fn restore_value(payload: &[u8]) -> Result<Value, SnapshotError> {
+    let Some(body_length) = payload.len().checked_sub(10) else {
+        return Err(SnapshotError::Truncated);
+    };
+    let (body, footer) = payload.split_at(body_length);
+    let version = u16::from_le_bytes([footer[0], footer[1]]);
+    if version != VERSION as u16 {
+        return Err(SnapshotError::UnsupportedVersion(version.min(u8::MAX as u16) as u8));
+    }
+    let checksum = u64::from_le_bytes(footer[2..].try_into().expect("8 bytes left"));
+    if checksum != crc64(0, &payload[..body_length + 2]) {
+        return Err(SnapshotError::BadChecksum);
+    }
+    let mut reader = Reader::new(body);
+    let value_type = reader.byte()?;
+    let value = read_value(&mut reader, value_type)?;
+    if !reader.remaining().is_empty() {
+        return Err(SnapshotError::Corrupt("trailing bytes"));
+    }
+    if value.is_empty_collection() {
+        return Err(SnapshotError::Corrupt("empty collection"));
+    }
+    Ok(value)
+}
+
+// Writes `data` to `path` without ever leaving a partial file there: it goes to
+// a temporary file first, which is synced and then renamed over the target.
+pub fn write_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    let temp_path = path.with_file_name(format!("temp-{}.kdb", std::process::id()));
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    match result {
+        Ok(()) => fs::rename(&temp_path, path),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+pub fn value_type(value: &Value) -> u8 {
+    match value {
+        Value::String(_) => TYPE_STRING,
+        Value::List(_) => TYPE_LIST,
+        Value::Set(_) => TYPE_SET,
+        Value::ZSet(_) => TYPE_ZSET,
+        Value::Hash(_) => TYPE_HASH,
+    }
+}
+
+// The value alone, without its type byte.
+pub fn write_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::String(string) => write_bytes(out, string.get_value()),
+        Value::List(list) => {
+            write_length(out, list.len());
+            for element in list.iter() {
+                write_bytes(out, element);
+            }
+        }
+        Value::Set(set) => {
+            write_length(out, set.len());
+            for member in set.iter() {
+                write_bytes(out, &member);
+            }
+        }
+        Value::ZSet(zset) => {
+            write_length(out, zset.len());
+            for (member, score) in zset.iter() {
+                write_bytes(out, member);
+                out.extend_from_slice(&score.to_le_bytes());
+            }
+        }
+        Value::Hash(hash) => {
+            write_length(out, hash.len());
+            for (field, value) in hash.iter() {
+                write_bytes(out, field);
+                write_bytes(out, value);
+            }
+        }
+    }
+}
+
+// Reads a value of `value_type`, rebuilding it through the stores' own API so
+// it gets whatever encoding the current config calls for.
+pub fn read_value(reader: &mut Reader, value_type: u8) -> Result<Value, SnapshotError> {
+    let value = match value_type {
+        TYPE_STRING => Value::String(StringStore::new(reader.bytes()?.to_vec())),
+        TYPE_LIST => {
+            let mut list = VectorStore::new();
+            for _ in 0..reader.length()? {
+                list.push_right(reader.bytes()?);
+            }
+            Value::List(list)
+        }
+        TYPE_SET => {
+            let mut set = SetStore::new();
+            for _ in 0..reader.length()? {
+                set.add_member(reader.bytes()?);
+            }
+            Value::Set(set)
+        }
+        TYPE_ZSET => {
+            let mut zset = SortedSetStore::new();
+            for _ in 0..reader.length()? {
+                let member = reader.bytes()?;
+                let score = f64::from_bits(reader.u64()?);
+                if score.is_nan() {
+                    return Err(SnapshotError::Corrupt("NaN score"));
+                }
+                zset.add_member(member, score);
+            }
+            Value::ZSet(zset)
+        }
+        TYPE_HASH => {
+            let mut hash = HashStore::new();
+            for _ in 0..reader.length()? {
+                let field = reader.bytes()?;
+                hash.set_field(field, reader.bytes()?);
+            }
+            Value::Hash(hash)
+        }
+        other => return Err(SnapshotError::UnknownType(other)),
+    };
+    Ok(value)
+}
+
+fn write_length(out: &mut Vec<u8>, mut length: usize) {
+    loop {
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_length(out, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+// Cursor over a snapshot's bytes; running out of them is `Truncated`.
+pub struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data, position: 0 }
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.position..]
+    }
+
+    pub fn byte(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn take(&mut self, count: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.position.checked_add(count).filter(|end| *end <= self.data.len());
+        let end = end.ok_or(SnapshotError::Truncated)?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    pub fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().expect("took 8 bytes")))
+    }
+
+    pub fn length(&mut self) -> Result<usize, SnapshotError> {
+        let mut length: usize = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            length |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(length);
+            }
+        }
+        Err(SnapshotError::Corrupt("length too long"))
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], SnapshotError> {
+        let length = self.length()?;
+        self.take(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_entries() -> Vec<SnapshotEntry> {
+        let mut list = VectorStore::new();
+        list.push_right(b"a");
+        list.push_right(&[b'x'; 300]);
+        let mut set = SetStore::new();
+        set.add_member(b"1");
+        set.add_member(b"member");
+        let mut zset = SortedSetStore::new();
+        zset.add_member(b"low", -1.5);
+        zset.add_member(b"high", f64::INFINITY);
+        let mut hash = HashStore::new();
+        hash.set_field(b"field", b"value");
+        vec![
+            (b"string".to_vec(), Value::String(StringStore::new(b"hello".to_vec())), Some(1_700_000_000_000)),
+            (b"list".to_vec(), Value::List(list), None),
+            (b"set".to_vec(), Value::Set(set), None),
+            (b"zset".to_vec(), Value::ZSet(zset), Some(5)),
+            (b"hash".to_vec(), Value::Hash(hash), None),
+            (Vec::new(), Value::String(StringStore::new(Vec::new())), None),
+        ]
+    }
+
+    fn serialize_entries(entries: &[SnapshotEntry]) -> Vec<u8> {
+        serialize(entries.iter().map(|(key, value, expires_at)| (key.as_slice(), value, *expires_at)))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let entries = sample_entries();
+        let data = serialize_entries(&entries);
+        let loaded = deserialize(&data).unwrap();
+        assert_eq!(loaded.len(), entries.len());
+        for ((key, value, expires_at), (loaded_key, loaded_value, loaded_expiry)) in entries.iter().zip(&loaded) {
+            assert_eq!(key, loaded_key);
+            assert_eq!(expires_at, loaded_expiry);
+            // Same bytes back out means the same contents
+            let mut expected = Vec::new();
+            write_value(&mut expected, value);
+            let mut actual = Vec::new();
+            write_value(&mut actual, loaded_value);
+            assert_eq!(expected, actual, "{}", String::from_utf8_lossy(key));
+        }
+    }
+
+    #[test]
+    fn test_damaged_files_are_refused() {
+        let data = serialize_entries(&sample_entries());
+        for cut in [0, 3, MAGIC.len() + 1, data.len() / 2, data.len() - 1] {
+            assert!(
+                matches!(deserialize(&data[..cut]), Err(SnapshotError::Truncated)),
+                "cut at {}",
+                cut
+            );
+        }
+        let mut flipped = data.clone();
+        flipped[data.len() / 2] ^= 0x01;
+        assert!(deserialize(&flipped).is_err());
+        let mut bad_checksum = data.clone();
+        *bad_checksum.last_mut().unwrap() ^= 0x01;
+        assert!(matches!(deserialize(&bad_checksum), Err(SnapshotError::BadChecksum)));
+        assert!(matches!(deserialize(b"REDIS0011"), Err(SnapshotError::BadMagic)));
+    }
+
+    #[test]
+    fn test_dump_payloads() {
+        for (key, value, _) in sample_entries() {
+            let payload = dump_value(&value);
+            let mut expected = Vec::new();
+            write_value(&mut expected, &value);
+            let mut actual = Vec::new();
+            write_value(&mut actual, &restore_value(&payload).unwrap());
+            assert_eq!(expected, actual, "{}", String::from_utf8_lossy(&key));
+            let mut flipped = payload.clone();
+            flipped[0] ^= 0x80;
+            assert!(matches!(restore_value(&flipped), Err(SnapshotError::BadChecksum)));
+        }
+        assert!(matches!(restore_value(b""), Err(SnapshotError::Truncated)));
+    }
+}
@@ -0,0 +1,282 @@
+// Incremental RESP request decoder.
+//
+// Bytes are fed in as they arrive from the socket and complete commands are
+// pulled out one at a time. A command split across several reads stays in the
+// buffer until the rest of it arrives, and a read holding several pipelined
+// commands yields all of them, in order. Both the multibulk form sent by client
+// libraries (`*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n`) and the inline form typed into
+// telnet (`GET key\r\n`) are understood.
+
+// Same limits as Redis: 512MB per bulk string, 64KB per inline command.
+const MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+const MAX_INLINE_LEN: usize = 64 * 1024;
+const MAX_MULTIBULK_LEN: usize = 1024 * 1024;
+
+#[derive(Debug)]
+pub struct RespParser {
+    buffer: Vec<u8>,
+    // Bytes of `buffer` already consumed by decoded frames.
+    pos: usize,
+    // State of a multibulk request that is only partially buffered.
+    args: Vec<Vec<u8>>,
+    remaining: usize,
+    bulk_len: Option<usize>,
+}
+
+impl RespParser {
+    pub fn new() -> Self {
+        RespParser {
+            buffer: Vec::new(),
+            pos: 0,
+            args: Vec::new(),
+            remaining: 0,
+            bulk_len: None,
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        // Drop consumed bytes once per read rather than once per command, so a
+        // long pipeline is not shifted down over and over.
+        if self.pos > 0 {
+            self.buffer.drain(..self.pos);
+            self.pos = 0;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    // Returns the next complete command, `Ok(None)` if more bytes are needed, or
+    // a protocol error. After an error the stream cannot be resynchronised and the
+    // connection should be closed, as Redis does.
+    pub fn next_command(&mut self) -> Result<Option<Vec<Vec<u8>>>, String> {
+        loop {
+            if self.remaining == 0 {
+                if self.pos == self.buffer.len() {
+                    return Ok(None);
+                }
+                if self.buffer[self.pos] != b'*' {
+                    match self.parse_inline()? {
+                        Some(args) if args.is_empty() => continue,
+                        other => return Ok(other),
+                    }
+                }
+
+                let line = match self.read_line() {
+                    Some(line) => line,
+                    None => return self.incomplete_line(),
+                };
+                let count = parse_length(&line[1..])
+                    .filter(|n| *n <= MAX_MULTIBULK_LEN as i64)
+                    .ok_or("Protocol error: invalid multibulk length")?;
+                if count <= 0 {
+                    // `*0` and `*-1` are valid frames that carry no command.
+                    continue;
+                }
+                self.remaining = count as usize;
+                self.args = Vec::with_capacity(self.remaining.min(1024));
+            }
+
+            while self.remaining > 0 {
+                let len = match self.bulk_len {
+                    Some(len) => len,
+                    None => {
+                        let line = match self.read_line() {
+                            Some(line) => line,
+                            None => return self.incomplete_line(),
+                        };
+                        if line.first() != Some(&b'$') {
+                            return Err(format!(
+                                "Protocol error: expected '$', got '{}'",
+                                line.first().map(|b| *b as char).unwrap_or(' ')
+                            ));
+                        }
+                        let len = parse_length(&line[1..])
+                            .filter(|n| *n >= 0 && *n <= MAX_BULK_LEN as i64)
+                            .ok_or("Protocol error: invalid bulk length")?
+                            as usize;
+                        self.bulk_len = Some(len);
+                        len
+                    }
+                };
+
+                let available = self.buffer.len() - self.pos;
+                if available < len + 2 {
+                    // Make room for the whole payload up front instead of growing
+                    // the buffer a read at a time.
+                    self.buffer.reserve(len + 2 - available);
+                    return Ok(None);
+                }
+                let end = self.pos + len;
+                if &self.buffer[end..end + 2] != b"\r\n" {
+                    return Err("Protocol error: bulk string not terminated by CRLF".to_string());
+                }
+                self.args.push(self.buffer[self.pos..end].to_vec());
+                self.pos = end + 2;
+                self.bulk_len = None;
+                self.remaining -= 1;
+            }
+
+            return Ok(Some(std::mem::take(&mut self.args)));
+        }
+    }
+
+    // Reads a CRLF terminated line starting at `pos`, without the terminator.
+    fn read_line(&mut self) -> Option<Vec<u8>> {
+        let offset = self.buffer[self.pos..]
+            .windows(2)
+            .position(|window| window == b"\r\n")?;
+        let line = self.buffer[self.pos..self.pos + offset].to_vec();
+        self.pos += offset + 2;
+        Some(line)
+    }
+
+    fn incomplete_line(&self) -> Result<Option<Vec<Vec<u8>>>, String> {
+        if self.buffer.len() - self.pos > MAX_INLINE_LEN {
+            return Err("Protocol error: too big request header".to_string());
+        }
+        Ok(None)
+    }
+
+    fn parse_inline(&mut self) -> Result<Option<Vec<Vec<u8>>>, String> {
+        let offset = match self.buffer[self.pos..].iter().position(|b| *b == b'\n') {
+            Some(offset) => offset,
+            None => {
+                if self.buffer.len() - self.pos > MAX_INLINE_LEN {
+                    return Err("Protocol error: too big inline request".to_string());
+                }
+                return Ok(None);
+            }
+        };
+        let line = &self.buffer[self.pos..self.pos + offset];
+        let args = line
+            .split(|b| b.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_vec())
+            .collect();
+        self.pos += offset + 1;
+        Ok(Some(args))
+    }
+}
+
+impl Default for RespParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn parse_length(digits: &[u8]) -> Option<i64> {
+    std::str::from_utf8(digits).ok()?.parse::<i64>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(items: &[&str]) -> Vec<Vec<u8>> {
+        items.iter().map(|item| item.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_single_command() {
+        let mut parser = RespParser::new();
+        parser.feed(b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n");
+
+        assert_eq!(parser.next_command().unwrap(), Some(args(&["GET", "key"])));
+        assert_eq!(parser.next_command().unwrap(), None);
+    }
+
+    #[test]
+    fn test_command_split_across_reads() {
+        let mut parser = RespParser::new();
+        let frame = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
+
+        // Feed one byte at a time; nothing comes out until the last byte lands.
+        for byte in &frame[..frame.len() - 1] {
+            parser.feed(&[*byte]);
+            assert_eq!(parser.next_command().unwrap(), None);
+        }
+        parser.feed(&frame[frame.len() - 1..]);
+        assert_eq!(
+            parser.next_command().unwrap(),
+            Some(args(&["SET", "key", "value"]))
+        );
+    }
+
+    #[test]
+    fn test_pipelined_commands() {
+        let mut parser = RespParser::new();
+        parser.feed(b"*1\r\n$4\r\nPING\r\n*2\r\n$4\r\nINCR\r\n$1\r\nc\r\n*2\r\n$3\r\nGET\r\n$1\r\nc\r\n");
+
+        assert_eq!(parser.next_command().unwrap(), Some(args(&["PING"])));
+        assert_eq!(parser.next_command().unwrap(), Some(args(&["INCR", "c"])));
+        assert_eq!(parser.next_command().unwrap(), Some(args(&["GET", "c"])));
+        assert_eq!(parser.next_command().unwrap(), None);
+    }
+
+    #[test]
+    fn test_pipeline_ending_in_partial_command() {
+        let mut parser = RespParser::new();
+        parser.feed(b"*1\r\n$4\r\nPING\r\n*2\r\n$3\r\nGET");
+
+        assert_eq!(parser.next_command().unwrap(), Some(args(&["PING"])));
+        assert_eq!(parser.next_command().unwrap(), None);
+
+        parser.feed(b"\r\n$1\r\nk\r\n");
+        assert_eq!(parser.next_command().unwrap(), Some(args(&["GET", "k"])));
+    }
+
+    #[test]
+    fn test_large_payload() {
+        let value = "x".repeat(100_000);
+        let frame = format!("*3\r\n$3\r\nSET\r\n$3\r\nbig\r\n${}\r\n{}\r\n", value.len(), value);
+
+        let mut parser = RespParser::new();
+        for chunk in frame.as_bytes().chunks(4096) {
+            parser.feed(chunk);
+        }
+        assert_eq!(
+            parser.next_command().unwrap(),
+            Some(args(&["SET", "big", &value]))
+        );
+    }
+
+    #[test]
+    fn test_inline_command() {
+        let mut parser = RespParser::new();
+        parser.feed(b"SET  key value\r\nPING\n");
+
+        assert_eq!(
+            parser.next_command().unwrap(),
+            Some(args(&["SET", "key", "value"]))
+        );
+        assert_eq!(parser.next_command().unwrap(), Some(args(&["PING"])));
+    }
+
+    #[test]
+    fn test_empty_frames_are_skipped() {
+        let mut parser = RespParser::new();
+        parser.feed(b"\r\n*0\r\n*1\r\n$4\r\nPING\r\n");
+
+        assert_eq!(parser.next_command().unwrap(), Some(args(&["PING"])));
+    }
+
+    #[test]
+    fn test_protocol_errors() {
+        let mut parser = RespParser::new();
+        parser.feed(b"*1\r\n:5\r\n");
+        assert_eq!(
+            parser.next_command().unwrap_err(),
+            "Protocol error: expected '$', got ':'"
+        );
+
+        let mut parser = RespParser::new();
+        parser.feed(b"*x\r\n");
+        assert_eq!(
+            parser.next_command().unwrap_err(),
+            "Protocol error: invalid multibulk length"
+        );
+
+        let mut parser = RespParser::new();
+        parser.feed(b"*1\r\n$3\r\nGETX\r\n");
+        assert!(parser.next_command().is_err());
+    }
+}
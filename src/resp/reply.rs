@@ -0,0 +1,80 @@
+// Helpers for building RESP replies out of raw, possibly non-UTF-8, bytes.
+
+pub fn bulk_string(value: &[u8]) -> Vec<u8> {
+    let mut reply = Vec::with_capacity(value.len() + 16);
+    reply.extend_from_slice(format!("${}\r\n", value.len()).as_bytes());
+    reply.extend_from_slice(value);
+    reply.extend_from_slice(b"\r\n");
+    reply
+}
+
+// Null bulk string, the reply for a missing value.
+pub const NIL: &[u8] = b"$-1\r\n";
+
+// Null array, for commands whose reply is an array when there is something to return.
+pub const NIL_ARRAY: &[u8] = b"*-1\r\n";
+
+// Wraps already encoded replies into a RESP array.
+pub fn array(items: Vec<Vec<u8>>) -> Vec<u8> {
+    let mut reply = format!("*{}\r\n", items.len()).into_bytes();
+    for item in items {
+        reply.extend(item);
+    }
+    reply
+}
+
+pub fn integer(value: i64) -> Vec<u8> {
+    format!(":{}\r\n", value).into_bytes()
+}
+
+// Formats a double the way Redis prints scores: the shortest representation
+// that reads back as the same number, switching to an exponent for very large
+// or very small magnitudes like %.17g, and "inf"/"-inf" for the infinities.
+pub fn format_double(value: f64) -> String {
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_owned();
+    }
+    let magnitude = value.abs();
+    if value == 0.0 || (1e-4..1e17).contains(&magnitude) {
+        return value.to_string();
+    }
+    // Rust writes 1e300 and 1e-7; C writes 1e+300 and 1e-07
+    let formatted = format!("{:e}", value);
+    let (mantissa, exponent) = formatted.split_once('e').expect("{:e} always has an exponent");
+    let (sign, digits) = match exponent.strip_prefix('-') {
+        Some(digits) => ('-', digits),
+        None => ('+', exponent),
+    };
+    format!("{}e{}{:0>2}", mantissa, sign, digits)
+}
+
+pub fn double(value: f64) -> Vec<u8> {
+    bulk_string(format_double(value).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bulk_string_is_binary_safe() {
+        assert_eq!(bulk_string(b"a\r\nb\x00\xff"), b"$6\r\na\r\nb\x00\xff\r\n");
+        assert_eq!(bulk_string(b""), b"$0\r\n\r\n");
+    }
+
+    #[test]
+    fn test_array() {
+        assert_eq!(array(vec![bulk_string(b"a"), NIL.to_vec(), integer(-3)]), b"*3\r\n$1\r\na\r\n$-1\r\n:-3\r\n");
+        assert_eq!(array(vec![]), b"*0\r\n");
+    }
+
+    #[test]
+    fn test_format_double() {
+        assert_eq!(format_double(1.5), "1.5");
+        assert_eq!(format_double(-3.0), "-3");
+        assert_eq!(format_double(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(format_double(1e300), "1e+300");
+        assert_eq!(format_double(-2.5e-7), "-2.5e-07");
+        assert_eq!(format_double(f64::NEG_INFINITY), "-inf");
+    }
+}
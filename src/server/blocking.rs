@@ -0,0 +1,98 @@
+use crate::command::command_executor::command_executor;
+use crate::store::vector_store::VectorStore;
+use crate::store_containers::core_context::context;
+
+// Hands the lists that received elements to the clients blocked on them. The
+// connection calls this right after every command, while it still holds the
+// context, so a pushed element reaches a blocked client before any other
+// client can observe it.
+//
+// Serving a client re-runs its command, which is then sure to find data. That
+// can push to another key (BLMOVE), which is picked up in the next round.
+pub fn serve_blocked_clients(context: &mut context) {
+    loop {
+        let ready_keys = context.BlockingStore.take_ready_keys();
+        if ready_keys.is_empty() {
+            return;
+        }
+        for key in ready_keys {
+            serve_key(context, &key);
+        }
+    }
+}
+
+// Serves the waiters on `key` in the order they blocked, until the list is gone.
+fn serve_key(context: &mut context, key: &[u8]) {
+    while let Some(id) = context.BlockingStore.first_waiter(key) {
+        if !matches!(context.get::<VectorStore>(key), Ok(Some(_))) {
+            return;
+        }
+        let client = match context.BlockingStore.unblock(id) {
+            Some(client) => client,
+            None => return,
+        };
+        // Its connection is gone: do not pop anything on its behalf
+        if client.reply.is_closed() {
+            continue;
+        }
+        let reply = command_executor::call(&client.command, &client.args, context)
+            .unwrap_or_else(|e| format!("-{}\r\n", e).into_bytes());
+        let _ = client.reply.send(reply);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::command_enum::Command;
+
+    fn run(ctx: &mut context, args: &[&str]) -> Vec<u8> {
+        let args: Vec<Vec<u8>> = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+        let reply = command_executor::call(&Command::new(&args), &args, ctx).unwrap();
+        serve_blocked_clients(ctx);
+        reply
+    }
+
+    fn block(ctx: &mut context, args: &[&str]) -> tokio::sync::oneshot::Receiver<Vec<u8>> {
+        let args: Vec<Vec<u8>> = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+        let command = Command::new(&args);
+        let (keys, _) = command.blocking_keys().unwrap();
+        ctx.BlockingStore.block(keys, command, args).1
+    }
+
+    #[test]
+    fn test_push_is_handed_to_the_first_waiter() {
+        let mut ctx = context::new();
+        let mut first = block(&mut ctx, &["BLPOP", "a", "b", "0"]);
+        let mut second = block(&mut ctx, &["BRPOP", "b", "0"]);
+
+        assert_eq!(run(&mut ctx, &["RPUSH", "b", "x", "y"]), b":2\r\n");
+        assert_eq!(first.try_recv().unwrap(), b"*2\r\n$1\r\nb\r\n$1\r\nx\r\n");
+        assert_eq!(second.try_recv().unwrap(), b"*2\r\n$1\r\nb\r\n$1\r\ny\r\n");
+
+        // Nothing was left for anyone else to see
+        assert_eq!(run(&mut ctx, &["EXISTS", "b"]), b":0\r\n");
+        assert!(ctx.BlockingStore.is_empty());
+    }
+
+    #[test]
+    fn test_blmove_chains_to_waiters_on_its_destination() {
+        let mut ctx = context::new();
+        let mut mover = block(&mut ctx, &["BLMOVE", "a", "b", "LEFT", "LEFT", "0"]);
+        let mut popper = block(&mut ctx, &["BLPOP", "b", "0"]);
+
+        run(&mut ctx, &["LPUSH", "a", "job"]);
+        assert_eq!(mover.try_recv().unwrap(), b"$3\r\njob\r\n");
+        assert_eq!(popper.try_recv().unwrap(), b"*2\r\n$1\r\nb\r\n$3\r\njob\r\n");
+    }
+
+    #[test]
+    fn test_disconnected_waiters_are_skipped() {
+        let mut ctx = context::new();
+        drop(block(&mut ctx, &["BLPOP", "a", "0"]));
+        let mut live = block(&mut ctx, &["BLPOP", "a", "0"]);
+
+        run(&mut ctx, &["RPUSH", "a", "x"]);
+        assert_eq!(live.try_recv().unwrap(), b"*2\r\n$1\r\na\r\n$1\r\nx\r\n");
+    }
+}
@@ -0,0 +1,241 @@
+use crate::command::command_enum::Command;
+use crate::command::command_executor;
+use crate::resp::parser::RespParser;
+use crate::resp::reply::{NIL, NIL_ARRAY};
+use crate::server::blocking;
+use crate::server::pubsub::Subscriber;
+use crate::server::transaction::Transaction;
+use crate::store_containers::core_context::context;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+const READ_CHUNK_SIZE: usize = 16 * 1024;
+
+// What running one command produced.
+enum Outcome {
+    Reply(Vec<u8>),
+    Blocked(Blocked),
+    // The last reply, after which the connection is closed
+    Close(Vec<u8>),
+}
+
+// A client parked by a blocking command that found nothing to pop.
+struct Blocked {
+    id: u64,
+    receiver: oneshot::Receiver<Vec<u8>>,
+    timeout_ms: u64,
+    // The nil the command replied with, sent if nothing arrives in time
+    timeout_reply: Vec<u8>,
+}
+
+// What a connection keeps between commands.
+struct Client {
+    transaction: Transaction,
+    subscriber: Subscriber,
+}
+
+impl Client {
+    // RESET: back to the state of a new connection, out of MULTI and
+    // subscribe mode, with no watched keys. Messages published to it before
+    // come first, as they would have been sent already.
+    fn reset(&mut self, context: &mut context) -> Vec<u8> {
+        self.transaction.reset(context);
+        self.subscriber.unsubscribe_all(context);
+        let mut reply = Vec::new();
+        while let Some(message) = self.subscriber.try_next_message() {
+            reply.extend(message);
+        }
+        reply.extend_from_slice(b"+RESET\r\n");
+        reply
+    }
+}
+
+// Runs one decoded command against the shared context, or queues it if the
+// client is in a transaction.
+fn execute(args: &[Vec<u8>], context: &Rc<RefCell<context>>, client: &mut Client) -> Outcome {
+    // The borrow is released before the reply is written, so other clients
+    // only ever see the keyspace between whole commands.
+    let mut context = context.borrow_mut();
+    let command_object = Command::new(args);
+    match command_object {
+        Command::QUIT => return Outcome::Close(b"+OK\r\n".to_vec()),
+        Command::RESET => return Outcome::Reply(client.reset(&mut context)),
+        _ => {}
+    }
+    // In MULTI everything else goes to the queue, subscriptions included
+    let handled = match client.transaction.in_multi() {
+        true => client.transaction.handle(command_object, args, &mut context),
+        false => client
+            .subscriber
+            .handle(command_object, args, &mut context)
+            .or_else(|command_object| client.transaction.handle(command_object, args, &mut context)),
+    };
+    let command_object = match handled {
+        Ok(reply) => {
+            // EXEC may have pushed to lists someone waits on
+            blocking::serve_blocked_clients(&mut context);
+            return Outcome::Reply(reply);
+        }
+        Err(command_object) => command_object,
+    };
+    let reply = match &command_object {
+        Command::Unknown { .. } => format!("-{}\r\n", command_object.rejection().unwrap_or_default()).into_bytes(),
+        _ => command_executor::command_executor::call(&command_object, args, &mut context)
+            .unwrap_or(b"-ERR empty command\r\n".to_vec()),
+    };
+
+    // Blocking executors only try once; their nil reply means "wait"
+    if let Some((keys, timeout_ms)) = command_object.blocking_keys() {
+        if reply == NIL || reply == NIL_ARRAY {
+            let (id, receiver) = context.BlockingStore.block(keys, command_object, args.to_vec());
+            return Outcome::Blocked(Blocked {
+                id,
+                receiver,
+                timeout_ms,
+                timeout_reply: reply,
+            });
+        }
+    }
+    blocking::serve_blocked_clients(&mut context);
+    Outcome::Reply(reply)
+}
+
+// Waits until a blocked client is served or times out, and returns its reply.
+// The socket is still read meanwhile: commands pipelined behind the blocking
+// one are buffered for later, and None means the client hung up.
+async fn wait_until_served(
+    stream: &mut TcpStream,
+    parser: &mut RespParser,
+    buffer: &mut [u8],
+    context: &Rc<RefCell<context>>,
+    blocked: Blocked,
+) -> std::io::Result<Option<Vec<u8>>> {
+    let Blocked {
+        id,
+        mut receiver,
+        timeout_ms,
+        timeout_reply,
+    } = blocked;
+    let deadline = match timeout_ms {
+        0 => None,
+        _ => Instant::now().checked_add(Duration::from_millis(timeout_ms)),
+    };
+    loop {
+        tokio::select! {
+            reply = &mut receiver => return Ok(Some(reply.unwrap_or(timeout_reply))),
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                // Served in the same tick the timer fired: the reply wins
+                if context.borrow_mut().BlockingStore.unblock(id).is_none() {
+                    if let Ok(reply) = receiver.try_recv() {
+                        return Ok(Some(reply));
+                    }
+                }
+                return Ok(Some(timeout_reply));
+            }
+            bytes_read = stream.read(buffer) => match bytes_read {
+                Ok(0) | Err(_) => {
+                    context.borrow_mut().BlockingStore.unblock(id);
+                    bytes_read?;
+                    return Ok(None);
+                }
+                Ok(bytes_read) => parser.feed(&buffer[..bytes_read]),
+            },
+        }
+    }
+}
+
+// Client handler
+pub async fn handle_client(stream: TcpStream, context: Rc<RefCell<context>>) -> std::io::Result<()> {
+    let mut client = {
+        let mut context = context.borrow_mut();
+        Client {
+            transaction: Transaction::new(&mut context),
+            subscriber: Subscriber::new(&mut context),
+        }
+    };
+    let result = serve_client(stream, &context, &mut client).await;
+    // However the connection ended, its watched keys and subscriptions go
+    // away with it
+    let mut context = context.borrow_mut();
+    client.transaction.reset(&mut context);
+    client.subscriber.reset(&mut context);
+    result
+}
+
+async fn serve_client(mut stream: TcpStream, context: &Rc<RefCell<context>>, client: &mut Client) -> std::io::Result<()> {
+    let peer = stream.peer_addr()?;
+    println!("Connected to: {}", peer);
+    let mut parser = RespParser::new();
+    let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+
+    loop {
+        // Messages published to the client are written out while it is idle
+        let bytes_read = tokio::select! {
+            bytes_read = stream.read(&mut buffer) => bytes_read?,
+            Some(message) = client.subscriber.next_message() => {
+                stream.write_all(&message).await?;
+                continue;
+            }
+        };
+        if bytes_read == 0 {
+            println!("Client {} disconnected.", peer);
+            break;
+        }
+        parser.feed(&buffer[..bytes_read]);
+
+        // Answer every complete command in this read, in order, with one write.
+        let mut responses = Vec::new();
+        loop {
+            match parser.next_command() {
+                Ok(Some(args)) => match execute(&args, context, client) {
+                    Outcome::Reply(reply) => {
+                        responses.extend(reply);
+                        // Including what the command itself published to it
+                        while let Some(message) = client.subscriber.try_next_message() {
+                            responses.extend(message);
+                        }
+                    }
+                    Outcome::Blocked(blocked) => {
+                        // Flush the replies that came before the blocking command
+                        if !responses.is_empty() {
+                            stream.write_all(&responses).await?;
+                            responses.clear();
+                        }
+                        let served =
+                            wait_until_served(&mut stream, &mut parser, &mut buffer, context, blocked).await?;
+                        match served {
+                            Some(reply) => responses.extend(reply),
+                            None => {
+                                println!("Client {} disconnected while blocked.", peer);
+                                return Ok(());
+                            }
+                        }
+                    }
+                    Outcome::Close(reply) => {
+                        responses.extend(reply);
+                        stream.write_all(&responses).await?;
+                        println!("Client {} quit.", peer);
+                        return Ok(());
+                    }
+                },
+                Ok(None) => break,
+                Err(e) => {
+                    responses.extend(format!("-ERR {}\r\n", e).into_bytes());
+                    stream.write_all(&responses).await?;
+                    println!("Closing connection to {}: {}", peer, e);
+                    return Ok(());
+                }
+            }
+        }
+        if !responses.is_empty() {
+            stream.write_all(&responses).await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use crate::persistence;
+use crate::store_containers::core_context::context;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+// Ten ticks a second, like Redis' default `hz 10`.
+const CRON_INTERVAL: Duration = Duration::from_millis(100);
+
+// Periodic housekeeping that runs between client commands, the counterpart of
+// Redis' serverCron: reclaims expired keys nobody reads, runs the snapshot
+// schedule and keeps the append-only file flushed.
+pub async fn server_cron(context: Rc<RefCell<context>>) {
+    let mut interval = tokio::time::interval(CRON_INTERVAL);
+    loop {
+        interval.tick().await;
+        let mut context = context.borrow_mut();
+        context.active_expire_cycle();
+        persistence::cron(&mut context);
+        persistence::aof::cron(&mut context);
+    }
+}
@@ -0,0 +1,35 @@
+pub mod blocking;
+pub mod connection;
+pub mod cron;
+pub mod pubsub;
+pub mod transaction;
+
+use crate::store_containers::core_context::context;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::net::TcpListener;
+
+// Accept loop. Every client gets its own task, and all of them share the single
+// context. This has to run inside a `LocalSet`: the context is an `Rc<RefCell<..>>`,
+// so client tasks are interleaved on one thread and a command always runs to
+// completion (no `.await` while the context is borrowed) before another client
+// can observe the keyspace.
+pub async fn run(listener: TcpListener, shared_context: Rc<RefCell<context>>) -> std::io::Result<()> {
+    tokio::task::spawn_local(cron::server_cron(Rc::clone(&shared_context)));
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let client_context = Rc::clone(&shared_context);
+                tokio::task::spawn_local(async move {
+                    if let Err(e) = connection::handle_client(stream, client_context).await {
+                        eprintln!("Client error: {}", e);
+                    }
+                });
+            }
+            Err(e) => {
+                eprintln!("Connection failed: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use crate::command::command_enum::Command;
+use crate::resp::reply::{array, bulk_string, integer, NIL};
+use crate::store_containers::core_context::context;
+use crate::store_containers::PubSubStore::Subscription;
+use tokio::sync::mpsc;
+
+// A connection's subscriptions, and where the messages published to them
+// arrive. While it has any, the connection is in subscribe mode and only takes
+// the commands that manage them, plus PING.
+pub struct Subscriber {
+    id: u64,
+    messages: mpsc::UnboundedReceiver<Vec<u8>>,
+    // In the order they were made, which UNSUBSCRIBE with no arguments follows
+    channels: Vec<Vec<u8>>,
+    patterns: Vec<Vec<u8>>,
+    shard_channels: Vec<Vec<u8>>,
+}
+
+impl Subscriber {
+    pub fn new(context: &mut context) -> Self {
+        let (id, messages) = context.PubSubStore.new_client();
+        Subscriber {
+            id,
+            messages,
+            channels: Vec::new(),
+            patterns: Vec::new(),
+            shard_channels: Vec::new(),
+        }
+    }
+
+    pub fn in_subscribe_mode(&self) -> bool {
+        !self.channels.is_empty() || !self.patterns.is_empty() || !self.shard_channels.is_empty()
+    }
+
+    // Handles the subscribe family, and refuses what subscribe mode does not
+    // allow. Any other command is handed back, to be run as usual.
+    pub fn handle(&mut self, command: Command, args: &[Vec<u8>], context: &mut context) -> Result<Vec<u8>, Command> {
+        let (kind, names, subscribing) = match command {
+            Command::SUBSCRIBE { channels } => (Subscription::Channel, channels, true),
+            Command::UNSUBSCRIBE { channels } => (Subscription::Channel, channels, false),
+            Command::PSUBSCRIBE { patterns } => (Subscription::Pattern, patterns, true),
+            Command::PUNSUBSCRIBE { patterns } => (Subscription::Pattern, patterns, false),
+            Command::SSUBSCRIBE { channels } => (Subscription::ShardChannel, channels, true),
+            Command::SUNSUBSCRIBE { channels } => (Subscription::ShardChannel, channels, false),
+            // Errors and unknown commands get their usual reply
+            Command::Invalid { .. } | Command::Unknown { .. } => return Err(command),
+            _ if !self.in_subscribe_mode() => return Err(command),
+            Command::PING => return Ok(array(vec![bulk_string(b"pong"), bulk_string(b"")])),
+            _ => {
+                return Ok(format!(
+                    "-ERR Can't execute '{}': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context\r\n",
+                    String::from_utf8_lossy(&args[0]).to_lowercase()
+                )
+                .into_bytes())
+            }
+        };
+        Ok(match subscribing {
+            true => self.subscribe(kind, names, context),
+            false => self.unsubscribe(kind, names, context),
+        })
+    }
+
+    // One confirmation per name, each with the number of subscriptions the
+    // client has now.
+    fn subscribe(&mut self, kind: Subscription, names: Vec<Vec<u8>>, context: &mut context) -> Vec<u8> {
+        let mut replies = Vec::new();
+        for name in names {
+            if !self.list(kind).contains(&name) {
+                context.PubSubStore.subscribe(kind, self.id, &name);
+                self.list_mut(kind).push(name.clone());
+            }
+            replies.extend(self.confirmation(kind, true, Some(&name)));
+        }
+        replies
+    }
+
+    // No names means all of this kind. Unsubscribing from nothing still gets
+    // one confirmation, with a nil name.
+    fn unsubscribe(&mut self, kind: Subscription, names: Vec<Vec<u8>>, context: &mut context) -> Vec<u8> {
+        let names = match names.is_empty() {
+            true => self.list(kind).clone(),
+            false => names,
+        };
+        if names.is_empty() {
+            return self.confirmation(kind, false, None);
+        }
+        let mut replies = Vec::new();
+        for name in names {
+            if let Some(position) = self.list(kind).iter().position(|subscribed| *subscribed == name) {
+                context.PubSubStore.unsubscribe(kind, self.id, &name);
+                self.list_mut(kind).remove(position);
+            }
+            replies.extend(self.confirmation(kind, false, Some(&name)));
+        }
+        replies
+    }
+
+    fn confirmation(&self, kind: Subscription, subscribing: bool, name: Option<&[u8]>) -> Vec<u8> {
+        let verb: &[u8] = match (kind, subscribing) {
+            (Subscription::Channel, true) => b"subscribe",
+            (Subscription::Channel, false) => b"unsubscribe",
+            (Subscription::Pattern, true) => b"psubscribe",
+            (Subscription::Pattern, false) => b"punsubscribe",
+            (Subscription::ShardChannel, true) => b"ssubscribe",
+            (Subscription::ShardChannel, false) => b"sunsubscribe",
+        };
+        // Shard channels are counted on their own
+        let count = match kind {
+            Subscription::ShardChannel => self.shard_channels.len(),
+            _ => self.channels.len() + self.patterns.len(),
+        };
+        array(vec![
+            bulk_string(verb),
+            name.map_or(NIL.to_vec(), bulk_string),
+            integer(count as i64),
+        ])
+    }
+
+    fn list(&self, kind: Subscription) -> &Vec<Vec<u8>> {
+        match kind {
+            Subscription::Channel => &self.channels,
+            Subscription::Pattern => &self.patterns,
+            Subscription::ShardChannel => &self.shard_channels,
+        }
+    }
+
+    fn list_mut(&mut self, kind: Subscription) -> &mut Vec<Vec<u8>> {
+        match kind {
+            Subscription::Channel => &mut self.channels,
+            Subscription::Pattern => &mut self.patterns,
+            Subscription::ShardChannel => &mut self.shard_channels,
+        }
+    }
+
+    // A message already published to this client, if any.
+    pub fn try_next_message(&mut self) -> Option<Vec<u8>> {
+        self.messages.try_recv().ok()
+    }
+
+    // Waits for the next message. None only once the connection is removed
+    // from the store.
+    pub async fn next_message(&mut self) -> Option<Vec<u8>> {
+        self.messages.recv().await
+    }
+
+    // Drops every subscription, leaving subscribe mode, for RESET.
+    pub fn unsubscribe_all(&mut self, context: &mut context) {
+        for kind in [Subscription::Channel, Subscription::Pattern, Subscription::ShardChannel] {
+            for name in std::mem::take(self.list_mut(kind)) {
+                context.PubSubStore.unsubscribe(kind, self.id, &name);
+            }
+        }
+    }
+
+    // Drops every subscription of a closing connection.
+    pub fn reset(&mut self, context: &mut context) {
+        self.unsubscribe_all(context);
+        context.PubSubStore.remove_client(self.id);
+    }
+}
@@ -0,0 +1,124 @@
+use crate::command::command_enum::Command;
+use crate::command::command_executor::command_executor;
+use crate::persistence::aof;
+use crate::resp::reply::{array, NIL_ARRAY};
+use crate::store_containers::core_context::context;
+
+// A connection's MULTI/EXEC state: the commands queued since MULTI and the id
+// its WATCHed keys are registered under in the WatchStore.
+pub struct Transaction {
+    id: u64,
+    // Some between MULTI and EXEC or DISCARD
+    queued: Option<Vec<(Command, Vec<Vec<u8>>)>>,
+    // A command was rejected while queueing, so EXEC must refuse to run
+    aborted: bool,
+}
+
+impl Transaction {
+    pub fn new(context: &mut context) -> Self {
+        Transaction {
+            id: context.WatchStore.new_client(),
+            queued: None,
+            aborted: false,
+        }
+    }
+
+    pub fn in_multi(&self) -> bool {
+        self.queued.is_some()
+    }
+
+    // Handles the transaction commands, and queues every other command while
+    // in MULTI. Any other command is handed back, to be run right away.
+    pub fn handle(&mut self, command: Command, args: &[Vec<u8>], context: &mut context) -> Result<Vec<u8>, Command> {
+        match command {
+            Command::MULTI if self.in_multi() => Ok(b"-ERR MULTI calls can not be nested\r\n".to_vec()),
+            Command::MULTI => {
+                self.queued = Some(Vec::new());
+                Ok(b"+OK\r\n".to_vec())
+            }
+            Command::EXEC => Ok(self.exec(context)),
+            Command::DISCARD if !self.in_multi() => Ok(b"-ERR DISCARD without MULTI\r\n".to_vec()),
+            Command::DISCARD => {
+                self.discard(context);
+                Ok(b"+OK\r\n".to_vec())
+            }
+            Command::WATCH { .. } if self.in_multi() => Ok(b"-ERR WATCH inside MULTI is not allowed\r\n".to_vec()),
+            Command::WATCH { keys } => {
+                for key in keys {
+                    // A key that already expired is deleted now, not later
+                    // under the watch
+                    context.peek_key(&key);
+                    context.WatchStore.watch(self.id, &key);
+                }
+                Ok(b"+OK\r\n".to_vec())
+            }
+            Command::UNWATCH if !self.in_multi() => {
+                context.WatchStore.unwatch(self.id);
+                Ok(b"+OK\r\n".to_vec())
+            }
+            command => match &mut self.queued {
+                None => Err(command),
+                Some(queued) => match command {
+                    // Rejected commands are not queued and doom the transaction
+                    command @ (Command::Invalid { .. } | Command::Unknown { .. }) => {
+                        self.aborted = true;
+                        Ok(format!("-{}\r\n", command.rejection().unwrap_or_default()).into_bytes())
+                    }
+                    // EXEC has no connection to subscribe
+                    command if command.is_subscription() => {
+                        self.aborted = true;
+                        Ok(b"-ERR Command not allowed inside a transaction\r\n".to_vec())
+                    }
+                    command => {
+                        queued.push((command, args.to_vec()));
+                        Ok(b"+QUEUED\r\n".to_vec())
+                    }
+                },
+            },
+        }
+    }
+
+    // Runs the queue with no other client in between: the connection holds
+    // the context for the whole EXEC.
+    fn exec(&mut self, context: &mut context) -> Vec<u8> {
+        let Some(queued) = self.queued.take() else {
+            return b"-ERR EXEC without MULTI\r\n".to_vec();
+        };
+        if std::mem::take(&mut self.aborted) {
+            context.WatchStore.unwatch(self.id);
+            return b"-EXECABORT Transaction discarded because of previous errors.\r\n".to_vec();
+        }
+        // Watched keys that expired since count as changed
+        for key in context.WatchStore.watched_keys(self.id) {
+            context.peek_key(&key);
+        }
+        let changed = context.WatchStore.is_dirty(self.id);
+        context.WatchStore.unwatch(self.id);
+        if changed {
+            return NIL_ARRAY.to_vec();
+        }
+        aof::begin_transaction(context);
+        let replies = queued
+            .iter()
+            .map(|(command, args)| match command {
+                // Its keys are unwatched by EXEC anyway
+                Command::UNWATCH => b"+OK\r\n".to_vec(),
+                _ => command_executor::call(command, args, context)
+                    .unwrap_or_else(|e| format!("-{}\r\n", e).into_bytes()),
+            })
+            .collect();
+        aof::end_transaction(context);
+        array(replies)
+    }
+
+    fn discard(&mut self, context: &mut context) {
+        self.queued = None;
+        self.aborted = false;
+        context.WatchStore.unwatch(self.id);
+    }
+
+    // Drops the transaction and the watched keys of a closing connection.
+    pub fn reset(&mut self, context: &mut context) {
+        self.discard(context);
+    }
+}
@@ -1,52 +1,199 @@
+use crate::config;
+use crate::store::value::{sampled_size, Value};
+use crate::store_containers::{Dict::Dict, Listpack::Listpack};
 use crate::traits::Store::Store;
-use std::collections::HashMap;
+use rand::Rng;
 
-#[derive(Debug)]
+// Small hashes are a listpack of alternating fields and values. Once one grows
+// past hash-max-listpack-entries fields, or gets a field or value longer than
+// hash-max-listpack-value bytes, it becomes a hash table for good.
+#[derive(Debug, Clone)]
+enum Encoding {
+    Listpack(Listpack),
+    Hashtable(Dict<Vec<u8>, Vec<u8>>),
+}
+
+#[derive(Debug, Clone)]
 pub struct HashStore {
-    fields: HashMap<String, String>,
+    fields: Encoding,
 }
 
 impl HashStore {
     pub fn new() -> Self {
         HashStore {
-            fields: HashMap::new(),
+            fields: Encoding::Listpack(Listpack::new()),
+        }
+    }
+
+    // Name reported by OBJECT ENCODING
+    pub fn encoding(&self) -> &'static str {
+        match self.fields {
+            Encoding::Listpack(_) => "listpack",
+            Encoding::Hashtable(_) => "hashtable",
+        }
+    }
+
+    pub fn set_field(&mut self, field: &[u8], value: &[u8]) -> bool {
+        let (max_entries, max_value) = config::with(|config| {
+            (config.hash_max_listpack_entries, config.hash_max_listpack_value)
+        });
+        if field.len() > max_value || value.len() > max_value {
+            self.convert_to_hashtable();
+        }
+        let added = match &mut self.fields {
+            Encoding::Listpack(listpack) => match listpack.find(field, 2) {
+                Some(index) => {
+                    listpack.replace(index + 1, value);
+                    false
+                }
+                None => {
+                    listpack.push(field);
+                    listpack.push(value);
+                    true
+                }
+            },
+            Encoding::Hashtable(dict) => dict.insert(field.to_vec(), value.to_vec()).is_none(),
+        };
+        if self.len() > max_entries {
+            self.convert_to_hashtable();
+        }
+        added
+    }
+
+    fn convert_to_hashtable(&mut self) {
+        if let Encoding::Listpack(listpack) = &self.fields {
+            let mut dict = Dict::new();
+            let mut entries = listpack.iter();
+            while let (Some(field), Some(value)) = (entries.next(), entries.next()) {
+                dict.insert(field.to_vec(), value.to_vec());
+            }
+            self.fields = Encoding::Hashtable(dict);
         }
     }
 
-    pub fn set_field(&mut self, field: &str, value: &str) -> bool {
-        self.fields.insert(field.to_string(), value.to_string()).is_none()
+    pub fn get_field(&self, field: &[u8]) -> Option<&[u8]> {
+        match &self.fields {
+            Encoding::Listpack(listpack) => listpack.get(listpack.find(field, 2)? + 1),
+            Encoding::Hashtable(dict) => dict.get(field).map(Vec::as_slice),
+        }
+    }
+
+    // Every field and its value, copied into a hash table.
+    pub fn get_all_fields(&self) -> Dict<Vec<u8>, Vec<u8>> {
+        self.iter()
+            .map(|(field, value)| (field.to_vec(), value.to_vec()))
+            .collect()
+    }
+
+    pub fn delete_field(&mut self, field: &[u8]) -> bool {
+        match &mut self.fields {
+            Encoding::Listpack(listpack) => match listpack.find(field, 2) {
+                Some(index) => {
+                    listpack.remove(index, 2);
+                    true
+                }
+                None => false,
+            },
+            Encoding::Hashtable(dict) => dict.remove(field).is_some(),
+        }
     }
 
-    pub fn get_field(&self, field: &str) -> Option<&String> {
-        self.fields.get(field)
+    pub fn exists_field(&self, field: &[u8]) -> bool {
+        self.get_field(field).is_some()
     }
 
-    pub fn get_all_fields(&self) -> &HashMap<String, String> {
-        &self.fields
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (&[u8], &[u8])> + '_> {
+        match &self.fields {
+            Encoding::Listpack(listpack) => {
+                let mut entries = listpack.iter();
+                Box::new(std::iter::from_fn(move || Some((entries.next()?, entries.next()?))))
+            }
+            Encoding::Hashtable(dict) => {
+                Box::new(dict.iter().map(|(field, value)| (field.as_slice(), value.as_slice())))
+            }
+        }
+    }
+
+    pub fn random_field(&self) -> Option<(&[u8], &[u8])> {
+        match &self.fields {
+            Encoding::Listpack(listpack) if listpack.is_empty() => None,
+            Encoding::Listpack(listpack) => {
+                let index = rand::thread_rng().gen_range(0..self.len()) * 2;
+                Some((listpack.get(index)?, listpack.get(index + 1)?))
+            }
+            Encoding::Hashtable(dict) => dict
+                .random_entry()
+                .map(|(field, value)| (field.as_slice(), value.as_slice())),
+        }
     }
 
-    pub fn delete_field(&mut self, field: &str) -> bool {
-        self.fields.remove(field).is_some()
+    pub fn get_keys(&self) -> Vec<Vec<u8>> {
+        self.iter().map(|(field, _)| field.to_vec()).collect()
     }
 
-    pub fn exists_field(&self, field: &str) -> bool {
-        self.fields.contains_key(field)
+    pub fn get_values(&self) -> Vec<Vec<u8>> {
+        self.iter().map(|(_, value)| value.to_vec()).collect()
     }
 
-    pub fn get_keys(&self) -> Vec<String> {
-        self.fields.keys().cloned().collect()
+    // One step of HSCAN, see Dict::scan_batch. A listpack is small enough to
+    // return in one go, as Redis does.
+    pub fn scan(&self, cursor: u64, count: usize, mut visit: impl FnMut(&[u8], &[u8])) -> u64 {
+        match &self.fields {
+            Encoding::Listpack(_) => {
+                self.iter().for_each(|(field, value)| visit(field, value));
+                0
+            }
+            Encoding::Hashtable(dict) => dict.scan_batch(cursor, count, |field, value| visit(field, value)),
+        }
     }
 
-    pub fn get_values(&self) -> Vec<String> {
-        self.fields.values().cloned().collect()
+    pub fn memory_usage(&self, samples: usize) -> usize {
+        match &self.fields {
+            Encoding::Listpack(listpack) => listpack.byte_len(),
+            Encoding::Hashtable(dict) => {
+                let fields = dict.iter().map(|(field, value)| field.capacity() + value.capacity());
+                dict.table_size() + sampled_size(fields, dict.len(), samples, |size| size)
+            }
+        }
     }
 
     pub fn len(&self) -> usize {
-        self.fields.len()
+        match &self.fields {
+            Encoding::Listpack(listpack) => listpack.len() / 2,
+            Encoding::Hashtable(dict) => dict.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for HashStore {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl Store for HashStore {}
+impl Store for HashStore {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::Hash(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::Hash(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Hash(self)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -63,13 +210,13 @@ mod tests {
         let mut store = HashStore::new();
         
         // Test setting a new field
-        assert!(store.set_field("name", "John"));
-        assert_eq!(store.get_field("name"), Some(&"John".to_string()));
+        assert!(store.set_field(b"name", b"John"));
+        assert_eq!(store.get_field(b"name"), Some(&b"John"[..]));
         assert_eq!(store.len(), 1);
         
         // Test updating existing field
-        assert!(!store.set_field("name", "Jane"));
-        assert_eq!(store.get_field("name"), Some(&"Jane".to_string()));
+        assert!(!store.set_field(b"name", b"Jane"));
+        assert_eq!(store.get_field(b"name"), Some(&b"Jane"[..]));
         assert_eq!(store.len(), 1);
     }
 
@@ -77,75 +224,99 @@ mod tests {
     fn test_multiple_fields() {
         let mut store = HashStore::new();
         
-        store.set_field("name", "John");
-        store.set_field("age", "30");
-        store.set_field("city", "New York");
+        store.set_field(b"name", b"John");
+        store.set_field(b"age", b"30");
+        store.set_field(b"city", b"New York");
         
         assert_eq!(store.len(), 3);
-        assert_eq!(store.get_field("name"), Some(&"John".to_string()));
-        assert_eq!(store.get_field("age"), Some(&"30".to_string()));
-        assert_eq!(store.get_field("city"), Some(&"New York".to_string()));
+        assert_eq!(store.get_field(b"name"), Some(&b"John"[..]));
+        assert_eq!(store.get_field(b"age"), Some(&b"30"[..]));
+        assert_eq!(store.get_field(b"city"), Some(&b"New York"[..]));
     }
 
     #[test]
     fn test_field_exists() {
         let mut store = HashStore::new();
         
-        assert!(!store.exists_field("name"));
-        store.set_field("name", "John");
-        assert!(store.exists_field("name"));
-        assert!(!store.exists_field("age"));
+        assert!(!store.exists_field(b"name"));
+        store.set_field(b"name", b"John");
+        assert!(store.exists_field(b"name"));
+        assert!(!store.exists_field(b"age"));
     }
 
     #[test]
     fn test_delete_field() {
         let mut store = HashStore::new();
         
-        store.set_field("name", "John");
-        store.set_field("age", "30");
+        store.set_field(b"name", b"John");
+        store.set_field(b"age", b"30");
         
-        assert!(store.delete_field("name"));
-        assert!(!store.exists_field("name"));
+        assert!(store.delete_field(b"name"));
+        assert!(!store.exists_field(b"name"));
         assert_eq!(store.len(), 1);
         
         // Test deleting non-existent field
-        assert!(!store.delete_field("nonexistent"));
+        assert!(!store.delete_field(b"nonexistent"));
     }
 
     #[test]
     fn test_get_keys() {
         let mut store = HashStore::new();
         
-        store.set_field("name", "John");
-        store.set_field("age", "30");
+        store.set_field(b"name", b"John");
+        store.set_field(b"age", b"30");
         
         let mut keys = store.get_keys();
         keys.sort();
-        assert_eq!(keys, vec!["age", "name"]);
+        assert_eq!(keys, vec![b"age".to_vec(), b"name".to_vec()]);
     }
 
     #[test]
     fn test_get_values() {
         let mut store = HashStore::new();
         
-        store.set_field("name", "John");
-        store.set_field("age", "30");
+        store.set_field(b"name", b"John");
+        store.set_field(b"age", b"30");
         
         let mut values = store.get_values();
         values.sort();
-        assert_eq!(values, vec!["30", "John"]);
+        assert_eq!(values, vec![b"30".to_vec(), b"John".to_vec()]);
     }
 
     #[test]
     fn test_get_all_fields() {
         let mut store = HashStore::new();
         
-        store.set_field("name", "John");
-        store.set_field("age", "30");
+        store.set_field(b"name", b"John");
+        store.set_field(b"age", b"30");
         
         let all_fields = store.get_all_fields();
         assert_eq!(all_fields.len(), 2);
-        assert_eq!(all_fields.get("name"), Some(&"John".to_string()));
-        assert_eq!(all_fields.get("age"), Some(&"30".to_string()));
+        assert_eq!(all_fields.get(b"name".as_slice()), Some(&b"John".to_vec()));
+        assert_eq!(all_fields.get(b"age".as_slice()), Some(&b"30".to_vec()));
+    }
+
+    #[test]
+    fn test_encoding_conversions() {
+        let mut store = HashStore::new();
+        store.set_field(b"name", b"John");
+        store.set_field(b"age", b"30");
+        assert_eq!(store.encoding(), "listpack");
+        assert!(store.delete_field(b"name"));
+        assert_eq!(store.iter().collect::<Vec<_>>(), vec![(&b"age"[..], &b"30"[..])]);
+
+        // A value longer than hash-max-listpack-value
+        store.set_field(b"bio", &[b'x'; 65]);
+        assert_eq!(store.encoding(), "hashtable");
+        assert_eq!(store.get_field(b"age"), Some(&b"30"[..]));
+        assert_eq!(store.len(), 2);
+
+        let mut store = HashStore::new();
+        for i in 0..=128 {
+            store.set_field(i.to_string().as_bytes(), b"v");
+        }
+        assert_eq!(store.encoding(), "hashtable");
+        assert_eq!(store.len(), 129);
     }
-} 
\ No newline at end of file
+}
+
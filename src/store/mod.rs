@@ -3,3 +3,4 @@ pub mod vector_store;
 pub mod hash_store;
 pub mod set_store;
 pub mod sorted_set_store;
+pub mod value;
@@ -1,40 +1,216 @@
+use crate::config;
+use crate::store::value::{sampled_size, Value};
+use crate::store_containers::{Dict::Dict, IntSet::IntSet, Listpack::Listpack};
 use crate::traits::Store::Store;
-use std::collections::HashSet;
+use crate::utils::integer::parse_canonical;
+use rand::Rng;
+use std::borrow::Cow;
 
-#[derive(Debug)]
+// Sets of integers start as an intset and small sets as a listpack, as in
+// Redis. A set holding a member that is not an integer, or growing past
+// set-max-intset-entries, leaves the intset; past set-max-listpack-entries
+// members, or given one longer than set-max-listpack-value bytes, it becomes a
+// hash table. Sets never convert back.
+#[derive(Debug, Clone)]
+enum Encoding {
+    Intset(IntSet),
+    Listpack(Listpack),
+    Hashtable(Dict<Vec<u8>, ()>),
+}
+
+#[derive(Debug, Clone)]
 pub struct SetStore {
-    members: HashSet<String>,
+    members: Encoding,
 }
 
 impl SetStore {
     pub fn new() -> Self {
         SetStore {
-            members: HashSet::new(),
+            members: Encoding::Intset(IntSet::new()),
         }
     }
 
-    pub fn add_member(&mut self, member: &str) -> bool {
-        self.members.insert(member.to_string())
+    // Name reported by OBJECT ENCODING
+    pub fn encoding(&self) -> &'static str {
+        match self.members {
+            Encoding::Intset(_) => "intset",
+            Encoding::Listpack(_) => "listpack",
+            Encoding::Hashtable(_) => "hashtable",
+        }
     }
 
-    pub fn remove_member(&mut self, member: &str) -> bool {
-        self.members.remove(member)
+    pub fn add_member(&mut self, member: &[u8]) -> bool {
+        let (max_intset_entries, max_entries, max_value) = config::with(|config| {
+            (
+                config.set_max_intset_entries,
+                config.set_max_listpack_entries,
+                config.set_max_listpack_value,
+            )
+        });
+        let integer = parse_canonical(member);
+
+        // First move to an encoding that can hold the new member
+        match &self.members {
+            Encoding::Intset(intset) if integer.is_none() => {
+                if intset.len() < max_entries && member.len() <= max_value {
+                    self.convert_to_listpack();
+                } else {
+                    self.convert_to_hashtable();
+                }
+            }
+            Encoding::Listpack(_) if member.len() > max_value => self.convert_to_hashtable(),
+            _ => {}
+        }
+
+        let added = match &mut self.members {
+            Encoding::Intset(intset) => integer.is_some_and(|integer| intset.insert(integer)),
+            Encoding::Listpack(listpack) => {
+                let is_new = listpack.find(member, 1).is_none();
+                if is_new {
+                    listpack.push(member);
+                }
+                is_new
+            }
+            Encoding::Hashtable(dict) => dict.insert(member.to_vec(), ()).is_none(),
+        };
+
+        let max_len = match self.members {
+            Encoding::Intset(_) => max_intset_entries,
+            Encoding::Listpack(_) => max_entries,
+            Encoding::Hashtable(_) => usize::MAX,
+        };
+        if self.len() > max_len {
+            self.convert_to_hashtable();
+        }
+        added
+    }
+
+    fn convert_to_listpack(&mut self) {
+        let mut listpack = Listpack::new();
+        for member in self.iter() {
+            listpack.push(&member);
+        }
+        self.members = Encoding::Listpack(listpack);
+    }
+
+    fn convert_to_hashtable(&mut self) {
+        if !matches!(self.members, Encoding::Hashtable(_)) {
+            let dict = self.iter().map(|member| (member.into_owned(), ())).collect();
+            self.members = Encoding::Hashtable(dict);
+        }
+    }
+
+    pub fn remove_member(&mut self, member: &[u8]) -> bool {
+        match &mut self.members {
+            Encoding::Intset(intset) => parse_canonical(member).is_some_and(|integer| intset.remove(integer)),
+            Encoding::Listpack(listpack) => match listpack.find(member, 1) {
+                Some(index) => {
+                    listpack.remove(index, 1);
+                    true
+                }
+                None => false,
+            },
+            Encoding::Hashtable(dict) => dict.remove(member).is_some(),
+        }
+    }
+
+    pub fn is_member(&self, member: &[u8]) -> bool {
+        match &self.members {
+            Encoding::Intset(intset) => parse_canonical(member).is_some_and(|integer| intset.contains(integer)),
+            Encoding::Listpack(listpack) => listpack.find(member, 1).is_some(),
+            Encoding::Hashtable(dict) => dict.contains_key(member),
+        }
+    }
+
+    pub fn get_members(&self) -> Vec<Vec<u8>> {
+        self.iter().map(Cow::into_owned).collect()
+    }
+
+    // Intset members are formatted on the way out, the others are borrowed.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = Cow<'_, [u8]>> + '_> {
+        match &self.members {
+            Encoding::Intset(intset) => Box::new(intset.iter().map(format_integer)),
+            Encoding::Listpack(listpack) => Box::new(listpack.iter().map(Cow::Borrowed)),
+            Encoding::Hashtable(dict) => Box::new(dict.keys().map(|member| Cow::Borrowed(member.as_slice()))),
+        }
+    }
+
+    pub fn random_member(&self) -> Option<Cow<'_, [u8]>> {
+        if self.is_empty() {
+            return None;
+        }
+        let index = rand::thread_rng().gen_range(0..self.len());
+        match &self.members {
+            Encoding::Intset(intset) => intset.get(index).map(format_integer),
+            Encoding::Listpack(listpack) => listpack.get(index).map(Cow::Borrowed),
+            Encoding::Hashtable(dict) => dict
+                .random_entry()
+                .map(|(member, _)| Cow::Borrowed(member.as_slice())),
+        }
     }
 
-    pub fn is_member(&self, member: &str) -> bool {
-        self.members.contains(member)
+    // One step of SSCAN, see Dict::scan_batch. Compact sets are small enough
+    // to return in one go, as Redis does.
+    pub fn scan(&self, cursor: u64, count: usize, mut visit: impl FnMut(&[u8])) -> u64 {
+        match &self.members {
+            Encoding::Hashtable(dict) => dict.scan_batch(cursor, count, |member, _| visit(member)),
+            _ => {
+                self.iter().for_each(|member| visit(&member));
+                0
+            }
+        }
     }
 
-    pub fn get_members(&self) -> Vec<String> {
-        self.members.iter().cloned().collect()
+    pub fn memory_usage(&self, samples: usize) -> usize {
+        match &self.members {
+            Encoding::Intset(intset) => intset.byte_len(),
+            Encoding::Listpack(listpack) => listpack.byte_len(),
+            Encoding::Hashtable(dict) => dict.table_size() + sampled_size(dict.keys(), dict.len(), samples, Vec::capacity),
+        }
     }
 
     pub fn len(&self) -> usize {
-        self.members.len()
+        match &self.members {
+            Encoding::Intset(intset) => intset.len(),
+            Encoding::Listpack(listpack) => listpack.len(),
+            Encoding::Hashtable(dict) => dict.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 }
 
-impl Store for SetStore {}
+fn format_integer<'a>(integer: i64) -> Cow<'a, [u8]> {
+    Cow::Owned(integer.to_string().into_bytes())
+}
+
+impl Default for SetStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Store for SetStore {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::Set(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::Set(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::Set(self)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -51,16 +227,16 @@ mod tests {
         let mut store = SetStore::new();
         
         // Test adding new member
-        assert!(store.add_member("member1"));
+        assert!(store.add_member(b"member1"));
         assert_eq!(store.len(), 1);
-        assert!(store.is_member("member1"));
+        assert!(store.is_member(b"member1"));
         
         // Test adding duplicate member
-        assert!(!store.add_member("member1"));
+        assert!(!store.add_member(b"member1"));
         assert_eq!(store.len(), 1);
         
         // Test adding another member
-        assert!(store.add_member("member2"));
+        assert!(store.add_member(b"member2"));
         assert_eq!(store.len(), 2);
     }
 
@@ -68,17 +244,17 @@ mod tests {
     fn test_remove_member() {
         let mut store = SetStore::new();
         
-        store.add_member("member1");
-        store.add_member("member2");
+        store.add_member(b"member1");
+        store.add_member(b"member2");
         
         // Test removing existing member
-        assert!(store.remove_member("member1"));
+        assert!(store.remove_member(b"member1"));
         assert_eq!(store.len(), 1);
-        assert!(!store.is_member("member1"));
-        assert!(store.is_member("member2"));
+        assert!(!store.is_member(b"member1"));
+        assert!(store.is_member(b"member2"));
         
         // Test removing non-existent member
-        assert!(!store.remove_member("member3"));
+        assert!(!store.remove_member(b"member3"));
         assert_eq!(store.len(), 1);
     }
 
@@ -86,14 +262,14 @@ mod tests {
     fn test_is_member() {
         let mut store = SetStore::new();
         
-        assert!(!store.is_member("member1"));
+        assert!(!store.is_member(b"member1"));
         
-        store.add_member("member1");
-        assert!(store.is_member("member1"));
-        assert!(!store.is_member("member2"));
+        store.add_member(b"member1");
+        assert!(store.is_member(b"member1"));
+        assert!(!store.is_member(b"member2"));
         
-        store.remove_member("member1");
-        assert!(!store.is_member("member1"));
+        store.remove_member(b"member1");
+        assert!(!store.is_member(b"member1"));
     }
 
     #[test]
@@ -102,37 +278,40 @@ mod tests {
         
         assert_eq!(store.get_members().len(), 0);
         
-        store.add_member("member1");
-        store.add_member("member2");
-        store.add_member("member3");
+        store.add_member(b"member1");
+        store.add_member(b"member2");
+        store.add_member(b"member3");
         
         let mut members = store.get_members();
         members.sort();
-        assert_eq!(members, vec!["member1", "member2", "member3"]);
+        assert_eq!(
+            members,
+            vec![b"member1".to_vec(), b"member2".to_vec(), b"member3".to_vec()]
+        );
     }
 
     #[test]
     fn test_set_uniqueness() {
         let mut store = SetStore::new();
         
-        store.add_member("duplicate");
-        store.add_member("duplicate");
-        store.add_member("duplicate");
+        store.add_member(b"duplicate");
+        store.add_member(b"duplicate");
+        store.add_member(b"duplicate");
         
         assert_eq!(store.len(), 1);
-        assert_eq!(store.get_members(), vec!["duplicate"]);
+        assert_eq!(store.get_members(), vec![b"duplicate".to_vec()]);
     }
 
     #[test]
     fn test_empty_string_member() {
         let mut store = SetStore::new();
         
-        assert!(store.add_member(""));
-        assert!(store.is_member(""));
+        assert!(store.add_member(b""));
+        assert!(store.is_member(b""));
         assert_eq!(store.len(), 1);
         
-        assert!(store.remove_member(""));
-        assert!(!store.is_member(""));
+        assert!(store.remove_member(b""));
+        assert!(!store.is_member(b""));
         assert_eq!(store.len(), 0);
     }
 
@@ -140,12 +319,53 @@ mod tests {
     fn test_unicode_members() {
         let mut store = SetStore::new();
         
-        store.add_member("hello");
-        store.add_member("世界");
-        store.add_member("🦀");
+        store.add_member(b"hello");
+        store.add_member("世界".as_bytes());
+        store.add_member("🦀".as_bytes());
         
         assert_eq!(store.len(), 3);
-        assert!(store.is_member("世界"));
-        assert!(store.is_member("🦀"));
+        assert!(store.is_member("世界".as_bytes()));
+        assert!(store.is_member("🦀".as_bytes()));
+    }
+
+    #[test]
+    fn test_encoding_conversions() {
+        let mut store = SetStore::new();
+        store.add_member(b"3");
+        store.add_member(b"-1");
+        assert_eq!(store.encoding(), "intset");
+        // Not canonical, so not an integer
+        store.add_member(b"007");
+        assert_eq!(store.encoding(), "listpack");
+        assert!(store.is_member(b"3") && store.is_member(b"007"));
+        assert!(!store.is_member(b"7"));
+
+        store.add_member(&[b'x'; 65]);
+        assert_eq!(store.encoding(), "hashtable");
+        assert_eq!(store.len(), 4);
+        assert!(store.remove_member(b"-1"));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_encoding_limits_follow_the_config() {
+        let mut limits = config::with(|config| config.clone());
+        limits.set_max_intset_entries = 2;
+        limits.set_max_listpack_entries = 2;
+        config::replace(limits);
+
+        let mut ints = SetStore::new();
+        ints.add_member(b"1");
+        ints.add_member(b"2");
+        assert_eq!(ints.encoding(), "intset");
+        ints.add_member(b"3");
+        assert_eq!(ints.encoding(), "hashtable");
+
+        let mut strings = SetStore::new();
+        strings.add_member(b"a");
+        strings.add_member(b"b");
+        assert_eq!(strings.encoding(), "listpack");
+        strings.add_member(b"c");
+        assert_eq!(strings.encoding(), "hashtable");
+        config::replace(config::Config::new());
+    }
+}
@@ -1,97 +1,288 @@
+use crate::store::value::{sampled_size, Value};
 use crate::traits::Store::Store;
-use std::collections::BTreeMap;
+use crate::store_containers::Dict::Dict;
+use crate::store_containers::SkipList::SkipList;
+use std::ops::Range;
 
-#[derive(Debug)]
+// One end of a score range: `1.5` is inclusive, `(1.5` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreBound {
+    pub score: f64,
+    pub exclusive: bool,
+}
+
+// One end of a lex range: `[a` is inclusive, `(a` exclusive, `-` and `+` are
+// the open ends.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LexBound {
+    Min,
+    Max,
+    Inclusive(Vec<u8>),
+    Exclusive(Vec<u8>),
+}
+
+// A sorted set, laid out like Redis' skiplist encoding: a dict for O(1) score
+// lookups and a skiplist ordered by (score, member) for ranks and ranges.
+// Scores are never NaN; the command parsers reject it.
+#[derive(Debug, Clone)]
 pub struct SortedSetStore {
-    // Using BTreeMap for efficient range queries
-    // Key: score, Value: Vec of members with that score
-    scores: BTreeMap<String, f64>, // member -> score mapping
-    members_by_score: BTreeMap<i64, Vec<String>>, // score_as_int -> members (for ordering)
+    scores: Dict<Vec<u8>, f64>, // member -> score mapping
+    ordered: SkipList,
 }
 
 impl SortedSetStore {
     pub fn new() -> Self {
         SortedSetStore {
-            scores: BTreeMap::new(),
-            members_by_score: BTreeMap::new(),
+            scores: Dict::new(),
+            ordered: SkipList::new(),
         }
     }
 
-    pub fn add_member(&mut self, member: &str, score: f64) -> bool {
-        let score_key = (score * 1000000.0) as i64; // Convert to int for ordering
-        let was_new = !self.scores.contains_key(member);
-        
-        // Remove old entry if exists
-        if let Some(old_score) = self.scores.get(member) {
-            let old_score_key = (*old_score * 1000000.0) as i64;
-            if let Some(members) = self.members_by_score.get_mut(&old_score_key) {
-                members.retain(|m| m != member);
-                if members.is_empty() {
-                    self.members_by_score.remove(&old_score_key);
-                }
+    // Adds the member or updates its score. Returns true if it is new.
+    pub fn add_member(&mut self, member: &[u8], score: f64) -> bool {
+        let was_new = match self.scores.insert(member.to_vec(), score) {
+            Some(old_score) if old_score == score => return false,
+            Some(old_score) => {
+                self.ordered.remove(old_score, member);
+                false
             }
+            None => true,
+        };
+        self.ordered.insert(score, member.to_vec());
+        was_new
+    }
+
+    pub fn remove_member(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => self.ordered.remove(score, member),
+            None => false,
         }
+    }
 
-        // Add new entry
-        self.scores.insert(member.to_string(), score);
-        self.members_by_score
-            .entry(score_key)
-            .or_insert_with(Vec::new)
-            .push(member.to_string());
+    pub fn get_score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
 
-        was_new
+    // Members between two ranks, inclusive; negative ranks count from the end.
+    pub fn get_range(&self, start: isize, stop: isize) -> Vec<Vec<u8>> {
+        self.range_entries(self.rank_range(start, stop), false)
+            .into_iter()
+            .map(|(member, _)| member)
+            .collect()
     }
 
-    pub fn remove_member(&mut self, member: &str) -> bool {
-        if let Some(score) = self.scores.remove(member) {
-            let score_key = (score * 1000000.0) as i64;
-            if let Some(members) = self.members_by_score.get_mut(&score_key) {
-                members.retain(|m| m != member);
-                if members.is_empty() {
-                    self.members_by_score.remove(&score_key);
-                }
-            }
-            true
-        } else {
-            false
+    // The ranks between `start` and `stop`, inclusive, resolved the way ZRANGE
+    // does: negative ranks count from the end and the range is clamped.
+    pub fn rank_range(&self, start: isize, stop: isize) -> Range<usize> {
+        let len = self.len() as isize;
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+        if start > stop || start >= len {
+            return 0..0;
         }
+        start as usize..stop as usize + 1
     }
 
-    pub fn get_score(&self, member: &str) -> Option<f64> {
-        self.scores.get(member).copied()
+    // The ranks of the members whose score lies between `min` and `max`.
+    pub fn score_range(&self, min: ScoreBound, max: ScoreBound) -> Range<usize> {
+        let start = self
+            .ordered
+            .count_before(|score, _| score < min.score || (min.exclusive && score == min.score));
+        let end = self
+            .ordered
+            .count_before(|score, _| score < max.score || (!max.exclusive && score == max.score));
+        start..end.max(start)
+    }
+
+    // The ranks of the members between `min` and `max` in byte order. Like in
+    // Redis this is only meaningful when every member has the same score.
+    pub fn lex_range(&self, min: &LexBound, max: &LexBound) -> Range<usize> {
+        let start = self.ordered.count_before(|_, member| match min {
+            LexBound::Min => false,
+            LexBound::Max => true,
+            LexBound::Inclusive(bound) => member < bound.as_slice(),
+            LexBound::Exclusive(bound) => member <= bound.as_slice(),
+        });
+        let end = self.ordered.count_before(|_, member| match max {
+            LexBound::Min => false,
+            LexBound::Max => true,
+            LexBound::Inclusive(bound) => member <= bound.as_slice(),
+            LexBound::Exclusive(bound) => member < bound.as_slice(),
+        });
+        start..end.max(start)
+    }
+
+    // The members at the given ranks with their scores, highest rank first if
+    // `reverse` is set.
+    pub fn range_entries(&self, ranks: Range<usize>, reverse: bool) -> Vec<(Vec<u8>, f64)> {
+        if ranks.is_empty() {
+            return Vec::new();
+        }
+        let entries: Box<dyn Iterator<Item = (&[u8], f64)>> = if reverse {
+            Box::new(self.ordered.iter_rev_from(ranks.end - 1))
+        } else {
+            Box::new(self.ordered.iter_from(ranks.start))
+        };
+        entries
+            .take(ranks.len())
+            .map(|(member, score)| (member.to_vec(), score))
+            .collect()
     }
 
-    pub fn get_range(&self, start: isize, stop: isize) -> Vec<String> {
-        let all_members: Vec<String> = self.members_by_score
-            .values()
-            .flat_map(|v| v.iter())
-            .cloned()
-            .collect();
-        
-        let len = all_members.len() as isize;
-        let start_idx = if start < 0 { (len + start).max(0) as usize } else { start.min(len) as usize };
-        let stop_idx = if stop < 0 { (len + stop + 1).max(0) as usize } else { (stop + 1).min(len) as usize };
-        
-        all_members[start_idx..stop_idx].to_vec()
+    // Removes the members at the given ranks. Returns how many were removed.
+    pub fn remove_range(&mut self, ranks: Range<usize>) -> usize {
+        let entries = self.range_entries(ranks, false);
+        for (member, _) in &entries {
+            self.remove_member(member);
+        }
+        entries.len()
     }
 
-    pub fn get_rank(&self, member: &str) -> Option<usize> {
-        if !self.scores.contains_key(member) {
-            return None;
+    // Removes and returns up to `count` members from the low end, or the high
+    // end with `max` set, in the order they were popped.
+    pub fn pop(&mut self, count: usize, max: bool) -> Vec<(Vec<u8>, f64)> {
+        let count = count.min(self.len());
+        let ranks = if max { self.len() - count..self.len() } else { 0..count };
+        let entries = self.range_entries(ranks, max);
+        for (member, _) in &entries {
+            self.remove_member(member);
         }
+        entries
+    }
+
+    pub fn random_member(&self) -> Option<(&Vec<u8>, f64)> {
+        self.scores.random_entry().map(|(member, score)| (member, *score))
+    }
+
+    // 0-based rank in ascending score order.
+    pub fn get_rank(&self, member: &[u8]) -> Option<usize> {
+        let score = self.get_score(member)?;
+        self.ordered.rank(score, member)
+    }
+
+    // Members with their scores, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], f64)> {
+        self.ordered.iter()
+    }
+
+    // One step of ZSCAN, see Dict::scan_batch.
+    pub fn scan(&self, cursor: u64, count: usize, mut visit: impl FnMut(&[u8], f64)) -> u64 {
+        self.scores.scan_batch(cursor, count, |member, score| visit(member, *score))
+    }
 
-        let all_members: Vec<String> = self.members_by_score
-            .values()
-            .flat_map(|v| v.iter())
-            .cloned()
-            .collect();
-        
-        all_members.iter().position(|m| m == member)
+    // Every member is stored twice, as a key of the dict and in its node.
+    pub fn memory_usage(&self, samples: usize) -> usize {
+        let members = self.scores.keys().map(|member| member.capacity() * 2);
+        self.scores.table_size() + self.ordered.node_size() + sampled_size(members, self.len(), samples, |size| size)
     }
 
     pub fn len(&self) -> usize {
         self.scores.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+}
+
+impl Default for SortedSetStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl Store for SortedSetStore {} 
\ No newline at end of file
+impl Store for SortedSetStore {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::ZSet(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::ZSet(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::ZSet(self)
+    }
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scores_keep_full_precision() {
+        let mut zset = SortedSetStore::new();
+        zset.add_member(b"tiny", 1e-9);
+        zset.add_member(b"zero", 0.0);
+        zset.add_member(b"big", 9007199254740993.0);
+        zset.add_member(b"bigger", 1e300);
+        zset.add_member(b"neg", -0.1);
+        zset.add_member(b"more_neg", -0.2);
+
+        assert_eq!(
+            zset.get_range(0, -1),
+            [&b"more_neg"[..], b"neg", b"zero", b"tiny", b"big", b"bigger"]
+        );
+        assert_eq!(zset.get_score(b"tiny"), Some(1e-9));
+    }
+
+    #[test]
+    fn test_ties_are_ordered_by_member() {
+        let mut zset = SortedSetStore::new();
+        for member in [&b"c"[..], b"a", b"b"] {
+            zset.add_member(member, 1.0);
+        }
+        zset.add_member(b"inf", f64::INFINITY);
+        zset.add_member(b"-inf", f64::NEG_INFINITY);
+
+        assert_eq!(zset.get_range(0, -1), [&b"-inf"[..], b"a", b"b", b"c", b"inf"]);
+        assert_eq!(zset.get_rank(b"b"), Some(2));
+        assert_eq!(zset.get_range(-2, -1), [&b"c"[..], b"inf"]);
+    }
+
+    #[test]
+    fn test_updating_a_score_moves_the_member() {
+        let mut zset = SortedSetStore::new();
+        assert!(zset.add_member(b"a", 1.0));
+        assert!(zset.add_member(b"b", 2.0));
+        assert!(!zset.add_member(b"a", 3.0));
+        assert!(!zset.add_member(b"a", 3.0));
+
+        assert_eq!(zset.get_rank(b"a"), Some(1));
+        assert_eq!(zset.len(), 2);
+        assert!(zset.remove_member(b"a"));
+        assert!(!zset.remove_member(b"a"));
+        assert_eq!(zset.get_rank(b"a"), None);
+        assert_eq!(zset.iter().collect::<Vec<_>>(), [(&b"b"[..], 2.0)]);
+    }
+
+    #[test]
+    fn test_score_and_lex_ranges() {
+        let mut zset = SortedSetStore::new();
+        for (member, score) in [(&b"a"[..], 1.0), (b"b", 2.0), (b"c", 2.0), (b"d", 3.0)] {
+            zset.add_member(member, score);
+        }
+        let inclusive = |score| ScoreBound { score, exclusive: false };
+        let exclusive = |score| ScoreBound { score, exclusive: true };
+
+        assert_eq!(zset.score_range(inclusive(2.0), inclusive(3.0)), 1..4);
+        assert_eq!(zset.score_range(exclusive(1.0), exclusive(3.0)), 1..3);
+        assert_eq!(zset.score_range(inclusive(f64::NEG_INFINITY), inclusive(f64::INFINITY)), 0..4);
+        assert!(zset.score_range(inclusive(3.0), inclusive(1.0)).is_empty());
+
+        assert_eq!(zset.lex_range(&LexBound::Min, &LexBound::Max), 0..4);
+        assert_eq!(zset.lex_range(&LexBound::Exclusive(b"a".to_vec()), &LexBound::Inclusive(b"c".to_vec())), 1..3);
+        assert_eq!(zset.rank_range(-2, 100), 2..4);
+
+        assert_eq!(zset.range_entries(1..3, true), [(b"c".to_vec(), 2.0), (b"b".to_vec(), 2.0)]);
+        assert_eq!(zset.pop(1, true), [(b"d".to_vec(), 3.0)]);
+        assert_eq!(zset.remove_range(0..2), 2);
+        assert_eq!(zset.get_range(0, -1), [b"c"]);
+    }
+}
@@ -1,18 +1,70 @@
+use crate::store::value::Value;
 use crate::traits::Store::Store;
-#[derive(Debug)]
+use crate::utils::integer::parse_canonical;
+
+// Largest string a command may build, the same limit as Redis' proto-max-bulk-len.
+pub const MAX_STRING_SIZE: usize = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default)]
 pub struct StringStore {
-    value: String,
+    value: Vec<u8>,
 }
 impl StringStore {
-    pub fn new(value: String) -> Self {
-        StringStore { value: value }
+    pub fn new(value: Vec<u8>) -> Self {
+        StringStore { value }
     }
-    pub fn get_value(&self) -> &String {
+    pub fn get_value(&self) -> &Vec<u8> {
         &self.value
     }
+    pub fn get_value_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.value
+    }
+    pub fn set_value(&mut self, value: Vec<u8>) {
+        self.value = value;
+    }
+    pub fn memory_usage(&self) -> usize {
+        self.value.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.value.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    // The value read as a signed 64-bit integer, if it is written as one the
+    // canonical way.
+    pub fn as_integer(&self) -> Option<i64> {
+        parse_canonical(&self.value)
+    }
+
+    // The value read as a finite float, if it is one.
+    pub fn as_float(&self) -> Option<f64> {
+        let value: f64 = std::str::from_utf8(&self.value).ok()?.parse().ok()?;
+        value.is_finite().then_some(value)
+    }
 }
 
-impl Store for StringStore {}
+impl Store for StringStore {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::String(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::String(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::String(self)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -20,19 +72,38 @@ mod tests {
 
     #[test]
     fn test_string_store_new() {
-        let store = StringStore::new("test_value".to_string());
-        assert_eq!(store.get_value(), "test_value");
+        let store = StringStore::new(b"test_value".to_vec());
+        assert_eq!(store.get_value(), b"test_value");
     }
 
     #[test]
     fn test_string_store_empty() {
-        let store = StringStore::new("".to_string());
-        assert_eq!(store.get_value(), "");
+        let store = StringStore::new(Vec::new());
+        assert!(store.get_value().is_empty());
     }
 
     #[test]
     fn test_string_store_unicode() {
-        let store = StringStore::new("hello 世界".to_string());
-        assert_eq!(store.get_value(), "hello 世界");
+        let store = StringStore::new("hello 世界".as_bytes().to_vec());
+        assert_eq!(store.get_value(), "hello 世界".as_bytes());
+    }
+
+    #[test]
+    fn test_string_store_numeric_views() {
+        assert_eq!(StringStore::new(b"-42".to_vec()).as_integer(), Some(-42));
+        assert_eq!(StringStore::new(b"4.2".to_vec()).as_integer(), None);
+        assert_eq!(StringStore::new(b" 42".to_vec()).as_integer(), None);
+        assert_eq!(StringStore::new(b"+42".to_vec()).as_integer(), None);
+        assert_eq!(StringStore::new(b"042".to_vec()).as_integer(), None);
+        assert_eq!(StringStore::new(b"4.5e1".to_vec()).as_float(), Some(45.0));
+        assert_eq!(StringStore::new(b"inf".to_vec()).as_float(), None);
+        assert_eq!(StringStore::new(b"nan".to_vec()).as_float(), None);
+    }
+
+    #[test]
+    fn test_string_store_binary() {
+        let value = vec![0u8, 255, b'\r', b'\n', 0x80, b'x'];
+        let store = StringStore::new(value.clone());
+        assert_eq!(store.get_value(), &value);
     }
 }
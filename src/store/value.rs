@@ -0,0 +1,152 @@
+use crate::store::hash_store::HashStore;
+use crate::store::set_store::SetStore;
+use crate::store::sorted_set_store::SortedSetStore;
+use crate::store::string_store::StringStore;
+use crate::store::vector_store::VectorStore;
+use std::error::Error;
+use std::fmt;
+
+// Every value a key can hold.
+#[derive(Debug, Clone)]
+pub enum Value {
+    String(StringStore),
+    List(VectorStore),
+    Hash(HashStore),
+    Set(SetStore),
+    ZSet(SortedSetStore),
+}
+
+impl Value {
+    // Name reported by TYPE
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Hash(_) => "hash",
+            Value::Set(_) => "set",
+            Value::ZSet(_) => "zset",
+        }
+    }
+
+    // Collections are deleted as soon as their last element goes, so a key
+    // never holds an empty one. An empty string is a valid value.
+    pub fn is_empty_collection(&self) -> bool {
+        match self {
+            Value::String(_) => false,
+            Value::List(list) => list.is_empty(),
+            Value::Hash(hash) => hash.is_empty(),
+            Value::Set(set) => set.is_empty(),
+            Value::ZSet(zset) => zset.is_empty(),
+        }
+    }
+
+    // Approximate heap bytes held by the value. Collections extrapolate from
+    // their first `samples` elements, or look at all of them for 0.
+    pub fn memory_usage(&self, samples: usize) -> usize {
+        match self {
+            Value::String(string_store) => string_store.memory_usage(),
+            Value::List(list) => list.memory_usage(samples),
+            Value::Hash(hash) => hash.memory_usage(samples),
+            Value::Set(set) => set.memory_usage(samples),
+            Value::ZSet(zset) => zset.memory_usage(samples),
+        }
+    }
+
+    // Name reported by OBJECT ENCODING
+    pub fn encoding(&self) -> &'static str {
+        match self {
+            Value::String(string_store) => {
+                let value = string_store.get_value();
+                if string_store.as_integer().is_some() {
+                    "int"
+                } else if value.len() <= 44 {
+                    "embstr"
+                } else {
+                    "raw"
+                }
+            }
+            Value::List(_) => "quicklist",
+            Value::Hash(hash_store) => hash_store.encoding(),
+            Value::Set(set_store) => set_store.encoding(),
+            Value::ZSet(_) => "skiplist",
+        }
+    }
+}
+
+// Total size of a collection's `len` elements, extrapolated from the first
+// `samples` of them like Redis' MEMORY USAGE does. 0 samples means all.
+pub fn sampled_size<T>(elements: impl Iterator<Item = T>, len: usize, samples: usize, size: impl Fn(T) -> usize) -> usize {
+    let sampled = if samples == 0 { len } else { samples.min(len) };
+    if sampled == 0 {
+        return 0;
+    }
+    elements.take(sampled).map(size).sum::<usize>() * len / sampled
+}
+
+// Returned when a command meets a key holding a different type than it works on.
+#[derive(Debug)]
+pub struct WrongTypeError;
+
+impl fmt::Display for WrongTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WRONGTYPE Operation against a key holding the wrong kind of value")
+    }
+}
+
+impl Error for WrongTypeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_type_names() {
+        assert_eq!(Value::String(StringStore::new(b"v".to_vec())).type_name(), "string");
+        assert_eq!(Value::List(VectorStore::new()).type_name(), "list");
+        assert_eq!(Value::Hash(HashStore::new()).type_name(), "hash");
+        assert_eq!(Value::Set(SetStore::new()).type_name(), "set");
+        assert_eq!(Value::ZSet(SortedSetStore::new()).type_name(), "zset");
+    }
+
+    #[test]
+    fn test_string_encodings() {
+        let encoding = |value: &[u8]| Value::String(StringStore::new(value.to_vec())).encoding();
+        assert_eq!(encoding(b"12345"), "int");
+        assert_eq!(encoding(b"-7"), "int");
+        assert_eq!(encoding(b"hello"), "embstr");
+        assert_eq!(encoding(&[b'x'; 45]), "raw");
+    }
+
+    #[test]
+    fn test_sampled_size() {
+        let sizes = [10, 10, 10, 1000];
+        // Two samples extrapolated to four elements, or every element with 0
+        assert_eq!(sampled_size(sizes.iter(), 4, 2, |size| *size), 40);
+        assert_eq!(sampled_size(sizes.iter(), 4, 0, |size| *size), 1030);
+        assert_eq!(sampled_size(sizes.iter(), 4, 10, |size| *size), 1030);
+        assert_eq!(sampled_size(std::iter::empty::<usize>(), 0, 5, |size| size), 0);
+    }
+
+    #[test]
+    fn test_memory_usage_grows_with_the_value() {
+        let mut list = VectorStore::new();
+        let empty = Value::List(list.clone()).memory_usage(0);
+        for _ in 0..100 {
+            list.push_right(&[b'x'; 100]);
+        }
+        assert!(Value::List(list).memory_usage(0) >= empty + 100 * 100);
+
+        let mut set = SetStore::new();
+        let mut zset = SortedSetStore::new();
+        let mut hash = HashStore::new();
+        for i in 0..1000 {
+            let member = format!("member-{}", i);
+            set.add_member(member.as_bytes());
+            zset.add_member(member.as_bytes(), i as f64);
+            hash.set_field(member.as_bytes(), b"value");
+        }
+        for value in [Value::Set(set), Value::ZSet(zset), Value::Hash(hash)] {
+            assert!(value.memory_usage(0) > 1000 * "member-000".len(), "{}", value.type_name());
+        }
+    }
+}
@@ -1,65 +1,190 @@
+use crate::store::value::{sampled_size, Value};
 use crate::traits::Store::Store;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 
-#[derive(Debug)]
+// A list. Backed by a ring buffer so pushes and pops are O(1) at both ends.
+#[derive(Debug, Clone)]
 pub struct VectorStore {
-    values: Vec<String>,
+    values: VecDeque<Vec<u8>>,
 }
 
 impl VectorStore {
     pub fn new() -> Self {
         VectorStore {
-            values: Vec::new(),
+            values: VecDeque::new(),
         }
     }
 
-    pub fn push_left(&mut self, value: &str) -> usize {
-        self.values.insert(0, value.to_string());
+    pub fn push_left(&mut self, value: &[u8]) -> usize {
+        self.values.push_front(value.to_vec());
         self.values.len()
     }
 
-    pub fn push_right(&mut self, value: &str) -> usize {
-        self.values.push(value.to_string());
+    pub fn push_right(&mut self, value: &[u8]) -> usize {
+        self.values.push_back(value.to_vec());
         self.values.len()
     }
 
-    pub fn pop_left(&mut self) -> Option<String> {
-        if !self.values.is_empty() {
-            Some(self.values.remove(0))
-        } else {
-            None
-        }
+    pub fn pop_left(&mut self) -> Option<Vec<u8>> {
+        self.values.pop_front()
     }
 
-    pub fn pop_right(&mut self) -> Option<String> {
-        self.values.pop()
+    pub fn pop_right(&mut self) -> Option<Vec<u8>> {
+        self.values.pop_back()
     }
 
-    pub fn get_range(&self, start: isize, stop: isize) -> Vec<String> {
+    // Resolves an inclusive [start, stop] range the way LRANGE and LTRIM do:
+    // negative indexes count from the tail and the range is clamped to the
+    // list. None if it is empty.
+    fn resolve_range(&self, start: isize, stop: isize) -> Option<(usize, usize)> {
         let len = self.values.len() as isize;
-        let start_idx = if start < 0 { (len + start).max(0) as usize } else { start.min(len) as usize };
-        let stop_idx = if stop < 0 { (len + stop + 1).max(0) as usize } else { (stop + 1).min(len) as usize };
-        
-        self.values[start_idx..stop_idx].to_vec()
+        let start = if start < 0 { (len + start).max(0) } else { start };
+        let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+        if start > stop || start >= len {
+            return None;
+        }
+        Some((start as usize, stop as usize))
     }
 
-    pub fn get_index(&self, index: isize) -> Option<&String> {
+    pub fn get_range(&self, start: isize, stop: isize) -> Vec<Vec<u8>> {
+        match self.resolve_range(start, stop) {
+            Some((start, stop)) => self.values.range(start..=stop).cloned().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn resolve_index(&self, index: isize) -> Option<usize> {
         let len = self.values.len() as isize;
         let idx = if index < 0 { len + index } else { index };
-        
-        if idx >= 0 && idx < len {
-            self.values.get(idx as usize)
+        (idx >= 0 && idx < len).then_some(idx as usize)
+    }
+
+    pub fn get_index(&self, index: isize) -> Option<&Vec<u8>> {
+        self.values.get(self.resolve_index(index)?)
+    }
+
+    // Replaces the element at `index`. Returns false if it is out of range.
+    pub fn set_index(&mut self, index: isize, value: &[u8]) -> bool {
+        match self.resolve_index(index) {
+            Some(idx) => {
+                self.values[idx] = value.to_vec();
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Inserts next to the first occurrence of `pivot`. Returns the new length,
+    // or None if the pivot is not in the list.
+    pub fn insert_at_pivot(&mut self, pivot: &[u8], value: &[u8], before: bool) -> Option<usize> {
+        let position = self.values.iter().position(|element| element == pivot)?;
+        let at = if before { position } else { position + 1 };
+        self.values.insert(at, value.to_vec());
+        Some(self.values.len())
+    }
+
+    // Removes up to |count| occurrences of `value`, from the head if count is
+    // positive, from the tail if negative, all of them if zero.
+    pub fn remove_value(&mut self, value: &[u8], count: i64) -> usize {
+        let limit = if count == 0 { usize::MAX } else { count.unsigned_abs() as usize };
+        let mut removed = 0;
+        if count >= 0 {
+            self.values.retain(|element| {
+                if removed < limit && element == value {
+                    removed += 1;
+                    return false;
+                }
+                true
+            });
         } else {
-            None
+            let mut idx = self.values.len();
+            while idx > 0 && removed < limit {
+                idx -= 1;
+                if self.values[idx] == value {
+                    self.values.remove(idx);
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    // Keeps only the elements in [start, stop], see resolve_range.
+    pub fn trim(&mut self, start: isize, stop: isize) {
+        match self.resolve_range(start, stop) {
+            Some((start, stop)) => {
+                self.values.truncate(stop + 1);
+                self.values.drain(..start);
+            }
+            None => self.values.clear(),
         }
     }
 
+    // Indexes of the elements equal to `value`, for LPOS. `rank` picks which
+    // match to start from (negative searches from the tail), `count` how many
+    // to return (0 for all), and `maxlen` how many elements to compare at most
+    // (0 for no limit).
+    pub fn positions(&self, value: &[u8], rank: i64, count: usize, maxlen: usize) -> Vec<usize> {
+        let len = self.values.len();
+        let limit = if maxlen == 0 { len } else { maxlen.min(len) };
+        let wanted = if count == 0 { usize::MAX } else { count };
+        let skip = rank.unsigned_abs() as usize - 1;
+        let indexes: Box<dyn Iterator<Item = usize>> = if rank > 0 {
+            Box::new(0..limit)
+        } else {
+            Box::new((len - limit..len).rev())
+        };
+        indexes
+            .filter(|&idx| self.values[idx] == value)
+            .skip(skip)
+            .take(wanted)
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.values.iter()
+    }
+
+    pub fn memory_usage(&self, samples: usize) -> usize {
+        self.values.capacity() * std::mem::size_of::<Vec<u8>>()
+            + sampled_size(self.values.iter(), self.len(), samples, Vec::capacity)
+    }
+
     pub fn len(&self) -> usize {
         self.values.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
 }
 
-impl Store for VectorStore {}
+impl Default for VectorStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Store for VectorStore {
+    fn from_value(value: &Value) -> Option<&Self> {
+        match value {
+            Value::List(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self> {
+        match value {
+            Value::List(store) => Some(store),
+            _ => None,
+        }
+    }
+
+    fn into_value(self) -> Value {
+        Value::List(self)
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -75,37 +200,37 @@ mod tests {
     fn test_push_left() {
         let mut store = VectorStore::new();
         
-        assert_eq!(store.push_left("first"), 1);
-        assert_eq!(store.push_left("second"), 2);
-        assert_eq!(store.push_left("third"), 3);
+        assert_eq!(store.push_left(b"first"), 1);
+        assert_eq!(store.push_left(b"second"), 2);
+        assert_eq!(store.push_left(b"third"), 3);
         
-        assert_eq!(store.get_range(0, -1), vec!["third", "second", "first"]);
+        assert_eq!(store.get_range(0, -1), vec![b"third".to_vec(), b"second".to_vec(), b"first".to_vec()]);
     }
 
     #[test]
     fn test_push_right() {
         let mut store = VectorStore::new();
         
-        assert_eq!(store.push_right("first"), 1);
-        assert_eq!(store.push_right("second"), 2);
-        assert_eq!(store.push_right("third"), 3);
+        assert_eq!(store.push_right(b"first"), 1);
+        assert_eq!(store.push_right(b"second"), 2);
+        assert_eq!(store.push_right(b"third"), 3);
         
-        assert_eq!(store.get_range(0, -1), vec!["first", "second", "third"]);
+        assert_eq!(store.get_range(0, -1), vec![b"first".to_vec(), b"second".to_vec(), b"third".to_vec()]);
     }
 
     #[test]
     fn test_pop_left() {
         let mut store = VectorStore::new();
         
-        store.push_right("first");
-        store.push_right("second");
-        store.push_right("third");
+        store.push_right(b"first");
+        store.push_right(b"second");
+        store.push_right(b"third");
         
-        assert_eq!(store.pop_left(), Some("first".to_string()));
-        assert_eq!(store.pop_left(), Some("second".to_string()));
+        assert_eq!(store.pop_left(), Some(b"first".to_vec()));
+        assert_eq!(store.pop_left(), Some(b"second".to_vec()));
         assert_eq!(store.len(), 1);
         
-        assert_eq!(store.pop_left(), Some("third".to_string()));
+        assert_eq!(store.pop_left(), Some(b"third".to_vec()));
         assert_eq!(store.pop_left(), None);
     }
 
@@ -113,15 +238,15 @@ mod tests {
     fn test_pop_right() {
         let mut store = VectorStore::new();
         
-        store.push_right("first");
-        store.push_right("second");
-        store.push_right("third");
+        store.push_right(b"first");
+        store.push_right(b"second");
+        store.push_right(b"third");
         
-        assert_eq!(store.pop_right(), Some("third".to_string()));
-        assert_eq!(store.pop_right(), Some("second".to_string()));
+        assert_eq!(store.pop_right(), Some(b"third".to_vec()));
+        assert_eq!(store.pop_right(), Some(b"second".to_vec()));
         assert_eq!(store.len(), 1);
         
-        assert_eq!(store.pop_right(), Some("first".to_string()));
+        assert_eq!(store.pop_right(), Some(b"first".to_vec()));
         assert_eq!(store.pop_right(), None);
     }
 
@@ -130,39 +255,39 @@ mod tests {
         let mut store = VectorStore::new();
         
         for i in 0..5 {
-            store.push_right(&format!("item{}", i));
+            store.push_right(format!("item{}", i).as_bytes());
         }
         
         // Test positive indices
-        assert_eq!(store.get_range(0, 2), vec!["item0", "item1", "item2"]);
-        assert_eq!(store.get_range(1, 3), vec!["item1", "item2", "item3"]);
+        assert_eq!(store.get_range(0, 2), vec![b"item0".to_vec(), b"item1".to_vec(), b"item2".to_vec()]);
+        assert_eq!(store.get_range(1, 3), vec![b"item1".to_vec(), b"item2".to_vec(), b"item3".to_vec()]);
         
         // Test negative indices
-        assert_eq!(store.get_range(-2, -1), vec!["item3", "item4"]);
-        assert_eq!(store.get_range(0, -1), vec!["item0", "item1", "item2", "item3", "item4"]);
+        assert_eq!(store.get_range(-2, -1), vec![b"item3".to_vec(), b"item4".to_vec()]);
+        assert_eq!(store.get_range(0, -1), vec![b"item0".to_vec(), b"item1".to_vec(), b"item2".to_vec(), b"item3".to_vec(), b"item4".to_vec()]);
         
         // Test out of bounds
-        assert_eq!(store.get_range(10, 20), vec![] as Vec<String>);
-        assert_eq!(store.get_range(-10, 2), vec!["item0", "item1", "item2"]);
+        assert_eq!(store.get_range(10, 20), Vec::<Vec<u8>>::new());
+        assert_eq!(store.get_range(-10, 2), vec![b"item0".to_vec(), b"item1".to_vec(), b"item2".to_vec()]);
     }
 
     #[test]
     fn test_get_index() {
         let mut store = VectorStore::new();
         
-        store.push_right("first");
-        store.push_right("second");
-        store.push_right("third");
+        store.push_right(b"first");
+        store.push_right(b"second");
+        store.push_right(b"third");
         
         // Test positive indices
-        assert_eq!(store.get_index(0), Some(&"first".to_string()));
-        assert_eq!(store.get_index(1), Some(&"second".to_string()));
-        assert_eq!(store.get_index(2), Some(&"third".to_string()));
+        assert_eq!(store.get_index(0), Some(&b"first".to_vec()));
+        assert_eq!(store.get_index(1), Some(&b"second".to_vec()));
+        assert_eq!(store.get_index(2), Some(&b"third".to_vec()));
         
         // Test negative indices
-        assert_eq!(store.get_index(-1), Some(&"third".to_string()));
-        assert_eq!(store.get_index(-2), Some(&"second".to_string()));
-        assert_eq!(store.get_index(-3), Some(&"first".to_string()));
+        assert_eq!(store.get_index(-1), Some(&b"third".to_vec()));
+        assert_eq!(store.get_index(-2), Some(&b"second".to_vec()));
+        assert_eq!(store.get_index(-3), Some(&b"first".to_vec()));
         
         // Test out of bounds
         assert_eq!(store.get_index(3), None);
@@ -173,14 +298,72 @@ mod tests {
     fn test_mixed_operations() {
         let mut store = VectorStore::new();
         
-        store.push_right("middle");
-        store.push_left("left");
-        store.push_right("right");
+        store.push_right(b"middle");
+        store.push_left(b"left");
+        store.push_right(b"right");
         
-        assert_eq!(store.get_range(0, -1), vec!["left", "middle", "right"]);
+        assert_eq!(store.get_range(0, -1), vec![b"left".to_vec(), b"middle".to_vec(), b"right".to_vec()]);
         
-        assert_eq!(store.pop_left(), Some("left".to_string()));
-        assert_eq!(store.pop_right(), Some("right".to_string()));
-        assert_eq!(store.get_range(0, -1), vec!["middle"]);
+        assert_eq!(store.pop_left(), Some(b"left".to_vec()));
+        assert_eq!(store.pop_right(), Some(b"right".to_vec()));
+        assert_eq!(store.get_range(0, -1), vec![b"middle".to_vec()]);
+    }
+
+    fn list_of(items: &[&str]) -> VectorStore {
+        let mut store = VectorStore::new();
+        for item in items {
+            store.push_right(item.as_bytes());
+        }
+        store
+    }
+
+    fn contents(store: &VectorStore) -> Vec<String> {
+        store.iter().map(|item| String::from_utf8(item.clone()).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_set_index_and_insert() {
+        let mut store = list_of(&["a", "b", "c"]);
+        assert!(store.set_index(-1, b"z"));
+        assert!(!store.set_index(3, b"x"));
+        assert_eq!(store.insert_at_pivot(b"b", b"before", true), Some(4));
+        assert_eq!(store.insert_at_pivot(b"b", b"after", false), Some(5));
+        assert_eq!(store.insert_at_pivot(b"missing", b"x", true), None);
+        assert_eq!(contents(&store), ["a", "before", "b", "after", "z"]);
+    }
+
+    #[test]
+    fn test_remove_value() {
+        let mut store = list_of(&["x", "a", "x", "b", "x"]);
+        assert_eq!(store.remove_value(b"x", -2), 2);
+        assert_eq!(contents(&store), ["x", "a", "b"]);
+
+        let mut store = list_of(&["x", "a", "x", "b", "x"]);
+        assert_eq!(store.remove_value(b"x", 1), 1);
+        assert_eq!(contents(&store), ["a", "x", "b", "x"]);
+        assert_eq!(store.remove_value(b"x", 0), 2);
+        assert_eq!(contents(&store), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_trim() {
+        let mut store = list_of(&["a", "b", "c", "d", "e"]);
+        store.trim(1, -2);
+        assert_eq!(contents(&store), ["b", "c", "d"]);
+        store.trim(5, 10);
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_positions() {
+        let store = list_of(&["a", "b", "c", "1", "2", "3", "c", "c"]);
+        assert_eq!(store.positions(b"c", 1, 1, 0), vec![2]);
+        assert_eq!(store.positions(b"c", 2, 1, 0), vec![6]);
+        assert_eq!(store.positions(b"c", -1, 1, 0), vec![7]);
+        assert_eq!(store.positions(b"c", 1, 0, 0), vec![2, 6, 7]);
+        assert_eq!(store.positions(b"c", -1, 2, 0), vec![7, 6]);
+        assert_eq!(store.positions(b"c", 1, 0, 3), vec![2]);
+        assert_eq!(store.positions(b"c", -1, 0, 2), vec![7, 6]);
+        assert!(store.positions(b"missing", 1, 0, 0).is_empty());
     }
 }
@@ -0,0 +1,108 @@
+use std::fs::File;
+use std::io;
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+
+// When the append-only file is flushed to disk (Redis' appendfsync).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AppendFsync {
+    // After every write, before the client gets its reply
+    Always,
+    // Once a second, in the background
+    EverySec,
+    // Whenever the OS decides
+    No,
+}
+
+impl AppendFsync {
+    pub const ALL: [AppendFsync; 3] = [AppendFsync::Always, AppendFsync::EverySec, AppendFsync::No];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppendFsync::Always => "always",
+            AppendFsync::EverySec => "everysec",
+            AppendFsync::No => "no",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<AppendFsync> {
+        AppendFsync::ALL.into_iter().find(|policy| policy.name() == name)
+    }
+}
+
+// A BGREWRITEAOF running on its own thread. Writes made meanwhile are kept in
+// `buffer` and appended to the new file once the thread is done.
+#[derive(Debug)]
+pub struct Rewrite {
+    pub handle: JoinHandle<io::Result<()>>,
+    pub temp_path: PathBuf,
+    pub buffer: Vec<u8>,
+}
+
+// State of the append-only file. Executors queue what they want logged here;
+// writing it out is up to `persistence::aof`.
+#[derive(Debug)]
+pub struct AofStore {
+    // Commands to log before the running one, like the DEL of a key that
+    // expired under it, or in its place when `prevent_command` is set
+    also: Vec<Vec<Vec<u8>>>,
+    prevent_command: bool,
+    // Some while EXEC runs, and true once the MULTI wrapping its commands is
+    // logged: only a transaction that writes something is
+    pub transaction: Option<bool>,
+    // Whether writes are being logged, even if only to a rewrite's buffer
+    pub enabled: bool,
+    // Open for appending; None while the first rewrite creates the file
+    pub file: Option<File>,
+    // Logged commands not written to `file` yet
+    pub buffer: Vec<u8>,
+    pub rewrite: Option<Rewrite>,
+    // When the last rewrite started, in seconds since the epoch
+    pub last_rewrite_try: u64,
+    // An everysec fsync running in the background, and when it started (ms)
+    pub fsync: Option<JoinHandle<io::Result<()>>>,
+    pub last_fsync: u64,
+    pub last_write_ok: bool,
+    pub last_rewrite_ok: bool,
+}
+
+impl AofStore {
+    pub fn new() -> Self {
+        AofStore {
+            also: Vec::new(),
+            prevent_command: false,
+            transaction: None,
+            enabled: false,
+            file: None,
+            buffer: Vec::new(),
+            rewrite: None,
+            last_rewrite_try: 0,
+            fsync: None,
+            last_fsync: 0,
+            last_write_ok: true,
+            last_rewrite_ok: true,
+        }
+    }
+
+    // Logs `args` as well as the running command, before it.
+    pub fn also_propagate(&mut self, args: Vec<Vec<u8>>) {
+        self.also.push(args);
+    }
+
+    // Logs only what was passed to `also_propagate`, not the running command:
+    // for commands that would not replay the same, like SPOP.
+    pub fn prevent_propagation(&mut self) {
+        self.prevent_command = true;
+    }
+
+    // What the running command asked for, resetting it for the next one.
+    pub fn take_propagation(&mut self) -> (Vec<Vec<Vec<u8>>>, bool) {
+        (std::mem::take(&mut self.also), std::mem::take(&mut self.prevent_command))
+    }
+}
+
+impl Default for AofStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
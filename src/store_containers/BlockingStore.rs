@@ -0,0 +1,162 @@
+use crate::command::command_enum::Command;
+use std::collections::{HashMap, HashSet, VecDeque};
+use tokio::sync::oneshot;
+
+// A client parked by BLPOP and friends: the keys it waits on, the command to
+// run again once one of them gets data, and where to send that command's reply.
+#[derive(Debug)]
+pub struct BlockedClient {
+    pub keys: Vec<Vec<u8>>,
+    pub command: Command,
+    // As sent, for the AOF
+    pub args: Vec<Vec<u8>>,
+    pub reply: oneshot::Sender<Vec<u8>>,
+}
+
+// Clients blocked on list keys. Each key has its own FIFO of waiters, so the
+// client that blocked first is served first. Pushes only mark a key as ready;
+// the waiters are served after the pushing command completes (see
+// `server::blocking`), the way Redis' handleClientsBlockedOnKeys works.
+#[derive(Debug)]
+pub struct BlockingStore {
+    next_id: u64,
+    clients: HashMap<u64, BlockedClient>,
+    waiters: HashMap<Vec<u8>, VecDeque<u64>>,
+    ready_keys: Vec<Vec<u8>>,
+    ready_set: HashSet<Vec<u8>>,
+}
+
+impl BlockingStore {
+    pub fn new() -> Self {
+        BlockingStore {
+            next_id: 0,
+            clients: HashMap::new(),
+            waiters: HashMap::new(),
+            ready_keys: Vec::new(),
+            ready_set: HashSet::new(),
+        }
+    }
+
+    // Parks a client on `keys`. The receiver gets the reply once it is served.
+    pub fn block(
+        &mut self,
+        keys: Vec<Vec<u8>>,
+        command: Command,
+        args: Vec<Vec<u8>>,
+    ) -> (u64, oneshot::Receiver<Vec<u8>>) {
+        let (sender, receiver) = oneshot::channel();
+        let id = self.next_id;
+        self.next_id += 1;
+        for key in &keys {
+            let queue = self.waiters.entry(key.clone()).or_default();
+            // BLPOP k k waits on k once
+            if !queue.contains(&id) {
+                queue.push_back(id);
+            }
+        }
+        self.clients.insert(
+            id,
+            BlockedClient {
+                keys,
+                command,
+                args,
+                reply: sender,
+            },
+        );
+        (id, receiver)
+    }
+
+    // Removes a client from every queue it waits in. None if it was already
+    // served or unblocked.
+    pub fn unblock(&mut self, id: u64) -> Option<BlockedClient> {
+        let client = self.clients.remove(&id)?;
+        for key in &client.keys {
+            if let Some(queue) = self.waiters.get_mut(key) {
+                queue.retain(|&waiter| waiter != id);
+                if queue.is_empty() {
+                    self.waiters.remove(key);
+                }
+            }
+        }
+        Some(client)
+    }
+
+    // Called whenever a list may have gained elements. Cheap when nobody waits.
+    pub fn signal_key_ready(&mut self, key: &[u8]) {
+        if self.waiters.contains_key(key) && !self.ready_set.contains(key) {
+            self.ready_set.insert(key.to_vec());
+            self.ready_keys.push(key.to_vec());
+        }
+    }
+
+    // Keys signalled since the last call, in the order they became ready.
+    pub fn take_ready_keys(&mut self) -> Vec<Vec<u8>> {
+        self.ready_set.clear();
+        std::mem::take(&mut self.ready_keys)
+    }
+
+    // The client that has waited longest on `key`.
+    pub fn first_waiter(&self, key: &[u8]) -> Option<u64> {
+        self.waiters.get(key)?.front().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+}
+
+impl Default for BlockingStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blpop(keys: &[&[u8]]) -> (Vec<Vec<u8>>, Command) {
+        let keys: Vec<Vec<u8>> = keys.iter().map(|key| key.to_vec()).collect();
+        let command = Command::BLPOP {
+            keys: keys.clone(),
+            timeout_ms: 0,
+        };
+        (keys, command)
+    }
+
+    #[test]
+    fn test_waiters_are_fifo_per_key() {
+        let mut store = BlockingStore::new();
+        let (keys, command) = blpop(&[b"a", b"b"]);
+        let (first, _first_reply) = store.block(keys, command, Vec::new());
+        let (keys, command) = blpop(&[b"b"]);
+        let (second, _second_reply) = store.block(keys, command, Vec::new());
+
+        assert_eq!(store.first_waiter(b"a"), Some(first));
+        assert_eq!(store.first_waiter(b"b"), Some(first));
+
+        // Unblocking leaves no trace in the other queues
+        assert!(store.unblock(first).is_some());
+        assert!(store.unblock(first).is_none());
+        assert_eq!(store.first_waiter(b"a"), None);
+        assert_eq!(store.first_waiter(b"b"), Some(second));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn test_only_watched_keys_become_ready() {
+        let mut store = BlockingStore::new();
+        let (keys, command) = blpop(&[b"a"]);
+        let _blocked = store.block(keys, command, Vec::new());
+
+        store.signal_key_ready(b"other");
+        store.signal_key_ready(b"a");
+        store.signal_key_ready(b"a");
+        assert_eq!(store.take_ready_keys(), vec![b"a".to_vec()]);
+        assert!(store.take_ready_keys().is_empty());
+    }
+}
@@ -0,0 +1,356 @@
+use rand::Rng;
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt::{self, Debug};
+use std::hash::{BuildHasher, Hash};
+
+// Tables never shrink below this many buckets.
+const MIN_BUCKETS: usize = 4;
+
+// Chained hash table with a power-of-two bucket count, the same shape as Redis'
+// dict. We keep our own instead of std's HashMap because SCAN needs to walk the
+// buckets with a cursor that stays valid across calls, even if the table is
+// resized in between, and RANDOMKEY needs to pick a bucket at random.
+#[derive(Clone)]
+pub struct Dict<K, V> {
+    buckets: Vec<Vec<(K, V)>>,
+    len: usize,
+    hasher: RandomState,
+}
+
+impl<K: Hash + Eq, V> Dict<K, V> {
+    pub fn new() -> Self {
+        Dict {
+            buckets: Vec::new(),
+            len: 0,
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Number of buckets, exposed for tests and memory accounting.
+    pub fn bucket_count(&self) -> usize {
+        self.buckets.len()
+    }
+
+    // Heap bytes of the table itself: the bucket array and the entries in the
+    // chains, not anything the keys and values point to.
+    pub fn table_size(&self) -> usize {
+        self.bucket_array_size() + self.len * std::mem::size_of::<(K, V)>()
+    }
+
+    // Heap bytes of the bucket array alone.
+    pub fn bucket_array_size(&self) -> usize {
+        self.buckets.capacity() * std::mem::size_of::<Vec<(K, V)>>()
+    }
+
+    fn bucket_index<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hasher.hash_one(key) as usize) & (self.buckets.len() - 1)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        self.buckets[self.bucket_index(key)]
+            .iter()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        let index = self.bucket_index(key);
+        self.buckets[index]
+            .iter_mut()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    // Inserts or replaces, returning the previous value.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(existing) = self.get_mut(&key) {
+            return Some(std::mem::replace(existing, value));
+        }
+        if self.len >= self.buckets.len() {
+            self.resize((self.len + 1).next_power_of_two().max(MIN_BUCKETS));
+        }
+        let index = self.bucket_index(&key);
+        self.buckets[index].push((key, value));
+        self.len += 1;
+        None
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, v)| v)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.len == 0 {
+            return None;
+        }
+        let index = self.bucket_index(key);
+        let bucket = &mut self.buckets[index];
+        let position = bucket.iter().position(|(k, _)| k.borrow() == key)?;
+        let entry = bucket.swap_remove(position);
+        self.len -= 1;
+
+        // Shrink once the table is under 1/8 full, like Redis' 10% rule
+        if self.len == 0 {
+            self.buckets = Vec::new();
+        } else if self.buckets.len() > MIN_BUCKETS && self.len * 8 < self.buckets.len() {
+            self.resize(self.len.next_power_of_two().max(MIN_BUCKETS));
+        }
+        Some(entry)
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets = Vec::new();
+        self.len = 0;
+    }
+
+    fn resize(&mut self, bucket_count: usize) {
+        let old = std::mem::replace(&mut self.buckets, (0..bucket_count).map(|_| Vec::new()).collect());
+        for (key, value) in old.into_iter().flatten() {
+            let index = self.bucket_index(&key);
+            self.buckets[index].push((key, value));
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.buckets.iter().flatten().map(|(k, v)| (k, v))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.buckets.iter_mut().flatten().map(|(k, v)| (&*k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&K, &mut V) -> bool) {
+        for bucket in &mut self.buckets {
+            bucket.retain_mut(|(k, v)| keep(k, v));
+        }
+        self.len = self.buckets.iter().map(Vec::len).sum();
+        if self.len == 0 {
+            self.buckets = Vec::new();
+        }
+    }
+
+    // A random entry: a random non-empty bucket, then a random entry in its
+    // chain. Not perfectly uniform, but O(1) on average, which is what Redis does.
+    pub fn random_entry(&self) -> Option<(&K, &V)> {
+        if self.len == 0 {
+            return None;
+        }
+        let mut rng = rand::thread_rng();
+        loop {
+            let bucket = &self.buckets[rng.gen_range(0..self.buckets.len())];
+            if !bucket.is_empty() {
+                let (k, v) = &bucket[rng.gen_range(0..bucket.len())];
+                return Some((k, v));
+            }
+        }
+    }
+
+    // Visits every entry of the bucket under `cursor` and returns the next
+    // cursor, 0 once the whole table has been covered.
+    //
+    // The cursor is advanced by incrementing its bit-reversed value, so buckets
+    // are visited high bits first. When the table doubles, every bucket already
+    // visited maps to buckets that are also behind the cursor, and when it
+    // halves they fold into buckets the cursor has passed or is about to
+    // revisit. Either way an entry present for the whole scan is returned at
+    // least once; after a shrink some may be returned twice.
+    pub fn scan(&self, cursor: u64, mut visit: impl FnMut(&K, &V)) -> u64 {
+        if self.buckets.is_empty() {
+            return 0;
+        }
+        let mask = (self.buckets.len() - 1) as u64;
+        for (k, v) in &self.buckets[(cursor & mask) as usize] {
+            visit(k, v);
+        }
+
+        // Set the unmasked bits so the increment carries into the masked ones
+        let cursor = cursor | !mask;
+        cursor.reverse_bits().wrapping_add(1).reverse_bits()
+    }
+
+    // Scans from `cursor` until at least `count` entries were visited or the
+    // scan is complete. Gives up after 10 * count buckets so a sparse table
+    // cannot make one call arbitrarily long.
+    pub fn scan_batch(&self, mut cursor: u64, count: usize, mut visit: impl FnMut(&K, &V)) -> u64 {
+        let mut visited = 0;
+        let mut budget = count.saturating_mul(10);
+        loop {
+            cursor = self.scan(cursor, |k, v| {
+                visited += 1;
+                visit(k, v);
+            });
+            budget = budget.saturating_sub(1);
+            if cursor == 0 || visited >= count || budget == 0 {
+                return cursor;
+            }
+        }
+    }
+}
+
+impl<K: Hash + Eq, V> Default for Dict<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq + Debug, V: Debug> Debug for Dict<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, V)> for Dict<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut dict = Dict::new();
+        for (key, value) in iter {
+            dict.insert(key, value);
+        }
+        dict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn full_scan(dict: &Dict<u32, ()>) -> Vec<u32> {
+        let mut seen = Vec::new();
+        let mut cursor = 0;
+        loop {
+            cursor = dict.scan(cursor, |k, _| seen.push(*k));
+            if cursor == 0 {
+                return seen;
+            }
+        }
+    }
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut dict: Dict<Vec<u8>, u32> = Dict::new();
+        assert_eq!(dict.insert(b"a".to_vec(), 1), None);
+        assert_eq!(dict.insert(b"a".to_vec(), 2), Some(1));
+        assert_eq!(dict.get(b"a".as_slice()), Some(&2));
+        assert_eq!(dict.len(), 1);
+        assert_eq!(dict.remove(b"a".as_slice()), Some(2));
+        assert_eq!(dict.remove(b"a".as_slice()), None);
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn test_grows_and_shrinks() {
+        let mut dict = Dict::new();
+        for i in 0..1000u32 {
+            dict.insert(i, ());
+        }
+        assert_eq!(dict.len(), 1000);
+        assert_eq!(dict.bucket_count(), 1024);
+        for i in 0..990u32 {
+            dict.remove(&i);
+        }
+        assert!(dict.bucket_count() <= 64);
+        assert!((990..1000).all(|i| dict.contains_key(&i)));
+    }
+
+    #[test]
+    fn test_scan_visits_everything_once_without_resizes() {
+        let dict: Dict<u32, ()> = (0..500).map(|i| (i, ())).collect();
+        let mut seen = full_scan(&dict);
+        seen.sort();
+        assert_eq!(seen, (0..500).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_scan_survives_growing_and_shrinking() {
+        let mut dict: Dict<u32, ()> = (0..100).map(|i| (i, ())).collect();
+        let mut seen = HashSet::new();
+        let mut cursor = 0;
+        let mut step = 0;
+        loop {
+            cursor = dict.scan(cursor, |k, _| {
+                seen.insert(*k);
+            });
+            // Grow the table a lot, then shrink it back, between calls
+            if step == 3 {
+                for i in 1000..5000 {
+                    dict.insert(i, ());
+                }
+            }
+            if step == 10 {
+                for i in 1000..5000 {
+                    dict.remove(&i);
+                }
+            }
+            step += 1;
+            if cursor == 0 {
+                break;
+            }
+        }
+        assert!((0..100).all(|i| seen.contains(&i)));
+    }
+
+    #[test]
+    fn test_scan_batch_respects_count() {
+        let dict: Dict<u32, ()> = (0..1000).map(|i| (i, ())).collect();
+        let mut seen = 0;
+        let cursor = dict.scan_batch(0, 10, |_, _| seen += 1);
+        assert_ne!(cursor, 0);
+        assert!((10..100).contains(&seen));
+        assert_eq!(Dict::<u32, ()>::new().scan_batch(0, 10, |_, _| {}), 0);
+    }
+
+    #[test]
+    fn test_random_entry() {
+        let mut dict = Dict::new();
+        assert!(dict.random_entry().is_none());
+        dict.insert(7, "seven");
+        assert_eq!(dict.random_entry(), Some((&7, &"seven")));
+    }
+}
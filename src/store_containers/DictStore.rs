@@ -1,17 +1,293 @@
-use crate::traits::Store::Store;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use crate::config;
+use crate::store::value::Value;
+use crate::store_containers::Dict::Dict;
+use rand::Rng;
 use std::fmt::Debug;
-use std::rc::Weak;
+use std::sync::Arc;
 
+// LFU counter of a new key, so it is not the first to go (Redis' LFU_INIT_VAL).
+const LFU_INIT_VAL: u8 = 5;
+
+// Elements sampled when estimating the size of a changed collection, also the
+// default of MEMORY USAGE.
+pub const MEMORY_SAMPLES: usize = 5;
+
+// A key, its value and its deadline, as taken by `DictStore::snapshot`.
+pub type SharedEntry = (Vec<u8>, Arc<Value>, Option<u64>);
+
+// A key's value together with the metadata Redis keeps next to it.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    // Shared with the snapshots being written in the background, and copied
+    // on the first write while one still holds it (see `value_mut`).
+    pub value: Arc<Value>,
+    // Absolute deadline in ms since the epoch, None for persistent keys.
+    pub expires_at: Option<u64>,
+    // Last time the key was accessed, in ms since the epoch.
+    pub lru: u64,
+    // Logarithmic access counter for the LFU eviction policies.
+    pub lfu: u8,
+    // Bytes this entry is counted for in `DictStore::used_memory`.
+    pub size: usize,
+}
+
+impl Entry {
+    pub fn new(value: Value, now: u64) -> Self {
+        Entry {
+            value: Arc::new(value),
+            expires_at: None,
+            lru: now,
+            lfu: LFU_INIT_VAL,
+            size: 0,
+        }
+    }
+
+    // The value for writing. Copies it first if a background save or rewrite
+    // still reads the current one, so that keeps its point-in-time view.
+    pub fn value_mut(&mut self) -> &mut Value {
+        Arc::make_mut(&mut self.value)
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        self.value.type_name()
+    }
+
+    pub fn encoding(&self) -> &'static str {
+        self.value.encoding()
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|deadline| deadline <= now)
+    }
+
+    // The LFU counter after decay: one less for every lfu-decay-time minutes
+    // since the last access.
+    pub fn lfu_count(&self, now: u64) -> u8 {
+        let decay_minutes = config::with(|config| config.lfu_decay_time);
+        if decay_minutes == 0 {
+            return self.lfu;
+        }
+        let periods = now.saturating_sub(self.lru) / (decay_minutes * 60_000);
+        self.lfu.saturating_sub(periods.min(u8::MAX as u64) as u8)
+    }
+
+    // Records an access. The LFU counter is decayed, then incremented with a
+    // probability that shrinks as it grows, so 255 takes about a million hits
+    // at the default lfu-log-factor of 10.
+    pub fn touch(&mut self, now: u64) {
+        let log_factor = config::with(|config| config.lfu_log_factor);
+        let mut counter = self.lfu_count(now);
+        if counter < u8::MAX {
+            let base = counter.saturating_sub(LFU_INIT_VAL) as f64;
+            if rand::thread_rng().gen::<f64>() < 1.0 / (base * log_factor as f64 + 1.0) {
+                counter += 1;
+            }
+        }
+        self.lfu = counter;
+        self.lru = now;
+    }
+}
+
+// The keyspace. It owns every value directly and keeps a running estimate of
+// the memory they use.
 #[derive(Debug)]
 pub struct DictStore {
-    pub store: HashMap<String, Option<Weak<RefCell<dyn Store>>>>,
+    pub store: Dict<Vec<u8>, Entry>,
+    // Sum of every entry's `size`, what maxmemory is checked against.
+    pub used_memory: usize,
+    // Keys written by the current command, not yet counted.
+    changed: Vec<Vec<u8>>,
+    // Keys handed out for writing, whose size must be measured again even
+    // if the command left them as they were.
+    resized: Vec<Vec<u8>>,
+    // Changes since the last save, what the `save` rules count: one per key
+    // a command wrote.
+    pub dirty: u64,
 }
 impl DictStore {
     pub fn new() -> Self {
         DictStore {
-            store: HashMap::new(),
+            store: Dict::new(),
+            used_memory: 0,
+            changed: Vec::new(),
+            resized: Vec::new(),
+            dirty: 0,
+        }
+    }
+
+    pub fn insert(&mut self, key: Vec<u8>, entry: Entry) {
+        self.changed.push(key.clone());
+        self.insert_uncounted(key, entry);
+    }
+
+    // Inserts a key without counting it as a change, for an empty collection
+    // created only to be written: the write is what counts, if it happens.
+    pub fn insert_uncounted(&mut self, key: Vec<u8>, mut entry: Entry) {
+        entry.size = entry_size(&key, &entry.value, MEMORY_SAMPLES);
+        self.used_memory += entry.size;
+        if let Some(old) = self.store.insert(key, entry) {
+            self.used_memory -= old.size;
+        }
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Option<Entry> {
+        let entry = self.remove_uncounted(key)?;
+        self.changed.push(key.to_vec());
+        Some(entry)
+    }
+
+    // Removes a key without counting it as a change, for keys the server
+    // drops on its own: expired or evicted.
+    pub fn remove_uncounted(&mut self, key: &[u8]) -> Option<Entry> {
+        let entry = self.store.remove(key)?;
+        self.used_memory -= entry.size;
+        Some(entry)
+    }
+
+    pub fn clear(&mut self) {
+        self.dirty += self.store.len() as u64;
+        self.store.clear();
+        self.used_memory = 0;
+        self.changed.clear();
+        self.resized.clear();
+    }
+
+    // The live keys as of `now`, sharing their values instead of copying them:
+    // the point in time BGSAVE and BGREWRITEAOF hand to their thread. Only the
+    // values written before the thread is done get copied, by `value_mut`.
+    pub fn snapshot(&self, now: u64) -> Vec<SharedEntry> {
+        self.store
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, entry)| (key.clone(), Arc::clone(&entry.value), entry.expires_at))
+            .collect()
+    }
+
+    // Notes that the value at `key` was modified in place. Only called once
+    // the command actually changed it, as this is what dirty, the AOF and
+    // WATCH go by.
+    pub fn mark_changed(&mut self, key: &[u8]) {
+        self.changed.push(key.to_vec());
+    }
+
+    // Notes that the value at `key` may be about to change size.
+    pub fn mark_resized(&mut self, key: &[u8]) {
+        self.resized.push(key.to_vec());
+    }
+
+    // Counts every key written since the last call as one change and
+    // recomputes the size of those still there, or handed out for writing.
+    // Run after each command, once its changes are done. Returns the keys
+    // written.
+    pub fn account_memory(&mut self) -> Vec<Vec<u8>> {
+        let mut changed = std::mem::take(&mut self.changed);
+        changed.sort_unstable();
+        changed.dedup();
+        self.dirty += changed.len() as u64;
+        let mut measured = std::mem::take(&mut self.resized);
+        measured.extend(changed.iter().cloned());
+        measured.sort_unstable();
+        measured.dedup();
+        for key in &measured {
+            if let Some(entry) = self.store.get_mut(key) {
+                let size = entry_size(key, &entry.value, MEMORY_SAMPLES);
+                self.used_memory = self.used_memory - entry.size + size;
+                entry.size = size;
+            }
+        }
+        changed
+    }
+}
+
+impl Default for DictStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// The value's estimate, plus the key and the slot holding the pair. `samples`
+// elements of a collection are measured, 0 for all of them.
+pub fn entry_size(key: &[u8], value: &Value, samples: usize) -> usize {
+    key.len() + std::mem::size_of::<(Vec<u8>, Entry)>() + value.memory_usage(samples)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::string_store::StringStore;
+
+    fn string(value: &[u8]) -> Entry {
+        Entry::new(Value::String(StringStore::new(value.to_vec())), 0)
+    }
+
+    #[test]
+    fn test_used_memory_follows_the_entries() {
+        let mut dict_store = DictStore::new();
+        dict_store.insert(b"a".to_vec(), string(&[b'x'; 100]));
+        let one_key = dict_store.used_memory;
+        assert!(one_key > 100);
+
+        // Replacing a value does not count the old one twice
+        dict_store.insert(b"a".to_vec(), string(&[b'x'; 100]));
+        assert_eq!(dict_store.used_memory, one_key);
+
+        dict_store.insert(b"b".to_vec(), string(b"small"));
+        if let Some(entry) = dict_store.store.get_mut(b"b".as_slice()) {
+            *entry.value_mut() = Value::String(StringStore::new(vec![b'y'; 1000]));
+        }
+        dict_store.mark_changed(b"b");
+        dict_store.account_memory();
+        assert!(dict_store.used_memory > one_key + 1000);
+        // "a" written twice and "b" twice, two keys changed
+        assert_eq!(dict_store.dirty, 2);
+
+        // Handed out for writing but left alone: measured, not counted
+        if let Some(entry) = dict_store.store.get_mut(b"a".as_slice()) {
+            *entry.value_mut() = Value::String(StringStore::new(b"tiny".to_vec()));
+        }
+        dict_store.mark_resized(b"a");
+        assert!(dict_store.account_memory().is_empty());
+        assert_eq!(dict_store.dirty, 2);
+        let tiny_a = dict_store.used_memory - dict_store.store.get(b"b".as_slice()).unwrap().size;
+        assert!(tiny_a < one_key);
+
+        dict_store.remove(b"b");
+        assert_eq!(dict_store.used_memory, tiny_a);
+        dict_store.account_memory();
+        assert_eq!(dict_store.dirty, 3);
+        dict_store.clear();
+        assert_eq!(dict_store.used_memory, 0);
+    }
+
+    #[test]
+    fn test_snapshot_shares_values_until_written() {
+        let mut dict_store = DictStore::new();
+        dict_store.insert(b"a".to_vec(), string(b"before"));
+        dict_store.insert(b"gone".to_vec(), Entry { expires_at: Some(1), ..string(b"v") });
+        let snapshot = dict_store.snapshot(10);
+        assert_eq!(snapshot.len(), 1);
+        let entry = dict_store.store.get_mut(b"a".as_slice()).unwrap();
+        assert!(Arc::ptr_eq(&snapshot[0].1, &entry.value));
+
+        *entry.value_mut() = Value::String(StringStore::new(b"after".to_vec()));
+        let Value::String(saved) = snapshot[0].1.as_ref() else { panic!() };
+        assert_eq!(saved.get_value(), b"before");
+        // Once the snapshot is gone, writes no longer copy
+        drop(snapshot);
+        let before = Arc::as_ptr(&entry.value);
+        entry.value_mut();
+        assert_eq!(Arc::as_ptr(&entry.value), before);
+    }
+
+    #[test]
+    fn test_lfu_counter_decays() {
+        let mut entry = string(b"v");
+        for _ in 0..100 {
+            entry.touch(0);
         }
+        let counter = entry.lfu_count(0);
+        assert!(counter > LFU_INIT_VAL);
+        // Two minutes idle at the default lfu-decay-time of 1
+        assert_eq!(entry.lfu_count(120_000), counter - 2);
     }
 }
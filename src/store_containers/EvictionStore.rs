@@ -0,0 +1,155 @@
+// Redis keeps this many of the best candidates between eviction cycles, so
+// each cycle's few samples add to what earlier cycles found.
+const EVICTION_POOL_SIZE: usize = 16;
+
+// What to delete once used memory passes maxmemory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvictionPolicy {
+    NoEviction,
+    AllKeysLru,
+    AllKeysLfu,
+    AllKeysRandom,
+    VolatileLru,
+    VolatileLfu,
+    VolatileRandom,
+    VolatileTtl,
+}
+
+impl EvictionPolicy {
+    pub const ALL: [EvictionPolicy; 8] = [
+        EvictionPolicy::VolatileLru,
+        EvictionPolicy::VolatileLfu,
+        EvictionPolicy::VolatileRandom,
+        EvictionPolicy::VolatileTtl,
+        EvictionPolicy::AllKeysLru,
+        EvictionPolicy::AllKeysLfu,
+        EvictionPolicy::AllKeysRandom,
+        EvictionPolicy::NoEviction,
+    ];
+
+    // Name used by CONFIG GET/SET maxmemory-policy
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvictionPolicy::NoEviction => "noeviction",
+            EvictionPolicy::AllKeysLru => "allkeys-lru",
+            EvictionPolicy::AllKeysLfu => "allkeys-lfu",
+            EvictionPolicy::AllKeysRandom => "allkeys-random",
+            EvictionPolicy::VolatileLru => "volatile-lru",
+            EvictionPolicy::VolatileLfu => "volatile-lfu",
+            EvictionPolicy::VolatileRandom => "volatile-random",
+            EvictionPolicy::VolatileTtl => "volatile-ttl",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<EvictionPolicy> {
+        EvictionPolicy::ALL.into_iter().find(|policy| policy.name() == name)
+    }
+
+    // volatile-* policies only evict keys that have a TTL.
+    pub fn is_volatile(&self) -> bool {
+        matches!(
+            self,
+            EvictionPolicy::VolatileLru
+                | EvictionPolicy::VolatileLfu
+                | EvictionPolicy::VolatileRandom
+                | EvictionPolicy::VolatileTtl
+        )
+    }
+
+    pub fn is_lfu(&self) -> bool {
+        matches!(self, EvictionPolicy::AllKeysLfu | EvictionPolicy::VolatileLfu)
+    }
+}
+
+// State of approximate eviction: the pool of candidates sampled so far, best
+// last, and how many keys were evicted. Choosing and deleting the victims is
+// up to the context, see `context::perform_evictions`.
+#[derive(Debug)]
+pub struct EvictionStore {
+    pool: Vec<(u64, Vec<u8>)>,
+    // The policy the pool was filled under; scores only compare within one.
+    pool_policy: EvictionPolicy,
+    pub evicted_keys: u64,
+}
+
+impl EvictionStore {
+    pub fn new() -> Self {
+        EvictionStore {
+            pool: Vec::with_capacity(EVICTION_POOL_SIZE),
+            pool_policy: EvictionPolicy::NoEviction,
+            evicted_keys: 0,
+        }
+    }
+
+    // Offers a sampled key; the higher its score, the better a victim it is.
+    // A full pool only takes keys that beat its worst candidate.
+    pub fn offer(&mut self, score: u64, key: Vec<u8>) {
+        if let Some(existing) = self.pool.iter().position(|(_, candidate)| *candidate == key) {
+            self.pool.remove(existing);
+        }
+        let position = self.pool.partition_point(|(candidate_score, _)| *candidate_score < score);
+        if self.pool.len() == EVICTION_POOL_SIZE {
+            if position == 0 {
+                return;
+            }
+            self.pool.remove(0);
+            self.pool.insert(position - 1, (score, key));
+        } else {
+            self.pool.insert(position, (score, key));
+        }
+    }
+
+    // The best candidate, removed from the pool.
+    pub fn take_best(&mut self) -> Option<Vec<u8>> {
+        self.pool.pop().map(|(_, key)| key)
+    }
+
+    // Empties the pool if it was filled under another policy.
+    pub fn use_policy(&mut self, policy: EvictionPolicy) {
+        if self.pool_policy != policy {
+            self.pool.clear();
+            self.pool_policy = policy;
+        }
+    }
+}
+
+impl Default for EvictionStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_keeps_the_best_candidates() {
+        let mut store = EvictionStore::new();
+        for score in 0..20u64 {
+            store.offer(score, score.to_string().into_bytes());
+        }
+        // A key offered again is moved, not duplicated
+        store.offer(100, b"4".to_vec());
+        // Worse than everything in a full pool
+        store.offer(1, b"too low".to_vec());
+
+        assert_eq!(store.take_best(), Some(b"4".to_vec()));
+        assert_eq!(store.take_best(), Some(b"19".to_vec()));
+        let mut remaining = 0;
+        while store.take_best().is_some() {
+            remaining += 1;
+        }
+        assert_eq!(remaining, EVICTION_POOL_SIZE - 2);
+    }
+
+    #[test]
+    fn test_policy_names() {
+        for policy in EvictionPolicy::ALL {
+            assert_eq!(EvictionPolicy::parse(policy.name()), Some(policy));
+        }
+        assert_eq!(EvictionPolicy::parse("lru"), None);
+        assert!(EvictionPolicy::VolatileTtl.is_volatile());
+        assert!(!EvictionPolicy::AllKeysLfu.is_volatile());
+    }
+}
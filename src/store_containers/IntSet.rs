@@ -0,0 +1,150 @@
+// Sorted array of integers stored at the narrowest width that fits them all (2,
+// 4 or 8 bytes), like Redis' intset. Adding a wider integer upgrades every
+// element; the set never narrows again.
+#[derive(Debug, Clone)]
+pub struct IntSet {
+    data: Vec<u8>,
+    width: usize,
+}
+
+impl IntSet {
+    pub fn new() -> Self {
+        IntSet {
+            data: Vec::new(),
+            width: 2,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len() / self.width
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    // Size of the packed array in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<i64> {
+        if index >= self.len() {
+            return None;
+        }
+        let bytes = &self.data[index * self.width..(index + 1) * self.width];
+        Some(match self.width {
+            2 => i16::from_le_bytes([bytes[0], bytes[1]]) as i64,
+            4 => i32::from_le_bytes(bytes.try_into().unwrap()) as i64,
+            _ => i64::from_le_bytes(bytes.try_into().unwrap()),
+        })
+    }
+
+    // Position of `value`, or where it would be inserted.
+    fn search(&self, value: i64) -> Result<usize, usize> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let middle = (low + high) / 2;
+            match self.get(middle).unwrap().cmp(&value) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => return Ok(middle),
+            }
+        }
+        Err(low)
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.search(value).is_ok()
+    }
+
+    pub fn insert(&mut self, value: i64) -> bool {
+        let width = width_of(value);
+        if width > self.width {
+            self.upgrade(width);
+        }
+        match self.search(value) {
+            Ok(_) => false,
+            Err(index) => {
+                let at = index * self.width;
+                self.data.splice(at..at, encode(value, self.width));
+                true
+            }
+        }
+    }
+
+    pub fn remove(&mut self, value: i64) -> bool {
+        match self.search(value) {
+            Ok(index) => {
+                self.data.drain(index * self.width..(index + 1) * self.width);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn upgrade(&mut self, width: usize) {
+        let values: Vec<i64> = self.iter().collect();
+        self.width = width;
+        self.data = values.into_iter().flat_map(|value| encode(value, width)).collect();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        (0..self.len()).map(|index| self.get(index).unwrap())
+    }
+}
+
+impl Default for IntSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn width_of(value: i64) -> usize {
+    if i16::try_from(value).is_ok() {
+        2
+    } else if i32::try_from(value).is_ok() {
+        4
+    } else {
+        8
+    }
+}
+
+fn encode(value: i64, width: usize) -> Vec<u8> {
+    value.to_le_bytes()[..width].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sorted_and_unique() {
+        let mut intset = IntSet::new();
+        assert!(intset.insert(5));
+        assert!(intset.insert(-3));
+        assert!(intset.insert(12));
+        assert!(!intset.insert(5));
+        assert_eq!(intset.iter().collect::<Vec<_>>(), vec![-3, 5, 12]);
+        assert!(intset.contains(12));
+        assert!(!intset.contains(4));
+
+        assert!(intset.remove(5));
+        assert!(!intset.remove(5));
+        assert_eq!(intset.iter().collect::<Vec<_>>(), vec![-3, 12]);
+    }
+
+    #[test]
+    fn test_upgrades_width() {
+        let mut intset = IntSet::new();
+        intset.insert(1);
+        intset.insert(-2);
+        assert_eq!(intset.byte_len(), 4);
+
+        intset.insert(100_000);
+        assert_eq!(intset.byte_len(), 12);
+        intset.insert(i64::MIN);
+        assert_eq!(intset.byte_len(), 32);
+        assert_eq!(intset.iter().collect::<Vec<_>>(), vec![i64::MIN, -2, 1, 100_000]);
+    }
+}
@@ -0,0 +1,180 @@
+// Small collections packed into one buffer, like Redis' listpack. Each entry is
+// its length as a LEB128 varint followed by its bytes, so a three-field hash
+// costs one allocation instead of six. Every operation walks the buffer, which
+// is why collections are converted to hash tables once they grow.
+#[derive(Debug, Clone, Default)]
+pub struct Listpack {
+    data: Vec<u8>,
+    len: usize,
+}
+
+impl Listpack {
+    pub fn new() -> Self {
+        Listpack {
+            data: Vec::new(),
+            len: 0,
+        }
+    }
+
+    // Number of entries.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // Size of the packed buffer in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn push(&mut self, value: &[u8]) {
+        self.data.extend(encode(value));
+        self.len += 1;
+    }
+
+    // The entry starting at byte `offset` and the offset of the next one.
+    fn entry_at(&self, offset: usize) -> (&[u8], usize) {
+        let mut length = 0;
+        let mut shift = 0;
+        let mut start = offset;
+        loop {
+            let byte = self.data[start];
+            start += 1;
+            length |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        (&self.data[start..start + length], start + length)
+    }
+
+    // Byte offset of entry `index`, or of the end of the buffer.
+    fn offset_of(&self, index: usize) -> usize {
+        let mut offset = 0;
+        for _ in 0..index {
+            offset = self.entry_at(offset).1;
+        }
+        offset
+    }
+
+    pub fn get(&self, index: usize) -> Option<&[u8]> {
+        if index >= self.len {
+            return None;
+        }
+        Some(self.entry_at(self.offset_of(index)).0)
+    }
+
+    // Index of the first entry equal to `value`, looking only at every
+    // `stride`-th entry: 1 for sets, 2 for the fields of a hash.
+    pub fn find(&self, value: &[u8], stride: usize) -> Option<usize> {
+        self.iter()
+            .enumerate()
+            .step_by(stride)
+            .find(|(_, entry)| *entry == value)
+            .map(|(index, _)| index)
+    }
+
+    pub fn replace(&mut self, index: usize, value: &[u8]) {
+        let start = self.offset_of(index);
+        let end = self.entry_at(start).1;
+        self.data.splice(start..end, encode(value));
+    }
+
+    // Removes `count` entries starting at `index`.
+    pub fn remove(&mut self, index: usize, count: usize) {
+        let start = self.offset_of(index);
+        let mut end = start;
+        for _ in 0..count {
+            end = self.entry_at(end).1;
+        }
+        self.data.drain(start..end);
+        self.len -= count;
+    }
+
+    pub fn iter(&self) -> ListpackIter<'_> {
+        ListpackIter {
+            listpack: self,
+            offset: 0,
+        }
+    }
+}
+
+// A value prefixed with its length.
+fn encode(value: &[u8]) -> Vec<u8> {
+    let mut entry = Vec::with_capacity(value.len() + 2);
+    let mut length = value.len();
+    loop {
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            entry.push(byte);
+            break;
+        }
+        entry.push(byte | 0x80);
+    }
+    entry.extend_from_slice(value);
+    entry
+}
+
+pub struct ListpackIter<'a> {
+    listpack: &'a Listpack,
+    offset: usize,
+}
+
+impl<'a> Iterator for ListpackIter<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        if self.offset >= self.listpack.data.len() {
+            return None;
+        }
+        let (entry, next) = self.listpack.entry_at(self.offset);
+        self.offset = next;
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_get_and_iter() {
+        let mut listpack = Listpack::new();
+        let long = vec![b'x'; 300];
+        listpack.push(b"a");
+        listpack.push(b"");
+        listpack.push(&long);
+        listpack.push(b"b");
+
+        assert_eq!(listpack.len(), 4);
+        assert_eq!(listpack.get(2), Some(long.as_slice()));
+        assert_eq!(listpack.get(4), None);
+        assert_eq!(listpack.iter().collect::<Vec<_>>(), vec![&b"a"[..], b"", &long, b"b"]);
+        // One length byte for short entries, two for the 300-byte one
+        assert_eq!(listpack.byte_len(), 1 + 1 + 1 + 2 + 300 + 1 + 1);
+    }
+
+    #[test]
+    fn test_find_replace_and_remove() {
+        let mut listpack = Listpack::new();
+        for entry in [&b"f1"[..], b"v1", b"v1", b"x"] {
+            listpack.push(entry);
+        }
+        assert_eq!(listpack.find(b"v1", 1), Some(1));
+        assert_eq!(listpack.find(b"v1", 2), Some(2));
+        assert_eq!(listpack.find(b"x", 2), None);
+
+        listpack.replace(1, b"a much longer value");
+        assert_eq!(listpack.get(1), Some(&b"a much longer value"[..]));
+        assert_eq!(listpack.get(3), Some(&b"x"[..]));
+
+        listpack.remove(0, 2);
+        assert_eq!(listpack.iter().collect::<Vec<_>>(), vec![&b"v1"[..], b"x"]);
+        assert_eq!(listpack.len(), 2);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::store_containers::TTLStore::now_ms;
+use std::io;
+use std::thread::JoinHandle;
+
+// A BGSAVE running on its own thread, writing a copy of the keyspace taken
+// when it started.
+#[derive(Debug)]
+struct BackgroundSave {
+    handle: JoinHandle<io::Result<()>>,
+    // Changes made before the copy was taken, which the snapshot covers
+    dirty_at_start: u64,
+}
+
+// Snapshot bookkeeping: when the dataset was last saved and the save in
+// progress, if any. Writing the files is up to `persistence`.
+#[derive(Debug)]
+pub struct PersistenceStore {
+    // Unix time in seconds of the last successful save, or of startup
+    pub last_save: u64,
+    pub last_bgsave_ok: bool,
+    // Unix time in seconds the last BGSAVE started, to space out retries
+    pub last_bgsave_try: u64,
+    background_save: Option<BackgroundSave>,
+}
+
+impl PersistenceStore {
+    pub fn new() -> Self {
+        PersistenceStore {
+            last_save: now_ms() / 1000,
+            last_bgsave_ok: true,
+            last_bgsave_try: 0,
+            background_save: None,
+        }
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.background_save.is_some()
+    }
+
+    pub fn start_background_save(&mut self, handle: JoinHandle<io::Result<()>>, dirty_at_start: u64) {
+        self.last_bgsave_try = now_ms() / 1000;
+        self.background_save = Some(BackgroundSave { handle, dirty_at_start });
+    }
+
+    // The outcome of the background save once it is done, with the number of
+    // changes it saved.
+    pub fn finished_background_save(&mut self) -> Option<(io::Result<()>, u64)> {
+        if !self.background_save.as_ref()?.handle.is_finished() {
+            return None;
+        }
+        let BackgroundSave { handle, dirty_at_start } = self.background_save.take()?;
+        let result = handle
+            .join()
+            .unwrap_or_else(|_| Err(io::Error::other("background save panicked")));
+        Some((result, dirty_at_start))
+    }
+}
+
+impl Default for PersistenceStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,180 @@
+use crate::resp::reply::{array, bulk_string};
+use crate::utils::glob::glob_match;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+
+// The three kinds of subscription, each with its own namespace: a channel
+// named "news" and a pattern "news" are unrelated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Subscription {
+    Channel,
+    Pattern,
+    ShardChannel,
+}
+
+// Who listens where. Each connection registers a sender when it starts, and
+// PUBLISH pushes the encoded message into the sender of every subscriber; the
+// connection writes it out as soon as it is idle (see `server::pubsub`).
+#[derive(Debug)]
+pub struct PubSubStore {
+    next_id: u64,
+    clients: HashMap<u64, mpsc::UnboundedSender<Vec<u8>>>,
+    // Subscribers in the order they subscribed
+    channels: HashMap<Vec<u8>, Vec<u64>>,
+    patterns: HashMap<Vec<u8>, Vec<u64>>,
+    shard_channels: HashMap<Vec<u8>, Vec<u64>>,
+}
+
+impl PubSubStore {
+    pub fn new() -> Self {
+        PubSubStore {
+            next_id: 0,
+            clients: HashMap::new(),
+            channels: HashMap::new(),
+            patterns: HashMap::new(),
+            shard_channels: HashMap::new(),
+        }
+    }
+
+    // Registers a connection. The receiver gets the messages published to it.
+    pub fn new_client(&mut self) -> (u64, mpsc::UnboundedReceiver<Vec<u8>>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let id = self.next_id;
+        self.next_id += 1;
+        self.clients.insert(id, sender);
+        (id, receiver)
+    }
+
+    // Forgets a connection. Its subscriptions must be dropped first.
+    pub fn remove_client(&mut self, id: u64) {
+        self.clients.remove(&id);
+    }
+
+    fn map(&self, kind: Subscription) -> &HashMap<Vec<u8>, Vec<u64>> {
+        match kind {
+            Subscription::Channel => &self.channels,
+            Subscription::Pattern => &self.patterns,
+            Subscription::ShardChannel => &self.shard_channels,
+        }
+    }
+
+    fn map_mut(&mut self, kind: Subscription) -> &mut HashMap<Vec<u8>, Vec<u64>> {
+        match kind {
+            Subscription::Channel => &mut self.channels,
+            Subscription::Pattern => &mut self.patterns,
+            Subscription::ShardChannel => &mut self.shard_channels,
+        }
+    }
+
+    pub fn subscribe(&mut self, kind: Subscription, id: u64, name: &[u8]) {
+        let subscribers = self.map_mut(kind).entry(name.to_vec()).or_default();
+        if !subscribers.contains(&id) {
+            subscribers.push(id);
+        }
+    }
+
+    pub fn unsubscribe(&mut self, kind: Subscription, id: u64, name: &[u8]) {
+        let map = self.map_mut(kind);
+        if let Some(subscribers) = map.get_mut(name) {
+            subscribers.retain(|&subscriber| subscriber != id);
+            if subscribers.is_empty() {
+                map.remove(name);
+            }
+        }
+    }
+
+    // Sends a message to the subscribers of `channel` and of every pattern
+    // matching it. Returns how many were sent: a client subscribed both ways
+    // gets it, and is counted, twice.
+    pub fn publish(&self, channel: &[u8], message: &[u8]) -> usize {
+        let mut receivers = 0;
+        if let Some(subscribers) = self.channels.get(channel) {
+            let frame = array(vec![bulk_string(b"message"), bulk_string(channel), bulk_string(message)]);
+            receivers += self.send(subscribers, &frame);
+        }
+        for (pattern, subscribers) in &self.patterns {
+            if glob_match(pattern, channel) {
+                let frame = array(vec![
+                    bulk_string(b"pmessage"),
+                    bulk_string(pattern),
+                    bulk_string(channel),
+                    bulk_string(message),
+                ]);
+                receivers += self.send(subscribers, &frame);
+            }
+        }
+        receivers
+    }
+
+    // SPUBLISH: only shard channel subscribers, patterns do not apply.
+    pub fn publish_shard(&self, channel: &[u8], message: &[u8]) -> usize {
+        match self.shard_channels.get(channel) {
+            Some(subscribers) => {
+                let frame = array(vec![bulk_string(b"smessage"), bulk_string(channel), bulk_string(message)]);
+                self.send(subscribers, &frame)
+            }
+            None => 0,
+        }
+    }
+
+    fn send(&self, subscribers: &[u64], frame: &[u8]) -> usize {
+        subscribers
+            .iter()
+            .filter_map(|id| self.clients.get(id))
+            .filter(|sender| sender.send(frame.to_vec()).is_ok())
+            .count()
+    }
+
+    // Channels or patterns with at least one subscriber.
+    pub fn names(&self, kind: Subscription) -> Vec<&Vec<u8>> {
+        self.map(kind).keys().collect()
+    }
+
+    pub fn subscriber_count(&self, kind: Subscription, name: &[u8]) -> usize {
+        self.map(kind).get(name).map_or(0, Vec::len)
+    }
+}
+
+impl Default for PubSubStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_reaches_channels_and_patterns() {
+        let mut store = PubSubStore::new();
+        let (first, mut first_messages) = store.new_client();
+        let (second, mut second_messages) = store.new_client();
+        store.subscribe(Subscription::Channel, first, b"news");
+        store.subscribe(Subscription::Channel, first, b"news");
+        store.subscribe(Subscription::Pattern, second, b"n*");
+        store.subscribe(Subscription::ShardChannel, second, b"news");
+
+        assert_eq!(store.publish(b"news", b"hi"), 2);
+        assert_eq!(
+            first_messages.try_recv().unwrap(),
+            b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$2\r\nhi\r\n"
+        );
+        assert!(first_messages.try_recv().is_err());
+        assert_eq!(
+            second_messages.try_recv().unwrap(),
+            b"*4\r\n$8\r\npmessage\r\n$2\r\nn*\r\n$4\r\nnews\r\n$2\r\nhi\r\n"
+        );
+        assert_eq!(store.publish_shard(b"news", b"hi"), 1);
+        assert_eq!(store.publish(b"other", b"hi"), 0);
+
+        store.unsubscribe(Subscription::Channel, first, b"news");
+        assert_eq!(store.subscriber_count(Subscription::Channel, b"news"), 0);
+        assert!(store.names(Subscription::Channel).is_empty());
+
+        // A connection that went away receives nothing
+        store.subscribe(Subscription::Channel, first, b"news");
+        store.remove_client(first);
+        assert_eq!(store.publish(b"news", b"hi"), 1);
+    }
+}
@@ -0,0 +1,421 @@
+use rand::Rng;
+use std::fmt::Debug;
+
+// Same parameters as Redis: 32 levels is plenty for 2^64 elements at p = 1/4.
+const MAX_LEVEL: usize = 32;
+const LEVEL_PROBABILITY: f64 = 0.25;
+
+// Index of the header node, and the "null pointer" of the arena.
+const HEAD: usize = 0;
+const NIL: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+struct Level {
+    forward: usize,
+    // Number of level-0 links this link jumps over, which is what makes rank
+    // lookups O(log n).
+    span: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    score: f64,
+    member: Vec<u8>,
+    backward: usize,
+    levels: Vec<Level>,
+}
+
+// Redis' zskiplist: elements ordered by (score, member), with spans on every
+// link so ranks can be computed on the way down. Nodes live in an arena and
+// link to each other by index; freed slots are reused. Scores must not be NaN.
+#[derive(Debug, Clone)]
+pub struct SkipList {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    tail: usize,
+    len: usize,
+    level: usize,
+}
+
+// Whether `node` sorts strictly before (score, member).
+fn precedes(node: &Node, score: f64, member: &[u8]) -> bool {
+    node.score < score || (node.score == score && node.member.as_slice() < member)
+}
+
+fn random_level() -> usize {
+    let mut rng = rand::thread_rng();
+    let mut level = 1;
+    while level < MAX_LEVEL && rng.gen::<f64>() < LEVEL_PROBABILITY {
+        level += 1;
+    }
+    level
+}
+
+impl SkipList {
+    pub fn new() -> Self {
+        let head = Node {
+            score: 0.0,
+            member: Vec::new(),
+            backward: NIL,
+            levels: vec![Level { forward: NIL, span: 0 }; MAX_LEVEL],
+        };
+        SkipList {
+            nodes: vec![head],
+            free: Vec::new(),
+            tail: NIL,
+            len: 0,
+            level: 1,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // Heap bytes of the nodes and their links, not counting the members. With
+    // p = 1/4 a node has 4/3 levels on average.
+    pub fn node_size(&self) -> usize {
+        self.nodes.capacity() * std::mem::size_of::<Node>()
+            + (MAX_LEVEL + self.len * 4 / 3) * std::mem::size_of::<Level>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn forward(&self, node: usize, level: usize) -> usize {
+        self.nodes[node].levels[level].forward
+    }
+
+    fn span(&self, node: usize, level: usize) -> usize {
+        self.nodes[node].levels[level].span
+    }
+
+    fn alloc(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    // Inserts an element. The caller guarantees the member is not already in
+    // the list (SortedSetStore tracks members in its dict).
+    pub fn insert(&mut self, score: f64, member: Vec<u8>) {
+        debug_assert!(!score.is_nan());
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut rank = [0usize; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            rank[i] = if i == self.level - 1 { 0 } else { rank[i + 1] };
+            loop {
+                let next = self.forward(x, i);
+                if next == NIL || !precedes(&self.nodes[next], score, &member) {
+                    break;
+                }
+                rank[i] += self.span(x, i);
+                x = next;
+            }
+            update[i] = x;
+        }
+
+        let level = random_level();
+        if level > self.level {
+            for i in self.level..level {
+                rank[i] = 0;
+                update[i] = HEAD;
+                self.nodes[HEAD].levels[i].span = self.len;
+            }
+            self.level = level;
+        }
+
+        let node = self.alloc(Node {
+            score,
+            member,
+            backward: NIL,
+            levels: vec![Level { forward: NIL, span: 0 }; level],
+        });
+        for i in 0..level {
+            let prev = update[i];
+            let skipped = rank[0] - rank[i];
+            self.nodes[node].levels[i] = Level {
+                forward: self.forward(prev, i),
+                span: self.span(prev, i) - skipped,
+            };
+            self.nodes[prev].levels[i] = Level {
+                forward: node,
+                span: skipped + 1,
+            };
+        }
+        // Links above the new node's height now jump over one more element
+        for (i, &prev) in update.iter().enumerate().take(self.level).skip(level) {
+            self.nodes[prev].levels[i].span += 1;
+        }
+
+        self.nodes[node].backward = if update[0] == HEAD { NIL } else { update[0] };
+        match self.forward(node, 0) {
+            NIL => self.tail = node,
+            next => self.nodes[next].backward = node,
+        }
+        self.len += 1;
+    }
+
+    // Removes the element, which must be looked up by its current score.
+    // Returns false if it is not in the list.
+    pub fn remove(&mut self, score: f64, member: &[u8]) -> bool {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut x = HEAD;
+        for i in (0..self.level).rev() {
+            loop {
+                let next = self.forward(x, i);
+                if next == NIL || !precedes(&self.nodes[next], score, member) {
+                    break;
+                }
+                x = next;
+            }
+            update[i] = x;
+        }
+
+        let target = self.forward(x, 0);
+        if target == NIL || self.nodes[target].score != score || self.nodes[target].member != member {
+            return false;
+        }
+        for (i, &prev) in update.iter().enumerate().take(self.level) {
+            if self.forward(prev, i) == target {
+                self.nodes[prev].levels[i] = Level {
+                    forward: self.forward(target, i),
+                    span: self.span(prev, i) + self.span(target, i) - 1,
+                };
+            } else {
+                self.nodes[prev].levels[i].span -= 1;
+            }
+        }
+        let backward = self.nodes[target].backward;
+        match self.forward(target, 0) {
+            NIL => self.tail = backward,
+            next => self.nodes[next].backward = backward,
+        }
+        while self.level > 1 && self.forward(HEAD, self.level - 1) == NIL {
+            self.level -= 1;
+        }
+        self.len -= 1;
+
+        // Release the member's memory now; the slot itself is reused later
+        self.nodes[target].member = Vec::new();
+        self.nodes[target].levels = Vec::new();
+        self.free.push(target);
+        true
+    }
+
+    // 0-based rank of an element, looked up by its current score.
+    pub fn rank(&self, score: f64, member: &[u8]) -> Option<usize> {
+        let mut x = HEAD;
+        let mut traversed = 0;
+        for i in (0..self.level).rev() {
+            loop {
+                let next = self.forward(x, i);
+                if next == NIL {
+                    break;
+                }
+                let node = &self.nodes[next];
+                let is_target = node.score == score && node.member == member;
+                if !is_target && !precedes(node, score, member) {
+                    break;
+                }
+                traversed += self.span(x, i);
+                x = next;
+            }
+            if x != HEAD && self.nodes[x].member == member {
+                return Some(traversed - 1);
+            }
+        }
+        None
+    }
+
+    // Arena index of the element at a 0-based rank.
+    fn node_at(&self, rank: usize) -> usize {
+        if rank >= self.len {
+            return NIL;
+        }
+        let target = rank + 1;
+        let mut x = HEAD;
+        let mut traversed = 0;
+        for i in (0..self.level).rev() {
+            while self.forward(x, i) != NIL && traversed + self.span(x, i) <= target {
+                traversed += self.span(x, i);
+                x = self.forward(x, i);
+            }
+            if traversed == target {
+                return x;
+            }
+        }
+        NIL
+    }
+
+    pub fn get_by_rank(&self, rank: usize) -> Option<(&[u8], f64)> {
+        match self.node_at(rank) {
+            NIL => None,
+            node => Some((&self.nodes[node].member, self.nodes[node].score)),
+        }
+    }
+
+    // How many elements satisfy `before`, which must hold for a prefix of the
+    // list and fail for the rest. That is the rank of the first element past a
+    // bound, so score and lex ranges both reduce to two of these calls.
+    pub fn count_before(&self, before: impl Fn(f64, &[u8]) -> bool) -> usize {
+        let mut x = HEAD;
+        let mut traversed = 0;
+        for i in (0..self.level).rev() {
+            loop {
+                let next = self.forward(x, i);
+                if next == NIL || !before(self.nodes[next].score, &self.nodes[next].member) {
+                    break;
+                }
+                traversed += self.span(x, i);
+                x = next;
+            }
+        }
+        traversed
+    }
+
+    // Elements from `rank` onwards, in order.
+    pub fn iter_from(&self, rank: usize) -> Iter<'_> {
+        Iter {
+            list: self,
+            node: self.node_at(rank),
+            reverse: false,
+        }
+    }
+
+    // Elements from `rank` backwards to the first one.
+    pub fn iter_rev_from(&self, rank: usize) -> Iter<'_> {
+        Iter {
+            list: self,
+            node: self.node_at(rank),
+            reverse: true,
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.iter_from(0)
+    }
+}
+
+impl Default for SkipList {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Iter<'a> {
+    list: &'a SkipList,
+    node: usize,
+    reverse: bool,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a [u8], f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.node == NIL {
+            return None;
+        }
+        let node = &self.list.nodes[self.node];
+        self.node = if self.reverse { node.backward } else { node.levels[0].forward };
+        Some((&node.member, node.score))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members(list: &SkipList) -> Vec<(String, f64)> {
+        list.iter()
+            .map(|(member, score)| (String::from_utf8(member.to_vec()).unwrap(), score))
+            .collect()
+    }
+
+    #[test]
+    fn test_orders_by_score_then_member() {
+        let mut list = SkipList::new();
+        list.insert(1.5, b"b".to_vec());
+        list.insert(-0.25, b"z".to_vec());
+        list.insert(1.5, b"a".to_vec());
+        list.insert(f64::INFINITY, b"top".to_vec());
+        list.insert(f64::NEG_INFINITY, b"bottom".to_vec());
+        list.insert(-0.5, b"y".to_vec());
+
+        let order: Vec<String> = members(&list).into_iter().map(|(member, _)| member).collect();
+        assert_eq!(order, ["bottom", "y", "z", "a", "b", "top"]);
+        assert_eq!(list.rank(1.5, b"b"), Some(4));
+        assert_eq!(list.rank(1.5, b"c"), None);
+        assert_eq!(list.get_by_rank(0), Some((b"bottom".as_slice(), f64::NEG_INFINITY)));
+        assert_eq!(list.get_by_rank(6), None);
+    }
+
+    #[test]
+    fn test_remove_and_reuse_slots() {
+        let mut list = SkipList::new();
+        for i in 0..10 {
+            list.insert(i as f64, vec![b'a' + i]);
+        }
+        assert!(list.remove(3.0, b"d"));
+        assert!(!list.remove(3.0, b"d"));
+        assert!(!list.remove(4.0, b"d"));
+        assert_eq!(list.len(), 9);
+        assert_eq!(list.rank(4.0, b"e"), Some(3));
+
+        list.insert(3.5, b"new".to_vec());
+        assert_eq!(list.rank(3.5, b"new"), Some(3));
+        assert_eq!(list.nodes.len(), 11);
+
+        let backwards: Vec<f64> = list.iter_rev_from(list.len() - 1).map(|(_, score)| score).collect();
+        assert_eq!(backwards, [9.0, 8.0, 7.0, 6.0, 5.0, 4.0, 3.5, 2.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_count_before() {
+        let mut list = SkipList::new();
+        for (score, member) in [(1.0, "a"), (2.0, "b"), (2.0, "c"), (3.0, "d")] {
+            list.insert(score, member.as_bytes().to_vec());
+        }
+        assert_eq!(list.count_before(|score, _| score < 2.0), 1);
+        assert_eq!(list.count_before(|score, _| score <= 2.0), 3);
+        assert_eq!(list.count_before(|_, _| true), 4);
+        assert_eq!(list.count_before(|_, member| member < b"c".as_slice()), 2);
+        assert_eq!(list.iter_from(1).map(|(member, _)| member).collect::<Vec<_>>(), [b"b", b"c", b"d"]);
+    }
+
+    #[test]
+    fn test_ranks_stay_consistent_under_churn() {
+        let mut list = SkipList::new();
+        let mut model: Vec<(i64, Vec<u8>)> = Vec::new();
+        let mut rng = rand::thread_rng();
+        for _ in 0..2000 {
+            let score = rng.gen_range(-50..50);
+            let member = format!("m{}", rng.gen_range(0..300)).into_bytes();
+            match model.iter().position(|(_, m)| *m == member) {
+                Some(position) => {
+                    let (old, _) = model.remove(position);
+                    assert!(list.remove(old as f64, &member));
+                }
+                None => {
+                    list.insert(score as f64, member.clone());
+                    model.push((score, member));
+                }
+            }
+        }
+        model.sort();
+        assert_eq!(list.len(), model.len());
+        for (rank, (score, member)) in model.iter().enumerate() {
+            assert_eq!(list.rank(*score as f64, member), Some(rank));
+            assert_eq!(list.get_by_rank(rank), Some((member.as_slice(), *score as f64)));
+        }
+    }
+}
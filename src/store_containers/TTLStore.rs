@@ -1,16 +1,118 @@
-use crate::traits::Store::Store;
-use std::cell::RefCell;
+use crate::store::value::sampled_size;
+use crate::store_containers::{Dict::Dict, DictStore::MEMORY_SAMPLES};
+use std::collections::BTreeSet;
 use std::fmt::Debug;
-use std::{collections::HashMap, rc::Rc};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Milliseconds since the Unix epoch, the unit every expiry deadline is kept in.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// Index of the keys that have a TTL, ordered by deadline so the active expiry
+// sweep can find what is due without scanning the keyspace. The deadline itself
+// lives on the key's `Entry`; this only mirrors it. The keys are also kept in a
+// Dict so the volatile-* eviction policies can sample them at random.
 #[derive(Debug)]
 pub struct TTLStore {
-    pub store: HashMap<usize, Rc<RefCell<dyn Store>>>,
+    deadlines: BTreeSet<(u64, Vec<u8>)>,
+    keys: Dict<Vec<u8>, ()>,
 }
 
 impl TTLStore {
     pub fn new() -> Self {
         TTLStore {
-            store: HashMap::new(),
+            deadlines: BTreeSet::new(),
+            keys: Dict::new(),
+        }
+    }
+
+    pub fn insert(&mut self, key: &[u8], deadline_ms: u64) {
+        self.deadlines.insert((deadline_ms, key.to_vec()));
+        self.keys.insert(key.to_vec(), ());
+    }
+
+    pub fn remove(&mut self, key: &[u8], deadline_ms: u64) -> bool {
+        let removed = self.deadlines.remove(&(deadline_ms, key.to_vec()));
+        if removed {
+            self.keys.remove(key);
         }
+        removed
+    }
+
+    pub fn random_key(&self) -> Option<Vec<u8>> {
+        self.keys.random_entry().map(|(key, _)| key.clone())
+    }
+
+    // Keys whose deadline has passed, earliest first, at most `limit` of them.
+    pub fn expired_keys(&self, now: u64, limit: usize) -> Vec<Vec<u8>> {
+        self.deadlines
+            .iter()
+            .take_while(|(deadline, _)| *deadline <= now)
+            .take(limit)
+            .map(|(_, key)| key.clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.deadlines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deadlines.is_empty()
+    }
+
+    // Approximate heap bytes of the index: both copies of each key, the
+    // ordered set's slots and the table.
+    pub fn memory_usage(&self) -> usize {
+        let key_bytes = sampled_size(self.keys.keys(), self.keys.len(), MEMORY_SAMPLES, Vec::capacity);
+        2 * key_bytes + self.deadlines.len() * std::mem::size_of::<(u64, Vec<u8>)>() + self.keys.table_size()
+    }
+
+    pub fn clear(&mut self) {
+        self.deadlines.clear();
+        self.keys.clear();
+    }
+}
+
+impl Default for TTLStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut ttl = TTLStore::new();
+        ttl.insert(b"key", 1_000);
+        assert_eq!(ttl.len(), 1);
+
+        assert!(!ttl.remove(b"key", 2_000));
+        assert_eq!(ttl.random_key(), Some(b"key".to_vec()));
+        assert!(ttl.remove(b"key", 1_000));
+        assert!(ttl.is_empty());
+        assert_eq!(ttl.random_key(), None);
+    }
+
+    #[test]
+    fn test_expired_keys_in_deadline_order() {
+        let mut ttl = TTLStore::new();
+        ttl.insert(b"late", 3_000);
+        ttl.insert(b"early", 1_000);
+        ttl.insert(b"middle", 2_000);
+
+        assert!(ttl.expired_keys(999, 10).is_empty());
+        assert_eq!(
+            ttl.expired_keys(2_500, 10),
+            vec![b"early".to_vec(), b"middle".to_vec()]
+        );
+        assert_eq!(ttl.expired_keys(5_000, 1), vec![b"early".to_vec()]);
     }
 }
@@ -0,0 +1,106 @@
+use std::collections::{HashMap, HashSet};
+
+// Keys WATCHed by clients in view of a later EXEC. Any change to a watched key
+// marks every client watching it as dirty, and a dirty client's EXEC fails.
+#[derive(Debug)]
+pub struct WatchStore {
+    next_id: u64,
+    watchers: HashMap<Vec<u8>, HashSet<u64>>,
+    watched: HashMap<u64, Vec<Vec<u8>>>,
+    dirty: HashSet<u64>,
+}
+
+impl WatchStore {
+    pub fn new() -> Self {
+        WatchStore {
+            next_id: 0,
+            watchers: HashMap::new(),
+            watched: HashMap::new(),
+            dirty: HashSet::new(),
+        }
+    }
+
+    // An id for a new connection to watch keys under.
+    pub fn new_client(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    pub fn watch(&mut self, id: u64, key: &[u8]) {
+        if self.watchers.entry(key.to_vec()).or_default().insert(id) {
+            self.watched.entry(id).or_default().push(key.to_vec());
+        }
+    }
+
+    // Forgets every key the client watches, and whether one of them changed.
+    pub fn unwatch(&mut self, id: u64) {
+        for key in self.watched.remove(&id).unwrap_or_default() {
+            if let Some(clients) = self.watchers.get_mut(&key) {
+                clients.remove(&id);
+                if clients.is_empty() {
+                    self.watchers.remove(&key);
+                }
+            }
+        }
+        self.dirty.remove(&id);
+    }
+
+    // Called whenever `key` is written, deleted or expires. Cheap when nobody
+    // watches it.
+    pub fn touch_key(&mut self, key: &[u8]) {
+        if let Some(clients) = self.watchers.get(key) {
+            self.dirty.extend(clients);
+        }
+    }
+
+    pub fn is_watched(&self, key: &[u8]) -> bool {
+        self.watchers.contains_key(key)
+    }
+
+    pub fn is_dirty(&self, id: u64) -> bool {
+        self.dirty.contains(&id)
+    }
+
+    pub fn watched_keys(&self, id: u64) -> Vec<Vec<u8>> {
+        self.watched.get(&id).cloned().unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.watchers.is_empty()
+    }
+}
+
+impl Default for WatchStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_touching_a_key_dirties_its_watchers() {
+        let mut store = WatchStore::new();
+        let first = store.new_client();
+        let second = store.new_client();
+        store.watch(first, b"a");
+        store.watch(first, b"a");
+        store.watch(second, b"b");
+
+        store.touch_key(b"other");
+        store.touch_key(b"a");
+        assert!(store.is_dirty(first));
+        assert!(!store.is_dirty(second));
+        assert_eq!(store.watched_keys(first), vec![b"a".to_vec()]);
+
+        // Unwatching leaves nothing behind
+        store.unwatch(first);
+        assert!(!store.is_dirty(first));
+        assert!(!store.is_watched(b"a"));
+        store.unwatch(second);
+        assert!(store.is_empty());
+    }
+}
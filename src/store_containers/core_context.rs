@@ -1,16 +1,420 @@
-use crate::store_containers::{DictStore::DictStore, TTLStore::TTLStore};
+use crate::config;
+use crate::store::value::{Value, WrongTypeError};
+use crate::store_containers::{
+    AofStore::AofStore,
+    BlockingStore::BlockingStore,
+    DictStore::{DictStore, Entry},
+    EvictionStore::{EvictionPolicy, EvictionStore},
+    PersistenceStore::PersistenceStore,
+    PubSubStore::PubSubStore,
+    TTLStore::{now_ms, TTLStore},
+    WatchStore::WatchStore,
+};
+use crate::traits::Store::Store;
+
+// Upper bound on keys reclaimed by one active expiry cycle, so a burst of
+// simultaneous deadlines cannot stall the event loop.
+const ACTIVE_EXPIRE_CYCLE_LIMIT: usize = 1000;
 
 pub struct context {
     pub DataBase: DictStore,
     pub TTLStore: TTLStore,
-    pub EvictionStore: String, // TODO
+    pub EvictionStore: EvictionStore,
+    pub BlockingStore: BlockingStore,
+    pub PersistenceStore: PersistenceStore,
+    pub AofStore: AofStore,
+    pub WatchStore: WatchStore,
+    pub PubSubStore: PubSubStore,
 }
 impl context {
     pub fn new() -> Self {
         context {
             DataBase: DictStore::new(),
             TTLStore: TTLStore::new(),
-            EvictionStore: "TODO".to_owned(),
+            EvictionStore: EvictionStore::new(),
+            BlockingStore: BlockingStore::new(),
+            PersistenceStore: PersistenceStore::new(),
+            AofStore: AofStore::new(),
+            WatchStore: WatchStore::new(),
+            PubSubStore: PubSubStore::new(),
+        }
+    }
+
+    // Returns the live entry at `key`, deleting it first if its deadline has
+    // passed (lazy expiry) and recording the access otherwise. Every command
+    // reads the keyspace through here.
+    pub fn lookup_key(&mut self, key: &[u8]) -> Option<&mut Entry> {
+        let now = now_ms();
+        if self.DataBase.store.get(key)?.is_expired(now) {
+            self.delete_and_propagate(key);
+            return None;
+        }
+        let entry = self.DataBase.store.get_mut(key)?;
+        entry.touch(now);
+        Some(entry)
+    }
+
+    // Like `lookup_key` without counting as an access, for commands that
+    // inspect the access metadata itself.
+    pub fn peek_key(&mut self, key: &[u8]) -> Option<&Entry> {
+        if self.DataBase.store.get(key)?.is_expired(now_ms()) {
+            self.delete_and_propagate(key);
+            return None;
+        }
+        self.DataBase.store.get(key)
+    }
+
+    // Typed read access: Ok(None) if the key does not exist, an error if it
+    // holds another type.
+    pub fn get<S: Store>(&mut self, key: &[u8]) -> Result<Option<&S>, WrongTypeError> {
+        match self.lookup_key(key) {
+            Some(entry) => S::from_value(entry.value.as_ref()).map(Some).ok_or(WrongTypeError),
+            None => Ok(None),
+        }
+    }
+
+    // Typed write access. Not a change by itself: the caller marks the key
+    // with `DataBase.mark_changed` once it has actually modified the value.
+    pub fn get_mut<S: Store>(&mut self, key: &[u8]) -> Result<Option<&mut S>, WrongTypeError> {
+        match self.lookup_key(key) {
+            Some(entry) if S::from_value(entry.value.as_ref()).is_none() => return Err(WrongTypeError),
+            Some(_) => {}
+            None => return Ok(None),
+        }
+        self.DataBase.mark_resized(key);
+        let entry = self.DataBase.store.get_mut(key).expect("key was just looked up");
+        Ok(S::from_value_mut(entry.value_mut()))
+    }
+
+    // Like `get_mut`, but creates an empty value first if the key is missing.
+    // The new key is not a change either until the caller writes to it.
+    pub fn get_or_create<S: Store + Default>(&mut self, key: &[u8]) -> Result<&mut S, WrongTypeError> {
+        if self.get_mut::<S>(key)?.is_none() {
+            self.DataBase
+                .insert_uncounted(key.to_vec(), Entry::new(S::default().into_value(), now_ms()));
+            self.DataBase.mark_resized(key);
+        }
+        let entry = self.DataBase.store.get_mut(key).expect("key was just created");
+        S::from_value_mut(entry.value_mut()).ok_or(WrongTypeError)
+    }
+
+    // Stores a new value at `key`, replacing any previous value and its TTL.
+    pub fn insert_key(&mut self, key: Vec<u8>, value: Value) {
+        self.remove_key(&key);
+        self.DataBase.insert(key, Entry::new(value, now_ms()));
+    }
+
+    // Called after removing elements from a collection: deletes the key if that
+    // left it empty. Returns whether the key was deleted. The removal that
+    // emptied it was already marked, and a key created only to stay empty was
+    // never a change, so the deletion is not counted again.
+    pub fn delete_if_empty(&mut self, key: &[u8]) -> bool {
+        let is_empty = self
+            .DataBase
+            .store
+            .get(key)
+            .is_some_and(|entry| entry.value.is_empty_collection());
+        if !is_empty {
+            return false;
+        }
+        if let Some(deadline) = self.DataBase.remove_uncounted(key).and_then(|entry| entry.expires_at) {
+            self.TTLStore.remove(key, deadline);
+        }
+        true
+    }
+
+    pub fn remove_key(&mut self, key: &[u8]) -> bool {
+        self.remove_entry(key).is_some()
+    }
+
+    // Deletes a key the server dropped on its own, because it expired or was
+    // evicted, and logs a DEL so that replaying the AOF drops it as well. Not
+    // a change of the running command, which is not logged for it.
+    fn delete_and_propagate(&mut self, key: &[u8]) {
+        if let Some(deadline) = self.DataBase.remove_uncounted(key).and_then(|entry| entry.expires_at) {
+            self.TTLStore.remove(key, deadline);
+        }
+        self.AofStore.also_propagate(vec![b"DEL".to_vec(), key.to_vec()]);
+        self.WatchStore.touch_key(key);
+    }
+
+    fn remove_entry(&mut self, key: &[u8]) -> Option<Entry> {
+        let entry = self.DataBase.remove(key)?;
+        if let Some(deadline) = entry.expires_at {
+            self.TTLStore.remove(key, deadline);
+        }
+        Some(entry)
+    }
+
+    // Removes a live key and hands back its entry, TTL included, so it can be
+    // stored again under another name.
+    pub fn take_entry(&mut self, key: &[u8]) -> Option<Entry> {
+        self.lookup_key(key)?;
+        self.remove_entry(key)
+    }
+
+    // Stores a whole entry at `key`, replacing any previous value. The entry
+    // keeps its own TTL.
+    pub fn insert_entry(&mut self, key: Vec<u8>, entry: Entry) {
+        self.remove_key(&key);
+        if let Some(deadline) = entry.expires_at {
+            self.TTLStore.insert(&key, deadline);
+        }
+        // A list moved in by RENAME or COPY can serve blocked clients
+        if let Value::List(_) = *entry.value {
+            self.BlockingStore.signal_key_ready(&key);
+        }
+        self.DataBase.insert(key, entry);
+    }
+
+    // Live keys, in no particular order. Expired keys are skipped but left for
+    // the expiry machinery to delete.
+    pub fn live_keys(&self) -> Vec<&Vec<u8>> {
+        let now = now_ms();
+        self.DataBase
+            .store
+            .iter()
+            .filter(|(_, entry)| !entry.is_expired(now))
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    // A random live key. Expired keys it happens to pick are deleted on the way.
+    pub fn random_key(&mut self) -> Option<Vec<u8>> {
+        while !self.DataBase.store.is_empty() {
+            let key = self.DataBase.store.random_entry()?.0.clone();
+            if self.lookup_key(&key).is_some() {
+                return Some(key);
+            }
+        }
+        None
+    }
+
+    // Sets an absolute deadline in milliseconds. Returns false if the key does
+    // not exist. A deadline already in the past deletes the key right away.
+    pub fn set_expiry(&mut self, key: &[u8], deadline_ms: u64) -> bool {
+        if self.lookup_key(key).is_none() {
+            return false;
+        }
+        if deadline_ms <= now_ms() {
+            self.remove_key(key);
+            return true;
+        }
+        self.persist_key(key);
+        if let Some(entry) = self.DataBase.store.get_mut(key) {
+            entry.expires_at = Some(deadline_ms);
+            self.TTLStore.insert(key, deadline_ms);
+        }
+        self.DataBase.mark_changed(key);
+        true
+    }
+
+    pub fn get_expiry(&self, key: &[u8]) -> Option<u64> {
+        self.DataBase.store.get(key)?.expires_at
+    }
+
+    // Removes the TTL of a key. Returns false if it had none.
+    pub fn persist_key(&mut self, key: &[u8]) -> bool {
+        let deadline = match self.DataBase.store.get_mut(key) {
+            Some(entry) => entry.expires_at.take(),
+            None => None,
+        };
+        match deadline {
+            Some(deadline) => {
+                self.DataBase.mark_changed(key);
+                self.TTLStore.remove(key, deadline)
+            }
+            None => false,
+        }
+    }
+
+    pub fn flush(&mut self) {
+        // Only keys that existed count as changed for WATCH
+        if !self.WatchStore.is_empty() {
+            let now = now_ms();
+            for (key, entry) in self.DataBase.store.iter() {
+                if !entry.is_expired(now) {
+                    self.WatchStore.touch_key(key);
+                }
+            }
+        }
+        self.DataBase.clear();
+        self.TTLStore.clear();
+    }
+
+    // Run after each command: accounts the memory of the keys it wrote and
+    // signals them to clients that WATCH them.
+    pub fn commit_changes(&mut self) {
+        for key in self.DataBase.account_memory() {
+            self.WatchStore.touch_key(&key);
+        }
+    }
+
+    // Deletes keys by maxmemory-policy until used memory is back under
+    // maxmemory, the way Redis' performEvictions runs before every command.
+    // Returns false if that is not possible: the policy is noeviction or no
+    // key qualifies.
+    pub fn perform_evictions(&mut self) -> bool {
+        let (maxmemory, policy, samples) = config::with(|config| {
+            (config.maxmemory, config.maxmemory_policy, config.maxmemory_samples)
+        });
+        if maxmemory == 0 {
+            return true;
+        }
+        self.EvictionStore.use_policy(policy);
+        while self.DataBase.used_memory as u64 > maxmemory {
+            let victim = match policy {
+                EvictionPolicy::NoEviction => None,
+                EvictionPolicy::AllKeysRandom => self.DataBase.store.random_entry().map(|(key, _)| key.clone()),
+                EvictionPolicy::VolatileRandom => self.TTLStore.random_key(),
+                _ => self.eviction_candidate(policy, samples),
+            };
+            match victim {
+                Some(key) => {
+                    self.delete_and_propagate(&key);
+                    self.EvictionStore.evicted_keys += 1;
+                }
+                None => return false,
+            }
+        }
+        true
+    }
+
+    // Approximate LRU, LFU and TTL eviction: samples a few keys into the pool
+    // and takes its best candidate that still qualifies.
+    fn eviction_candidate(&mut self, policy: EvictionPolicy, samples: usize) -> Option<Vec<u8>> {
+        let now = now_ms();
+        for _ in 0..samples {
+            let key = if policy.is_volatile() {
+                self.TTLStore.random_key()
+            } else {
+                self.DataBase.store.random_entry().map(|(key, _)| key.clone())
+            };
+            let Some(entry) = key.as_ref().and_then(|key| self.DataBase.store.get(key)) else {
+                break;
+            };
+            // Higher is evicted first
+            let score = match policy {
+                EvictionPolicy::VolatileTtl => u64::MAX - entry.expires_at.unwrap_or(u64::MAX),
+                _ if policy.is_lfu() => (u8::MAX - entry.lfu_count(now)) as u64,
+                _ => now.saturating_sub(entry.lru),
+            };
+            self.EvictionStore.offer(score, key.expect("sampled above"));
+        }
+        while let Some(key) = self.EvictionStore.take_best() {
+            let qualifies = self
+                .DataBase
+                .store
+                .get(&key)
+                .is_some_and(|entry| !policy.is_volatile() || entry.expires_at.is_some());
+            if qualifies {
+                return Some(key);
+            }
         }
+        None
+    }
+
+    // Active expiry: reclaims keys whose deadline has passed even if no client
+    // ever touches them again. Returns how many keys were deleted.
+    pub fn active_expire_cycle(&mut self) -> usize {
+        let expired = self
+            .TTLStore
+            .expired_keys(now_ms(), ACTIVE_EXPIRE_CYCLE_LIMIT);
+        for key in &expired {
+            self.delete_and_propagate(key);
+        }
+        expired.len()
+    }
+}
+
+impl Default for context {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::hash_store::HashStore;
+    use crate::store::string_store::StringStore;
+
+    #[test]
+    fn test_typed_access() {
+        let mut ctx = context::new();
+        ctx.insert_key(b"str".to_vec(), Value::String(StringStore::new(b"v".to_vec())));
+
+        assert_eq!(ctx.get::<StringStore>(b"str").unwrap().unwrap().get_value(), b"v");
+        assert!(ctx.get::<HashStore>(b"str").is_err());
+        assert!(ctx.get_mut::<HashStore>(b"str").is_err());
+        assert!(ctx.get_or_create::<HashStore>(b"str").is_err());
+        assert!(ctx.get::<HashStore>(b"missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_or_create() {
+        let mut ctx = context::new();
+        ctx.get_or_create::<HashStore>(b"hash").unwrap().set_field(b"f", b"v");
+        ctx.get_or_create::<HashStore>(b"hash").unwrap().set_field(b"g", b"w");
+
+        assert_eq!(ctx.get::<HashStore>(b"hash").unwrap().unwrap().len(), 2);
+        assert_eq!(ctx.DataBase.store.get(b"hash".as_slice()).unwrap().type_name(), "hash");
+    }
+
+    #[test]
+    fn test_expiry_metadata_follows_the_key() {
+        let mut ctx = context::new();
+        ctx.insert_key(b"key".to_vec(), Value::String(StringStore::new(b"v".to_vec())));
+        let deadline = now_ms() + 10_000;
+
+        assert!(ctx.set_expiry(b"key", deadline));
+        assert_eq!(ctx.get_expiry(b"key"), Some(deadline));
+        assert_eq!(ctx.TTLStore.len(), 1);
+
+        // Overwriting the key drops its TTL, and the index stays in sync
+        ctx.insert_key(b"key".to_vec(), Value::String(StringStore::new(b"w".to_vec())));
+        assert_eq!(ctx.get_expiry(b"key"), None);
+        assert!(ctx.TTLStore.is_empty());
+
+        assert!(!ctx.set_expiry(b"missing", deadline));
+    }
+
+    #[test]
+    fn test_moving_an_entry_keeps_its_ttl() {
+        let mut ctx = context::new();
+        ctx.insert_key(b"old".to_vec(), Value::String(StringStore::new(b"v".to_vec())));
+        let deadline = now_ms() + 10_000;
+        ctx.set_expiry(b"old", deadline);
+
+        let entry = ctx.take_entry(b"old").unwrap();
+        ctx.insert_entry(b"new".to_vec(), entry);
+
+        assert!(ctx.lookup_key(b"old").is_none());
+        assert_eq!(ctx.get_expiry(b"new"), Some(deadline));
+        assert_eq!(ctx.TTLStore.len(), 1);
+        assert_eq!(ctx.active_expire_cycle(), 0);
+    }
+
+    #[test]
+    fn test_empty_collections_are_deleted() {
+        let mut ctx = context::new();
+        ctx.get_or_create::<HashStore>(b"hash").unwrap().set_field(b"f", b"v");
+        ctx.insert_key(b"str".to_vec(), Value::String(StringStore::new(Vec::new())));
+
+        assert!(!ctx.delete_if_empty(b"hash"));
+        ctx.get_mut::<HashStore>(b"hash").unwrap().unwrap().delete_field(b"f");
+        assert!(ctx.delete_if_empty(b"hash"));
+        assert!(ctx.lookup_key(b"hash").is_none());
+
+        // An empty string is still a value
+        assert!(!ctx.delete_if_empty(b"str"));
+    }
+
+    #[test]
+    fn test_random_key() {
+        let mut ctx = context::new();
+        assert_eq!(ctx.random_key(), None);
+        ctx.insert_key(b"only".to_vec(), Value::String(StringStore::new(b"v".to_vec())));
+        assert_eq!(ctx.random_key(), Some(b"only".to_vec()));
     }
 }
@@ -1,3 +1,13 @@
+pub mod Dict;
 pub mod DictStore;
 pub mod TTLStore;
 pub mod core_context;
+pub mod BlockingStore;
+pub mod SkipList;
+pub mod Listpack;
+pub mod IntSet;
+pub mod EvictionStore;
+pub mod PersistenceStore;
+pub mod AofStore;
+pub mod WatchStore;
+pub mod PubSubStore;
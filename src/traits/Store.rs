@@ -1,4 +1,10 @@
-use std::any::Any;
+use crate::store::value::Value;
 use std::fmt::Debug;
 
-pub trait Store: Debug + Any {}
+// Implemented by every value type the keyspace can hold. Commands ask the
+// keyspace for the type they expect, and anything else is a WRONGTYPE error.
+pub trait Store: Debug + Sized {
+    fn from_value(value: &Value) -> Option<&Self>;
+    fn from_value_mut(value: &mut Value) -> Option<&mut Self>;
+    fn into_value(self) -> Value;
+}
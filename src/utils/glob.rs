@@ -0,0 +1,147 @@
+// Glob-style matching with the same rules as Redis' stringmatchlen, used by KEYS
+// and the MATCH option of the SCAN family:
+//   *      any sequence of bytes, including none
+//   ?      exactly one byte
+//   [abc]  one of the listed bytes; [^abc] negates, [a-z] is a range
+//   \x     the byte x literally, also inside brackets
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let mut p = 0;
+    let mut s = 0;
+    // Where to resume after the last `*`: pattern index after it, and the
+    // position in the string it currently stands for.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        if p < pattern.len() {
+            match pattern[p] {
+                b'*' => {
+                    while p < pattern.len() && pattern[p] == b'*' {
+                        p += 1;
+                    }
+                    if p == pattern.len() {
+                        return true;
+                    }
+                    backtrack = Some((p, s));
+                    continue;
+                }
+                b'?' => {
+                    p += 1;
+                    s += 1;
+                    continue;
+                }
+                b'[' => {
+                    let (matched, next) = match_class(pattern, p, string[s]);
+                    if matched {
+                        p = next;
+                        s += 1;
+                        continue;
+                    }
+                }
+                b'\\' if p + 1 < pattern.len() => {
+                    if pattern[p + 1] == string[s] {
+                        p += 2;
+                        s += 1;
+                        continue;
+                    }
+                }
+                byte => {
+                    if byte == string[s] {
+                        p += 1;
+                        s += 1;
+                        continue;
+                    }
+                }
+            }
+        }
+        // Mismatch: let the last `*` swallow one more byte, if there was one
+        match backtrack {
+            Some((star_p, star_s)) => {
+                backtrack = Some((star_p, star_s + 1));
+                p = star_p;
+                s = star_s + 1;
+            }
+            None => return false,
+        }
+    }
+
+    pattern[p..].iter().all(|&byte| byte == b'*')
+}
+
+// Matches `byte` against the class starting at pattern[start] == '['. Returns
+// whether it matched and the pattern index just past the closing ']'. An
+// unterminated class runs to the end of the pattern.
+fn match_class(pattern: &[u8], start: usize, byte: u8) -> (bool, usize) {
+    let mut i = start + 1;
+    let negate = pattern.get(i) == Some(&b'^');
+    if negate {
+        i += 1;
+    }
+
+    let mut matched = false;
+    while i < pattern.len() {
+        if pattern[i] == b']' {
+            i += 1;
+            break;
+        }
+        if pattern[i] == b'\\' && i + 1 < pattern.len() {
+            matched |= pattern[i + 1] == byte;
+            i += 2;
+        } else if i + 2 < pattern.len() && pattern[i + 1] == b'-' {
+            let (low, high) = if pattern[i] <= pattern[i + 2] {
+                (pattern[i], pattern[i + 2])
+            } else {
+                (pattern[i + 2], pattern[i])
+            };
+            matched |= (low..=high).contains(&byte);
+            i += 3;
+        } else {
+            matched |= pattern[i] == byte;
+            i += 1;
+        }
+    }
+    (matched != negate, i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wildcards() {
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"*", b"anything"));
+        assert!(glob_match(b"h?llo", b"hello"));
+        assert!(!glob_match(b"h?llo", b"hllo"));
+        assert!(glob_match(b"h*llo", b"hllo"));
+        assert!(glob_match(b"h*llo", b"heeeello"));
+        assert!(glob_match(b"user:*:name", b"user:42:name"));
+        assert!(!glob_match(b"user:*:name", b"user:42:age"));
+        assert!(glob_match(b"*a*b*c", b"xxaxxbxxbc"));
+        assert!(!glob_match(b"a*", b"ba"));
+        assert!(glob_match(b"abc", b"abc"));
+        assert!(!glob_match(b"abc", b"abcd"));
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(glob_match(b"h[ae]llo", b"hello"));
+        assert!(glob_match(b"h[ae]llo", b"hallo"));
+        assert!(!glob_match(b"h[ae]llo", b"hillo"));
+        assert!(glob_match(b"h[^e]llo", b"hallo"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"h[a-b]llo", b"hbllo"));
+        assert!(glob_match(b"h[z-a]llo", b"hqllo"));
+        assert!(!glob_match(b"h[a-b]llo", b"hcllo"));
+        assert!(glob_match(b"[\\]]", b"]"));
+        assert!(glob_match(b"key[0-9", b"key5"));
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert!(glob_match(b"a\\*b", b"a*b"));
+        assert!(!glob_match(b"a\\*b", b"axb"));
+        assert!(glob_match(b"\\?", b"?"));
+        assert!(glob_match(b"a\\", b"a\\"));
+        assert!(glob_match(b"\x00*\xff", b"\x00binary\xff"));
+    }
+}
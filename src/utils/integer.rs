@@ -0,0 +1,23 @@
+// Reads `bytes` as a signed 64-bit integer only if it is written the canonical
+// way, like Redis' string2ll: "12" and "-12", but not "+12", "012", "-0" or
+// " 12", which would not read back the same.
+pub fn parse_canonical(bytes: &[u8]) -> Option<i64> {
+    let integer: i64 = std::str::from_utf8(bytes).ok()?.parse().ok()?;
+    (integer.to_string().as_bytes() == bytes).then_some(integer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_canonical() {
+        assert_eq!(parse_canonical(b"12"), Some(12));
+        assert_eq!(parse_canonical(b"-12"), Some(-12));
+        assert_eq!(parse_canonical(b"0"), Some(0));
+        assert_eq!(parse_canonical(b"-9223372036854775808"), Some(i64::MIN));
+        for rejected in [&b"+1"[..], b"01", b"-0", b" 1", b"1 ", b"", b"9223372036854775808"] {
+            assert_eq!(parse_canonical(rejected), None, "{:?}", rejected);
+        }
+    }
+}
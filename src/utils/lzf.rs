@@ -0,0 +1,71 @@
+// The most an LZF byte can expand to: a three byte back reference copies 264.
+const MAX_EXPANSION: usize = 88;
+
+// LZF decompression, for the compressed strings in Redis RDB files. Returns
+// None if `data` is not valid LZF or does not expand to exactly `length` bytes.
+pub fn decompress(data: &[u8], length: usize) -> Option<Vec<u8>> {
+    // `length` comes from the file: one no input could expand to is rejected
+    // before it is allocated
+    if length > data.len().saturating_mul(MAX_EXPANSION) {
+        return None;
+    }
+    let mut out = Vec::with_capacity(length);
+    let mut position = 0;
+    while position < data.len() {
+        let control = data[position] as usize;
+        position += 1;
+        if control < 32 {
+            // A run of control + 1 literal bytes
+            let literal = data.get(position..position + control + 1)?;
+            out.extend_from_slice(literal);
+            position += control + 1;
+        } else {
+            // A back reference: copy len + 2 bytes from `offset` back, which
+            // may overlap what is being written
+            let mut len = control >> 5;
+            if len == 7 {
+                len += *data.get(position)? as usize;
+                position += 1;
+            }
+            let offset = ((control & 0x1f) << 8) + *data.get(position)? as usize + 1;
+            position += 1;
+            let start = out.len().checked_sub(offset)?;
+            for i in 0..len + 2 {
+                out.push(out[start + i]);
+            }
+        }
+        if out.len() > length {
+            return None;
+        }
+    }
+    (out.len() == length).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decompress() {
+        // "aaaaaaaaaaaaaaaaaaaabcd": one literal, then a 19 byte reference to it
+        let data = [0x00, b'a', 0xe0, 0x0a, 0x00, 0x02, b'b', b'c', b'd'];
+        let expected = [&[b'a'; 20][..], b"bcd"].concat();
+        assert_eq!(decompress(&data, expected.len()), Some(expected));
+        assert_eq!(decompress(&data, 5), None);
+        assert_eq!(decompress(&[0x05, b'a'], 6), None);
+        // A reference before the start of the output
+        assert_eq!(decompress(&[0x20, 0x00], 3), None);
+    }
+
+    #[test]
+    fn test_decompress_with_a_huge_length() {
+        let data = [0x00, b'a', 0xe0, 0x0a, 0x00, 0x02, b'b', b'c', b'd'];
+        assert_eq!(decompress(&data, usize::MAX), None);
+        assert_eq!(decompress(&data, 1 << 40), None);
+        assert_eq!(decompress(&[], 1), None);
+
+        // The longest reference there is still decompresses
+        let data = [0x00, b'a', 0xe0, 0xff, 0x00];
+        assert_eq!(decompress(&data, 265), Some(vec![b'a'; 265]));
+    }
+}
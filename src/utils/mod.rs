@@ -0,0 +1,4 @@
+pub mod glob;
+pub mod crc64;
+pub mod lzf;
+pub mod integer;
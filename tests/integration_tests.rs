@@ -1,32 +1,46 @@
-use KiloDB::command::command_enum::Command;
+use KiloDB::command::command_enum::{Command, Expiry, SetCondition};
 use KiloDB::command::command_executor::command_executor;
+use KiloDB::persistence::{self, aof};
+use KiloDB::server::pubsub::Subscriber;
+use KiloDB::server::transaction::Transaction;
 use KiloDB::store_containers::core_context::context;
-use KiloDB::store_containers::{DictStore::DictStore, TTLStore::TTLStore};
+use KiloDB::store_containers::{AofStore::AofStore, BlockingStore::BlockingStore, DictStore::DictStore, EvictionStore::EvictionStore, PersistenceStore::PersistenceStore, PubSubStore::PubSubStore, TTLStore::{now_ms, TTLStore}, WatchStore::WatchStore};
 
 fn create_test_context() -> context {
     context {
         DataBase: DictStore::new(),
         TTLStore: TTLStore::new(),
-        EvictionStore: "TODO".to_owned(),
+        EvictionStore: EvictionStore::new(),
+        BlockingStore: BlockingStore::new(),
+        PersistenceStore: PersistenceStore::new(),
+        AofStore: AofStore::new(),
+        WatchStore: WatchStore::new(),
+        PubSubStore: PubSubStore::new(),
     }
 }
 
+// Parses and runs one command given as plain arguments.
+fn run(ctx: &mut context, args: &[&str]) -> Vec<u8> {
+    let args: Vec<Vec<u8>> = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+    command_executor::call(&Command::new(&args), &args, ctx).unwrap()
+}
+
 #[test]
 fn test_string_operations_flow() {
     let mut ctx = create_test_context();
     
     // Test SET command
-    let set_command = Command::new(&["SET".to_string(), "mykey".to_string(), "hello".to_string()]);
+    let set_command = Command::new(&[b"SET".to_vec(), b"mykey".to_vec(), b"hello".to_vec()]);
     let result = command_executor::execute_command(&set_command, &mut ctx).unwrap();
     assert_eq!(result, b"+OK\r\n");
     
     // Test GET command
-    let get_command = Command::new(&["GET".to_string(), "mykey".to_string()]);
+    let get_command = Command::new(&[b"GET".to_vec(), b"mykey".to_vec()]);
     let result = command_executor::execute_command(&get_command, &mut ctx).unwrap();
     assert_eq!(result, b"$5\r\nhello\r\n");
     
     // Test INCR command on new key
-    let incr_command = Command::new(&["INCR".to_string(), "counter".to_string()]);
+    let incr_command = Command::new(&[b"INCR".to_vec(), b"counter".to_vec()]);
     let result = command_executor::execute_command(&incr_command, &mut ctx).unwrap();
     assert_eq!(result, b":1\r\n");
     
@@ -35,7 +49,7 @@ fn test_string_operations_flow() {
     assert_eq!(result, b":2\r\n");
     
     // Test DECR command
-    let decr_command = Command::new(&["DECR".to_string(), "counter".to_string()]);
+    let decr_command = Command::new(&[b"DECR".to_vec(), b"counter".to_vec()]);
     let result = command_executor::execute_command(&decr_command, &mut ctx).unwrap();
     assert_eq!(result, b":1\r\n");
 }
@@ -46,30 +60,30 @@ fn test_hash_operations_flow() {
     
     // Test HSET command
     let hset_command = Command::new(&[
-        "HSET".to_string(),
-        "user:1".to_string(),
-        "name".to_string(),
-        "John".to_string(),
-        "age".to_string(),
-        "30".to_string(),
+        b"HSET".to_vec(),
+        b"user:1".to_vec(),
+        b"name".to_vec(),
+        b"John".to_vec(),
+        b"age".to_vec(),
+        b"30".to_vec(),
     ]);
     let result = command_executor::execute_command(&hset_command, &mut ctx).unwrap();
     assert_eq!(result, b":2\r\n");
     
     // Test HGET command
     let hget_command = Command::new(&[
-        "HGET".to_string(),
-        "user:1".to_string(),
-        "name".to_string(),
+        b"HGET".to_vec(),
+        b"user:1".to_vec(),
+        b"name".to_vec(),
     ]);
     let result = command_executor::execute_command(&hget_command, &mut ctx).unwrap();
     assert_eq!(result, b"$4\r\nJohn\r\n");
     
     // Test HGET on non-existent field
     let hget_command = Command::new(&[
-        "HGET".to_string(),
-        "user:1".to_string(),
-        "email".to_string(),
+        b"HGET".to_vec(),
+        b"user:1".to_vec(),
+        b"email".to_vec(),
     ]);
     let result = command_executor::execute_command(&hget_command, &mut ctx).unwrap();
     assert_eq!(result, b"$-1\r\n");
@@ -81,20 +95,20 @@ fn test_list_operations_flow() {
     
     // Test LPUSH command
     let lpush_command = Command::new(&[
-        "LPUSH".to_string(),
-        "mylist".to_string(),
-        "item1".to_string(),
-        "item2".to_string(),
-        "item3".to_string(),
+        b"LPUSH".to_vec(),
+        b"mylist".to_vec(),
+        b"item1".to_vec(),
+        b"item2".to_vec(),
+        b"item3".to_vec(),
     ]);
     let result = command_executor::execute_command(&lpush_command, &mut ctx).unwrap();
     assert_eq!(result, b":3\r\n");
     
     // Test another LPUSH on existing list
     let lpush_command = Command::new(&[
-        "LPUSH".to_string(),
-        "mylist".to_string(),
-        "item0".to_string(),
+        b"LPUSH".to_vec(),
+        b"mylist".to_vec(),
+        b"item0".to_vec(),
     ]);
     let result = command_executor::execute_command(&lpush_command, &mut ctx).unwrap();
     assert_eq!(result, b":4\r\n");
@@ -106,21 +120,21 @@ fn test_set_operations_flow() {
     
     // Test SADD command
     let sadd_command = Command::new(&[
-        "SADD".to_string(),
-        "myset".to_string(),
-        "member1".to_string(),
-        "member2".to_string(),
-        "member3".to_string(),
+        b"SADD".to_vec(),
+        b"myset".to_vec(),
+        b"member1".to_vec(),
+        b"member2".to_vec(),
+        b"member3".to_vec(),
     ]);
     let result = command_executor::execute_command(&sadd_command, &mut ctx).unwrap();
     assert_eq!(result, b":3\r\n");
     
     // Test SADD with duplicate members
     let sadd_command = Command::new(&[
-        "SADD".to_string(),
-        "myset".to_string(),
-        "member1".to_string(), // Duplicate
-        "member4".to_string(), // New
+        b"SADD".to_vec(),
+        b"myset".to_vec(),
+        b"member1".to_vec(), // Duplicate
+        b"member4".to_vec(), // New
     ]);
     let result = command_executor::execute_command(&sadd_command, &mut ctx).unwrap();
     assert_eq!(result, b":1\r\n"); // Only 1 new member added
@@ -132,26 +146,26 @@ fn test_sorted_set_operations_flow() {
     
     // Test ZADD command
     let zadd_command = Command::new(&[
-        "ZADD".to_string(),
-        "myzset".to_string(),
-        "1.0".to_string(),
-        "first".to_string(),
-        "2.0".to_string(),
-        "second".to_string(),
-        "3.0".to_string(),
-        "third".to_string(),
+        b"ZADD".to_vec(),
+        b"myzset".to_vec(),
+        b"1.0".to_vec(),
+        b"first".to_vec(),
+        b"2.0".to_vec(),
+        b"second".to_vec(),
+        b"3.0".to_vec(),
+        b"third".to_vec(),
     ]);
     let result = command_executor::execute_command(&zadd_command, &mut ctx).unwrap();
     assert_eq!(result, b":3\r\n");
     
     // Test ZADD with score update
     let zadd_command = Command::new(&[
-        "ZADD".to_string(),
-        "myzset".to_string(),
-        "1.5".to_string(),
-        "first".to_string(), // Update existing member
-        "4.0".to_string(),
-        "fourth".to_string(), // Add new member
+        b"ZADD".to_vec(),
+        b"myzset".to_vec(),
+        b"1.5".to_vec(),
+        b"first".to_vec(), // Update existing member
+        b"4.0".to_vec(),
+        b"fourth".to_vec(), // Add new member
     ]);
     let result = command_executor::execute_command(&zadd_command, &mut ctx).unwrap();
     assert_eq!(result, b":1\r\n"); // Only 1 new member added
@@ -162,24 +176,24 @@ fn test_database_operations_flow() {
     let mut ctx = create_test_context();
     
     // Add some data
-    let set_command = Command::new(&["SET".to_string(), "key1".to_string(), "value1".to_string()]);
+    let set_command = Command::new(&[b"SET".to_vec(), b"key1".to_vec(), b"value1".to_vec()]);
     command_executor::execute_command(&set_command, &mut ctx).unwrap();
     
-    let set_command = Command::new(&["SET".to_string(), "key2".to_string(), "value2".to_string()]);
+    let set_command = Command::new(&[b"SET".to_vec(), b"key2".to_vec(), b"value2".to_vec()]);
     command_executor::execute_command(&set_command, &mut ctx).unwrap();
     
     // Test DBSIZE
-    let dbsize_command = Command::new(&["DBSIZE".to_string()]);
+    let dbsize_command = Command::new(&[b"DBSIZE".to_vec()]);
     let result = command_executor::execute_command(&dbsize_command, &mut ctx).unwrap();
     assert_eq!(result, b":2\r\n");
     
     // Test PING
-    let ping_command = Command::new(&["PING".to_string()]);
+    let ping_command = Command::new(&[b"PING".to_vec()]);
     let result = command_executor::execute_command(&ping_command, &mut ctx).unwrap();
     assert_eq!(result, b"+PONG\r\n");
     
     // Test ECHO
-    let echo_command = Command::new(&["ECHO".to_string(), "hello world".to_string()]);
+    let echo_command = Command::new(&[b"ECHO".to_vec(), b"hello world".to_vec()]);
     let result = command_executor::execute_command(&echo_command, &mut ctx).unwrap();
     assert_eq!(result, b"$11\r\nhello world\r\n");
 }
@@ -189,20 +203,20 @@ fn test_error_handling() {
     let mut ctx = create_test_context();
     
     // Test GET on non-existent key
-    let get_command = Command::new(&["GET".to_string(), "nonexistent".to_string()]);
+    let get_command = Command::new(&[b"GET".to_vec(), b"nonexistent".to_vec()]);
     let result = command_executor::execute_command(&get_command, &mut ctx).unwrap();
     assert_eq!(result, b"$-1\r\n");
     
     // Test INCR on non-numeric value
-    let set_command = Command::new(&["SET".to_string(), "text".to_string(), "hello".to_string()]);
+    let set_command = Command::new(&[b"SET".to_vec(), b"text".to_vec(), b"hello".to_vec()]);
     command_executor::execute_command(&set_command, &mut ctx).unwrap();
     
-    let incr_command = Command::new(&["INCR".to_string(), "text".to_string()]);
+    let incr_command = Command::new(&[b"INCR".to_vec(), b"text".to_vec()]);
     let result = command_executor::execute_command(&incr_command, &mut ctx).unwrap();
     assert_eq!(result, b"-ERR value is not an integer or out of range\r\n");
     
     // Test unknown command
-    let unknown_command = Command::new(&["UNKNOWN".to_string()]);
+    let unknown_command = Command::new(&[b"UNKNOWN".to_vec()]);
     let result = command_executor::execute_command(&unknown_command, &mut ctx).unwrap();
     assert_eq!(result, b"$-1\r\n");
 }
@@ -210,39 +224,2289 @@ fn test_error_handling() {
 #[test]
 fn test_command_parsing() {
     // Test valid commands
-    let set_command = Command::new(&["SET".to_string(), "key".to_string(), "value".to_string()]);
+    let set_command = Command::new(&[b"SET".to_vec(), b"key".to_vec(), b"value".to_vec()]);
     match set_command {
-        Command::SET { key, value, ttl } => {
-            assert_eq!(key, "key");
-            assert_eq!(value, "value");
-            assert_eq!(ttl, None);
+        Command::SET { key, value, expiry, condition, get, keep_ttl } => {
+            assert_eq!(key, b"key");
+            assert_eq!(value, b"value");
+            assert_eq!(expiry, None);
+            assert_eq!(condition, None);
+            assert!(!get && !keep_ttl);
         }
         _ => panic!("Expected SET command"),
     }
     
     // Test SET with TTL
     let set_command = Command::new(&[
-        "SET".to_string(),
-        "key".to_string(),
-        "value".to_string(),
-        "EX".to_string(),
-        "60".to_string(),
+        b"SET".to_vec(),
+        b"key".to_vec(),
+        b"value".to_vec(),
+        b"EX".to_vec(),
+        b"60".to_vec(),
     ]);
     match set_command {
-        Command::SET { key, value, ttl } => {
-            assert_eq!(key, "key");
-            assert_eq!(value, "value");
-            assert_eq!(ttl, Some(60));
+        Command::SET { key, value, expiry, .. } => {
+            assert_eq!(key, b"key");
+            assert_eq!(value, b"value");
+            assert_eq!(expiry, Some(Expiry::Ex(60)));
         }
         _ => panic!("Expected SET command with TTL"),
     }
+
+    // Options are case-insensitive and may come in any order
+    let set_command = Command::new(&[
+        b"SET".to_vec(),
+        b"key".to_vec(),
+        b"value".to_vec(),
+        b"px".to_vec(),
+        b"30000".to_vec(),
+        b"Get".to_vec(),
+        b"NX".to_vec(),
+    ]);
+    match set_command {
+        Command::SET { expiry, condition, get, keep_ttl, .. } => {
+            assert_eq!(expiry, Some(Expiry::Px(30000)));
+            assert_eq!(condition, Some(SetCondition::NX));
+            assert!(get && !keep_ttl);
+        }
+        _ => panic!("Expected SET command with options"),
+    }
     
     // Test invalid command
-    let invalid_command = Command::new(&["INVALID".to_string(), "arg".to_string()]);
+    let invalid_command = Command::new(&[b"INVALID".to_vec(), b"arg".to_vec()]);
     match invalid_command {
-        Command::Unknown { raw: _ } => {
+        Command::Unknown { wrong_arity: false, .. } => {
             // Expected
         }
         _ => panic!("Expected Unknown command"),
     }
-} 
\ No newline at end of file
+} 
+// The error a connection answers a command with, if it is rejected before it
+// runs.
+fn rejection(args: &[&str]) -> Option<String> {
+    let args: Vec<Vec<u8>> = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+    Command::new(&args).rejection()
+}
+
+#[test]
+fn test_wrong_number_of_arguments() {
+    let wrong_arity = |name: &str| Some(format!("ERR wrong number of arguments for '{}' command", name));
+    assert_eq!(rejection(&["GET"]), wrong_arity("get"));
+    assert_eq!(rejection(&["mset", "a"]), wrong_arity("mset"));
+    assert_eq!(rejection(&["SET", "x"]), wrong_arity("set"));
+    assert_eq!(rejection(&["HSET", "h", "f"]), wrong_arity("hset"));
+    assert_eq!(rejection(&["OBJECT"]), wrong_arity("object"));
+    assert_eq!(rejection(&["GET", "x"]), None);
+
+    // Inside MULTI the error comes back while queueing, and aborts the EXEC
+    let mut ctx = create_test_context();
+    let mut client = Transaction::new(&mut ctx);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(
+        run_in(&mut client, &mut ctx, &["SET", "x"]),
+        b"-ERR wrong number of arguments for 'set' command\r\n"
+    );
+    assert_eq!(
+        run_in(&mut client, &mut ctx, &["EXEC"]),
+        b"-EXECABORT Transaction discarded because of previous errors.\r\n"
+    );
+}
+
+#[test]
+fn test_key_commands_reject_bad_arity() {
+    let wrong_arity = |name: &str| Some(format!("ERR wrong number of arguments for '{}' command", name));
+    assert_eq!(rejection(&["KEYS"]), wrong_arity("keys"));
+    assert_eq!(rejection(&["KEYS", "a", "b"]), wrong_arity("keys"));
+    assert_eq!(rejection(&["TYPE"]), wrong_arity("type"));
+    assert_eq!(rejection(&["TTL"]), wrong_arity("ttl"));
+    assert_eq!(rejection(&["PTTL", "a", "b"]), wrong_arity("pttl"));
+    assert_eq!(rejection(&["PERSIST"]), wrong_arity("persist"));
+    assert_eq!(rejection(&["RENAME", "a"]), wrong_arity("rename"));
+    assert_eq!(rejection(&["RENAMENX", "a", "b", "c"]), wrong_arity("renamenx"));
+    assert_eq!(rejection(&["COPY", "a"]), wrong_arity("copy"));
+    assert_eq!(rejection(&["TOUCH"]), wrong_arity("touch"));
+    assert_eq!(rejection(&["UNLINK"]), wrong_arity("unlink"));
+    assert_eq!(rejection(&["RANDOMKEY", "a"]), wrong_arity("randomkey"));
+    assert_eq!(rejection(&["DEL"]), wrong_arity("del"));
+    assert_eq!(rejection(&["EXISTS"]), wrong_arity("exists"));
+    assert_eq!(rejection(&["EXPIRE", "a"]), wrong_arity("expire"));
+    assert_eq!(rejection(&["PEXPIREAT", "a"]), wrong_arity("pexpireat"));
+    assert_eq!(rejection(&["DUMP"]), wrong_arity("dump"));
+    assert_eq!(rejection(&["SCAN"]), wrong_arity("scan"));
+
+    // A name the server does not know is a different error, which quotes
+    // the start of the arguments
+    assert_eq!(
+        rejection(&["FOO", "bar"]),
+        Some("ERR unknown command 'FOO', with args beginning with: 'bar' ".to_owned())
+    );
+    assert_eq!(
+        rejection(&["foo"]),
+        Some("ERR unknown command 'foo', with args beginning with: ".to_owned())
+    );
+    let long = "x".repeat(200);
+    let error = rejection(&["FOO", &long, "next"]).unwrap();
+    assert_eq!(error, format!("ERR unknown command 'FOO', with args beginning with: '{}' ", "x".repeat(128)));
+    assert_eq!(
+        rejection(&["FOO", "a\r\nb"]),
+        Some("ERR unknown command 'FOO', with args beginning with: 'a  b' ".to_owned())
+    );
+}
+
+#[test]
+fn test_binary_safe_values() {
+    let mut ctx = create_test_context();
+    let key = b"bin\x00key\xff".to_vec();
+    let value = b"line1\r\nline2\x00\x80\xfe".to_vec();
+
+    let set_command = Command::new(&[b"SET".to_vec(), key.clone(), value.clone()]);
+    let result = command_executor::execute_command(&set_command, &mut ctx).unwrap();
+    assert_eq!(result, b"+OK\r\n");
+
+    let get_command = Command::new(&[b"GET".to_vec(), key.clone()]);
+    let result = command_executor::execute_command(&get_command, &mut ctx).unwrap();
+    let mut expected = format!("${}\r\n", value.len()).into_bytes();
+    expected.extend_from_slice(&value);
+    expected.extend_from_slice(b"\r\n");
+    assert_eq!(result, expected);
+
+    // Hash fields and values are binary safe as well
+    let hash_key = b"hash\xfe".to_vec();
+    let hset_command = Command::new(&[b"HSET".to_vec(), hash_key.clone(), b"f\r\n".to_vec(), value.clone()]);
+    command_executor::execute_command(&hset_command, &mut ctx).unwrap();
+    let hget_command = Command::new(&[b"HGET".to_vec(), hash_key, b"f\r\n".to_vec()]);
+    let result = command_executor::execute_command(&hget_command, &mut ctx).unwrap();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn test_keys_without_ttl_survive_later_sets() {
+    let mut ctx = create_test_context();
+
+    // Every key must own its value; a later SET must not drop an earlier key.
+    for i in 0..5 {
+        let set_command = Command::new(&[b"SET".to_vec(), format!("key{}", i).into_bytes(), b"v".to_vec()]);
+        command_executor::execute_command(&set_command, &mut ctx).unwrap();
+    }
+    for i in 0..5 {
+        let get_command = Command::new(&[b"GET".to_vec(), format!("key{}", i).into_bytes()]);
+        let result = command_executor::execute_command(&get_command, &mut ctx).unwrap();
+        assert_eq!(result, b"$1\r\nv\r\n");
+    }
+}
+
+#[test]
+fn test_expiration_flow() {
+    let mut ctx = create_test_context();
+
+    let set_command = Command::new(&[b"SET".to_vec(), b"session".to_vec(), b"abc".to_vec(), b"EX".to_vec(), b"100".to_vec()]);
+    command_executor::execute_command(&set_command, &mut ctx).unwrap();
+    let ttl_command = Command::new(&[b"TTL".to_vec(), b"session".to_vec()]);
+    let result = command_executor::execute_command(&ttl_command, &mut ctx).unwrap();
+    assert_eq!(result, b":100\r\n");
+
+    // PERSIST removes the deadline
+    let persist_command = Command::new(&[b"PERSIST".to_vec(), b"session".to_vec()]);
+    let result = command_executor::execute_command(&persist_command, &mut ctx).unwrap();
+    assert_eq!(result, b":1\r\n");
+    let result = command_executor::execute_command(&ttl_command, &mut ctx).unwrap();
+    assert_eq!(result, b":-1\r\n");
+    let result = command_executor::execute_command(&persist_command, &mut ctx).unwrap();
+    assert_eq!(result, b":0\r\n");
+
+    // EXPIRE sets a new one, and a plain SET clears it again
+    let expire_command = Command::new(&[b"EXPIRE".to_vec(), b"session".to_vec(), b"50".to_vec()]);
+    let result = command_executor::execute_command(&expire_command, &mut ctx).unwrap();
+    assert_eq!(result, b":1\r\n");
+    let result = command_executor::execute_command(&ttl_command, &mut ctx).unwrap();
+    assert_eq!(result, b":50\r\n");
+    let set_command = Command::new(&[b"SET".to_vec(), b"session".to_vec(), b"xyz".to_vec()]);
+    command_executor::execute_command(&set_command, &mut ctx).unwrap();
+    let result = command_executor::execute_command(&ttl_command, &mut ctx).unwrap();
+    assert_eq!(result, b":-1\r\n");
+
+    // A non-positive EXPIRE deletes the key immediately
+    let expire_command = Command::new(&[b"EXPIRE".to_vec(), b"session".to_vec(), b"-1".to_vec()]);
+    let result = command_executor::execute_command(&expire_command, &mut ctx).unwrap();
+    assert_eq!(result, b":1\r\n");
+    let result = command_executor::execute_command(&ttl_command, &mut ctx).unwrap();
+    assert_eq!(result, b":-2\r\n");
+
+    // EXPIRE on a missing key
+    let expire_command = Command::new(&[b"EXPIRE".to_vec(), b"missing".to_vec(), b"10".to_vec()]);
+    let result = command_executor::execute_command(&expire_command, &mut ctx).unwrap();
+    assert_eq!(result, b":0\r\n");
+}
+
+#[test]
+fn test_lazy_and_active_expiry() {
+    let mut ctx = create_test_context();
+    for key in ["lazy", "active", "alive"] {
+        let set_command = Command::new(&[b"SET".to_vec(), key.as_bytes().to_vec(), b"v".to_vec()]);
+        command_executor::execute_command(&set_command, &mut ctx).unwrap();
+    }
+    let soon = now_ms() + 1;
+    ctx.set_expiry(b"lazy", soon);
+    ctx.set_expiry(b"active", soon);
+    std::thread::sleep(std::time::Duration::from_millis(5));
+
+    // Reading an expired key deletes it on access
+    let get_command = Command::new(&[b"GET".to_vec(), b"lazy".to_vec()]);
+    let result = command_executor::execute_command(&get_command, &mut ctx).unwrap();
+    assert_eq!(result, b"$-1\r\n");
+
+    // The sweep reclaims the one nobody touched
+    let dbsize_command = Command::new(&[b"DBSIZE".to_vec()]);
+    let result = command_executor::execute_command(&dbsize_command, &mut ctx).unwrap();
+    assert_eq!(result, b":2\r\n");
+    assert_eq!(ctx.active_expire_cycle(), 1);
+    let result = command_executor::execute_command(&dbsize_command, &mut ctx).unwrap();
+    assert_eq!(result, b":1\r\n");
+}
+
+#[test]
+fn test_wrong_type_errors() {
+    let mut ctx = create_test_context();
+    let set_command = Command::new(&[b"SET".to_vec(), b"str".to_vec(), b"v".to_vec()]);
+    command_executor::execute_command(&set_command, &mut ctx).unwrap();
+
+    let wrongtype: &[u8] = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+    let commands: [&[&[u8]]; 5] = [
+        &[b"HSET", b"str", b"f", b"v"],
+        &[b"HGET", b"str", b"f"],
+        &[b"LPUSH", b"str", b"a"],
+        &[b"SADD", b"str", b"a"],
+        &[b"ZADD", b"str", b"1", b"a"],
+    ];
+    for args in commands {
+        let args: Vec<Vec<u8>> = args.iter().map(|arg| arg.to_vec()).collect();
+        let result = command_executor::execute_command(&Command::new(&args), &mut ctx).unwrap();
+        assert_eq!(result, wrongtype);
+    }
+
+    // The string itself is untouched
+    let get_command = Command::new(&[b"GET".to_vec(), b"str".to_vec()]);
+    let result = command_executor::execute_command(&get_command, &mut ctx).unwrap();
+    assert_eq!(result, b"$1\r\nv\r\n");
+
+    // And a string command on a hash fails the same way
+    let hset_command = Command::new(&[b"HSET".to_vec(), b"hash".to_vec(), b"f".to_vec(), b"v".to_vec()]);
+    command_executor::execute_command(&hset_command, &mut ctx).unwrap();
+    let get_command = Command::new(&[b"GET".to_vec(), b"hash".to_vec()]);
+    let result = command_executor::execute_command(&get_command, &mut ctx).unwrap();
+    assert_eq!(result, wrongtype);
+}
+
+#[test]
+fn test_counter_commands() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["INCRBY", "n", "10"]), b":10\r\n");
+    assert_eq!(run(&mut ctx, &["DECRBY", "n", "3"]), b":7\r\n");
+    assert_eq!(run(&mut ctx, &["INCRBY", "n", "-10"]), b":-3\r\n");
+    assert_eq!(run(&mut ctx, &["INCRBYFLOAT", "n", "0.5"]), b"$4\r\n-2.5\r\n");
+    assert_eq!(run(&mut ctx, &["INCR", "n"]), b"-ERR value is not an integer or out of range\r\n");
+
+    // Argument and overflow errors
+    assert_eq!(run(&mut ctx, &["INCRBY", "n", "ten"]), b"-ERR value is not an integer or out of range\r\n");
+    assert_eq!(run(&mut ctx, &["INCRBYFLOAT", "n", "nan"]), b"-ERR value is not a valid float\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "max", &i64::MAX.to_string()]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["INCRBY", "max", "1"]), b"-ERR increment or decrement would overflow\r\n");
+    assert_eq!(run(&mut ctx, &["DECRBY", "zero", &i64::MIN.to_string()]), b"-ERR decrement would overflow\r\n");
+
+    // Counters keep their TTL
+    assert_eq!(run(&mut ctx, &["SETEX", "c", "100", "1"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["INCRBY", "c", "1"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "c"]), b":100\r\n");
+}
+
+#[test]
+fn test_string_editing_commands() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["APPEND", "s", "Hello"]), b":5\r\n");
+    assert_eq!(run(&mut ctx, &["APPEND", "s", " World"]), b":11\r\n");
+    assert_eq!(run(&mut ctx, &["STRLEN", "s"]), b":11\r\n");
+    assert_eq!(run(&mut ctx, &["STRLEN", "missing"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["GETRANGE", "s", "0", "4"]), b"$5\r\nHello\r\n");
+    assert_eq!(run(&mut ctx, &["GETRANGE", "s", "-5", "-1"]), b"$5\r\nWorld\r\n");
+    assert_eq!(run(&mut ctx, &["GETRANGE", "missing", "0", "-1"]), b"$0\r\n\r\n");
+
+    assert_eq!(run(&mut ctx, &["SETRANGE", "s", "6", "Redis"]), b":11\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "s"]), b"$11\r\nHello Redis\r\n");
+    assert_eq!(run(&mut ctx, &["SETRANGE", "pad", "3", "x"]), b":4\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "pad"]), b"$4\r\n\x00\x00\x00x\r\n");
+    assert_eq!(run(&mut ctx, &["SETRANGE", "empty", "5", ""]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "empty"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["SETRANGE", "s", "-1", "x"]), b"-ERR offset is out of range\r\n");
+    assert_eq!(
+        run(&mut ctx, &["SETRANGE", "s", "536870912", "x"]),
+        b"-ERR string exceeds maximum allowed size (proto-max-bulk-len)\r\n"
+    );
+}
+
+#[test]
+fn test_get_and_set_variants() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["GETSET", "k", "a"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["EXPIRE", "k", "100"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["GETSET", "k", "b"]), b"$1\r\na\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "k"]), b":-1\r\n");
+
+    assert_eq!(run(&mut ctx, &["GETEX", "k", "EX", "50"]), b"$1\r\nb\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "k"]), b":50\r\n");
+    assert_eq!(run(&mut ctx, &["GETEX", "k", "PERSIST"]), b"$1\r\nb\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "k"]), b":-1\r\n");
+    assert_eq!(run(&mut ctx, &["GETEX", "k", "EX", "1", "PERSIST"]), b"-ERR syntax error\r\n");
+    assert_eq!(run(&mut ctx, &["GETEX", "k", "PX", "0"]), b"-ERR invalid expire time in 'getex' command\r\n");
+    assert_eq!(run(&mut ctx, &["GETEX", "k", "PXAT", "1"]), b"$1\r\nb\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "k"]), b":0\r\n");
+
+    assert_eq!(run(&mut ctx, &["SET", "d", "v"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["GETDEL", "d"]), b"$1\r\nv\r\n");
+    assert_eq!(run(&mut ctx, &["GETDEL", "d"]), b"$-1\r\n");
+
+    assert_eq!(run(&mut ctx, &["SETNX", "n", "1"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["SETNX", "n", "2"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "n"]), b"$1\r\n1\r\n");
+
+    assert_eq!(run(&mut ctx, &["PSETEX", "p", "100000", "v"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "p"]), b":100\r\n");
+    assert_eq!(run(&mut ctx, &["SETEX", "p", "0", "v"]), b"-ERR invalid expire time in 'setex' command\r\n");
+    assert_eq!(run(&mut ctx, &["SETEX", "p", "x", "v"]), b"-ERR value is not an integer or out of range\r\n");
+    assert_eq!(
+        run(&mut ctx, &["SETEX", "p", "9223372036854775807", "v"]),
+        b"-ERR invalid expire time in 'setex' command\r\n"
+    );
+
+    // Type errors
+    assert_eq!(run(&mut ctx, &["HSET", "h", "f", "v"]), b":1\r\n");
+    let wrongtype: &[u8] = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+    for args in [&["GETSET", "h", "v"][..], &["GETDEL", "h"], &["APPEND", "h", "v"], &["STRLEN", "h"], &["INCRBYFLOAT", "h", "1"]] {
+        assert_eq!(run(&mut ctx, args), wrongtype);
+    }
+}
+
+#[test]
+fn test_multi_key_string_commands() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["MSET", "a", "1", "b", "2"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["HSET", "h", "f", "v"]), b":1\r\n");
+    assert_eq!(
+        run(&mut ctx, &["MGET", "a", "missing", "h", "b"]),
+        b"*4\r\n$1\r\n1\r\n$-1\r\n$-1\r\n$1\r\n2\r\n"
+    );
+
+    // MSETNX sets nothing if any key exists
+    assert_eq!(run(&mut ctx, &["MSETNX", "c", "3", "a", "x"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "c"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["MSETNX", "c", "3", "d", "4"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["MGET", "c", "d"]), b"*2\r\n$1\r\n3\r\n$1\r\n4\r\n");
+}
+
+#[test]
+fn test_set_conditions_and_get() {
+    let mut ctx = create_test_context();
+    // The lock pattern: only the first NX wins
+    assert_eq!(run(&mut ctx, &["SET", "lock", "a", "NX", "PX", "30000"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "lock", "b", "NX", "PX", "30000"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "lock"]), b"$1\r\na\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "lock"]), b":30\r\n");
+
+    assert_eq!(run(&mut ctx, &["SET", "missing", "v", "XX"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "missing"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "lock", "c", "xx"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "lock"]), b":-1\r\n");
+
+    // GET returns the old value whether or not the write happens
+    assert_eq!(run(&mut ctx, &["SET", "lock", "d", "GET"]), b"$1\r\nc\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "new", "v", "GET"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "lock", "e", "NX", "GET"]), b"$1\r\nd\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "lock"]), b"$1\r\nd\r\n");
+
+    // GET against another type fails without writing
+    assert_eq!(run(&mut ctx, &["HSET", "h", "f", "v"]), b":1\r\n");
+    assert_eq!(
+        run(&mut ctx, &["SET", "h", "v", "GET"]),
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["HGET", "h", "f"]), b"$1\r\nv\r\n");
+    // Without GET, SET simply replaces the hash
+    assert_eq!(run(&mut ctx, &["SET", "h", "v"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "h"]), b"$1\r\nv\r\n");
+}
+
+#[test]
+fn test_set_expiry_options() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["SET", "k", "v", "EX", "100"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "k"]), b":100\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "k", "w", "KEEPTTL"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "k"]), b":100\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "k", "x"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "k"]), b":-1\r\n");
+
+    let in_a_minute = (now_ms() / 1000 + 60).to_string();
+    assert_eq!(run(&mut ctx, &["SET", "k", "v", "EXAT", &in_a_minute]), b"+OK\r\n");
+    let ttl = run(&mut ctx, &["TTL", "k"]);
+    assert!(ttl == b":60\r\n" || ttl == b":59\r\n");
+    let in_a_minute_ms = (now_ms() + 60_000).to_string();
+    assert_eq!(run(&mut ctx, &["SET", "k", "v", "PXAT", &in_a_minute_ms, "XX"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "k"]), b":60\r\n");
+
+    // A deadline in the past stores nothing visible
+    assert_eq!(run(&mut ctx, &["SET", "k", "v", "PXAT", "1"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "k"]), b":0\r\n");
+}
+
+#[test]
+fn test_set_option_errors() {
+    let mut ctx = create_test_context();
+    let syntax_error: &[u8] = b"-ERR syntax error\r\n";
+    for args in [
+        &["SET", "k", "v", "NX", "XX"][..],
+        &["SET", "k", "v", "EX", "10", "PX", "100"],
+        &["SET", "k", "v", "EX", "10", "KEEPTTL"],
+        &["SET", "k", "v", "KEEPTTL", "EXAT", "10"],
+        &["SET", "k", "v", "EX", "10", "EX", "10"],
+        &["SET", "k", "v", "EX"],
+        &["SET", "k", "v", "FOO"],
+    ] {
+        assert_eq!(run(&mut ctx, args), syntax_error);
+    }
+    assert_eq!(run(&mut ctx, &["SET", "k", "v", "EX", "0"]), b"-ERR invalid expire time in 'set' command\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "k", "v", "PX", "-5"]), b"-ERR invalid expire time in 'set' command\r\n");
+    assert_eq!(
+        run(&mut ctx, &["SET", "k", "v", "EX", "9223372036854775807"]),
+        b"-ERR invalid expire time in 'set' command\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["SET", "k", "v", "EX", "ten"]), b"-ERR value is not an integer or out of range\r\n");
+    assert_eq!(run(&mut ctx, &["DBSIZE"]), b":0\r\n");
+}
+
+#[test]
+fn test_keys_and_type() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["MSET", "user:1", "a", "user:2", "b", "user:10", "c", "other", "d"]);
+    run(&mut ctx, &["HSET", "hash", "f", "v"]);
+
+    assert!(run(&mut ctx, &["KEYS", "user:?"]).starts_with(b"*2\r\n"));
+    assert_eq!(run(&mut ctx, &["KEYS", "user:1*"]).len(), b"*2\r\n$6\r\nuser:1\r\n$7\r\nuser:10\r\n".len());
+    assert_eq!(run(&mut ctx, &["KEYS", "user:[^1]"]), b"*1\r\n$6\r\nuser:2\r\n");
+    assert_eq!(run(&mut ctx, &["KEYS", "nomatch*"]), b"*0\r\n");
+    assert!(run(&mut ctx, &["KEYS", "*"]).starts_with(b"*5\r\n"));
+
+    assert_eq!(run(&mut ctx, &["TYPE", "user:1"]), b"+string\r\n");
+    assert_eq!(run(&mut ctx, &["TYPE", "hash"]), b"+hash\r\n");
+    assert_eq!(run(&mut ctx, &["TYPE", "missing"]), b"+none\r\n");
+}
+
+#[test]
+fn test_ttl_family() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "k", "v", "PX", "5000"]);
+    let pttl = String::from_utf8(run(&mut ctx, &["PTTL", "k"])).unwrap();
+    let remaining: i64 = pttl.trim_start_matches(':').trim_end().parse().unwrap();
+    assert!(remaining > 4000 && remaining <= 5000);
+
+    assert_eq!(run(&mut ctx, &["PERSIST", "k"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["PERSIST", "k"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["PTTL", "k"]), b":-1\r\n");
+    assert_eq!(run(&mut ctx, &["PTTL", "missing"]), b":-2\r\n");
+}
+
+#[test]
+fn test_rename_and_copy() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "a", "1", "EX", "100"]);
+    run(&mut ctx, &["SET", "b", "2"]);
+
+    // RENAME overwrites the destination and carries the TTL over
+    assert_eq!(run(&mut ctx, &["RENAME", "a", "b"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "b"]), b"$1\r\n1\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "b"]), b":100\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "a"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["RENAME", "a", "b"]), b"-ERR no such key\r\n");
+    assert_eq!(run(&mut ctx, &["RENAME", "b", "b"]), b"+OK\r\n");
+
+    run(&mut ctx, &["SET", "c", "3"]);
+    assert_eq!(run(&mut ctx, &["RENAMENX", "b", "c"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["RENAMENX", "b", "d"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["RENAMENX", "missing", "e"]), b"-ERR no such key\r\n");
+
+    // COPY leaves the source alone and only replaces when asked
+    run(&mut ctx, &["HSET", "h", "f", "v"]);
+    assert_eq!(run(&mut ctx, &["COPY", "h", "h2"]), b":1\r\n");
+    run(&mut ctx, &["HSET", "h2", "f", "changed"]);
+    assert_eq!(run(&mut ctx, &["HGET", "h", "f"]), b"$1\r\nv\r\n");
+    assert_eq!(run(&mut ctx, &["COPY", "h", "h2"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["COPY", "h", "h2", "REPLACE", "DB", "0"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["HGET", "h2", "f"]), b"$1\r\nv\r\n");
+    assert_eq!(run(&mut ctx, &["COPY", "d", "d2"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["TTL", "d2"]), b":100\r\n");
+    assert_eq!(run(&mut ctx, &["COPY", "missing", "x"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["COPY", "h", "h"]), b"-ERR source and destination objects are the same\r\n");
+    assert_eq!(run(&mut ctx, &["COPY", "h", "x", "DB", "1"]), b"-ERR DB index is out of range\r\n");
+    assert_eq!(run(&mut ctx, &["COPY", "h", "x", "BOGUS"]), b"-ERR syntax error\r\n");
+}
+
+#[test]
+fn test_delete_touch_and_randomkey() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["RANDOMKEY"]), b"$-1\r\n");
+    run(&mut ctx, &["MSET", "a", "1", "b", "2", "c", "3"]);
+
+    assert_eq!(run(&mut ctx, &["TOUCH", "a", "b", "missing"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["DEL", "a", "missing"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["UNLINK", "b", "b"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["RANDOMKEY"]), b"$1\r\nc\r\n");
+}
+
+// Decodes an array reply made only of (non-nil) bulk strings.
+fn parse_bulk_array(reply: &[u8]) -> Vec<Vec<u8>> {
+    let mut lines = reply.split(|&byte| byte == b'\n').map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+    let count: usize = std::str::from_utf8(&lines.next().unwrap()[1..]).unwrap().parse().unwrap();
+    (0..count)
+        .map(|_| {
+            lines.next(); // item length
+            lines.next().unwrap().to_vec()
+        })
+        .collect()
+}
+
+// Splits a SCAN-family reply into the next cursor and the returned items.
+fn parse_scan_reply(reply: &[u8]) -> (u64, Vec<Vec<u8>>) {
+    let mut lines = reply.split(|&byte| byte == b'\n').map(|line| line.strip_suffix(b"\r").unwrap_or(line));
+    assert_eq!(lines.next(), Some(&b"*2"[..]));
+    lines.next(); // cursor length
+    let cursor = std::str::from_utf8(lines.next().unwrap()).unwrap().parse().unwrap();
+    let count: usize = std::str::from_utf8(&lines.next().unwrap()[1..]).unwrap().parse().unwrap();
+    let items = (0..count)
+        .map(|_| {
+            lines.next(); // item length
+            lines.next().unwrap().to_vec()
+        })
+        .collect();
+    (cursor, items)
+}
+
+fn scan_all(ctx: &mut context, args: &[&str]) -> Vec<Vec<u8>> {
+    let mut cursor = 0;
+    let mut items = Vec::new();
+    loop {
+        let cursor_arg = cursor.to_string();
+        let mut full_args = args.to_vec();
+        let cursor_at = if args[0] == "SCAN" { 1 } else { 2 };
+        full_args.insert(cursor_at, &cursor_arg);
+        let (next, page) = parse_scan_reply(&run(ctx, &full_args));
+        items.extend(page);
+        cursor = next;
+        if cursor == 0 {
+            return items;
+        }
+    }
+}
+
+#[test]
+fn test_scan_returns_every_key() {
+    let mut ctx = create_test_context();
+    for i in 0..500 {
+        run(&mut ctx, &["SET", &format!("key:{}", i), "v"]);
+    }
+    let mut keys = scan_all(&mut ctx, &["SCAN", "COUNT", "20"]);
+    keys.sort();
+    keys.dedup();
+    assert_eq!(keys.len(), 500);
+}
+
+#[test]
+fn test_scan_is_stable_while_the_keyspace_resizes() {
+    let mut ctx = create_test_context();
+    for i in 0..200 {
+        run(&mut ctx, &["SET", &format!("stable:{}", i), "v"]);
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut cursor = 0u64;
+    let mut page = 0;
+    loop {
+        let (next, items) = parse_scan_reply(&run(&mut ctx, &["SCAN", &cursor.to_string(), "COUNT", "10"]));
+        seen.extend(items);
+        // Grow the table well past its size, then delete the extra keys again
+        if page == 2 {
+            for i in 0..3000 {
+                run(&mut ctx, &["SET", &format!("extra:{}", i), "v"]);
+            }
+        }
+        if page == 6 {
+            for i in 0..3000 {
+                run(&mut ctx, &["DEL", &format!("extra:{}", i)]);
+            }
+        }
+        page += 1;
+        cursor = next;
+        if cursor == 0 {
+            break;
+        }
+    }
+    for i in 0..200 {
+        assert!(seen.contains(format!("stable:{}", i).as_bytes()), "stable:{} was never returned", i);
+    }
+}
+
+#[test]
+fn test_scan_filters() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["MSET", "user:1", "a", "user:2", "b", "other", "c"]);
+    run(&mut ctx, &["HSET", "user:hash", "f", "v"]);
+    run(&mut ctx, &["SADD", "user:set", "m"]);
+
+    let mut matched = scan_all(&mut ctx, &["SCAN", "MATCH", "user:?"]);
+    matched.sort();
+    assert_eq!(matched, vec![b"user:1".to_vec(), b"user:2".to_vec()]);
+
+    let typed = scan_all(&mut ctx, &["SCAN", "TYPE", "HASH"]);
+    assert_eq!(typed, vec![b"user:hash".to_vec()]);
+    let typed = scan_all(&mut ctx, &["SCAN", "MATCH", "user:*", "TYPE", "set", "COUNT", "1000"]);
+    assert_eq!(typed, vec![b"user:set".to_vec()]);
+
+    assert_eq!(run(&mut ctx, &["SCAN", "abc"]), b"-ERR invalid cursor\r\n");
+    assert_eq!(run(&mut ctx, &["SCAN", "0", "COUNT", "0"]), b"-ERR syntax error\r\n");
+    assert_eq!(run(&mut ctx, &["SCAN", "0", "COUNT", "x"]), b"-ERR value is not an integer or out of range\r\n");
+    assert_eq!(run(&mut ctx, &["SCAN", "0", "MATCH"]), b"-ERR syntax error\r\n");
+    assert_eq!(run(&mut ctx, &["SSCAN", "user:set", "0", "TYPE", "set"]), b"-ERR syntax error\r\n");
+}
+
+#[test]
+fn test_collection_scans() {
+    let mut ctx = create_test_context();
+    for i in 0..100 {
+        run(&mut ctx, &["HSET", "h", &format!("field:{}", i), &i.to_string()]);
+        run(&mut ctx, &["SADD", "s", &format!("member:{}", i)]);
+        run(&mut ctx, &["ZADD", "z", &i.to_string(), &format!("member:{}", i)]);
+    }
+
+    let pairs = scan_all(&mut ctx, &["HSCAN", "h", "COUNT", "7"]);
+    assert_eq!(pairs.len(), 200);
+    let fields: std::collections::HashSet<_> = pairs.chunks(2).map(|pair| pair[0].clone()).collect();
+    assert_eq!(fields.len(), 100);
+    assert!(pairs.chunks(2).any(|pair| pair == [b"field:42".to_vec(), b"42".to_vec()]));
+    assert_eq!(scan_all(&mut ctx, &["HSCAN", "h", "NOVALUES"]).len(), 100);
+    assert_eq!(scan_all(&mut ctx, &["HSCAN", "h", "MATCH", "field:1?"]).len(), 20);
+
+    assert_eq!(scan_all(&mut ctx, &["SSCAN", "s"]).len(), 100);
+    assert_eq!(scan_all(&mut ctx, &["SSCAN", "s", "MATCH", "*:9*"]).len(), 11);
+
+    let pairs = scan_all(&mut ctx, &["ZSCAN", "z", "MATCH", "member:7"]);
+    assert_eq!(pairs, vec![b"member:7".to_vec(), b"7".to_vec()]);
+
+    // Missing keys read as empty, other types fail
+    assert_eq!(run(&mut ctx, &["HSCAN", "missing", "0"]), b"*2\r\n$1\r\n0\r\n*0\r\n");
+    assert_eq!(
+        run(&mut ctx, &["SSCAN", "h", "0"]),
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+    );
+}
+
+#[test]
+fn test_hash_read_commands() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["HSET", "user", "name", "John", "age", "30"]);
+
+    let all = run(&mut ctx, &["HGETALL", "user"]);
+    assert!(all.starts_with(b"*4\r\n"));
+    assert!(all.windows(20).any(|w| w == b"$4\r\nname\r\n$4\r\nJohn\r\n"));
+    assert!(run(&mut ctx, &["HKEYS", "user"]).starts_with(b"*2\r\n"));
+    assert!(run(&mut ctx, &["HVALS", "user"]).starts_with(b"*2\r\n"));
+    assert_eq!(run(&mut ctx, &["HLEN", "user"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["HEXISTS", "user", "name"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["HEXISTS", "user", "email"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["HSTRLEN", "user", "name"]), b":4\r\n");
+    assert_eq!(run(&mut ctx, &["HSTRLEN", "user", "email"]), b":0\r\n");
+    assert_eq!(
+        run(&mut ctx, &["HMGET", "user", "age", "email", "name"]),
+        b"*3\r\n$2\r\n30\r\n$-1\r\n$4\r\nJohn\r\n"
+    );
+
+    // Missing keys read as empty
+    assert_eq!(run(&mut ctx, &["HGETALL", "missing"]), b"*0\r\n");
+    assert_eq!(run(&mut ctx, &["HLEN", "missing"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["HMGET", "missing", "f"]), b"*1\r\n$-1\r\n");
+
+    run(&mut ctx, &["SET", "str", "v"]);
+    assert_eq!(
+        run(&mut ctx, &["HGETALL", "str"]),
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+    );
+}
+
+#[test]
+fn test_hash_write_commands() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["HSETNX", "h", "f", "1"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["HSETNX", "h", "f", "2"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["HGET", "h", "f"]), b"$1\r\n1\r\n");
+
+    assert_eq!(run(&mut ctx, &["HINCRBY", "h", "f", "41"]), b":42\r\n");
+    assert_eq!(run(&mut ctx, &["HINCRBY", "h", "new", "-5"]), b":-5\r\n");
+    assert_eq!(run(&mut ctx, &["HINCRBYFLOAT", "h", "f", "0.5"]), b"$4\r\n42.5\r\n");
+    assert_eq!(run(&mut ctx, &["HINCRBY", "h", "f", "1"]), b"-ERR hash value is not an integer\r\n");
+    assert_eq!(run(&mut ctx, &["HINCRBY", "h", "f", "x"]), b"-ERR value is not an integer or out of range\r\n");
+    run(&mut ctx, &["HSET", "h", "text", "abc", "max", &i64::MAX.to_string()]);
+    assert_eq!(run(&mut ctx, &["HINCRBYFLOAT", "h", "text", "1"]), b"-ERR hash value is not a float\r\n");
+    assert_eq!(run(&mut ctx, &["HINCRBY", "h", "max", "1"]), b"-ERR increment or decrement would overflow\r\n");
+
+    // Deleting the last field deletes the key
+    assert_eq!(run(&mut ctx, &["HDEL", "h", "f", "new", "missing"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["HDEL", "h", "text", "max"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "h"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["TYPE", "h"]), b"+none\r\n");
+    assert_eq!(run(&mut ctx, &["HDEL", "h", "f"]), b":0\r\n");
+}
+
+#[test]
+fn test_hrandfield() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["HRANDFIELD", "h"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["HRANDFIELD", "h", "3"]), b"*0\r\n");
+    run(&mut ctx, &["HSET", "h", "a", "1", "b", "2", "c", "3"]);
+
+    let single = run(&mut ctx, &["HRANDFIELD", "h"]);
+    assert!([&b"$1\r\na\r\n"[..], b"$1\r\nb\r\n", b"$1\r\nc\r\n"].contains(&single.as_slice()));
+
+    // Positive counts are distinct and capped at the hash size
+    let mut fields = parse_bulk_array(&run(&mut ctx, &["HRANDFIELD", "h", "10"]));
+    fields.sort();
+    assert_eq!(fields, vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()]);
+
+    // Negative counts may repeat
+    assert!(run(&mut ctx, &["HRANDFIELD", "h", "-7"]).starts_with(b"*7\r\n"));
+    let with_values = run(&mut ctx, &["HRANDFIELD", "h", "2", "WITHVALUES"]);
+    assert!(with_values.starts_with(b"*4\r\n"));
+    assert_eq!(run(&mut ctx, &["HRANDFIELD", "h", "2", "WITHSCORES"]), b"-ERR syntax error\r\n");
+}
+
+#[test]
+fn test_list_push_pop_and_range() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["RPUSH", "l", "a", "b", "c"]), b":3\r\n");
+    assert_eq!(run(&mut ctx, &["LPUSH", "l", "z"]), b":4\r\n");
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["LRANGE", "l", "0", "-1"])), [b"z", b"a", b"b", b"c"]);
+    assert_eq!(run(&mut ctx, &["LRANGE", "l", "-2", "100"]), b"*2\r\n$1\r\nb\r\n$1\r\nc\r\n");
+    assert_eq!(run(&mut ctx, &["LRANGE", "l", "x", "1"]), b"-ERR value is not an integer or out of range\r\n");
+    assert_eq!(run(&mut ctx, &["LINDEX", "l", "-1"]), b"$1\r\nc\r\n");
+    assert_eq!(run(&mut ctx, &["LINDEX", "l", "9"]), b"$-1\r\n");
+
+    assert_eq!(run(&mut ctx, &["LPOP", "l"]), b"$1\r\nz\r\n");
+    assert_eq!(run(&mut ctx, &["RPOP", "l", "2"]), b"*2\r\n$1\r\nc\r\n$1\r\nb\r\n");
+    assert_eq!(run(&mut ctx, &["LPOP", "l", "-1"]), b"-ERR value is out of range, must be positive\r\n");
+
+    // Popping the last element deletes the key
+    assert_eq!(run(&mut ctx, &["LPOP", "l", "5"]), b"*1\r\n$1\r\na\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "l"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["LPOP", "l"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["LPOP", "l", "1"]), b"*-1\r\n");
+    assert_eq!(run(&mut ctx, &["LLEN", "l"]), b":0\r\n");
+
+    // The X variants only push to existing lists
+    assert_eq!(run(&mut ctx, &["LPUSHX", "l", "a"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "l"]), b":0\r\n");
+    run(&mut ctx, &["RPUSH", "l", "a"]);
+    assert_eq!(run(&mut ctx, &["RPUSHX", "l", "b", "c"]), b":3\r\n");
+
+    run(&mut ctx, &["SET", "str", "v"]);
+    assert_eq!(
+        run(&mut ctx, &["RPUSH", "str", "a"]),
+        b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+    );
+}
+
+#[test]
+fn test_list_editing_commands() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["RPUSH", "l", "a", "b", "a", "c", "a"]);
+
+    assert_eq!(run(&mut ctx, &["LSET", "l", "1", "B"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["LSET", "l", "10", "x"]), b"-ERR index out of range\r\n");
+    assert_eq!(run(&mut ctx, &["LSET", "missing", "0", "x"]), b"-ERR no such key\r\n");
+
+    assert_eq!(run(&mut ctx, &["LINSERT", "l", "BEFORE", "c", "x"]), b":6\r\n");
+    assert_eq!(run(&mut ctx, &["LINSERT", "l", "AFTER", "nope", "x"]), b":-1\r\n");
+    assert_eq!(run(&mut ctx, &["LINSERT", "missing", "AFTER", "a", "x"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["LINSERT", "l", "ASIDE", "a", "x"]), b"-ERR syntax error\r\n");
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["LRANGE", "l", "0", "-1"])), [b"a", b"B", b"a", b"x", b"c", b"a"]);
+
+    assert_eq!(run(&mut ctx, &["LPOS", "l", "a"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["LPOS", "l", "a", "RANK", "-1"]), b":5\r\n");
+    assert_eq!(run(&mut ctx, &["LPOS", "l", "a", "COUNT", "0"]), b"*3\r\n:0\r\n:2\r\n:5\r\n");
+    assert_eq!(run(&mut ctx, &["LPOS", "l", "a", "COUNT", "0", "MAXLEN", "3"]), b"*2\r\n:0\r\n:2\r\n");
+    assert_eq!(run(&mut ctx, &["LPOS", "l", "nope"]), b"$-1\r\n");
+    assert!(run(&mut ctx, &["LPOS", "l", "a", "RANK", "0"]).starts_with(b"-ERR RANK can't be zero"));
+
+    assert_eq!(run(&mut ctx, &["LREM", "l", "-1", "a"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["LREM", "l", "0", "a"]), b":2\r\n");
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["LRANGE", "l", "0", "-1"])), [b"B", b"x", b"c"]);
+
+    assert_eq!(run(&mut ctx, &["LTRIM", "l", "1", "-1"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["LLEN", "l"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["LTRIM", "l", "5", "10"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "l"]), b":0\r\n");
+}
+
+#[test]
+fn test_lmove_and_lmpop() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["RPUSH", "src", "a", "b", "c"]);
+
+    assert_eq!(run(&mut ctx, &["LMOVE", "src", "dst", "LEFT", "RIGHT"]), b"$1\r\na\r\n");
+    assert_eq!(run(&mut ctx, &["LMOVE", "src", "dst", "RIGHT", "LEFT"]), b"$1\r\nc\r\n");
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["LRANGE", "dst", "0", "-1"])), [b"c", b"a"]);
+
+    // Rotating a list onto itself
+    assert_eq!(run(&mut ctx, &["LMOVE", "dst", "dst", "LEFT", "RIGHT"]), b"$1\r\nc\r\n");
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["LRANGE", "dst", "0", "-1"])), [b"a", b"c"]);
+    assert_eq!(run(&mut ctx, &["LMOVE", "missing", "dst", "LEFT", "LEFT"]), b"$-1\r\n");
+
+    // A wrong-type destination leaves the source alone
+    run(&mut ctx, &["SET", "str", "v"]);
+    assert!(run(&mut ctx, &["LMOVE", "src", "str", "LEFT", "LEFT"]).starts_with(b"-WRONGTYPE"));
+    assert_eq!(run(&mut ctx, &["LLEN", "src"]), b":1\r\n");
+
+    assert_eq!(
+        run(&mut ctx, &["LMPOP", "3", "missing", "dst", "src", "RIGHT", "COUNT", "5"]),
+        b"*2\r\n$3\r\ndst\r\n*2\r\n$1\r\nc\r\n$1\r\na\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["LMPOP", "2", "dst", "src", "LEFT"]), b"*2\r\n$3\r\nsrc\r\n*1\r\n$1\r\nb\r\n");
+    assert_eq!(run(&mut ctx, &["LMPOP", "1", "src", "LEFT"]), b"*-1\r\n");
+    assert_eq!(run(&mut ctx, &["LMPOP", "0", "src", "LEFT"]), b"-ERR numkeys should be greater than 0\r\n");
+    assert_eq!(run(&mut ctx, &["LMPOP", "1", "src", "LEFT", "COUNT", "0"]), b"-ERR count should be greater than 0\r\n");
+    assert_eq!(run(&mut ctx, &["LMPOP", "2", "src", "LEFT"]), b"-ERR syntax error\r\n");
+}
+
+#[test]
+fn test_blocking_pops_with_data_do_not_block() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["RPUSH", "b", "1", "2", "3"]);
+
+    assert_eq!(run(&mut ctx, &["BLPOP", "a", "b", "0"]), b"*2\r\n$1\r\nb\r\n$1\r\n1\r\n");
+    assert_eq!(run(&mut ctx, &["BRPOP", "b", "1.5"]), b"*2\r\n$1\r\nb\r\n$1\r\n3\r\n");
+    assert_eq!(run(&mut ctx, &["BLMOVE", "b", "c", "RIGHT", "LEFT", "0"]), b"$1\r\n2\r\n");
+    assert_eq!(
+        run(&mut ctx, &["BLMPOP", "0", "2", "b", "c", "LEFT", "COUNT", "2"]),
+        b"*2\r\n$1\r\nc\r\n*1\r\n$1\r\n2\r\n"
+    );
+
+    // Without a connection to park, an empty result is just nil
+    assert_eq!(run(&mut ctx, &["BLPOP", "a", "0"]), b"*-1\r\n");
+    assert_eq!(run(&mut ctx, &["BLPOP", "a", "-1"]), b"-ERR timeout is negative\r\n");
+    assert_eq!(run(&mut ctx, &["BLPOP", "a", "soon"]), b"-ERR timeout is not a float or out of range\r\n");
+    assert_eq!(run(&mut ctx, &["BLMPOP", "0", "1", "a", "UP"]), b"-ERR syntax error\r\n");
+}
+
+#[test]
+fn test_zadd_scores() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "inf", "top", "-inf", "bottom", "-0.5", "m"]), b":3\r\n");
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "nan", "x"]), b"-ERR value is not a valid float\r\n");
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "1.5x", "x"]), b"-ERR value is not a valid float\r\n");
+
+    let (_, items) = parse_scan_reply(&run(&mut ctx, &["ZSCAN", "z", "0", "MATCH", "top"]));
+    assert_eq!(items, [b"top".to_vec(), b"inf".to_vec()]);
+}
+
+#[test]
+fn test_zrange_variants() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["ZADD", "z", "1", "a", "2", "b", "2", "c", "3", "d", "4.5", "e"]);
+
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["ZRANGE", "z", "0", "-1"])), [b"a", b"b", b"c", b"d", b"e"]);
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["ZRANGE", "z", "0", "1", "REV"])), [b"e", b"d"]);
+    assert_eq!(
+        run(&mut ctx, &["ZRANGE", "z", "0", "0", "WITHSCORES"]),
+        b"*2\r\n$1\r\na\r\n$1\r\n1\r\n"
+    );
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["ZRANGE", "z", "(1", "3", "BYSCORE"])), [b"b", b"c", b"d"]);
+    assert_eq!(
+        parse_bulk_array(&run(&mut ctx, &["ZRANGE", "z", "+inf", "2", "BYSCORE", "REV", "LIMIT", "1", "2"])),
+        [b"d", b"c"]
+    );
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["ZRANGE", "z", "[b", "(d", "BYLEX"])), [b"b", b"c"]);
+    assert_eq!(parse_bulk_array(&run(&mut ctx, &["ZRANGE", "z", "-", "+", "BYLEX", "LIMIT", "3", "-1"])), [b"d", b"e"]);
+
+    assert_eq!(run(&mut ctx, &["ZRANGE", "z", "x", "1"]), b"-ERR value is not an integer or out of range\r\n");
+    assert_eq!(run(&mut ctx, &["ZRANGE", "z", "x", "1", "BYSCORE"]), b"-ERR min or max is not a float\r\n");
+    assert_eq!(run(&mut ctx, &["ZRANGE", "z", "a", "b", "BYLEX"]), b"-ERR min or max not valid string range item\r\n");
+    assert!(run(&mut ctx, &["ZRANGE", "z", "0", "1", "LIMIT", "0", "1"]).starts_with(b"-ERR syntax error, LIMIT"));
+    assert!(run(&mut ctx, &["ZRANGE", "z", "-", "+", "BYLEX", "WITHSCORES"]).starts_with(b"-ERR syntax error, WITHSCORES"));
+    assert_eq!(run(&mut ctx, &["ZRANGE", "missing", "0", "-1"]), b"*0\r\n");
+}
+
+#[test]
+fn test_zset_read_commands() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["ZADD", "z", "1", "a", "2", "b", "3", "c"]);
+
+    assert_eq!(run(&mut ctx, &["ZCARD", "z"]), b":3\r\n");
+    assert_eq!(run(&mut ctx, &["ZSCORE", "z", "b"]), b"$1\r\n2\r\n");
+    assert_eq!(run(&mut ctx, &["ZSCORE", "z", "x"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["ZMSCORE", "z", "a", "x"]), b"*2\r\n$1\r\n1\r\n$-1\r\n");
+    assert_eq!(run(&mut ctx, &["ZRANK", "z", "c"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["ZREVRANK", "z", "c"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["ZREVRANK", "z", "a", "WITHSCORE"]), b"*2\r\n:2\r\n$1\r\n1\r\n");
+    assert_eq!(run(&mut ctx, &["ZRANK", "z", "x"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["ZRANK", "z", "x", "WITHSCORE"]), b"*-1\r\n");
+    assert_eq!(run(&mut ctx, &["ZCOUNT", "z", "(1", "+inf"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["ZCOUNT", "z", "nan", "1"]), b"-ERR min or max is not a float\r\n");
+    assert_eq!(run(&mut ctx, &["ZLEXCOUNT", "z", "-", "(c"]), b":2\r\n");
+
+    let mut members = parse_bulk_array(&run(&mut ctx, &["ZRANDMEMBER", "z", "5"]));
+    members.sort();
+    assert_eq!(members, [b"a", b"b", b"c"]);
+    assert!(run(&mut ctx, &["ZRANDMEMBER", "z", "-4", "WITHSCORES"]).starts_with(b"*8\r\n"));
+    assert_eq!(run(&mut ctx, &["ZRANDMEMBER", "missing"]), b"$-1\r\n");
+}
+
+#[test]
+fn test_zset_write_commands() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["ZINCRBY", "z", "2.5", "a"]), b"$3\r\n2.5\r\n");
+    assert_eq!(run(&mut ctx, &["ZINCRBY", "z", "-1", "a"]), b"$3\r\n1.5\r\n");
+    run(&mut ctx, &["ZADD", "z", "inf", "top"]);
+    assert_eq!(run(&mut ctx, &["ZINCRBY", "z", "-inf", "top"]), b"-ERR resulting score is not a number (NaN)\r\n");
+
+    run(&mut ctx, &["ZADD", "z", "2", "b", "3", "c", "4", "d"]);
+    assert_eq!(run(&mut ctx, &["ZPOPMAX", "z"]), b"*2\r\n$3\r\ntop\r\n$3\r\ninf\r\n");
+    assert_eq!(run(&mut ctx, &["ZPOPMIN", "z", "2"]), b"*4\r\n$1\r\na\r\n$3\r\n1.5\r\n$1\r\nb\r\n$1\r\n2\r\n");
+    assert_eq!(run(&mut ctx, &["ZPOPMIN", "z", "-1"]), b"-ERR value is out of range, must be positive\r\n");
+
+    run(&mut ctx, &["ZADD", "z", "5", "e", "6", "f"]);
+    assert_eq!(run(&mut ctx, &["ZREM", "z", "c", "missing"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["ZREMRANGEBYSCORE", "z", "-inf", "(5"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["ZREMRANGEBYRANK", "z", "-1", "-1"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["ZREMRANGEBYLEX", "z", "-", "+"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "z"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["ZPOPMIN", "z"]), b"*0\r\n");
+}
+
+#[test]
+fn test_zadd_flags() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "XX", "1", "a"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "z"]), b":0\r\n");
+
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "NX", "1", "a", "2", "b"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "NX", "5", "a", "3", "c"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["ZSCORE", "z", "a"]), b"$1\r\n1\r\n");
+
+    // CH also counts updated members; GT only lets scores go up
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "GT", "CH", "0", "a", "9", "b", "4", "new"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["ZSCORE", "z", "b"]), b"$1\r\n9\r\n");
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "LT", "CH", "0", "b"]), b":1\r\n");
+
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "INCR", "2", "a"]), b"$1\r\n3\r\n");
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "NX", "INCR", "2", "a"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "GT", "INCR", "-1", "a"]), b"$-1\r\n");
+
+    assert_eq!(
+        run(&mut ctx, &["ZADD", "z", "NX", "XX", "1", "a"]),
+        b"-ERR XX and NX options at the same time are not compatible\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["ZADD", "z", "GT", "LT", "1", "a"]),
+        b"-ERR GT, LT, and/or NX options at the same time are not compatible\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["ZADD", "z", "INCR", "1", "a", "2", "b"]),
+        b"-ERR INCR option supports a single increment-element pair\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["ZADD", "z", "CH", "1"]), b"-ERR syntax error\r\n");
+}
+
+#[test]
+fn test_zset_algebra() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["ZADD", "a", "1", "x", "2", "y", "3", "z"]);
+    run(&mut ctx, &["ZADD", "b", "10", "y", "20", "z", "30", "w"]);
+    run(&mut ctx, &["SADD", "s", "z", "v"]);
+
+    assert_eq!(
+        run(&mut ctx, &["ZUNION", "2", "a", "b", "WITHSCORES"]),
+        b"*8\r\n$1\r\nx\r\n$1\r\n1\r\n$1\r\ny\r\n$2\r\n12\r\n$1\r\nz\r\n$2\r\n23\r\n$1\r\nw\r\n$2\r\n30\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["ZINTER", "2", "a", "b", "WEIGHTS", "2", "1", "AGGREGATE", "MIN", "WITHSCORES"]),
+        b"*4\r\n$1\r\ny\r\n$1\r\n4\r\n$1\r\nz\r\n$1\r\n6\r\n"
+    );
+    // Plain sets take part with a score of 1
+    assert_eq!(
+        run(&mut ctx, &["ZINTER", "2", "a", "s", "AGGREGATE", "MAX", "WITHSCORES"]),
+        b"*2\r\n$1\r\nz\r\n$1\r\n3\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["ZDIFF", "3", "a", "b", "s"]), b"*1\r\n$1\r\nx\r\n");
+    assert_eq!(run(&mut ctx, &["ZINTER", "2", "a", "missing"]), b"*0\r\n");
+
+    assert_eq!(run(&mut ctx, &["ZUNIONSTORE", "dest", "2", "a", "b", "AGGREGATE", "MAX"]), b":4\r\n");
+    assert_eq!(run(&mut ctx, &["ZSCORE", "dest", "y"]), b"$2\r\n10\r\n");
+    assert_eq!(run(&mut ctx, &["ZINTERSTORE", "dest", "1", "dest", "WEIGHTS", "0.5"]), b":4\r\n");
+    assert_eq!(run(&mut ctx, &["ZSCORE", "dest", "x"]), b"$3\r\n0.5\r\n");
+    // An empty result deletes the destination
+    assert_eq!(run(&mut ctx, &["ZDIFFSTORE", "dest", "2", "a", "a"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "dest"]), b":0\r\n");
+
+    assert_eq!(run(&mut ctx, &["ZINTERCARD", "2", "a", "b"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["ZINTERCARD", "2", "a", "b", "LIMIT", "1"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["ZINTERCARD", "2", "a", "b", "LIMIT", "0"]), b":2\r\n");
+
+    run(&mut ctx, &["SET", "str", "v"]);
+    assert!(run(&mut ctx, &["ZUNION", "2", "a", "str"]).starts_with(b"-WRONGTYPE"));
+    assert_eq!(
+        run(&mut ctx, &["ZUNION", "0", "a"]),
+        b"-ERR at least 1 input key is needed for 'zunion' command\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["ZINTER", "2", "a", "b", "WEIGHTS", "1"]), b"-ERR syntax error\r\n");
+    assert_eq!(
+        run(&mut ctx, &["ZINTER", "2", "a", "b", "WEIGHTS", "1", "x"]),
+        b"-ERR weight value is not a float\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["ZDIFF", "1", "a", "WEIGHTS", "1"]), b"-ERR syntax error\r\n");
+    assert_eq!(
+        run(&mut ctx, &["ZINTERCARD", "1", "a", "LIMIT", "-1"]),
+        b"-ERR LIMIT can't be negative\r\n"
+    );
+}
+
+// Set replies come in no particular order.
+fn sorted_members(reply: &[u8]) -> Vec<String> {
+    let mut members: Vec<String> = parse_bulk_array(reply)
+        .into_iter()
+        .map(|member| String::from_utf8(member).unwrap())
+        .collect();
+    members.sort();
+    members
+}
+
+#[test]
+fn test_set_member_commands() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SADD", "s", "a", "b", "c"]);
+
+    assert_eq!(sorted_members(&run(&mut ctx, &["SMEMBERS", "s"])), ["a", "b", "c"]);
+    assert_eq!(run(&mut ctx, &["SMEMBERS", "missing"]), b"*0\r\n");
+    assert_eq!(run(&mut ctx, &["SCARD", "s"]), b":3\r\n");
+    assert_eq!(run(&mut ctx, &["SISMEMBER", "s", "a"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["SISMEMBER", "missing", "a"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["SMISMEMBER", "s", "a", "x", "c"]), b"*3\r\n:1\r\n:0\r\n:1\r\n");
+
+    assert_eq!(run(&mut ctx, &["SREM", "s", "a", "x"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["SMOVE", "s", "t", "b"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["SMOVE", "s", "t", "b"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["SMOVE", "s", "s", "c"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["SMEMBERS", "t"]), b"*1\r\n$1\r\nb\r\n");
+    assert_eq!(run(&mut ctx, &["SREM", "s", "c"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "s"]), b":0\r\n");
+
+    run(&mut ctx, &["SET", "str", "v"]);
+    assert_eq!(run(&mut ctx, &["SMOVE", "missing", "str", "b"]), b":0\r\n");
+    assert!(run(&mut ctx, &["SMOVE", "t", "str", "b"]).starts_with(b"-WRONGTYPE"));
+    assert!(run(&mut ctx, &["SCARD", "str"]).starts_with(b"-WRONGTYPE"));
+}
+
+#[test]
+fn test_spop_and_srandmember() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["SPOP", "s"]), b"$-1\r\n");
+    assert_eq!(run(&mut ctx, &["SPOP", "s", "2"]), b"*0\r\n");
+    assert_eq!(run(&mut ctx, &["SRANDMEMBER", "s"]), b"$-1\r\n");
+    run(&mut ctx, &["SADD", "s", "a", "b", "c"]);
+
+    assert_eq!(sorted_members(&run(&mut ctx, &["SRANDMEMBER", "s", "10"])), ["a", "b", "c"]);
+    assert!(run(&mut ctx, &["SRANDMEMBER", "s", "-5"]).starts_with(b"*5\r\n"));
+    assert_eq!(run(&mut ctx, &["SCARD", "s"]), b":3\r\n");
+
+    assert!(run(&mut ctx, &["SPOP", "s"]).starts_with(b"$1\r\n"));
+    assert_eq!(run(&mut ctx, &["SPOP", "s", "0"]), b"*0\r\n");
+    assert!(run(&mut ctx, &["SPOP", "s", "5"]).starts_with(b"*2\r\n"));
+    assert_eq!(run(&mut ctx, &["EXISTS", "s"]), b":0\r\n");
+
+    assert_eq!(
+        run(&mut ctx, &["SPOP", "s", "-1"]),
+        b"-ERR value is out of range, must be positive\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["SRANDMEMBER", "s", "x"]), b"-ERR value is not an integer or out of range\r\n");
+}
+
+#[test]
+fn test_set_algebra() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SADD", "a", "1", "2", "3", "4"]);
+    run(&mut ctx, &["SADD", "b", "3", "4", "5"]);
+    run(&mut ctx, &["SADD", "c", "4", "6"]);
+
+    assert_eq!(sorted_members(&run(&mut ctx, &["SUNION", "a", "b", "missing"])), ["1", "2", "3", "4", "5"]);
+    assert_eq!(sorted_members(&run(&mut ctx, &["SINTER", "a", "b"])), ["3", "4"]);
+    assert_eq!(run(&mut ctx, &["SINTER", "a", "b", "c"]), b"*1\r\n$1\r\n4\r\n");
+    assert_eq!(run(&mut ctx, &["SINTER", "a", "missing"]), b"*0\r\n");
+    assert_eq!(sorted_members(&run(&mut ctx, &["SDIFF", "a", "b", "missing"])), ["1", "2"]);
+    assert_eq!(run(&mut ctx, &["SDIFF", "missing", "a"]), b"*0\r\n");
+
+    assert_eq!(run(&mut ctx, &["SUNIONSTORE", "dest", "b", "c"]), b":4\r\n");
+    assert_eq!(run(&mut ctx, &["SINTERSTORE", "dest", "dest", "a"]), b":2\r\n");
+    assert_eq!(sorted_members(&run(&mut ctx, &["SMEMBERS", "dest"])), ["3", "4"]);
+    // An empty result deletes the destination
+    assert_eq!(run(&mut ctx, &["SDIFFSTORE", "dest", "dest", "a"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "dest"]), b":0\r\n");
+
+    assert_eq!(run(&mut ctx, &["SINTERCARD", "2", "a", "b"]), b":2\r\n");
+    assert_eq!(run(&mut ctx, &["SINTERCARD", "2", "a", "b", "LIMIT", "1"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["SINTERCARD", "2", "a", "missing"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["SINTERCARD", "0", "a"]), b"-ERR numkeys should be greater than 0\r\n");
+    assert_eq!(run(&mut ctx, &["SINTERCARD", "3", "a", "b"]), b"-ERR syntax error\r\n");
+
+    run(&mut ctx, &["SET", "str", "v"]);
+    assert!(run(&mut ctx, &["SUNION", "a", "str"]).starts_with(b"-WRONGTYPE"));
+    assert!(run(&mut ctx, &["SINTER", "missing", "str"]).starts_with(b"-WRONGTYPE"));
+}
+
+#[test]
+fn test_object_encoding() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SADD", "ints", "1", "2", "3"]);
+    run(&mut ctx, &["SADD", "words", "a", "b"]);
+    run(&mut ctx, &["HSET", "h", "f", "v"]);
+    run(&mut ctx, &["SET", "n", "12"]);
+
+    assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "ints"]), b"$6\r\nintset\r\n");
+    assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "words"]), b"$8\r\nlistpack\r\n");
+    assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "h"]), b"$8\r\nlistpack\r\n");
+    assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "n"]), b"$3\r\nint\r\n");
+    assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "missing"]), b"$-1\r\n");
+    // Only integers written the canonical way
+    for not_canonical in ["+1", "01", "-0"] {
+        run(&mut ctx, &["SET", "n", not_canonical]);
+        assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "n"]), b"$6\r\nembstr\r\n", "{}", not_canonical);
+    }
+
+    // Growing past the thresholds converts to a hash table
+    run(&mut ctx, &["SADD", "ints", "x"]);
+    assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "ints"]), b"$8\r\nlistpack\r\n");
+    let long = "v".repeat(65);
+    run(&mut ctx, &["HSET", "h", "g", &long]);
+    assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "h"]), b"$9\r\nhashtable\r\n");
+    assert_eq!(run(&mut ctx, &["HGET", "h", "f"]), b"$1\r\nv\r\n");
+    assert_eq!(sorted_members(&run(&mut ctx, &["SMEMBERS", "ints"])), ["1", "2", "3", "x"]);
+
+    assert_eq!(
+        run(&mut ctx, &["OBJECT", "FOO", "h"]),
+        b"-ERR unknown subcommand 'FOO'. Try OBJECT HELP.\r\n"
+    );
+}
+
+#[test]
+fn test_config_get_and_set() {
+    let mut ctx = create_test_context();
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "GET", "hash-max-listpack-*"]),
+        b"*4\r\n$25\r\nhash-max-listpack-entries\r\n$3\r\n128\r\n$23\r\nhash-max-listpack-value\r\n$2\r\n64\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["CONFIG", "GET", "nothing"]), b"*0\r\n");
+
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "set-max-intset-entries", "2"]), b"+OK\r\n");
+    run(&mut ctx, &["SADD", "s", "1", "2", "3"]);
+    assert_eq!(run(&mut ctx, &["OBJECT", "ENCODING", "s"]), b"$9\r\nhashtable\r\n");
+
+    // A bad pair leaves the others unchanged
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "SET", "set-max-intset-entries", "5", "set-max-listpack-value", "x"]),
+        b"-ERR CONFIG SET failed (possibly related to argument 'set-max-listpack-value') - argument couldn't be parsed into an integer\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "GET", "set-max-intset-entries"]),
+        b"*2\r\n$22\r\nset-max-intset-entries\r\n$1\r\n2\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "SET", "nope", "1"]),
+        b"-ERR Unknown option or number of arguments for CONFIG SET - 'nope'\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "SET", "set-max-intset-entries"]),
+        b"-ERR wrong number of arguments for 'config|set' command\r\n"
+    );
+}
+
+// Limits maxmemory to what is in use now plus `headroom` bytes.
+fn limit_memory(ctx: &mut context, headroom: usize, policy: &str) {
+    let maxmemory = (ctx.DataBase.used_memory + headroom).to_string();
+    assert_eq!(
+        run(ctx, &["CONFIG", "SET", "maxmemory", &maxmemory, "maxmemory-policy", policy, "maxmemory-samples", "64"]),
+        b"+OK\r\n"
+    );
+}
+
+#[test]
+fn test_maxmemory_noeviction() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "a", "1"]);
+    limit_memory(&mut ctx, 0, "noeviction");
+    run(&mut ctx, &["SET", "big", &"x".repeat(1000)]);
+
+    // Over the limit: writes that grow the dataset are refused, the rest run
+    let oom = b"-OOM command not allowed when used memory > 'maxmemory'.\r\n";
+    assert_eq!(run(&mut ctx, &["SET", "b", "2"]), oom);
+    assert_eq!(run(&mut ctx, &["RPUSH", "list", "x"]), oom);
+    assert_eq!(run(&mut ctx, &["GET", "a"]), b"$1\r\n1\r\n");
+    assert_eq!(run(&mut ctx, &["DEL", "big"]), b":1\r\n");
+    assert_eq!(run(&mut ctx, &["SET", "b", "2"]), b"+OK\r\n");
+    assert!(String::from_utf8(run(&mut ctx, &["INFO", "stats"])).unwrap().contains("evicted_keys:0\r\n"));
+}
+
+#[test]
+fn test_maxmemory_lru_and_lfu_keep_the_hot_keys() {
+    for policy in ["allkeys-lru", "allkeys-lfu"] {
+        // Same-sized keys, so each write over the limit costs one eviction
+        let mut ctx = create_test_context();
+        for i in 0..10 {
+            run(&mut ctx, &["SET", &format!("c{}", i), "v"]);
+        }
+        run(&mut ctx, &["SET", "hh", "v"]);
+        for i in 0..10 {
+            let entry = ctx.DataBase.store.get_mut(format!("c{}", i).as_bytes()).unwrap();
+            entry.lru = 0;
+            entry.lfu = 0;
+        }
+        ctx.DataBase.store.get_mut(b"hh".as_slice()).unwrap().lfu = 200;
+
+        limit_memory(&mut ctx, 0, policy);
+        for i in 0..5 {
+            assert_eq!(run(&mut ctx, &["SET", &format!("n{}", i), "v"]), b"+OK\r\n");
+        }
+        assert_eq!(run(&mut ctx, &["EXISTS", "hh"]), b":1\r\n", "{}", policy);
+        // One eviction before each SET but the first, and one before EXISTS
+        let info = String::from_utf8(run(&mut ctx, &["INFO"])).unwrap();
+        assert!(info.contains("evicted_keys:5\r\n"), "{}: {}", policy, info);
+        assert!(info.contains(&format!("maxmemory_policy:{}\r\n", policy)));
+        assert!(info.contains("db0:keys=11,expires=0\r\n"));
+    }
+}
+
+#[test]
+fn test_maxmemory_volatile_policies() {
+    let oom = b"-OOM command not allowed when used memory > 'maxmemory'.\r\n";
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "p1", "v"]);
+    run(&mut ctx, &["SET", "t1", "v", "EX", "100"]);
+    run(&mut ctx, &["SET", "t2", "v", "EX", "1000"]);
+    limit_memory(&mut ctx, 0, "volatile-ttl");
+
+    // The key closest to expiring goes first, keys without a TTL never do
+    run(&mut ctx, &["SET", "n1", "v"]);
+    assert_eq!(run(&mut ctx, &["EXISTS", "t1"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "t2"]), b":1\r\n");
+    run(&mut ctx, &["SET", "n2", "v"]);
+    assert_eq!(run(&mut ctx, &["EXISTS", "t2"]), b":0\r\n");
+    run(&mut ctx, &["SET", "n3", "v"]);
+    assert_eq!(run(&mut ctx, &["SET", "n4", "v"]), oom);
+    assert_eq!(run(&mut ctx, &["EXISTS", "p1", "n1", "n2", "n3"]), b":4\r\n");
+
+    for policy in ["volatile-random", "volatile-lru", "volatile-lfu"] {
+        let mut ctx = create_test_context();
+        run(&mut ctx, &["SET", "p1", "v"]);
+        run(&mut ctx, &["SET", "t1", "v", "EX", "100"]);
+        limit_memory(&mut ctx, 0, policy);
+        run(&mut ctx, &["SET", "n1", "v"]);
+        assert_eq!(run(&mut ctx, &["EXISTS", "t1"]), b":0\r\n", "{}", policy);
+        assert_eq!(run(&mut ctx, &["SET", "n2", "v"]), b"+OK\r\n");
+        assert_eq!(run(&mut ctx, &["SET", "n3", "v"]), oom);
+        assert_eq!(run(&mut ctx, &["EXISTS", "p1", "n1", "n2"]), b":3\r\n");
+    }
+}
+
+#[test]
+fn test_maxmemory_allkeys_random() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "start", "v"]);
+    limit_memory(&mut ctx, 2000, "allkeys-random");
+    for i in 0..100 {
+        assert_eq!(run(&mut ctx, &["HSET", &format!("h{}", i), "field", "value"]), b":1\r\n");
+    }
+    // Eviction runs before each command, so only the last write can overshoot
+    run(&mut ctx, &["PING"]);
+    let maxmemory: usize = {
+        let reply = run(&mut ctx, &["CONFIG", "GET", "maxmemory"]);
+        let reply = String::from_utf8(reply).unwrap();
+        reply.split("\r\n").nth(4).unwrap().parse().unwrap()
+    };
+    assert!(ctx.DataBase.used_memory <= maxmemory);
+    assert!(ctx.DataBase.store.len() < 100);
+}
+
+#[test]
+fn test_maxmemory_config() {
+    let mut ctx = create_test_context();
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "maxmemory", "2mb"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["CONFIG", "GET", "maxmemory"]), b"*2\r\n$9\r\nmaxmemory\r\n$7\r\n2097152\r\n");
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "maxmemory", "1k"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["CONFIG", "GET", "maxmemory"]), b"*2\r\n$9\r\nmaxmemory\r\n$4\r\n1000\r\n");
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "SET", "maxmemory", "lots"]),
+        b"-ERR CONFIG SET failed (possibly related to argument 'maxmemory') - argument must be a memory value\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "SET", "maxmemory-policy", "lru"]),
+        b"-ERR CONFIG SET failed (possibly related to argument 'maxmemory-policy') - argument(s) must be one of the following: volatile-lru, volatile-lfu, volatile-random, volatile-ttl, allkeys-lru, allkeys-lfu, allkeys-random, noeviction\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "SET", "maxmemory-samples", "0"]),
+        b"-ERR CONFIG SET failed (possibly related to argument 'maxmemory-samples') - argument must be between 1 and 64 inclusive\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "maxmemory", "0"]), b"+OK\r\n");
+}
+
+#[test]
+fn test_object_freq_and_idletime() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "k", "v"]);
+    ctx.DataBase.store.get_mut(b"k".as_slice()).unwrap().lru -= 3000;
+    assert_eq!(run(&mut ctx, &["OBJECT", "IDLETIME", "k"]), b":3\r\n");
+    // OBJECT itself is not an access
+    assert_eq!(run(&mut ctx, &["OBJECT", "IDLETIME", "k"]), b":3\r\n");
+    assert_eq!(run(&mut ctx, &["OBJECT", "IDLETIME", "missing"]), b"$-1\r\n");
+    assert_eq!(
+        run(&mut ctx, &["OBJECT", "FREQ", "k"]),
+        b"-ERR An LFU maxmemory policy is not selected, access frequency not tracked.\r\n"
+    );
+
+    run(&mut ctx, &["CONFIG", "SET", "maxmemory-policy", "allkeys-lfu"]);
+    assert_eq!(run(&mut ctx, &["OBJECT", "FREQ", "k"]), b":5\r\n");
+    for _ in 0..50 {
+        run(&mut ctx, &["GET", "k"]);
+    }
+    let freq = run(&mut ctx, &["OBJECT", "FREQ", "k"]);
+    assert_ne!(freq, b":5\r\n");
+    assert_eq!(
+        run(&mut ctx, &["OBJECT", "FREQ"]),
+        b"-ERR wrong number of arguments for 'object|freq' command\r\n"
+    );
+}
+
+// The integer reply of `args`.
+fn run_integer(ctx: &mut context, args: &[&str]) -> i64 {
+    let reply = String::from_utf8(run(ctx, args)).unwrap();
+    reply.trim_start_matches(':').trim_end().parse().unwrap()
+}
+
+#[test]
+fn test_memory_usage() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "small", "v"]);
+    run(&mut ctx, &["SET", "big", &"x".repeat(10_000)]);
+    let small = run_integer(&mut ctx, &["MEMORY", "USAGE", "small"]);
+    let big = run_integer(&mut ctx, &["MEMORY", "USAGE", "big"]);
+    assert!(small > 0 && big - small > 9_900, "{} {}", small, big);
+    assert_eq!(run(&mut ctx, &["MEMORY", "USAGE", "missing"]), b"$-1\r\n");
+
+    // Every element with SAMPLES 0, an estimate from the first few otherwise
+    for i in 0..200 {
+        let value = if i < 10 { "x".to_string() } else { "x".repeat(1000) };
+        run(&mut ctx, &["RPUSH", "list", &value]);
+    }
+    let exact = run_integer(&mut ctx, &["MEMORY", "USAGE", "list", "SAMPLES", "0"]);
+    let sampled = run_integer(&mut ctx, &["MEMORY", "USAGE", "list", "SAMPLES", "5"]);
+    assert_eq!(run_integer(&mut ctx, &["MEMORY", "USAGE", "list"]), sampled);
+    assert!(exact > 190 * 1000 && sampled < exact, "{} {}", exact, sampled);
+
+    // The running total matches the keys it is made of
+    let total = ["small", "big", "list"]
+        .iter()
+        .map(|key| run_integer(&mut ctx, &["MEMORY", "USAGE", key]))
+        .sum::<i64>();
+    assert_eq!(ctx.DataBase.used_memory as i64, total);
+    run(&mut ctx, &["DEL", "big", "list"]);
+    assert_eq!(ctx.DataBase.used_memory as i64, small);
+
+    assert_eq!(run(&mut ctx, &["MEMORY", "USAGE", "small", "SAMPLES", "x"]), b"-ERR value is not an integer or out of range\r\n");
+    assert_eq!(run(&mut ctx, &["MEMORY", "USAGE", "small", "FOO", "1"]), b"-ERR syntax error\r\n");
+    assert_eq!(run(&mut ctx, &["MEMORY", "USAGE", "small", "SAMPLES", "-1"]), b"-ERR syntax error\r\n");
+    assert_eq!(run(&mut ctx, &["MEMORY", "NOPE"]), b"-ERR unknown subcommand 'NOPE'. Try MEMORY HELP.\r\n");
+}
+
+#[test]
+fn test_memory_stats() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "a", "1"]);
+    run(&mut ctx, &["SET", "b", "2", "EX", "100"]);
+    let stats = String::from_utf8(run(&mut ctx, &["MEMORY", "STATS"])).unwrap();
+    let lines: Vec<&str> = stats.split("\r\n").collect();
+    let field = |name: &str| {
+        let at = lines.iter().position(|line| *line == name).unwrap();
+        lines[at + 1].to_owned()
+    };
+
+    assert!(stats.starts_with("*14\r\n"));
+    assert_eq!(field("keys.count"), ":2");
+    assert_eq!(field("dataset.bytes"), format!(":{}", ctx.DataBase.used_memory));
+    let total: usize = field("total.allocated")[1..].parse().unwrap();
+    let overhead: usize = field("overhead.total")[1..].parse().unwrap();
+    assert_eq!(total, ctx.DataBase.used_memory + overhead);
+    assert_eq!(field("keys.bytes-per-key"), format!(":{}", total / 2));
+    assert!(stats.contains("overhead.hashtable.expires\r\n:"));
+}
+
+// A fresh directory for one test's files, made the snapshot dir.
+fn use_temp_dir(ctx: &mut context, name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("kilodb-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    assert_eq!(run(ctx, &["CONFIG", "SET", "dir", dir.to_str().unwrap()]), b"+OK\r\n");
+    dir
+}
+
+// Runs the cron until the background save in progress is done.
+fn wait_for_background_save(ctx: &mut context) {
+    for _ in 0..500 {
+        persistence::cron(ctx);
+        if !ctx.PersistenceStore.is_saving() {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    panic!("background save did not finish");
+}
+
+#[test]
+fn test_save_and_load() {
+    let mut ctx = create_test_context();
+    let dir = use_temp_dir(&mut ctx, "save");
+    run(&mut ctx, &["SET", "string", "hello"]);
+    run(&mut ctx, &["SET", "expiring", "v", "EX", "100"]);
+    run(&mut ctx, &["RPUSH", "list", "a", "b", "c"]);
+    run(&mut ctx, &["HSET", "hash", "field", "value"]);
+    run(&mut ctx, &["SADD", "set", "1", "2", "x"]);
+    run(&mut ctx, &["ZADD", "zset", "1.5", "a", "-inf", "b"]);
+    assert!(String::from_utf8(run(&mut ctx, &["INFO", "persistence"])).unwrap().contains("rdb_changes_since_last_save:6\r\n"));
+
+    assert_eq!(run(&mut ctx, &["SAVE"]), b"+OK\r\n");
+    assert!(dir.join("dump.kdb").exists());
+    let info = String::from_utf8(run(&mut ctx, &["INFO", "persistence"])).unwrap();
+    assert!(info.contains("rdb_changes_since_last_save:0\r\n"), "{}", info);
+    let last_save = run_integer(&mut ctx, &["LASTSAVE"]);
+    assert!((now_ms() / 1000) as i64 - last_save <= 1);
+
+    let mut loaded = create_test_context();
+    assert_eq!(persistence::load(&mut loaded).unwrap(), 6);
+    assert_eq!(run(&mut loaded, &["GET", "string"]), b"$5\r\nhello\r\n");
+    assert!(run_integer(&mut loaded, &["TTL", "expiring"]) > 90);
+    assert_eq!(run(&mut loaded, &["LRANGE", "list", "0", "-1"]), b"*3\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n");
+    assert_eq!(run(&mut loaded, &["HGET", "hash", "field"]), b"$5\r\nvalue\r\n");
+    assert_eq!(run(&mut loaded, &["SCARD", "set"]), b":3\r\n");
+    assert_eq!(run(&mut loaded, &["ZRANGE", "zset", "0", "-1", "WITHSCORES"]), b"*4\r\n$1\r\nb\r\n$4\r\n-inf\r\n$1\r\na\r\n$3\r\n1.5\r\n");
+    assert_eq!(run(&mut loaded, &["OBJECT", "ENCODING", "set"]), b"$8\r\nlistpack\r\n");
+
+    // A truncated file is refused rather than half loaded
+    let path = dir.join("dump.kdb");
+    let data = std::fs::read(&path).unwrap();
+    std::fs::write(&path, &data[..data.len() - 3]).unwrap();
+    assert!(persistence::load(&mut create_test_context()).is_err());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(persistence::load(&mut create_test_context()).unwrap(), 0);
+    assert_eq!(run(&mut ctx, &["SAVE", "now"]), b"-ERR wrong number of arguments for 'save' command\r\n");
+}
+
+#[test]
+fn test_bgsave_is_a_point_in_time_copy() {
+    let mut ctx = create_test_context();
+    use_temp_dir(&mut ctx, "bgsave");
+    run(&mut ctx, &["SET", "before", "1"]);
+    run(&mut ctx, &["RPUSH", "list", "a"]);
+    assert_eq!(run(&mut ctx, &["BGSAVE"]), b"+Background saving started\r\n");
+    run(&mut ctx, &["SET", "after", "2"]);
+    run(&mut ctx, &["SET", "before", "changed"]);
+    // Changed in place: the save keeps the value it started with
+    run(&mut ctx, &["RPUSH", "list", "b"]);
+    wait_for_background_save(&mut ctx);
+
+    // The writes made during the save are still pending
+    let info = String::from_utf8(run(&mut ctx, &["INFO", "persistence"])).unwrap();
+    assert!(info.contains("rdb_changes_since_last_save:3\r\n"), "{}", info);
+    assert!(info.contains("rdb_last_bgsave_status:ok\r\n"));
+    let mut loaded = create_test_context();
+    assert_eq!(persistence::load(&mut loaded).unwrap(), 2);
+    assert_eq!(run(&mut loaded, &["GET", "before"]), b"$1\r\n1\r\n");
+    assert_eq!(run(&mut loaded, &["LRANGE", "list", "0", "-1"]), b"*1\r\n$1\r\na\r\n");
+    assert_eq!(run(&mut ctx, &["LRANGE", "list", "0", "-1"]), b"*2\r\n$1\r\na\r\n$1\r\nb\r\n");
+}
+
+#[test]
+fn test_save_rules() {
+    let mut ctx = create_test_context();
+    let dir = use_temp_dir(&mut ctx, "rules");
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "save", "10 2"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["CONFIG", "GET", "save"]), b"*2\r\n$4\r\nsave\r\n$4\r\n10 2\r\n");
+    ctx.PersistenceStore.last_save -= 60;
+
+    // One change is not enough for the rule
+    run(&mut ctx, &["SET", "a", "1"]);
+    persistence::cron(&mut ctx);
+    assert!(!ctx.PersistenceStore.is_saving());
+    run(&mut ctx, &["SET", "b", "1"]);
+    persistence::cron(&mut ctx);
+    wait_for_background_save(&mut ctx);
+    assert!(dir.join("dump.kdb").exists());
+    assert_eq!(ctx.DataBase.dirty, 0);
+
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "save", ""]), b"+OK\r\n");
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "SET", "save", "10"]),
+        b"-ERR CONFIG SET failed (possibly related to argument 'save') - Invalid save parameters\r\n"
+    );
+    assert_eq!(
+        run(&mut ctx, &["CONFIG", "SET", "dbfilename", "a/b"]),
+        b"-ERR CONFIG SET failed (possibly related to argument 'dbfilename') - dbfilename can't be a path, just a filename\r\n"
+    );
+}
+
+// Turns appendonly on and waits for the rewrite that creates the file.
+fn enable_aof(ctx: &mut context, name: &str) -> std::path::PathBuf {
+    let dir = use_temp_dir(ctx, name);
+    assert_eq!(run(ctx, &["CONFIG", "SET", "appendonly", "yes"]), b"+OK\r\n");
+    wait_for_rewrite(ctx);
+    dir.join("appendonly.aof")
+}
+
+// Runs the AOF cron until the rewrite in progress is done.
+fn wait_for_rewrite(ctx: &mut context) {
+    for _ in 0..500 {
+        aof::cron(ctx);
+        if ctx.AofStore.rewrite.is_none() {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    panic!("AOF rewrite did not finish");
+}
+
+#[test]
+fn test_aof_logs_writes_and_replays_them() {
+    let mut ctx = create_test_context();
+    let path = enable_aof(&mut ctx, "aof");
+    assert_eq!(std::fs::read(&path).unwrap(), b"");
+
+    run(&mut ctx, &["SET", "counter", "1"]);
+    run(&mut ctx, &["INCR", "counter"]);
+    run(&mut ctx, &["GET", "counter"]);
+    run(&mut ctx, &["DEL", "missing"]);
+    run(&mut ctx, &["SET", "session", "x", "EX", "100"]);
+    run(&mut ctx, &["SADD", "set", "a", "b", "c"]);
+    let popped = run(&mut ctx, &["SPOP", "set"]);
+    let log = String::from_utf8(std::fs::read(&path).unwrap()).unwrap();
+    assert!(log.starts_with("*3\r\n$3\r\nSET\r\n$7\r\ncounter\r\n$1\r\n1\r\n*2\r\n$4\r\nINCR\r\n"), "{}", log);
+
+    // Reads and no-op writes are not logged, relative TTLs are pinned and SPOP
+    // becomes the SREM of what it popped
+    assert!(!log.contains("GET") && !log.contains("DEL") && !log.contains("SPOP"), "{}", log);
+    assert!(log.contains("PEXPIREAT"));
+    let member = String::from_utf8(popped[4..5].to_vec()).unwrap();
+    assert!(log.ends_with(&format!("*3\r\n$4\r\nSREM\r\n$3\r\nset\r\n$1\r\n{}\r\n", member)), "{}", log);
+
+    let mut loaded = create_test_context();
+    assert_eq!(aof::load(&mut loaded).unwrap(), 6);
+    assert_eq!(run(&mut loaded, &["GET", "counter"]), b"$1\r\n2\r\n");
+    assert_eq!(run(&mut loaded, &["PTTL", "session"]), run(&mut ctx, &["PTTL", "session"]));
+    assert_eq!(run(&mut loaded, &["SMEMBERS", "set"]).len(), run(&mut ctx, &["SMEMBERS", "set"]).len());
+    assert_eq!(run(&mut loaded, &["SISMEMBER", "set", &member]), b":0\r\n");
+
+    // A key that expires is logged as deleted
+    run(&mut ctx, &["PEXPIREAT", "counter", &(now_ms() - 1).to_string()]);
+    run(&mut ctx, &["SET", "gone", "1", "PX", "1"]);
+    std::thread::sleep(std::time::Duration::from_millis(5));
+    run(&mut ctx, &["GET", "gone"]);
+    let log = String::from_utf8(std::fs::read(&path).unwrap()).unwrap();
+    assert!(log.ends_with("*2\r\n$3\r\nDEL\r\n$4\r\ngone\r\n"), "{}", log);
+    let mut loaded = create_test_context();
+    aof::load(&mut loaded).unwrap();
+    assert_eq!(run(&mut loaded, &["EXISTS", "counter", "gone"]), b":0\r\n");
+
+    // Turning it off stops the logging
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "appendonly", "no"]), b"+OK\r\n");
+    let length = std::fs::metadata(&path).unwrap().len();
+    run(&mut ctx, &["SET", "unlogged", "1"]);
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), length);
+    assert!(ctx.AofStore.file.is_none());
+}
+
+#[test]
+fn test_aof_truncated_tail() {
+    let mut ctx = create_test_context();
+    let path = enable_aof(&mut ctx, "aof-truncated");
+    run(&mut ctx, &["SET", "a", "1"]);
+    run(&mut ctx, &["RPUSH", "list", "x", "y"]);
+    let complete = std::fs::read(&path).unwrap();
+
+    // A crash in the middle of the last command
+    let mut data = complete.clone();
+    data.extend_from_slice(b"*3\r\n$3\r\nSET\r\n$1\r\nb\r\n$5\r\nhel");
+    std::fs::write(&path, &data).unwrap();
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "aof-load-truncated", "no"]), b"+OK\r\n");
+    assert!(matches!(aof::load(&mut create_test_context()), Err(aof::AofError::Truncated(_))));
+
+    run(&mut ctx, &["CONFIG", "SET", "aof-load-truncated", "yes"]);
+    let mut loaded = create_test_context();
+    assert_eq!(aof::load(&mut loaded).unwrap(), 2);
+    assert_eq!(run(&mut loaded, &["LRANGE", "list", "0", "-1"]), b"*2\r\n$1\r\nx\r\n$1\r\ny\r\n");
+    assert_eq!(run(&mut loaded, &["EXISTS", "b"]), b":0\r\n");
+    // The partial command is cut off so new ones are appended after whole ones
+    assert_eq!(std::fs::read(&path).unwrap(), complete);
+
+    // Garbage is an error whatever the setting
+    std::fs::write(&path, b"*1\r\n$4\r\nPING\r\nnot a command\r\n").unwrap();
+    assert!(matches!(aof::load(&mut create_test_context()), Err(aof::AofError::BadFormat(14))));
+}
+
+#[test]
+fn test_aof_skips_failed_and_no_op_writes() {
+    let mut ctx = create_test_context();
+    let path = enable_aof(&mut ctx, "aof-failed-writes");
+    run(&mut ctx, &["SET", "s", "v"]);
+    run(&mut ctx, &["HSET", "h", "f", "v"]);
+    run(&mut ctx, &["SADD", "set", "m"]);
+    let logged = std::fs::read(&path).unwrap();
+    let dirty = ctx.DataBase.dirty;
+
+    let wrongtype = b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n";
+    assert_eq!(run(&mut ctx, &["HSET", "s", "f", "v"]), wrongtype);
+    assert_eq!(run(&mut ctx, &["SADD", "s", "m"]), wrongtype);
+    assert_eq!(run(&mut ctx, &["LPUSH", "s", "x"]), wrongtype);
+    assert_eq!(run(&mut ctx, &["ZADD", "s", "1", "m"]), wrongtype);
+    assert_eq!(run(&mut ctx, &["HDEL", "h", "missing"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["SREM", "set", "missing"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["SADD", "set", "m"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["ZADD", "zset", "XX", "1", "m"]), b":0\r\n");
+    assert_eq!(run(&mut ctx, &["LPOP", "missing"]), b"$-1\r\n");
+    assert_eq!(std::fs::read(&path).unwrap(), logged);
+    assert_eq!(ctx.DataBase.dirty, dirty);
+    assert_eq!(run(&mut ctx, &["EXISTS", "zset"]), b":0\r\n");
+
+    let mut loaded = create_test_context();
+    assert_eq!(aof::load(&mut loaded).unwrap(), 3);
+    assert_eq!(run(&mut loaded, &["GET", "s"]), b"$1\r\nv\r\n");
+
+    // A file that does hold a failing command still loads, past it
+    let mut data = logged.clone();
+    data.extend_from_slice(b"*4\r\n$4\r\nHSET\r\n$1\r\ns\r\n$1\r\nf\r\n$1\r\nv\r\n");
+    data.extend_from_slice(b"*3\r\n$3\r\nSET\r\n$5\r\nafter\r\n$1\r\n1\r\n");
+    std::fs::write(&path, &data).unwrap();
+    let mut loaded = create_test_context();
+    assert_eq!(aof::load(&mut loaded).unwrap(), 5);
+    assert_eq!(run(&mut loaded, &["GET", "after"]), b"$1\r\n1\r\n");
+
+    // Unlike a command the server does not know
+    std::fs::write(&path, b"*1\r\n$7\r\nNOSUCHC\r\n").unwrap();
+    assert!(matches!(aof::load(&mut create_test_context()), Err(aof::AofError::BadCommand(_))));
+}
+
+#[test]
+fn test_aof_replays_whole_under_a_small_maxmemory() {
+    let mut ctx = create_test_context();
+    enable_aof(&mut ctx, "aof-maxmemory");
+    for i in 0..50 {
+        run(&mut ctx, &["SET", &format!("key{}", i), &"v".repeat(100)]);
+    }
+    run(&mut ctx, &["RPUSH", "list", "a", "b"]);
+
+    // This run allows far less than was logged, under either kind of policy
+    for policy in ["allkeys-lru", "noeviction"] {
+        let mut loaded = create_test_context();
+        run(&mut loaded, &["CONFIG", "SET", "maxmemory", "1000", "maxmemory-policy", policy]);
+        assert_eq!(aof::load(&mut loaded).unwrap(), 51);
+        assert_eq!(loaded.DataBase.store.len(), 51);
+        assert_eq!(loaded.EvictionStore.evicted_keys, 0);
+        run(&mut loaded, &["CONFIG", "SET", "maxmemory", "0"]);
+    }
+}
+
+#[test]
+fn test_aof_retries_the_rewrite_that_creates_it() {
+    let mut ctx = create_test_context();
+    let dir = use_temp_dir(&mut ctx, "aof-retry");
+    // A directory in the way of the rewrite's temporary file
+    let blocker = dir.join(format!("temp-rewriteaof-{}.aof", std::process::id()));
+    std::fs::create_dir(&blocker).unwrap();
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "appendonly", "yes"]), b"+OK\r\n");
+    wait_for_rewrite(&mut ctx);
+    assert!(ctx.AofStore.file.is_none());
+    assert!(!ctx.AofStore.last_rewrite_ok);
+
+    // Not right away
+    run(&mut ctx, &["SET", "a", "1"]);
+    aof::cron(&mut ctx);
+    assert!(ctx.AofStore.rewrite.is_none());
+
+    // Once the delay is over, with what was written meanwhile
+    std::fs::remove_dir(&blocker).unwrap();
+    ctx.AofStore.last_rewrite_try = 0;
+    aof::cron(&mut ctx);
+    wait_for_rewrite(&mut ctx);
+    assert!(ctx.AofStore.file.is_some() && ctx.AofStore.last_rewrite_ok);
+    run(&mut ctx, &["SET", "b", "2"]);
+    let mut loaded = create_test_context();
+    aof::load(&mut loaded).unwrap();
+    assert_eq!(run(&mut loaded, &["MGET", "a", "b"]), b"*2\r\n$1\r\n1\r\n$1\r\n2\r\n");
+    run(&mut ctx, &["CONFIG", "SET", "appendonly", "no"]);
+}
+
+#[test]
+fn test_bgrewriteaof_compacts_the_log() {
+    let mut ctx = create_test_context();
+    let path = enable_aof(&mut ctx, "aof-rewrite");
+    for _ in 0..100 {
+        run(&mut ctx, &["INCR", "counter"]);
+    }
+    for i in 0..100 {
+        run(&mut ctx, &["HSET", "hash", &format!("field{}", i), "v"]);
+    }
+    run(&mut ctx, &["ZADD", "zset", "-inf", "low", "2.5", "high"]);
+    run(&mut ctx, &["SET", "session", "x", "EX", "100"]);
+    run(&mut ctx, &["DEL", "counter"]);
+    run(&mut ctx, &["SET", "counter", "100"]);
+    let before = std::fs::metadata(&path).unwrap().len();
+
+    assert_eq!(run(&mut ctx, &["BGREWRITEAOF"]), b"+Background append only file rewriting started\r\n");
+    // Writes made during the rewrite are kept
+    run(&mut ctx, &["SET", "late", "1"]);
+    assert_eq!(
+        run(&mut ctx, &["BGREWRITEAOF"]),
+        b"-ERR Background append only file rewriting already in progress\r\n"
+    );
+    wait_for_rewrite(&mut ctx);
+    run(&mut ctx, &["SET", "later", "2"]);
+
+    let log = String::from_utf8(std::fs::read(&path).unwrap()).unwrap();
+    assert!(std::fs::metadata(&path).unwrap().len() < before);
+    assert!(!log.contains("INCR") && !log.contains("DEL"), "{}", log);
+    let info = String::from_utf8(run(&mut ctx, &["INFO", "persistence"])).unwrap();
+    assert!(info.contains("aof_enabled:1\r\naof_rewrite_in_progress:0\r\naof_last_bgrewrite_status:ok\r\n"), "{}", info);
+
+    let mut loaded = create_test_context();
+    aof::load(&mut loaded).unwrap();
+    assert_eq!(run(&mut loaded, &["GET", "counter"]), b"$3\r\n100\r\n");
+    assert_eq!(run(&mut loaded, &["HLEN", "hash"]), b":100\r\n");
+    assert_eq!(
+        run(&mut loaded, &["ZRANGE", "zset", "0", "-1", "WITHSCORES"]),
+        b"*4\r\n$3\r\nlow\r\n$4\r\n-inf\r\n$4\r\nhigh\r\n$3\r\n2.5\r\n"
+    );
+    assert_eq!(run(&mut loaded, &["PTTL", "session"]), run(&mut ctx, &["PTTL", "session"]));
+    assert_eq!(run(&mut loaded, &["MGET", "late", "later"]), b"*2\r\n$1\r\n1\r\n$1\r\n2\r\n");
+
+    // With appendonly off the file is written but not kept open
+    run(&mut ctx, &["CONFIG", "SET", "appendonly", "no"]);
+    std::fs::remove_file(&path).unwrap();
+    run(&mut ctx, &["BGREWRITEAOF"]);
+    wait_for_rewrite(&mut ctx);
+    assert!(path.exists());
+    assert!(ctx.AofStore.file.is_none());
+}
+
+// A Redis 7 RDB file with a plain key and one whose deadline has passed.
+fn redis_rdb() -> Vec<u8> {
+    let mut data = b"REDIS0011\xfe\x00\x00\x03key\x05value\xfc".to_vec();
+    data.extend_from_slice(&1000u64.to_le_bytes());
+    data.extend_from_slice(b"\x00\x04gone\x01x\xff");
+    let checksum = KiloDB::utils::crc64::crc64(0, &data);
+    data.extend_from_slice(&checksum.to_le_bytes());
+    data
+}
+
+#[test]
+fn test_load_redis_rdb() {
+    let mut ctx = create_test_context();
+    let dir = use_temp_dir(&mut ctx, "rdb");
+    std::fs::write(dir.join("dump.rdb"), redis_rdb()).unwrap();
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "dbfilename", "dump.rdb"]), b"+OK\r\n");
+
+    let mut loaded = create_test_context();
+    assert_eq!(persistence::load(&mut loaded).unwrap(), 1);
+    assert_eq!(run(&mut loaded, &["GET", "key"]), b"$5\r\nvalue\r\n");
+    assert_eq!(run(&mut loaded, &["EXISTS", "gone"]), b":0\r\n");
+
+    // Saving writes KiloDB's own format, which loads the same
+    assert_eq!(run(&mut loaded, &["SAVE"]), b"+OK\r\n");
+    assert!(std::fs::read(dir.join("dump.rdb")).unwrap().starts_with(b"KILODB"));
+    assert_eq!(persistence::load(&mut create_test_context()).unwrap(), 1);
+}
+
+#[test]
+fn test_import_rdb_tool() {
+    let dir = std::env::temp_dir().join(format!("kilodb-import-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("dump.rdb"), redis_rdb()).unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_kilodb-import-rdb"))
+        .arg(dir.join("dump.rdb"))
+        .arg(dir.join("dump.kdb"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).starts_with("Imported 2 keys"));
+    let entries = persistence::snapshot::deserialize(&std::fs::read(dir.join("dump.kdb")).unwrap()).unwrap();
+    assert_eq!(entries.len(), 2);
+
+    // Unreadable input fails with a message
+    std::fs::write(dir.join("bad.rdb"), b"REDIS0011\x0f\x06events").unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_kilodb-import-rdb"))
+        .arg(dir.join("bad.rdb"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("stream value at key 'events' not supported"));
+}
+
+// The payload DUMP returns for `key`.
+fn dump(ctx: &mut context, key: &str) -> Vec<u8> {
+    let reply = run(ctx, &["DUMP", key]);
+    let start = reply.iter().position(|&byte| byte == b'\n').unwrap() + 1;
+    reply[start..reply.len() - 2].to_vec()
+}
+
+fn restore(ctx: &mut context, key: &str, ttl: &str, payload: &[u8], options: &[&str]) -> Vec<u8> {
+    let mut args = vec![b"RESTORE".to_vec(), key.as_bytes().to_vec(), ttl.as_bytes().to_vec(), payload.to_vec()];
+    args.extend(options.iter().map(|option| option.as_bytes().to_vec()));
+    command_executor::call(&Command::new(&args), &args, ctx).unwrap()
+}
+
+#[test]
+fn test_dump_and_restore() {
+    let mut ctx = create_test_context();
+    run(&mut ctx, &["SET", "string", "hello"]);
+    run(&mut ctx, &["RPUSH", "list", "a", "b", "c"]);
+    run(&mut ctx, &["SADD", "set", "1", "2", "x"]);
+    run(&mut ctx, &["HSET", "hash", "f", "v", "g", "w"]);
+    run(&mut ctx, &["ZADD", "zset", "1.5", "a", "-inf", "b"]);
+    let reads: [&[&str]; 5] = [
+        &["GET", "string"],
+        &["LRANGE", "list", "0", "-1"],
+        &["SMEMBERS", "set"],
+        &["HGETALL", "hash"],
+        &["ZRANGE", "zset", "0", "-1", "WITHSCORES"],
+    ];
+    for read in reads {
+        let payload = dump(&mut ctx, read[1]);
+        assert_eq!(restore(&mut ctx, "copy", "0", &payload, &["REPLACE"]), b"+OK\r\n");
+        let mut copied = read.to_vec();
+        copied[1] = "copy";
+        let (original, copy) = (run(&mut ctx, read), run(&mut ctx, &copied));
+        if read[0] == "SMEMBERS" || read[0] == "HGETALL" {
+            assert_eq!(sorted_members(&original), sorted_members(&copy));
+        } else {
+            assert_eq!(original, copy, "{}", read[1]);
+        }
+        assert_eq!(run(&mut ctx, &["TTL", "copy"]), b":-1\r\n");
+    }
+    assert_eq!(run(&mut ctx, &["DUMP", "missing"]), b"$-1\r\n");
+
+    // Type, "hello", format version 1, checksum
+    let payload = dump(&mut ctx, "string");
+    assert_eq!(&payload[..9], b"\x00\x05hello\x01\x00");
+    assert_eq!(payload.len(), 17);
+
+    assert_eq!(restore(&mut ctx, "string", "0", &payload, &[]), b"-BUSYKEY Target key name already exists.\r\n");
+    assert_eq!(restore(&mut ctx, "ttl", "5000", &payload, &[]), b"+OK\r\n");
+    let ttl = run_integer(&mut ctx, &["PTTL", "ttl"]);
+    assert!(ttl > 4000 && ttl <= 5000, "{}", ttl);
+    let deadline = (now_ms() + 60_000).to_string();
+    assert_eq!(restore(&mut ctx, "abs", &deadline, &payload, &["ABSTTL"]), b"+OK\r\n");
+    assert!(run_integer(&mut ctx, &["PTTL", "abs"]) > 59_000);
+    // A deadline in the past leaves no key, not even the one it replaces
+    assert_eq!(restore(&mut ctx, "abs", "1000", &payload, &["ABSTTL", "REPLACE"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "abs"]), b":0\r\n");
+
+    // Damaged payloads
+    let mut flipped = payload.clone();
+    flipped[3] ^= 1;
+    assert_eq!(restore(&mut ctx, "bad", "0", &flipped, &[]), b"-ERR DUMP payload version or checksum are wrong\r\n");
+    let mut other_version = payload.clone();
+    other_version[7] = 9;
+    assert_eq!(restore(&mut ctx, "bad", "0", &other_version, &[]), b"-ERR DUMP payload version or checksum are wrong\r\n");
+    assert_eq!(restore(&mut ctx, "bad", "0", b"short", &[]), b"-ERR Bad data format\r\n");
+    let mut garbage = b"\x09\x05hello\x01\x00".to_vec();
+    let checksum = KiloDB::utils::crc64::crc64(0, &garbage);
+    garbage.extend_from_slice(&checksum.to_le_bytes());
+    assert_eq!(restore(&mut ctx, "bad", "0", &garbage, &[]), b"-ERR Bad data format\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "bad"]), b":0\r\n");
+
+    // Options
+    assert_eq!(restore(&mut ctx, "k", "-1", &payload, &[]), b"-ERR Invalid TTL value, must be >= 0\r\n");
+    assert_eq!(restore(&mut ctx, "k", "0", &payload, &["IDLETIME", "1", "FREQ", "1"]), b"-ERR syntax error\r\n");
+    assert_eq!(restore(&mut ctx, "k", "0", &payload, &["IDLETIME", "-1"]), b"-ERR Invalid IDLETIME value, must be >= 0\r\n");
+    assert_eq!(restore(&mut ctx, "k", "0", &payload, &["FREQ", "256"]), b"-ERR Invalid FREQ value, must be >= 0 and <= 255\r\n");
+    assert_eq!(restore(&mut ctx, "idle", "0", &payload, &["IDLETIME", "100"]), b"+OK\r\n");
+    assert!(run_integer(&mut ctx, &["OBJECT", "IDLETIME", "idle"]) >= 100);
+    run(&mut ctx, &["CONFIG", "SET", "maxmemory-policy", "allkeys-lfu"]);
+    assert_eq!(restore(&mut ctx, "hot", "0", &payload, &["FREQ", "100"]), b"+OK\r\n");
+    assert_eq!(run_integer(&mut ctx, &["OBJECT", "FREQ", "hot"]), 100);
+}
+
+// Runs a command the way a connection does: through the client's transaction
+// first.
+fn run_in(transaction: &mut Transaction, ctx: &mut context, args: &[&str]) -> Vec<u8> {
+    let args: Vec<Vec<u8>> = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+    match transaction.handle(Command::new(&args), &args, ctx) {
+        Ok(reply) => reply,
+        Err(command) => command_executor::call(&command, &args, ctx).unwrap(),
+    }
+}
+
+#[test]
+fn test_multi_exec_and_discard() {
+    let mut ctx = create_test_context();
+    let mut client = Transaction::new(&mut ctx);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"-ERR EXEC without MULTI\r\n");
+    assert_eq!(run_in(&mut client, &mut ctx, &["DISCARD"]), b"-ERR DISCARD without MULTI\r\n");
+
+    assert_eq!(run_in(&mut client, &mut ctx, &["MULTI"]), b"+OK\r\n");
+    assert_eq!(run_in(&mut client, &mut ctx, &["MULTI"]), b"-ERR MULTI calls can not be nested\r\n");
+    assert_eq!(run_in(&mut client, &mut ctx, &["SET", "k", "v"]), b"+QUEUED\r\n");
+    assert_eq!(run_in(&mut client, &mut ctx, &["INCR", "counter"]), b"+QUEUED\r\n");
+    // Runtime errors are part of the replies and do not stop the rest
+    assert_eq!(run_in(&mut client, &mut ctx, &["LPUSH", "k", "x"]), b"+QUEUED\r\n");
+    assert_eq!(run_in(&mut client, &mut ctx, &["GET", "k"]), b"+QUEUED\r\n");
+    // Nothing ran yet
+    assert_eq!(run(&mut ctx, &["EXISTS", "k"]), b":0\r\n");
+    assert_eq!(
+        run_in(&mut client, &mut ctx, &["EXEC"]),
+        b"*4\r\n+OK\r\n:1\r\n-WRONGTYPE Operation against a key holding the wrong kind of value\r\n$1\r\nv\r\n"
+    );
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"-ERR EXEC without MULTI\r\n");
+
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["INCR", "counter"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["DISCARD"]), b"+OK\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "counter"]), b"$1\r\n1\r\n");
+
+    // A syntax error while queueing aborts the whole transaction
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["INCR", "counter"]);
+    assert_eq!(
+        run_in(&mut client, &mut ctx, &["LASTSAVE", "now"]),
+        b"-ERR wrong number of arguments for 'lastsave' command\r\n"
+    );
+    assert_eq!(
+        run_in(&mut client, &mut ctx, &["EXEC"]),
+        b"-EXECABORT Transaction discarded because of previous errors.\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["GET", "counter"]), b"$1\r\n1\r\n");
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["NOSUCHCOMMAND"]);
+    assert_eq!(
+        run_in(&mut client, &mut ctx, &["EXEC"]),
+        b"-EXECABORT Transaction discarded because of previous errors.\r\n"
+    );
+
+    // An empty transaction
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*0\r\n");
+}
+
+#[test]
+fn test_watch() {
+    let mut ctx = create_test_context();
+    let mut client = Transaction::new(&mut ctx);
+    let mut other = Transaction::new(&mut ctx);
+    run(&mut ctx, &["SET", "balance", "10"]);
+
+    // Untouched watched keys let EXEC through
+    assert_eq!(run_in(&mut client, &mut ctx, &["WATCH", "balance", "missing"]), b"+OK\r\n");
+    run(&mut ctx, &["SET", "unrelated", "x"]);
+    run(&mut ctx, &["GET", "balance"]);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["WATCH", "x"]), b"-ERR WATCH inside MULTI is not allowed\r\n");
+    run_in(&mut client, &mut ctx, &["DECRBY", "balance", "3"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*1\r\n:7\r\n");
+
+    // Another client's write fails the EXEC, and EXEC unwatches
+    run_in(&mut client, &mut ctx, &["WATCH", "balance"]);
+    run_in(&mut other, &mut ctx, &["INCR", "balance"]);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["DECRBY", "balance", "3"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*-1\r\n");
+    assert_eq!(run(&mut ctx, &["GET", "balance"]), b"$1\r\n8\r\n");
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["DECRBY", "balance", "3"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*1\r\n:5\r\n");
+
+    // So does a transaction of another client
+    run_in(&mut client, &mut ctx, &["WATCH", "balance"]);
+    run_in(&mut other, &mut ctx, &["MULTI"]);
+    run_in(&mut other, &mut ctx, &["DEL", "balance"]);
+    run_in(&mut other, &mut ctx, &["EXEC"]);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*-1\r\n");
+
+    // Writes that fail or change nothing do not count
+    run(&mut ctx, &["SET", "balance", "5"]);
+    run(&mut ctx, &["SADD", "members", "a"]);
+    run_in(&mut client, &mut ctx, &["WATCH", "balance", "members", "missing"]);
+    run_in(&mut other, &mut ctx, &["HSET", "balance", "f", "v"]);
+    run_in(&mut other, &mut ctx, &["SADD", "members", "a"]);
+    run_in(&mut other, &mut ctx, &["SREM", "members", "b"]);
+    run_in(&mut other, &mut ctx, &["LPOP", "missing"]);
+    run_in(&mut other, &mut ctx, &["ZADD", "missing", "XX", "1", "m"]);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["DECRBY", "balance", "3"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*1\r\n:2\r\n");
+
+    // Creating a watched key counts; UNWATCH forgets the change
+    run_in(&mut client, &mut ctx, &["WATCH", "balance"]);
+    run(&mut ctx, &["SET", "balance", "1"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["UNWATCH"]), b"+OK\r\n");
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*0\r\n");
+
+    // DISCARD unwatches too
+    run_in(&mut client, &mut ctx, &["WATCH", "balance"]);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["DISCARD"]);
+    run(&mut ctx, &["SET", "balance", "2"]);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*0\r\n");
+
+    // FLUSHDB changes the keys that existed, not the missing ones
+    run_in(&mut client, &mut ctx, &["WATCH", "balance"]);
+    run_in(&mut other, &mut ctx, &["WATCH", "missing"]);
+    run(&mut ctx, &["FLUSHDB"]);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*-1\r\n");
+    run_in(&mut other, &mut ctx, &["MULTI"]);
+    assert_eq!(run_in(&mut other, &mut ctx, &["EXEC"]), b"*0\r\n");
+}
+
+#[test]
+fn test_exec_is_logged_as_a_transaction() {
+    let mut ctx = create_test_context();
+    let path = enable_aof(&mut ctx, "aof-exec");
+    let mut client = Transaction::new(&mut ctx);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["SET", "a", "1"]);
+    run_in(&mut client, &mut ctx, &["GET", "a"]);
+    run_in(&mut client, &mut ctx, &["INCR", "a"]);
+    run_in(&mut client, &mut ctx, &["EXEC"]);
+    let logged = std::fs::read(&path).unwrap();
+    assert_eq!(
+        logged,
+        b"*1\r\n$5\r\nMULTI\r\n*3\r\n$3\r\nSET\r\n$1\r\na\r\n$1\r\n1\r\n*2\r\n$4\r\nINCR\r\n$1\r\na\r\n*1\r\n$4\r\nEXEC\r\n"
+    );
+
+    // A transaction that writes nothing logs nothing
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["GET", "a"]);
+    run_in(&mut client, &mut ctx, &["SADD", "a", "m"]);
+    run_in(&mut client, &mut ctx, &["EXEC"]);
+    assert_eq!(std::fs::read(&path).unwrap(), logged);
+
+    let mut loaded = create_test_context();
+    assert_eq!(aof::load(&mut loaded).unwrap(), 2);
+    assert_eq!(run(&mut loaded, &["GET", "a"]), b"$1\r\n2\r\n");
+
+    // A transaction cut off before its EXEC is dropped whole
+    let mut data = logged.clone();
+    data.extend_from_slice(b"*1\r\n$5\r\nMULTI\r\n*3\r\n$3\r\nSET\r\n$1\r\nb\r\n$1\r\n1\r\n");
+    std::fs::write(&path, &data).unwrap();
+    assert_eq!(run(&mut ctx, &["CONFIG", "SET", "aof-load-truncated", "no"]), b"+OK\r\n");
+    assert!(matches!(
+        aof::load(&mut create_test_context()),
+        Err(aof::AofError::Truncated(offset)) if offset == logged.len()
+    ));
+    run(&mut ctx, &["CONFIG", "SET", "aof-load-truncated", "yes"]);
+    let mut loaded = create_test_context();
+    assert_eq!(aof::load(&mut loaded).unwrap(), 2);
+    assert_eq!(run(&mut loaded, &["EXISTS", "b"]), b":0\r\n");
+    assert_eq!(std::fs::read(&path).unwrap(), logged);
+}
+
+#[test]
+fn test_watched_key_expiring_fails_exec() {
+    let mut ctx = create_test_context();
+    let mut client = Transaction::new(&mut ctx);
+    run(&mut ctx, &["SET", "lock", "held", "PX", "20"]);
+    run_in(&mut client, &mut ctx, &["WATCH", "lock"]);
+    std::thread::sleep(std::time::Duration::from_millis(30));
+
+    // Nobody touched the key, it just expired
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["SET", "lock", "mine"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*-1\r\n");
+    assert_eq!(run(&mut ctx, &["EXISTS", "lock"]), b":0\r\n");
+
+    // The same when the active expiry cycle reclaimed it first
+    run(&mut ctx, &["SET", "lock", "held", "PX", "20"]);
+    run_in(&mut client, &mut ctx, &["WATCH", "lock"]);
+    std::thread::sleep(std::time::Duration::from_millis(30));
+    assert_eq!(ctx.active_expire_cycle(), 1);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*-1\r\n");
+}
+
+// Runs a command the way a connection does, for a client that may subscribe.
+fn run_as(subscriber: &mut Subscriber, ctx: &mut context, args: &[&str]) -> Vec<u8> {
+    let args: Vec<Vec<u8>> = args.iter().map(|arg| arg.as_bytes().to_vec()).collect();
+    match subscriber.handle(Command::new(&args), &args, ctx) {
+        Ok(reply) => reply,
+        Err(command) => command_executor::call(&command, &args, ctx).unwrap(),
+    }
+}
+
+fn messages(subscriber: &mut Subscriber) -> Vec<Vec<u8>> {
+    std::iter::from_fn(|| subscriber.try_next_message()).collect()
+}
+
+#[test]
+fn test_subscribe_and_publish() {
+    let mut ctx = create_test_context();
+    let mut first = Subscriber::new(&mut ctx);
+    let mut second = Subscriber::new(&mut ctx);
+
+    assert_eq!(
+        run_as(&mut first, &mut ctx, &["SUBSCRIBE", "news", "sport"]),
+        b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$9\r\nsubscribe\r\n$5\r\nsport\r\n:2\r\n"
+    );
+    assert_eq!(
+        run_as(&mut second, &mut ctx, &["PSUBSCRIBE", "n*"]),
+        b"*3\r\n$10\r\npsubscribe\r\n$2\r\nn*\r\n:1\r\n"
+    );
+    assert_eq!(run_as(&mut second, &mut ctx, &["SUBSCRIBE", "news"]), b"*3\r\n$9\r\nsubscribe\r\n$4\r\nnews\r\n:2\r\n");
+
+    // Every subscription that matches counts as a receiver
+    assert_eq!(run(&mut ctx, &["PUBLISH", "news", "hello"]), b":3\r\n");
+    assert_eq!(messages(&mut first), [b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n".to_vec()]);
+    let received = messages(&mut second);
+    assert_eq!(received.len(), 2);
+    assert!(received.contains(&b"*4\r\n$8\r\npmessage\r\n$2\r\nn*\r\n$4\r\nnews\r\n$5\r\nhello\r\n".to_vec()));
+    assert_eq!(run(&mut ctx, &["PUBLISH", "weather", "rain"]), b":0\r\n");
+    assert!(messages(&mut first).is_empty());
+
+    // Subscribe mode only takes the subscribe family and PING
+    assert_eq!(
+        run_as(&mut first, &mut ctx, &["GET", "k"]),
+        b"-ERR Can't execute 'get': only (P|S)SUBSCRIBE / (P|S)UNSUBSCRIBE / PING / QUIT / RESET are allowed in this context\r\n"
+    );
+    assert_eq!(run_as(&mut first, &mut ctx, &["PING"]), b"*2\r\n$4\r\npong\r\n$0\r\n\r\n");
+
+    // Leaving every channel leaves subscribe mode
+    assert_eq!(
+        run_as(&mut first, &mut ctx, &["UNSUBSCRIBE"]),
+        b"*3\r\n$11\r\nunsubscribe\r\n$4\r\nnews\r\n:1\r\n*3\r\n$11\r\nunsubscribe\r\n$5\r\nsport\r\n:0\r\n"
+    );
+    assert!(!first.in_subscribe_mode());
+    assert_eq!(run_as(&mut first, &mut ctx, &["PING"]), b"+PONG\r\n");
+    assert_eq!(run_as(&mut first, &mut ctx, &["UNSUBSCRIBE"]), b"*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n");
+    assert_eq!(
+        run_as(&mut second, &mut ctx, &["PUNSUBSCRIBE", "n*", "other"]),
+        b"*3\r\n$12\r\npunsubscribe\r\n$2\r\nn*\r\n:1\r\n*3\r\n$12\r\npunsubscribe\r\n$5\r\nother\r\n:1\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["PUBLISH", "news", "again"]), b":1\r\n");
+
+    // A closed connection no longer receives anything
+    second.reset(&mut ctx);
+    assert_eq!(run(&mut ctx, &["PUBLISH", "news", "again"]), b":0\r\n");
+
+    assert_eq!(run(&mut ctx, &["SUBSCRIBE"]), b"-ERR wrong number of arguments for 'subscribe' command\r\n");
+    assert_eq!(run(&mut ctx, &["PUBLISH", "news"]), b"-ERR wrong number of arguments for 'publish' command\r\n");
+}
+
+#[test]
+fn test_sharded_pubsub() {
+    let mut ctx = create_test_context();
+    let mut client = Subscriber::new(&mut ctx);
+    assert_eq!(
+        run_as(&mut client, &mut ctx, &["SSUBSCRIBE", "orders"]),
+        b"*3\r\n$10\r\nssubscribe\r\n$6\r\norders\r\n:1\r\n"
+    );
+    // Shard channels are counted apart from the others
+    assert_eq!(run_as(&mut client, &mut ctx, &["SUBSCRIBE", "orders"]), b"*3\r\n$9\r\nsubscribe\r\n$6\r\norders\r\n:1\r\n");
+
+    assert_eq!(run(&mut ctx, &["SPUBLISH", "orders", "42"]), b":1\r\n");
+    assert_eq!(messages(&mut client), [b"*3\r\n$8\r\nsmessage\r\n$6\r\norders\r\n$2\r\n42\r\n".to_vec()]);
+    assert_eq!(run(&mut ctx, &["PUBLISH", "orders", "43"]), b":1\r\n");
+    assert_eq!(messages(&mut client), [b"*3\r\n$7\r\nmessage\r\n$6\r\norders\r\n$2\r\n43\r\n".to_vec()]);
+
+    assert_eq!(
+        run_as(&mut client, &mut ctx, &["SUNSUBSCRIBE"]),
+        b"*3\r\n$12\r\nsunsubscribe\r\n$6\r\norders\r\n:0\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["SPUBLISH", "orders", "44"]), b":0\r\n");
+    assert!(client.in_subscribe_mode());
+}
+
+#[test]
+fn test_pubsub_introspection() {
+    let mut ctx = create_test_context();
+    let mut first = Subscriber::new(&mut ctx);
+    let mut second = Subscriber::new(&mut ctx);
+    run_as(&mut first, &mut ctx, &["SUBSCRIBE", "news", "sport"]);
+    run_as(&mut second, &mut ctx, &["SUBSCRIBE", "news"]);
+    run_as(&mut first, &mut ctx, &["PSUBSCRIBE", "n*", "s*"]);
+    run_as(&mut second, &mut ctx, &["PSUBSCRIBE", "n*"]);
+    run_as(&mut second, &mut ctx, &["SSUBSCRIBE", "orders"]);
+
+    assert_eq!(sorted_members(&run(&mut ctx, &["PUBSUB", "CHANNELS"])), ["news", "sport"]);
+    assert_eq!(sorted_members(&run(&mut ctx, &["PUBSUB", "CHANNELS", "s*"])), ["sport"]);
+    assert_eq!(
+        run(&mut ctx, &["PUBSUB", "NUMSUB", "news", "sport", "none"]),
+        b"*6\r\n$4\r\nnews\r\n:2\r\n$5\r\nsport\r\n:1\r\n$4\r\nnone\r\n:0\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["PUBSUB", "NUMSUB"]), b"*0\r\n");
+    // Distinct patterns
+    assert_eq!(run(&mut ctx, &["PUBSUB", "NUMPAT"]), b":2\r\n");
+    assert_eq!(sorted_members(&run(&mut ctx, &["PUBSUB", "SHARDCHANNELS"])), ["orders"]);
+    assert_eq!(
+        run(&mut ctx, &["PUBSUB", "SHARDNUMSUB", "orders"]),
+        b"*2\r\n$6\r\norders\r\n:1\r\n"
+    );
+    let info = String::from_utf8(run(&mut ctx, &["INFO", "stats"])).unwrap();
+    assert!(info.contains("pubsub_channels:2\r\npubsub_patterns:2\r\npubsubshard_channels:1\r\n"));
+
+    run_as(&mut first, &mut ctx, &["UNSUBSCRIBE", "sport"]);
+    assert_eq!(sorted_members(&run(&mut ctx, &["PUBSUB", "CHANNELS"])), ["news"]);
+
+    assert_eq!(
+        run(&mut ctx, &["PUBSUB", "NUMPAT", "x"]),
+        b"-ERR wrong number of arguments for 'pubsub|numpat' command\r\n"
+    );
+    assert_eq!(run(&mut ctx, &["PUBSUB", "NOPE"]), b"-ERR unknown subcommand 'NOPE'. Try PUBSUB HELP.\r\n");
+}
+
+#[test]
+fn test_subscribe_inside_multi_aborts() {
+    let mut ctx = create_test_context();
+    let mut client = Transaction::new(&mut ctx);
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    assert_eq!(
+        run_in(&mut client, &mut ctx, &["SUBSCRIBE", "news"]),
+        b"-ERR Command not allowed inside a transaction\r\n"
+    );
+    assert_eq!(
+        run_in(&mut client, &mut ctx, &["EXEC"]),
+        b"-EXECABORT Transaction discarded because of previous errors.\r\n"
+    );
+
+    // PUBLISH is an ordinary command in a transaction
+    run_in(&mut client, &mut ctx, &["MULTI"]);
+    run_in(&mut client, &mut ctx, &["PUBLISH", "news", "hi"]);
+    assert_eq!(run_in(&mut client, &mut ctx, &["EXEC"]), b"*1\r\n:0\r\n");
+}
+
+#[test]
+fn test_incr_requires_a_canonical_integer() {
+    let mut ctx = create_test_context();
+    for not_canonical in ["+1", "01", "-0", " 1"] {
+        run(&mut ctx, &["SET", "n", not_canonical]);
+        assert_eq!(
+            run(&mut ctx, &["INCR", "n"]),
+            b"-ERR value is not an integer or out of range\r\n",
+            "{}",
+            not_canonical
+        );
+        assert_eq!(run(&mut ctx, &["INCRBY", "n", "5"]), b"-ERR value is not an integer or out of range\r\n");
+    }
+    run(&mut ctx, &["HSET", "h", "f", "+1"]);
+    assert_eq!(run(&mut ctx, &["HINCRBY", "h", "f", "1"]), b"-ERR hash value is not an integer\r\n");
+    run(&mut ctx, &["SET", "n", "-1"]);
+    assert_eq!(run(&mut ctx, &["INCR", "n"]), b":0\r\n");
+}
@@ -0,0 +1,409 @@
+use KiloDB::server;
+use KiloDB::store_containers::core_context::context;
+use std::cell::RefCell;
+use std::net::SocketAddr;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::LocalSet;
+use tokio::time::timeout;
+
+// Starts a server on an ephemeral port. Must be called inside a LocalSet.
+async fn start_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shared_context = Rc::new(RefCell::new(context::new()));
+    tokio::task::spawn_local(server::run(listener, shared_context));
+    addr
+}
+
+async fn send(stream: &mut TcpStream, request: &[u8]) -> Vec<u8> {
+    stream.write_all(request).await.unwrap();
+    let mut buffer = [0u8; 512];
+    let n = timeout(Duration::from_secs(2), stream.read(&mut buffer))
+        .await
+        .expect("server did not answer in time")
+        .unwrap();
+    buffer[..n].to_vec()
+}
+
+async fn read_reply(stream: &mut TcpStream, len: usize) -> Vec<u8> {
+    let mut reply = vec![0u8; len];
+    timeout(Duration::from_secs(2), stream.read_exact(&mut reply))
+        .await
+        .expect("server did not answer in time")
+        .unwrap();
+    reply
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_second_client_served_while_first_is_connected() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+
+            // The first client connects and stays idle.
+            let mut idle = TcpStream::connect(addr).await.unwrap();
+            assert_eq!(send(&mut idle, b"*1\r\n$4\r\nPING\r\n").await, b"+PONG\r\n");
+
+            // A second client must still get answers.
+            let mut active = TcpStream::connect(addr).await.unwrap();
+            assert_eq!(send(&mut active, b"*1\r\n$4\r\nPING\r\n").await, b"+PONG\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_clients_share_the_keyspace() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut writer = TcpStream::connect(addr).await.unwrap();
+            let mut reader = TcpStream::connect(addr).await.unwrap();
+
+            let reply = send(&mut writer, b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n").await;
+            assert_eq!(reply, b"+OK\r\n");
+
+            let reply = send(&mut reader, b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n").await;
+            assert_eq!(reply, b"$5\r\nvalue\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_concurrent_increments_are_atomic() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+
+            let mut clients = Vec::new();
+            for _ in 0..10 {
+                clients.push(tokio::task::spawn_local(async move {
+                    let mut stream = TcpStream::connect(addr).await.unwrap();
+                    for _ in 0..20 {
+                        send(&mut stream, b"*2\r\n$4\r\nINCR\r\n$7\r\ncounter\r\n").await;
+                    }
+                }));
+            }
+            for client in clients {
+                client.await.unwrap();
+            }
+
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let reply = send(&mut stream, b"*2\r\n$3\r\nGET\r\n$7\r\ncounter\r\n").await;
+            assert_eq!(reply, b"$3\r\n200\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_command_split_across_writes() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+
+            stream.write_all(b"*3\r\n$3\r\nSET\r\n$3\r\nk").await.unwrap();
+            stream.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            stream.write_all(b"ey\r\n$5\r\nva").await.unwrap();
+            stream.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            assert_eq!(send(&mut stream, b"lue\r\n").await, b"+OK\r\n");
+
+            let reply = send(&mut stream, b"*2\r\n$3\r\nGET\r\n$3\r\nkey\r\n").await;
+            assert_eq!(reply, b"$5\r\nvalue\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_pipelined_commands_answered_in_order() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+
+            stream
+                .write_all(
+                    b"*2\r\n$4\r\nINCR\r\n$1\r\nc\r\n*2\r\n$4\r\nINCR\r\n$1\r\nc\r\n*1\r\n$4\r\nPING\r\n*2\r\n$3\r\nGET\r\n$1\r\nc\r\n",
+                )
+                .await
+                .unwrap();
+            let expected: &[u8] = b":1\r\n:2\r\n+PONG\r\n$1\r\n2\r\n";
+            assert_eq!(read_reply(&mut stream, expected.len()).await, expected);
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_large_value_round_trip() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+
+            let value = "v".repeat(200_000);
+            let request = format!("*3\r\n$3\r\nSET\r\n$3\r\nbig\r\n${}\r\n{}\r\n", value.len(), value);
+            stream.write_all(request.as_bytes()).await.unwrap();
+            assert_eq!(read_reply(&mut stream, 5).await, b"+OK\r\n");
+
+            stream.write_all(b"*2\r\n$3\r\nGET\r\n$3\r\nbig\r\n").await.unwrap();
+            let expected = format!("${}\r\n{}\r\n", value.len(), value);
+            assert_eq!(read_reply(&mut stream, expected.len()).await, expected.as_bytes());
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_protocol_error_closes_connection() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+
+            let reply = send(&mut stream, b"*1\r\n:1\r\n").await;
+            assert_eq!(reply, b"-ERR Protocol error: expected '$', got ':'\r\n");
+
+            let mut buffer = [0u8; 16];
+            let n = timeout(Duration::from_secs(2), stream.read(&mut buffer))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(n, 0);
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_binary_value_round_trip() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+
+            let value: &[u8] = b"\x00\xff\r\n*1\r\n$4\r\nPING\r\n\x80";
+            let mut request = b"*3\r\n$3\r\nSET\r\n$3\r\nbin\r\n".to_vec();
+            request.extend_from_slice(format!("${}\r\n", value.len()).as_bytes());
+            request.extend_from_slice(value);
+            request.extend_from_slice(b"\r\n");
+            assert_eq!(send(&mut stream, &request).await, b"+OK\r\n");
+
+            stream.write_all(b"*2\r\n$3\r\nGET\r\n$3\r\nbin\r\n").await.unwrap();
+            let mut expected = format!("${}\r\n", value.len()).into_bytes();
+            expected.extend_from_slice(value);
+            expected.extend_from_slice(b"\r\n");
+            assert_eq!(read_reply(&mut stream, expected.len()).await, expected);
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_blpop_is_woken_by_a_push() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut waiter = TcpStream::connect(addr).await.unwrap();
+            let mut pusher = TcpStream::connect(addr).await.unwrap();
+
+            waiter.write_all(b"*3\r\n$5\r\nBLPOP\r\n$4\r\njobs\r\n$1\r\n0\r\n").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+
+            // The element goes straight to the waiter, the list never shows it
+            let reply = send(&mut pusher, b"*3\r\n$5\r\nRPUSH\r\n$4\r\njobs\r\n$3\r\njob\r\n").await;
+            assert_eq!(reply, b":1\r\n");
+            let expected: &[u8] = b"*2\r\n$4\r\njobs\r\n$3\r\njob\r\n";
+            assert_eq!(read_reply(&mut waiter, expected.len()).await, expected);
+            assert_eq!(send(&mut pusher, b"*2\r\n$4\r\nLLEN\r\n$4\r\njobs\r\n").await, b":0\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_blocked_clients_are_served_in_order() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut first = TcpStream::connect(addr).await.unwrap();
+            let mut second = TcpStream::connect(addr).await.unwrap();
+            let mut pusher = TcpStream::connect(addr).await.unwrap();
+
+            first.write_all(b"*3\r\n$5\r\nBRPOP\r\n$1\r\nq\r\n$1\r\n0\r\n").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            second.write_all(b"*3\r\n$5\r\nBRPOP\r\n$1\r\nq\r\n$1\r\n0\r\n").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+
+            send(&mut pusher, b"*3\r\n$5\r\nLPUSH\r\n$1\r\nq\r\n$1\r\n1\r\n").await;
+            assert_eq!(read_reply(&mut first, 18).await, b"*2\r\n$1\r\nq\r\n$1\r\n1\r\n");
+            send(&mut pusher, b"*3\r\n$5\r\nLPUSH\r\n$1\r\nq\r\n$1\r\n2\r\n").await;
+            assert_eq!(read_reply(&mut second, 18).await, b"*2\r\n$1\r\nq\r\n$1\r\n2\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_blocking_pop_times_out() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+
+            // The PING pipelined behind the blocked command waits its turn
+            stream
+                .write_all(b"*3\r\n$5\r\nBLPOP\r\n$1\r\nq\r\n$4\r\n0.05\r\n*1\r\n$4\r\nPING\r\n")
+                .await
+                .unwrap();
+            let expected: &[u8] = b"*-1\r\n+PONG\r\n";
+            assert_eq!(read_reply(&mut stream, expected.len()).await, expected);
+
+            let reply = send(&mut stream, b"*6\r\n$6\r\nBLMOVE\r\n$1\r\nq\r\n$1\r\nd\r\n$4\r\nLEFT\r\n$4\r\nLEFT\r\n$4\r\n0.01\r\n").await;
+            assert_eq!(reply, b"$-1\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_watch_across_clients() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            let mut other = TcpStream::connect(addr).await.unwrap();
+
+            let reply = send(&mut client, b"*2\r\n$5\r\nWATCH\r\n$7\r\ncounter\r\n").await;
+            assert_eq!(reply, b"+OK\r\n");
+            assert_eq!(send(&mut client, b"*1\r\n$5\r\nMULTI\r\n").await, b"+OK\r\n");
+            let reply = send(&mut client, b"*2\r\n$4\r\nINCR\r\n$7\r\ncounter\r\n").await;
+            assert_eq!(reply, b"+QUEUED\r\n");
+
+            // The other client is not in the transaction
+            let reply = send(&mut other, b"*2\r\n$4\r\nINCR\r\n$7\r\ncounter\r\n").await;
+            assert_eq!(reply, b":1\r\n");
+            assert_eq!(send(&mut client, b"*1\r\n$4\r\nEXEC\r\n").await, b"*-1\r\n");
+
+            // Retried without interference
+            let reply = send(
+                &mut client,
+                b"*1\r\n$5\r\nMULTI\r\n*2\r\n$4\r\nINCR\r\n$7\r\ncounter\r\n*1\r\n$4\r\nEXEC\r\n",
+            )
+            .await;
+            assert_eq!(reply, b"+OK\r\n+QUEUED\r\n*1\r\n:2\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_exec_serves_blocked_clients() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut blocked = TcpStream::connect(addr).await.unwrap();
+            let mut client = TcpStream::connect(addr).await.unwrap();
+
+            blocked.write_all(b"*3\r\n$5\r\nBLPOP\r\n$1\r\nq\r\n$1\r\n0\r\n").await.unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+
+            let reply = send(
+                &mut client,
+                b"*1\r\n$5\r\nMULTI\r\n*3\r\n$5\r\nLPUSH\r\n$1\r\nq\r\n$1\r\n1\r\n*1\r\n$4\r\nEXEC\r\n",
+            )
+            .await;
+            assert_eq!(reply, b"+OK\r\n+QUEUED\r\n*1\r\n:1\r\n");
+            assert_eq!(read_reply(&mut blocked, 18).await, b"*2\r\n$1\r\nq\r\n$1\r\n1\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_publish_fans_out_to_subscribers() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut first = TcpStream::connect(addr).await.unwrap();
+            let mut second = TcpStream::connect(addr).await.unwrap();
+            let mut publisher = TcpStream::connect(addr).await.unwrap();
+
+            let reply = send(&mut first, b"*2\r\n$9\r\nSUBSCRIBE\r\n$5\r\ncache\r\n").await;
+            assert_eq!(reply, b"*3\r\n$9\r\nsubscribe\r\n$5\r\ncache\r\n:1\r\n");
+            let reply = send(&mut second, b"*2\r\n$10\r\nPSUBSCRIBE\r\n$2\r\nc*\r\n").await;
+            assert_eq!(reply, b"*3\r\n$10\r\npsubscribe\r\n$2\r\nc*\r\n:1\r\n");
+
+            let reply = send(&mut publisher, b"*3\r\n$7\r\nPUBLISH\r\n$5\r\ncache\r\n$3\r\nkey\r\n").await;
+            assert_eq!(reply, b":2\r\n");
+
+            // Delivered to idle connections, without them asking
+            let expected: &[u8] = b"*3\r\n$7\r\nmessage\r\n$5\r\ncache\r\n$3\r\nkey\r\n";
+            assert_eq!(read_reply(&mut first, expected.len()).await, expected);
+            let expected: &[u8] = b"*4\r\n$8\r\npmessage\r\n$2\r\nc*\r\n$5\r\ncache\r\n$3\r\nkey\r\n";
+            assert_eq!(read_reply(&mut second, expected.len()).await, expected);
+
+            // A subscriber that hangs up stops counting
+            drop(first);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let reply = send(&mut publisher, b"*3\r\n$7\r\nPUBLISH\r\n$5\r\ncache\r\n$3\r\nkey\r\n").await;
+            assert_eq!(reply, b":1\r\n");
+            let reply = send(&mut publisher, b"*2\r\n$6\r\nPUBSUB\r\n$8\r\nCHANNELS\r\n").await;
+            assert_eq!(reply, b"*0\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_reset_and_quit() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut client = TcpStream::connect(addr).await.unwrap();
+            let mut publisher = TcpStream::connect(addr).await.unwrap();
+
+            // RESET leaves subscribe mode and drops the subscriptions
+            let reply = send(&mut client, b"*2\r\n$9\r\nSUBSCRIBE\r\n$5\r\ncache\r\n").await;
+            assert_eq!(reply, b"*3\r\n$9\r\nsubscribe\r\n$5\r\ncache\r\n:1\r\n");
+            assert_eq!(send(&mut client, b"*1\r\n$5\r\nRESET\r\n").await, b"+RESET\r\n");
+            let reply = send(&mut publisher, b"*3\r\n$7\r\nPUBLISH\r\n$5\r\ncache\r\n$3\r\nkey\r\n").await;
+            assert_eq!(reply, b":0\r\n");
+            assert_eq!(send(&mut client, b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\n").await, b"$-1\r\n");
+
+            // It discards a transaction, which it is not queued in
+            let reply = send(&mut client, b"*1\r\n$5\r\nMULTI\r\n*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n").await;
+            assert_eq!(reply, b"+OK\r\n+QUEUED\r\n");
+            assert_eq!(send(&mut client, b"*1\r\n$5\r\nRESET\r\n").await, b"+RESET\r\n");
+            assert_eq!(send(&mut client, b"*1\r\n$4\r\nEXEC\r\n").await, b"-ERR EXEC without MULTI\r\n");
+            assert_eq!(send(&mut client, b"*2\r\n$3\r\nGET\r\n$1\r\nk\r\n").await, b"$-1\r\n");
+
+            // And forgets the watched keys
+            let reply = send(&mut client, b"*2\r\n$5\r\nWATCH\r\n$1\r\nk\r\n*1\r\n$5\r\nRESET\r\n").await;
+            assert_eq!(reply, b"+OK\r\n+RESET\r\n");
+            send(&mut publisher, b"*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nx\r\n").await;
+            let reply = send(&mut client, b"*1\r\n$5\r\nMULTI\r\n*1\r\n$4\r\nEXEC\r\n").await;
+            assert_eq!(reply, b"+OK\r\n*0\r\n");
+
+            // QUIT answers, even in subscribe mode, then closes the connection
+            send(&mut client, b"*2\r\n$9\r\nSUBSCRIBE\r\n$5\r\ncache\r\n").await;
+            let reply = send(&mut client, b"*1\r\n$4\r\nQUIT\r\n*1\r\n$4\r\nPING\r\n").await;
+            assert_eq!(reply, b"+OK\r\n");
+            let mut buffer = [0u8; 16];
+            let read = timeout(Duration::from_secs(2), client.read(&mut buffer)).await.unwrap().unwrap();
+            assert_eq!(read, 0);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            let reply = send(&mut publisher, b"*3\r\n$7\r\nPUBLISH\r\n$5\r\ncache\r\n$3\r\nkey\r\n").await;
+            assert_eq!(reply, b":0\r\n");
+        })
+        .await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_rejected_commands_get_redis_errors() {
+    LocalSet::new()
+        .run_until(async {
+            let addr = start_server().await;
+            let mut client = TcpStream::connect(addr).await.unwrap();
+
+            let reply = send(&mut client, b"*1\r\n$3\r\nGET\r\n").await;
+            assert_eq!(reply, b"-ERR wrong number of arguments for 'get' command\r\n");
+            let reply = send(&mut client, b"*2\r\n$3\r\nFOO\r\n$3\r\nbar\r\n").await;
+            assert_eq!(reply, b"-ERR unknown command 'FOO', with args beginning with: 'bar' \r\n");
+        })
+        .await;
+}